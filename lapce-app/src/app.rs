@@ -67,9 +67,11 @@
         color::LapceColor, icon::LapceIcons, watcher::ConfigWatcher, LapceConfig,
     },
     db::LapceDb,
-    debug::RunDebugMode,
+    debug::{BreakpointEditKind, RunDebugMode},
     editor::{
-        diff::diff_show_more_section_view,
+        diff::{
+            diff_editor_header_view, diff_show_more_section_view, merge_result_view,
+        },
         location::{EditorLocation, EditorPosition},
         view::editor_container_view,
     },
@@ -1097,6 +1099,7 @@ fn editor_tab_content(
                 });
                 if let Some(diff_editor_data) = diff_editor_data {
                     let focus_right = diff_editor_data.focus_right;
+                    let focus_result = diff_editor_data.focus_result;
                     let diff_editor_tab_id = diff_editor_data.editor_tab_id;
                     let diff_editor_scope = diff_editor_data.scope;
                     let is_active = move |tracked: bool| {
@@ -1121,6 +1124,22 @@ fn editor_tab_content(
                             false
                         }
                     };
+                    // Whether the right side, or the result of a merge, is the
+                    // focused editor
+                    let is_focused =
+                        move |tracked: bool, right: bool, result: bool| {
+                            let (focus_right, focus_result) = if tracked {
+                                (focus_right.get(), focus_result.get())
+                            } else {
+                                (
+                                    focus_right.get_untracked(),
+                                    focus_result.get_untracked(),
+                                )
+                            };
+                            is_active(tracked)
+                                && focus_result == result
+                                && (result || focus_right == right)
+                        };
                     let left_viewport = diff_editor_data.left.viewport();
                     let left_scroll_to = diff_editor_data.left.scroll_to();
                     let right_viewport = diff_editor_data.right.viewport();
@@ -1143,60 +1162,123 @@ fn editor_tab_content(
                         create_rw_signal(diff_editor_data.left.clone());
                     let right_editor =
                         create_rw_signal(diff_editor_data.right.clone());
-                    container(
+                    let inline_editor =
+                        create_rw_signal(diff_editor_data.inline.clone());
+                    let show_inline = diff_editor_data.show_inline;
+                    let diff_view = stack((
+                        container(editor_container_view(
+                            window_tab_data.clone(),
+                            workspace.clone(),
+                            move |track| is_focused(track, false, false),
+                            left_editor,
+                        ))
+                        .on_event_cont(EventListener::PointerDown, move |_| {
+                            focus_right.set(false);
+                            focus_result.set(false);
+                        })
+                        .style(move |s| {
+                            s.height_full()
+                                .flex_grow(1.0)
+                                .flex_basis(0.0)
+                                .border_right(1.0)
+                                .border_color(
+                                    config.get().color(LapceColor::LAPCE_BORDER),
+                                )
+                                .apply_if(show_inline.get(), |s| s.hide())
+                        }),
+                        container(editor_container_view(
+                            window_tab_data.clone(),
+                            workspace.clone(),
+                            move |track| is_focused(track, true, false),
+                            right_editor,
+                        ))
+                        .on_event_cont(EventListener::PointerDown, move |_| {
+                            focus_right.set(true);
+                            focus_result.set(false);
+                        })
+                        .style(move |s| {
+                            s.height_full()
+                                .flex_grow(1.0)
+                                .flex_basis(0.0)
+                                .apply_if(show_inline.get(), |s| s.hide())
+                        }),
+                        container(diff_show_more_section_view(
+                            Some(diff_editor_data.left.clone()),
+                            diff_editor_data.right.clone(),
+                        ))
+                        .style(move |s| {
+                            s.absolute()
+                                .size_full()
+                                .apply_if(show_inline.get(), |s| s.hide())
+                        }),
+                        // The inline editor takes the place of both sides, and
+                        // is focused as the right one
+                        container(editor_container_view(
+                            window_tab_data.clone(),
+                            workspace.clone(),
+                            move |track| is_focused(track, true, false),
+                            inline_editor,
+                        ))
+                        .on_event_cont(EventListener::PointerDown, move |_| {
+                            focus_right.set(true);
+                            focus_result.set(false);
+                        })
+                        .style(move |s| {
+                            s.height_full()
+                                .flex_grow(1.0)
+                                .flex_basis(0.0)
+                                .apply_if(!show_inline.get(), |s| s.hide())
+                        }),
+                        container(diff_show_more_section_view(
+                            None,
+                            diff_editor_data.inline.clone(),
+                        ))
+                        .style(move |s| {
+                            s.absolute()
+                                .size_full()
+                                .apply_if(!show_inline.get(), |s| s.hide())
+                        }),
+                    ));
+                    let view = if let Some(result) = diff_editor_data.result.clone()
+                    {
+                        // A merge editor has the current and incoming sides
+                        // above the result
                         stack((
-                            container(editor_container_view(
+                            diff_view.style(|s| {
+                                s.width_full().flex_grow(1.0).flex_basis(0.0)
+                            }),
+                            merge_result_view(
                                 window_tab_data.clone(),
                                 workspace.clone(),
-                                move |track| {
-                                    is_active(track)
-                                        && if track {
-                                            !focus_right.get()
-                                        } else {
-                                            !focus_right.get_untracked()
-                                        }
-                                },
-                                left_editor,
-                            ))
+                                result,
+                                move |track| is_focused(track, false, true),
+                            )
                             .on_event_cont(EventListener::PointerDown, move |_| {
-                                focus_right.set(false);
+                                focus_result.set(true);
                             })
                             .style(move |s| {
-                                s.height_full()
+                                s.width_full()
                                     .flex_grow(1.0)
                                     .flex_basis(0.0)
-                                    .border_right(1.0)
+                                    .border_top(1.0)
                                     .border_color(
                                         config.get().color(LapceColor::LAPCE_BORDER),
                                     )
                             }),
-                            container(editor_container_view(
-                                window_tab_data.clone(),
-                                workspace.clone(),
-                                move |track| {
-                                    is_active(track)
-                                        && if track {
-                                            focus_right.get()
-                                        } else {
-                                            focus_right.get_untracked()
-                                        }
-                                },
-                                right_editor,
-                            ))
-                            .on_event_cont(EventListener::PointerDown, move |_| {
-                                focus_right.set(true);
-                            })
-                            .style(|s| {
-                                s.height_full().flex_grow(1.0).flex_basis(0.0)
+                        ))
+                        .style(|s| s.flex_col().size_full())
+                        .any()
+                    } else {
+                        stack((
+                            diff_editor_header_view(diff_editor_data.clone()),
+                            diff_view.style(|s| {
+                                s.width_full().flex_grow(1.0).flex_basis(0.0)
                             }),
-                            diff_show_more_section_view(
-                                diff_editor_data.left.clone(),
-                                diff_editor_data.right.clone(),
-                            ),
                         ))
-                        .style(|s| s.size_full()),
-                    )
-                    .on_cleanup(move || {
+                        .style(|s| s.flex_col().size_full())
+                        .any()
+                    };
+                    container(view).on_cleanup(move || {
                         diff_editor_scope.dispose();
                     })
                 } else {
@@ -2188,11 +2270,7 @@ fn palette_item(
         } => {
             let mode = *mode;
             let text = format!("{mode} {}", run_config.name);
-            let hint = format!(
-                "{} {}",
-                run_config.program,
-                run_config.args.clone().unwrap_or_default().join(" ")
-            );
+            let hint = run_config.target();
             let text_indices: Vec<usize> = item
                 .indices
                 .iter()
@@ -2308,6 +2386,10 @@ fn palette_item(
         | PaletteItemContent::LineEnding { .. }
         | PaletteItemContent::ColorTheme { .. }
         | PaletteItemContent::SCMReference { .. }
+        | PaletteItemContent::SCMCommit { .. }
+        | PaletteItemContent::SCMStash { .. }
+        | PaletteItemContent::Process { .. }
+        | PaletteItemContent::OpenEditor { .. }
         | PaletteItemContent::TerminalProfile { .. }
         | PaletteItemContent::IconTheme { .. } => {
             let text = item.filter_text;
@@ -2968,6 +3050,80 @@ fn rename(window_tab_data: Rc<WindowTabData>) -> impl View {
     })
 }
 
+fn breakpoint_edit(window_tab_data: Rc<WindowTabData>) -> impl View {
+    let breakpoint_edit = window_tab_data.breakpoint_edit.clone();
+    let editor = breakpoint_edit.editor.clone();
+    let active = breakpoint_edit.active;
+    let kind = breakpoint_edit.kind;
+    let layout_rect = breakpoint_edit.layout_rect;
+    let config = window_tab_data.common.config;
+
+    let kind_tab = move |edit_kind: BreakpointEditKind| {
+        let breakpoint_edit = breakpoint_edit.clone();
+        label(move || edit_kind.label().to_string())
+            .on_click_stop(move |_| {
+                breakpoint_edit.set_kind(edit_kind);
+            })
+            .style(move |s| {
+                let config = config.get();
+                s.padding_horiz(6.0)
+                    .border_radius(4.0)
+                    .cursor(CursorStyle::Pointer)
+                    .apply_if(kind.get() == edit_kind, |s| {
+                        s.background(
+                            config.color(LapceColor::PANEL_CURRENT_BACKGROUND),
+                        )
+                    })
+                    .hover(|s| {
+                        s.background(
+                            config.color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                        )
+                    })
+            })
+    };
+
+    container(
+        stack((
+            stack((
+                kind_tab(BreakpointEditKind::Condition),
+                kind_tab(BreakpointEditKind::HitCount),
+                kind_tab(BreakpointEditKind::LogMessage),
+            ))
+            .style(|s| s.margin_bottom(6.0)),
+            container(
+                text_input(editor, move || active.get())
+                    .placeholder(move || kind.get().placeholder().to_string())
+                    .style(|s| s.width(400.0)),
+            )
+            .style(move |s| {
+                let config = config.get();
+                s.font_family(config.editor.font_family.clone())
+                    .font_size(config.editor.font_size() as f32)
+                    .border(1.0)
+                    .border_radius(6.0)
+                    .border_color(config.color(LapceColor::LAPCE_BORDER))
+                    .background(config.color(LapceColor::EDITOR_BACKGROUND))
+            }),
+        ))
+        .style(|s| s.flex_col()),
+    )
+    .on_resize(move |rect| {
+        layout_rect.set(rect);
+    })
+    .on_event_stop(EventListener::PointerMove, |_| {})
+    .on_event_stop(EventListener::PointerDown, |_| {})
+    .style(move |s| {
+        let origin = window_tab_data.breakpoint_edit_origin();
+        s.position(Position::Absolute)
+            .apply_if(!active.get(), |s| s.hide())
+            .margin_left(origin.x as f32)
+            .margin_top(origin.y as f32)
+            .background(config.get().color(LapceColor::PANEL_BACKGROUND))
+            .border_radius(6.0)
+            .padding(6.0)
+    })
+}
+
 fn window_tab(window_tab_data: Rc<WindowTabData>) -> impl View {
     let source_control = window_tab_data.source_control.clone();
     let window_origin = window_tab_data.common.window_origin;
@@ -3001,6 +3157,7 @@ fn window_tab(window_tab_data: Rc<WindowTabData>) -> impl View {
         hover(window_tab_data.clone()),
         code_action(window_tab_data.clone()),
         rename(window_tab_data.clone()),
+        breakpoint_edit(window_tab_data.clone()),
         palette(window_tab_data.clone()),
         about::about_popup(window_tab_data.clone()),
         alert::alert_box(window_tab_data.alert_data.clone()),
@@ -12,6 +12,7 @@
     dap_types::{DapId, RunDebugConfig},
     plugin::{PluginId, VoltID},
     proxy::ProxyStatus,
+    source_control::FileDiff,
     terminal::{TermId, TerminalProfile},
 };
 use lsp_types::{CodeActionOrCommand, Position, WorkspaceEdit};
@@ -352,6 +353,12 @@ pub enum LapceWorkbenchCommand {
     #[strum(serialize = "palette.run_and_debug_stop")]
     RunAndDebugStop,
 
+    #[strum(serialize = "debug_console_evaluate")]
+    DebugConsoleEvaluate,
+
+    #[strum(serialize = "confirm_breakpoint_edit")]
+    ConfirmBreakpointEdit,
+
     #[strum(serialize = "source_control.checkout_reference")]
     CheckoutReference,
 
@@ -408,6 +415,14 @@ pub enum LapceWorkbenchCommand {
     #[strum(serialize = "toggle_search_focus")]
     ToggleSearchFocus,
 
+    #[strum(message = "Replace All in Files")]
+    #[strum(serialize = "global_search_replace_all")]
+    GlobalSearchReplaceAll,
+
+    #[strum(message = "Undo Replace in Files")]
+    #[strum(serialize = "global_search_undo_replace")]
+    GlobalSearchUndoReplace,
+
     // Visual toggle commands
     #[strum(serialize = "toggle_terminal_visual")]
     ToggleTerminalVisual,
@@ -430,6 +445,12 @@ pub enum LapceWorkbenchCommand {
     #[strum(serialize = "toggle_search_visual")]
     ToggleSearchVisual,
 
+    #[strum(serialize = "toggle_hierarchy_visual")]
+    ToggleHierarchyVisual,
+
+    #[strum(serialize = "toggle_git_log_visual")]
+    ToggleGitLogVisual,
+
     #[strum(serialize = "focus_editor")]
     FocusEditor,
 
@@ -440,6 +461,74 @@ pub enum LapceWorkbenchCommand {
     #[strum(serialize = "source_control_init")]
     SourceControlInit,
 
+    #[strum(message = "Go to Implementation")]
+    #[strum(serialize = "goto_implementation")]
+    GotoImplementation,
+
+    #[strum(message = "Go to Declaration")]
+    #[strum(serialize = "goto_declaration")]
+    GotoDeclaration,
+
+    #[strum(message = "Format Selection")]
+    #[strum(serialize = "format_selection")]
+    FormatSelection,
+
+    #[strum(message = "Show Call Hierarchy")]
+    #[strum(serialize = "show_call_hierarchy")]
+    ShowCallHierarchy,
+
+    #[strum(message = "Show Type Hierarchy")]
+    #[strum(serialize = "show_type_hierarchy")]
+    ShowTypeHierarchy,
+
+    #[strum(message = "Fold")]
+    #[strum(serialize = "fold")]
+    Fold,
+
+    #[strum(message = "Unfold")]
+    #[strum(serialize = "unfold")]
+    Unfold,
+
+    #[strum(message = "Toggle Fold")]
+    #[strum(serialize = "toggle_fold")]
+    ToggleFold,
+
+    #[strum(message = "Fold All")]
+    #[strum(serialize = "fold_all")]
+    FoldAll,
+
+    #[strum(message = "Unfold All")]
+    #[strum(serialize = "unfold_all")]
+    UnfoldAll,
+
+    #[strum(message = "Fold Level 1")]
+    #[strum(serialize = "fold_level_1")]
+    FoldLevel1,
+
+    #[strum(message = "Fold Level 2")]
+    #[strum(serialize = "fold_level_2")]
+    FoldLevel2,
+
+    #[strum(message = "Fold Level 3")]
+    #[strum(serialize = "fold_level_3")]
+    FoldLevel3,
+
+    #[strum(message = "Fold Level 4")]
+    #[strum(serialize = "fold_level_4")]
+    FoldLevel4,
+
+    #[strum(message = "Fold Level 5")]
+    #[strum(serialize = "fold_level_5")]
+    FoldLevel5,
+
+    #[strum(message = "Fold Level 6")]
+    #[strum(serialize = "fold_level_6")]
+    FoldLevel6,
+
+    #[strum(message = "Fold Level 7")]
+    #[strum(serialize = "fold_level_7")]
+    FoldLevel7,
+
     #[strum(serialize = "source_control_commit")]
     SourceControlCommit,
 
@@ -458,6 +547,90 @@ pub enum LapceWorkbenchCommand {
     #[strum(serialize = "source_control_discard_workspace_changes")]
     SourceControlDiscardWorkspaceChanges,
 
+    #[strum(message = "Source Control: Stage All Changes")]
+    #[strum(serialize = "source_control_stage_all")]
+    SourceControlStageAll,
+
+    #[strum(message = "Source Control: Unstage All Changes")]
+    #[strum(serialize = "source_control_unstage_all")]
+    SourceControlUnstageAll,
+
+    #[strum(message = "Source Control: Stage Change")]
+    #[strum(serialize = "source_control_stage_hunk")]
+    SourceControlStageHunk,
+
+    #[strum(message = "Source Control: Unstage Change")]
+    #[strum(serialize = "source_control_unstage_hunk")]
+    SourceControlUnstageHunk,
+
+    #[strum(message = "Source Control: Revert Change")]
+    #[strum(serialize = "source_control_revert_hunk")]
+    SourceControlRevertHunk,
+
+    #[strum(message = "Source Control: Toggle Blame")]
+    #[strum(serialize = "source_control_toggle_blame")]
+    SourceControlToggleBlame,
+
+    #[strum(message = "Source Control: Show Log")]
+    #[strum(serialize = "source_control_show_log")]
+    SourceControlShowLog,
+
+    #[strum(message = "Source Control: Show File History")]
+    #[strum(serialize = "source_control_show_file_history")]
+    SourceControlShowFileHistory,
+
+    #[strum(message = "Source Control: Fetch")]
+    #[strum(serialize = "source_control_fetch")]
+    SourceControlFetch,
+
+    #[strum(message = "Source Control: Pull")]
+    #[strum(serialize = "source_control_pull")]
+    SourceControlPull,
+
+    #[strum(message = "Source Control: Pull (Rebase)")]
+    #[strum(serialize = "source_control_pull_rebase")]
+    SourceControlPullRebase,
+
+    #[strum(message = "Source Control: Push")]
+    #[strum(serialize = "source_control_push")]
+    SourceControlPush,
+
+    #[strum(message = "Source Control: Create Branch")]
+    #[strum(serialize = "source_control_create_branch")]
+    SourceControlCreateBranch,
+
+    #[strum(message = "Source Control: Create Branch From...")]
+    #[strum(serialize = "source_control_create_branch_from")]
+    SourceControlCreateBranchFrom,
+
+    #[strum(message = "Source Control: Rename Branch")]
+    #[strum(serialize = "source_control_rename_branch")]
+    SourceControlRenameBranch,
+
+    #[strum(message = "Source Control: Delete Branch")]
+    #[strum(serialize = "source_control_delete_branch")]
+    SourceControlDeleteBranch,
+
+    #[strum(message = "Source Control: Merge Branch")]
+    #[strum(serialize = "source_control_merge_branch")]
+    SourceControlMergeBranch,
+
+    #[strum(message = "Source Control: Stash Changes")]
+    #[strum(serialize = "source_control_stash")]
+    SourceControlStash,
+
+    #[strum(message = "Source Control: Pop Stash")]
+    #[strum(serialize = "source_control_stash_pop")]
+    SourceControlStashPop,
+
+    #[strum(message = "Source Control: Apply Stash")]
+    #[strum(serialize = "source_control_stash_apply")]
+    SourceControlStashApply,
+
+    #[strum(message = "Source Control: Drop Stash")]
+    #[strum(serialize = "source_control_stash_drop")]
+    SourceControlStashDrop,
+
     #[strum(serialize = "export_current_theme_settings")]
     #[strum(message = "Export current settings to a theme file")]
     ExportCurrentThemeSettings,
@@ -531,6 +704,34 @@ pub enum LapceWorkbenchCommand {
     #[strum(serialize = "diff_files")]
     DiffFiles,
 
+    #[strum(message = "Compare File with Revision")]
+    #[strum(serialize = "compare_with_revision")]
+    CompareWithRevision,
+
+    #[strum(message = "Compare File with Open Editor")]
+    #[strum(serialize = "compare_with_open_editor")]
+    CompareWithOpenEditor,
+
+    #[strum(message = "Compare File with Clipboard")]
+    #[strum(serialize = "compare_with_clipboard")]
+    CompareWithClipboard,
+
+    #[strum(message = "Next Change in Diff Editor")]
+    #[strum(serialize = "next_diff_change")]
+    NextDiffChange,
+
+    #[strum(message = "Previous Change in Diff Editor")]
+    #[strum(serialize = "previous_diff_change")]
+    PreviousDiffChange,
+
+    #[strum(message = "Toggle Inline Diff")]
+    #[strum(serialize = "toggle_inline_diff")]
+    ToggleInlineDiff,
+
+    #[strum(message = "Toggle Ignore Whitespace in Diff")]
+    #[strum(serialize = "toggle_diff_ignore_whitespace")]
+    ToggleDiffIgnoreWhitespace,
+
     #[strum(serialize = "quit")]
     #[strum(message = "Quit Editor")]
     Quit,
@@ -548,6 +749,17 @@ pub enum InternalCommand {
     MakeConfirmed,
     OpenFileChanges {
         path: PathBuf,
+        /// Whether to show the staged changes instead of the unstaged ones
+        staged: bool,
+    },
+    /// Open the merge editor of a file with conflicts
+    OpenMergeEditor {
+        path: PathBuf,
+    },
+    /// Open the changes a commit made to a file against its first parent
+    OpenCommitFileChanges {
+        commit: String,
+        diff: FileDiff,
     },
     ReloadFileExplorer,
     /// Test whether a file/directory can be created at that path
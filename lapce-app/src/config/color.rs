@@ -71,6 +71,10 @@ impl LapceColor {
     pub const EDITOR_DEBUG_BREAK_LINE: &'static str = "editor.debug_break_line";
     pub const EDITOR_CURRENT_LINE: &'static str = "editor.current_line";
     pub const EDITOR_LINK: &'static str = "editor.link";
+    pub const EDITOR_DOCUMENT_HIGHLIGHT_READ: &'static str =
+        "editor.document_highlight_read";
+    pub const EDITOR_DOCUMENT_HIGHLIGHT_WRITE: &'static str =
+        "editor.document_highlight_write";
     pub const EDITOR_VISIBLE_WHITESPACE: &'static str = "editor.visible_whitespace";
     pub const EDITOR_INDENT_GUIDE: &'static str = "editor.indent_guide";
     pub const EDITOR_DRAG_DROP_BACKGROUND: &'static str =
@@ -102,6 +106,7 @@ impl LapceColor {
     pub const SOURCE_CONTROL_ADDED: &'static str = "source_control.added";
     pub const SOURCE_CONTROL_REMOVED: &'static str = "source_control.removed";
     pub const SOURCE_CONTROL_MODIFIED: &'static str = "source_control.modified";
+    pub const SOURCE_CONTROL_CONFLICTED: &'static str = "source_control.conflicted";
 
     pub const TERMINAL_CURSOR: &'static str = "terminal.cursor";
     pub const TERMINAL_BACKGROUND: &'static str = "terminal.background";
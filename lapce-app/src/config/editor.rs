@@ -61,7 +61,9 @@ pub struct EditorConfig {
     pub font_family: String,
     #[field_names(desc = "Set the editor font size")]
     font_size: usize,
-    #[field_names(desc = "Set the font size in the code lens")]
+    #[field_names(
+        desc = "Set the font size of the code lenses, such as Run | Debug. If 0 it uses the inlay hint font size."
+    )]
     pub code_lens_font_size: usize,
     #[field_names(
         desc = "Set the editor line height. If less than 5.0, line height will be a multiple of the font size."
@@ -125,6 +127,10 @@ pub struct EditorConfig {
         desc = "Whether it should format the document on save (if there is an available formatter)"
     )]
     pub format_on_save: bool,
+    #[field_names(
+        desc = "Whether it should format the pasted text (if there is an available range formatter)"
+    )]
+    pub format_on_paste: bool,
 
     #[field_names(
         desc = "Whether newlines should be automatically converted to the current line ending"
@@ -134,6 +140,11 @@ pub struct EditorConfig {
     #[field_names(desc = "If matching brackets are highlighted")]
     pub highlight_matching_brackets: bool,
 
+    #[field_names(
+        desc = "If the other occurrences of the symbol under the cursor are highlighted"
+    )]
+    pub highlight_selection_occurrences: bool,
+
     #[field_names(desc = "If scope lines are highlighted")]
     pub highlight_scope_lines: bool,
 
@@ -182,6 +193,14 @@ pub struct EditorConfig {
         desc = "Set the completion lens font size. If 0 it uses the inlay hint font size."
     )]
     pub completion_lens_font_size: usize,
+    #[field_names(
+        desc = "If the code lenses of language servers, such as Run | Debug, should be displayed"
+    )]
+    pub enable_lsp_code_lens: bool,
+    #[field_names(
+        desc = "If the author, date and summary of the commit which last changed the cursor line should be displayed at the end of it"
+    )]
+    pub inline_blame: bool,
     #[field_names(
         desc = "Set the cursor blink interval (in milliseconds). Set to 0 to completely disable."
     )]
@@ -266,6 +285,14 @@ pub fn error_lens_font_size(&self) -> usize {
         }
     }
 
+    pub fn code_lens_font_size(&self) -> usize {
+        if self.code_lens_font_size == 0 {
+            self.inlay_hint_font_size()
+        } else {
+            self.code_lens_font_size
+        }
+    }
+
     pub fn completion_lens_font_size(&self) -> usize {
         if self.completion_lens_font_size == 0 {
             self.inlay_hint_font_size()
@@ -17,6 +17,9 @@ impl LapceIcons {
     pub const DEBUG: &'static str = "debug";
     pub const DEBUG_ALT: &'static str = "debug_alt";
     pub const DEBUG_BREAKPOINT: &'static str = "debug_breakpoint";
+    pub const DEBUG_BREAKPOINT_CONDITIONAL: &'static str =
+        "debug_breakpoint_conditional";
+    pub const DEBUG_BREAKPOINT_LOG: &'static str = "debug_breakpoint_log";
     pub const DEBUG_SMALL: &'static str = "debug_small";
     pub const DEBUG_RESTART: &'static str = "debug_restart";
     pub const DEBUG_CONTINUE: &'static str = "debug_continue";
@@ -38,6 +41,10 @@ impl LapceIcons {
     pub const KEYBOARD: &'static str = "keyboard";
     pub const BREADCRUMB_SEPARATOR: &'static str = "breadcrumb_separator";
     pub const SYMBOL_COLOR: &'static str = "symbol_color";
+    pub const HIERARCHY: &'static str = "hierarchy";
+    pub const HIERARCHY_SWAP: &'static str = "hierarchy.swap";
+    pub const GIT_LOG: &'static str = "git_log";
+    pub const GIT_LOG_REFRESH: &'static str = "git_log.refresh";
 
     pub const FILE: &'static str = "file";
     pub const FILE_EXPLORER: &'static str = "file_explorer";
@@ -51,6 +58,7 @@ impl LapceIcons {
     pub const SCM_DIFF_ADDED: &'static str = "scm.diff.added";
     pub const SCM_DIFF_REMOVED: &'static str = "scm.diff.removed";
     pub const SCM_DIFF_RENAMED: &'static str = "scm.diff.renamed";
+    pub const SCM_DIFF_CONFLICTED: &'static str = "scm.diff.conflicted";
     pub const SCM_CHANGE_ADD: &'static str = "scm.change.add";
     pub const SCM_CHANGE_REMOVE: &'static str = "scm.change.remove";
 
@@ -13,6 +13,7 @@
 use crate::{
     app::{AppData, AppInfo},
     doc::DocInfo,
+    folding::FoldingRange,
     panel::{data::PanelOrder, kind::PanelKind, position::PanelPosition},
     window::{WindowData, WindowInfo},
     window_tab::WindowTabData,
@@ -395,12 +396,14 @@ pub fn save_doc_position(
         path: PathBuf,
         cursor_offset: usize,
         scroll_offset: Vec2,
+        folded: Vec<FoldingRange>,
     ) {
         let info = DocInfo {
             workspace: workspace.clone(),
             path,
             scroll_offset: (scroll_offset.x, scroll_offset.y),
             cursor_offset,
+            folded,
         };
         let _ = self.save_tx.send(SaveEvent::Doc(info));
     }
@@ -1,30 +1,49 @@
 use std::{
     collections::{BTreeMap, HashMap},
     fmt::Display,
-    path::PathBuf,
+    path::{Path, PathBuf},
     rc::Rc,
     time::Instant,
 };
 
 use floem::{
     ext_event::create_ext_action,
+    keyboard::ModifiersState,
+    peniko::kurbo::Rect,
     reactive::{Memo, RwSignal, Scope},
-    views::VirtualVector,
+    views::{editor::id::EditorId, VirtualVector},
+};
+use lapce_core::{
+    command::{EditCommand, FocusCommand},
+    mode::Mode,
+    selection::Selection,
 };
 use lapce_rpc::{
     dap_types::{
-        self, DapId, RunDebugConfig, SourceBreakpoint, StackFrame, Stopped,
+        self, DapId, DebuggerCapabilities, EvaluateResponse,
+        ExceptionBreakpointsFilter, FunctionBreakpoint, GlobalBreakpoints, Output,
+        RunDebugConfig, RunDebugRequest, SourceBreakpoint, StackFrame, Stopped,
         ThreadId, Variable,
     },
-    proxy::ProxyResponse,
+    proxy::{ProxyResponse, ProxyRpcHandler},
     terminal::TermId,
 };
+use lapce_xi_rope::Rope;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    command::InternalCommand,
-    editor::location::{EditorLocation, EditorPosition},
-    window_tab::CommonData,
+    command::{
+        CommandExecuted, CommandKind, InternalCommand, LapceCommand,
+        LapceWorkbenchCommand,
+    },
+    editor::{
+        location::{EditorLocation, EditorPosition},
+        EditorData,
+    },
+    keypress::{condition::Condition, KeyPressFocus},
+    panel::kind::PanelKind,
+    terminal::panel::TerminalPanelData,
+    window_tab::{CommonData, Focus},
 };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -57,11 +76,108 @@ pub struct RunDebugConfigs {
     pub configs: Vec<RunDebugConfig>,
 }
 
+/// The argument of rust-analyzer's `rust-analyzer.runSingle` and
+/// `rust-analyzer.debugSingle` code lens commands.
+#[derive(Deserialize)]
+struct Runnable {
+    label: String,
+    kind: String,
+    args: CargoRunnableArgs,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CargoRunnableArgs {
+    workspace_root: Option<String>,
+    cwd: Option<String>,
+    environment: Option<HashMap<String, String>>,
+    cargo_args: Vec<String>,
+    #[serde(default)]
+    cargo_extra_args: Vec<String>,
+    #[serde(default)]
+    executable_args: Vec<String>,
+}
+
+/// The type of the debugger which debugs the runnables of rust-analyzer,
+/// which is CodeLLDB.
+const RUNNABLE_DEBUGGER_TYPE: &str = "lldb";
+
+/// The number of entries the debug console keeps
+const MAX_CONSOLE_ENTRIES: usize = 5000;
+
+fn parse_runnable(runnable: serde_json::Value) -> Option<Runnable> {
+    let runnable: Runnable = serde_json::from_value(runnable).ok()?;
+    (runnable.kind == "cargo").then_some(runnable)
+}
+
+/// Convert a cargo runnable sent by rust-analyzer into a run config.
+pub fn runnable_run_config(runnable: serde_json::Value) -> Option<RunDebugConfig> {
+    let runnable = parse_runnable(runnable)?;
+    let args = runnable.args;
+    let mut cargo_args = args.cargo_args;
+    cargo_args.extend(args.cargo_extra_args);
+    if !args.executable_args.is_empty() {
+        cargo_args.push("--".to_string());
+        cargo_args.extend(args.executable_args);
+    }
+    Some(RunDebugConfig {
+        ty: None,
+        name: runnable.label,
+        request: RunDebugRequest::Launch,
+        program: "cargo".to_string(),
+        args: Some(cargo_args),
+        cwd: args.cwd.or(args.workspace_root),
+        env: args.environment,
+        prelaunch: None,
+        pid: None,
+        host: None,
+        port: None,
+        debug_command: None,
+        dap_id: DapId::next(),
+    })
+}
+
+/// Convert a cargo runnable sent by rust-analyzer into the cargo arguments
+/// which build its executable, and the config which debugs the executable
+/// once its path is filled in as the program.
+pub fn runnable_debug_config(
+    runnable: serde_json::Value,
+) -> Option<(Vec<String>, RunDebugConfig)> {
+    let runnable = parse_runnable(runnable)?;
+    let args = runnable.args;
+    let mut cargo_args = args.cargo_args;
+    cargo_args.extend(args.cargo_extra_args);
+    let config = RunDebugConfig {
+        ty: Some(RUNNABLE_DEBUGGER_TYPE.to_string()),
+        name: runnable.label,
+        request: RunDebugRequest::Launch,
+        program: String::new(),
+        args: Some(args.executable_args),
+        cwd: args.cwd.or(args.workspace_root),
+        env: args.environment,
+        prelaunch: None,
+        pid: None,
+        host: None,
+        port: None,
+        debug_command: None,
+        dap_id: DapId::next(),
+    };
+    Some((cargo_args, config))
+}
+
 #[derive(Clone)]
 pub struct RunDebugData {
     pub active_term: RwSignal<Option<TermId>>,
     pub daps: RwSignal<im::HashMap<DapId, DapData>>,
+    /// The debug sessions which attached to a process, which don't have a
+    /// terminal, by the id which stands in for the terminal's
+    pub attached: RwSignal<im::HashMap<TermId, RunDebugProcess>>,
     pub breakpoints: RwSignal<BTreeMap<PathBuf, BTreeMap<usize, LapceBreakpoint>>>,
+    /// The watch expressions, which are evaluated by every debug session
+    pub watches: RwSignal<im::Vector<String>>,
+    pub function_breakpoints: RwSignal<im::Vector<LapceFunctionBreakpoint>>,
+    /// The exception filters the user turned on or off, by their id
+    pub exception_filters: RwSignal<HashMap<String, bool>>,
 }
 
 impl RunDebugData {
@@ -76,10 +192,33 @@ pub fn new(
         Self {
             active_term,
             daps,
+            attached: cx.create_rw_signal(im::HashMap::new()),
             breakpoints,
+            watches: cx.create_rw_signal(im::Vector::new()),
+            function_breakpoints: cx.create_rw_signal(im::Vector::new()),
+            exception_filters: cx.create_rw_signal(HashMap::new()),
+        }
+    }
+
+    /// The function breakpoints and exception filters to start a debug
+    /// session with
+    pub fn global_breakpoints(&self) -> GlobalBreakpoints {
+        GlobalBreakpoints {
+            exception_filters: self.exception_filters.get_untracked(),
+            functions: self.active_function_breakpoints(),
         }
     }
 
+    fn active_function_breakpoints(&self) -> Vec<FunctionBreakpoint> {
+        self.function_breakpoints.with_untracked(|breakpoints| {
+            breakpoints
+                .iter()
+                .filter(|b| b.active)
+                .map(LapceFunctionBreakpoint::function_breakpoint)
+                .collect()
+        })
+    }
+
     pub fn source_breakpoints(&self) -> HashMap<PathBuf, Vec<SourceBreakpoint>> {
         self.breakpoints
             .get_untracked()
@@ -88,25 +227,270 @@ pub fn source_breakpoints(&self) -> HashMap<PathBuf, Vec<SourceBreakpoint>> {
                 (
                     path.to_path_buf(),
                     breakpoints
-                        .iter()
-                        .filter_map(|(_, b)| {
-                            if b.active {
-                                Some(SourceBreakpoint {
-                                    line: b.line + 1,
-                                    column: None,
-                                    condition: None,
-                                    hit_condition: None,
-                                    log_message: None,
-                                })
-                            } else {
-                                None
-                            }
-                        })
+                        .values()
+                        .filter(|b| b.active)
+                        .map(LapceBreakpoint::source_breakpoint)
                         .collect(),
                 )
             })
             .collect()
     }
+
+    /// Send the breakpoints of the file to all the debug sessions
+    pub fn sync_breakpoints(&self, path: &Path, proxy: &ProxyRpcHandler) {
+        let source_breakpoints: Vec<SourceBreakpoint> =
+            self.breakpoints.with_untracked(|breakpoints| {
+                breakpoints
+                    .get(path)
+                    .map(|breakpoints| {
+                        breakpoints
+                            .values()
+                            .filter(|b| b.active)
+                            .map(LapceBreakpoint::source_breakpoint)
+                            .collect()
+                    })
+                    .unwrap_or_default()
+            });
+        let daps: Vec<DapId> = self
+            .daps
+            .with_untracked(|daps| daps.keys().cloned().collect());
+        for dap_id in daps {
+            proxy.dap_set_breakpoints(
+                dap_id,
+                path.to_path_buf(),
+                source_breakpoints.clone(),
+            );
+        }
+    }
+
+    pub fn add_function_breakpoint(&self, name: String, proxy: &ProxyRpcHandler) {
+        if self
+            .function_breakpoints
+            .with_untracked(|breakpoints| breakpoints.iter().any(|b| b.name == name))
+        {
+            return;
+        }
+        self.function_breakpoints.update(|breakpoints| {
+            breakpoints.push_back(LapceFunctionBreakpoint::new(name));
+        });
+        self.sync_function_breakpoints(proxy);
+    }
+
+    pub fn remove_function_breakpoint(&self, name: &str, proxy: &ProxyRpcHandler) {
+        self.function_breakpoints.update(|breakpoints| {
+            breakpoints.retain(|b| b.name != name);
+        });
+        self.sync_function_breakpoints(proxy);
+    }
+
+    pub fn toggle_function_breakpoint(&self, name: &str, proxy: &ProxyRpcHandler) {
+        self.function_breakpoints.update(|breakpoints| {
+            for breakpoint in breakpoints.iter_mut() {
+                if breakpoint.name == name {
+                    breakpoint.active = !breakpoint.active;
+                }
+            }
+        });
+        self.sync_function_breakpoints(proxy);
+    }
+
+    /// Send the function breakpoints to the debug sessions which support them
+    fn sync_function_breakpoints(&self, proxy: &ProxyRpcHandler) {
+        let breakpoints = self.active_function_breakpoints();
+        for dap in self.daps.get_untracked().values() {
+            let supported = dap.capabilities.with_untracked(|c| {
+                c.as_ref()
+                    .and_then(|c| c.supports_function_breakpoints)
+                    .unwrap_or(false)
+            });
+            if supported {
+                proxy.dap_set_function_breakpoints(dap.dap_id, breakpoints.clone());
+            }
+        }
+    }
+
+    /// Take the verification of the function breakpoints, which the debug
+    /// adapter replies with in the order they were sent
+    pub fn function_breakpoints_resp(
+        &self,
+        dap_id: DapId,
+        breakpoints: &[dap_types::Breakpoint],
+    ) {
+        self.function_breakpoints.update(|current_breakpoints| {
+            let active = current_breakpoints.iter_mut().filter(|b| b.active);
+            for (current_breakpoint, breakpoint) in active.zip(breakpoints) {
+                set_breakpoint_id(
+                    &mut current_breakpoint.ids,
+                    dap_id,
+                    breakpoint.id,
+                );
+                current_breakpoint.verified = breakpoint.verified;
+                current_breakpoint.message = breakpoint.message.clone();
+            }
+        });
+    }
+
+    /// Update the breakpoint the debug adapter changed on its own, e.g. when
+    /// it got verified once the code it is in was loaded. `offset_of_line`
+    /// gives the offset of a line of a file, for when the breakpoint is moved.
+    pub fn breakpoint_changed(
+        &self,
+        dap_id: DapId,
+        reason: &str,
+        breakpoint: &dap_types::Breakpoint,
+        offset_of_line: impl Fn(&Path, usize) -> Option<usize>,
+    ) {
+        let Some(id) = breakpoint.id else {
+            return;
+        };
+        let removed = reason == "removed";
+
+        self.breakpoints.update(|all_breakpoints| {
+            for (path, breakpoints) in all_breakpoints.iter_mut() {
+                if update_changed_breakpoint(
+                    breakpoints,
+                    dap_id,
+                    removed,
+                    breakpoint,
+                    |line| offset_of_line(path.as_path(), line),
+                ) {
+                    break;
+                }
+            }
+        });
+        self.function_breakpoints.update(|breakpoints| {
+            for current_breakpoint in breakpoints.iter_mut() {
+                if current_breakpoint.ids.get(&dap_id) == Some(&id) {
+                    if removed {
+                        current_breakpoint.ids.remove(&dap_id);
+                        current_breakpoint.verified = false;
+                    } else {
+                        current_breakpoint.verified = breakpoint.verified;
+                    }
+                    current_breakpoint.message = breakpoint.message.clone();
+                }
+            }
+        });
+    }
+
+    /// Turn the exception filter on or off, and send the filters that are on
+    /// to the debug sessions that have it
+    pub fn toggle_exception_filter(
+        &self,
+        filter: &ExceptionBreakpointsFilter,
+        proxy: &ProxyRpcHandler,
+    ) {
+        self.exception_filters.update(|choices| {
+            let enabled = filter.enabled(choices);
+            choices.insert(filter.filter.clone(), !enabled);
+        });
+
+        let choices = self.exception_filters.get_untracked();
+        for dap in self.daps.get_untracked().values() {
+            let filters = dap.capabilities.with_untracked(|c| {
+                c.as_ref()?
+                    .exception_breakpoint_filters
+                    .as_ref()
+                    .map(|filters| {
+                        filters
+                            .iter()
+                            .filter(|f| f.enabled(&choices))
+                            .map(|f| f.filter.clone())
+                            .collect::<Vec<_>>()
+                    })
+            });
+            if let Some(filters) = filters {
+                proxy.dap_set_exception_breakpoints(dap.dap_id, filters);
+            }
+        }
+    }
+
+    /// Add a watch expression, evaluating it right away in the debug sessions
+    /// that are stopped
+    pub fn add_watch(&self, expression: String) {
+        if self.watches.with_untracked(|w| w.contains(&expression)) {
+            return;
+        }
+        self.watches.update(|watches| {
+            watches.push_back(expression.clone());
+        });
+        for dap in self.daps.get_untracked().values() {
+            dap.evaluate_watch(expression.clone());
+        }
+    }
+
+    pub fn remove_watch(&self, expression: &str) {
+        self.watches.update(|watches| {
+            watches.retain(|w| w != expression);
+        });
+        for dap in self.daps.get_untracked().values() {
+            let value = dap
+                .watch_values
+                .try_update(|values| values.remove(expression))
+                .flatten();
+            if let Some(value) = value {
+                value.dispose();
+            }
+        }
+    }
+}
+
+/// Set or clear the id the debug session gave a breakpoint
+pub fn set_breakpoint_id(
+    ids: &mut HashMap<DapId, usize>,
+    dap_id: DapId,
+    id: Option<usize>,
+) {
+    match id {
+        Some(id) => ids.insert(dap_id, id),
+        None => ids.remove(&dap_id),
+    };
+}
+
+/// Update the breakpoint of the file that has the id the debug session gave
+/// the changed one, moving it when the debug adapter put it on another line.
+/// Returns whether the breakpoint was found.
+fn update_changed_breakpoint(
+    breakpoints: &mut BTreeMap<usize, LapceBreakpoint>,
+    dap_id: DapId,
+    removed: bool,
+    breakpoint: &dap_types::Breakpoint,
+    offset_of_line: impl Fn(usize) -> Option<usize>,
+) -> bool {
+    let Some(line) = breakpoints
+        .values()
+        .find(|b| {
+            b.ids
+                .get(&dap_id)
+                .is_some_and(|id| Some(*id) == breakpoint.id)
+        })
+        .map(|b| b.line)
+    else {
+        return false;
+    };
+    let Some(mut current_breakpoint) = breakpoints.remove(&line) else {
+        return false;
+    };
+
+    if removed {
+        current_breakpoint.ids.remove(&dap_id);
+        current_breakpoint.verified = false;
+    } else {
+        current_breakpoint.verified = breakpoint.verified;
+        if let Some(new_line) = breakpoint.line {
+            let new_line = new_line.saturating_sub(1);
+            // Don't move it onto another breakpoint
+            if new_line != line && !breakpoints.contains_key(&new_line) {
+                current_breakpoint.line = new_line;
+                if let Some(offset) = offset_of_line(new_line) {
+                    current_breakpoint.offset = offset;
+                }
+            }
+        }
+    }
+    current_breakpoint.message = breakpoint.message.clone();
+    breakpoints.insert(current_breakpoint.line, current_breakpoint);
+    true
 }
 
 #[derive(Clone, PartialEq)]
@@ -118,13 +502,306 @@ pub struct StackTraceData {
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct LapceBreakpoint {
-    pub id: Option<usize>,
+    /// The ids the debug sessions gave the breakpoint, which only make sense
+    /// while the sessions last
+    #[serde(skip)]
+    pub ids: HashMap<DapId, usize>,
     pub verified: bool,
     pub message: Option<String>,
     pub line: usize,
     pub offset: usize,
     pub dap_line: Option<usize>,
     pub active: bool,
+    /// The expression which has to be true for the breakpoint to stop
+    #[serde(default)]
+    pub condition: Option<String>,
+    /// How many hits of the breakpoint are ignored, in a format that depends
+    /// on the debug adapter, e.g. `>= 5`
+    #[serde(default)]
+    pub hit_condition: Option<String>,
+    /// The message logged instead of stopping, which makes the breakpoint a
+    /// logpoint
+    #[serde(default)]
+    pub log_message: Option<String>,
+}
+
+impl LapceBreakpoint {
+    pub fn new(line: usize, offset: usize) -> Self {
+        Self {
+            ids: HashMap::new(),
+            verified: false,
+            message: None,
+            line,
+            offset,
+            dap_line: None,
+            active: true,
+            condition: None,
+            hit_condition: None,
+            log_message: None,
+        }
+    }
+
+    pub fn source_breakpoint(&self) -> SourceBreakpoint {
+        SourceBreakpoint {
+            line: self.line + 1,
+            column: None,
+            condition: self.condition.clone(),
+            hit_condition: self.hit_condition.clone(),
+            log_message: self.log_message.clone(),
+        }
+    }
+
+    pub fn is_logpoint(&self) -> bool {
+        self.log_message.is_some()
+    }
+
+    pub fn is_conditional(&self) -> bool {
+        self.condition.is_some() || self.hit_condition.is_some()
+    }
+}
+
+/// A breakpoint which stops when a function with the name is called
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct LapceFunctionBreakpoint {
+    pub name: String,
+    pub active: bool,
+    /// The ids the debug sessions gave the breakpoint
+    #[serde(skip)]
+    pub ids: HashMap<DapId, usize>,
+    pub verified: bool,
+    pub message: Option<String>,
+}
+
+impl LapceFunctionBreakpoint {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            active: true,
+            ids: HashMap::new(),
+            verified: false,
+            message: None,
+        }
+    }
+
+    pub fn function_breakpoint(&self) -> FunctionBreakpoint {
+        FunctionBreakpoint {
+            name: self.name.clone(),
+            condition: None,
+            hit_condition: None,
+        }
+    }
+}
+
+/// What the breakpoint editor sets on a breakpoint
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BreakpointEditKind {
+    Condition,
+    HitCount,
+    LogMessage,
+}
+
+impl BreakpointEditKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            BreakpointEditKind::Condition => "Expression",
+            BreakpointEditKind::HitCount => "Hit Count",
+            BreakpointEditKind::LogMessage => "Log Message",
+        }
+    }
+
+    pub fn placeholder(&self) -> &'static str {
+        match self {
+            BreakpointEditKind::Condition => {
+                "Break when the expression evaluates to true"
+            }
+            BreakpointEditKind::HitCount => {
+                "Break when the hit count condition is met"
+            }
+            BreakpointEditKind::LogMessage => {
+                "Message to log when the breakpoint is hit, with {expressions}"
+            }
+        }
+    }
+
+    fn value(self, breakpoint: &LapceBreakpoint) -> Option<&str> {
+        match self {
+            BreakpointEditKind::Condition => breakpoint.condition.as_deref(),
+            BreakpointEditKind::HitCount => breakpoint.hit_condition.as_deref(),
+            BreakpointEditKind::LogMessage => breakpoint.log_message.as_deref(),
+        }
+    }
+
+    fn value_mut(self, breakpoint: &mut LapceBreakpoint) -> &mut Option<String> {
+        match self {
+            BreakpointEditKind::Condition => &mut breakpoint.condition,
+            BreakpointEditKind::HitCount => &mut breakpoint.hit_condition,
+            BreakpointEditKind::LogMessage => &mut breakpoint.log_message,
+        }
+    }
+}
+
+/// The editor of the condition, hit count and log message of a breakpoint,
+/// shown below its line when opened from the gutter
+#[derive(Clone)]
+pub struct BreakpointEditData {
+    pub active: RwSignal<bool>,
+    pub editor: EditorData,
+    pub kind: RwSignal<BreakpointEditKind>,
+    /// The editor whose gutter the breakpoint editor was opened from
+    pub editor_id: RwSignal<Option<EditorId>>,
+    pub path: RwSignal<PathBuf>,
+    /// The breakpoint being edited, which is only put in the breakpoints of the
+    /// file when the edit is confirmed
+    pub breakpoint: RwSignal<LapceBreakpoint>,
+    pub layout_rect: RwSignal<Rect>,
+    pub debug: RunDebugData,
+    pub common: Rc<CommonData>,
+}
+
+impl KeyPressFocus for BreakpointEditData {
+    fn get_mode(&self) -> Mode {
+        Mode::Insert
+    }
+
+    fn check_condition(&self, condition: Condition) -> bool {
+        matches!(
+            condition,
+            Condition::BreakpointEditFocus | Condition::ModalFocus
+        )
+    }
+
+    fn run_command(
+        &self,
+        command: &LapceCommand,
+        count: Option<usize>,
+        mods: ModifiersState,
+    ) -> CommandExecuted {
+        match &command.kind {
+            CommandKind::Workbench(LapceWorkbenchCommand::ConfirmBreakpointEdit) => {
+                self.confirm();
+                CommandExecuted::Yes
+            }
+            CommandKind::Focus(FocusCommand::ModalClose) => {
+                self.cancel();
+                CommandExecuted::Yes
+            }
+            CommandKind::Edit(_)
+            | CommandKind::Move(_)
+            | CommandKind::MultiSelection(_) => {
+                self.editor.run_command(command, count, mods)
+            }
+            _ => CommandExecuted::No,
+        }
+    }
+
+    fn receive_char(&self, c: &str) {
+        self.editor.receive_char(c);
+    }
+}
+
+impl BreakpointEditData {
+    pub fn new(
+        cx: Scope,
+        editors: RwSignal<im::HashMap<EditorId, Rc<EditorData>>>,
+        debug: RunDebugData,
+        common: Rc<CommonData>,
+    ) -> Self {
+        Self {
+            active: cx.create_rw_signal(false),
+            editor: EditorData::new_local(cx, editors, common.clone()),
+            kind: cx.create_rw_signal(BreakpointEditKind::Condition),
+            editor_id: cx.create_rw_signal(None),
+            path: cx.create_rw_signal(PathBuf::new()),
+            breakpoint: cx.create_rw_signal(LapceBreakpoint::new(0, 0)),
+            layout_rect: cx.create_rw_signal(Rect::ZERO),
+            debug,
+            common,
+        }
+    }
+
+    /// Edit the breakpoint at the line, which is added if there's none
+    pub fn start(
+        &self,
+        editor_id: EditorId,
+        path: PathBuf,
+        line: usize,
+        offset: usize,
+        kind: BreakpointEditKind,
+    ) {
+        let breakpoint = self
+            .debug
+            .breakpoints
+            .with_untracked(|breakpoints| {
+                breakpoints
+                    .get(&path)
+                    .and_then(|breakpoints| breakpoints.get(&line))
+                    .cloned()
+            })
+            .unwrap_or_else(|| LapceBreakpoint::new(line, offset));
+        self.breakpoint.set(breakpoint);
+        self.editor_id.set(Some(editor_id));
+        self.path.set(path);
+        self.kind.set(kind);
+        self.load_value();
+        self.active.set(true);
+        self.common.focus.set(Focus::BreakpointEdit);
+    }
+
+    /// Switch to editing another value of the breakpoint, keeping what was typed
+    /// for the current one
+    pub fn set_kind(&self, kind: BreakpointEditKind) {
+        self.save_value();
+        self.kind.set(kind);
+        self.load_value();
+        self.common.focus.set(Focus::BreakpointEdit);
+    }
+
+    fn load_value(&self) {
+        let kind = self.kind.get_untracked();
+        let value = self.breakpoint.with_untracked(|breakpoint| {
+            kind.value(breakpoint).unwrap_or_default().to_string()
+        });
+        self.editor.doc().reload(Rope::from(&value), true);
+        self.editor
+            .cursor()
+            .update(|cursor| cursor.set_insert(Selection::caret(value.len())));
+    }
+
+    fn save_value(&self) {
+        let kind = self.kind.get_untracked();
+        let value = self
+            .editor
+            .doc()
+            .buffer
+            .with_untracked(|buffer| buffer.to_string());
+        let value = value.trim();
+        self.breakpoint.update(|breakpoint| {
+            *kind.value_mut(breakpoint) =
+                (!value.is_empty()).then(|| value.to_string());
+        });
+    }
+
+    fn cancel(&self) {
+        self.active.set(false);
+        if let Focus::BreakpointEdit = self.common.focus.get_untracked() {
+            self.common.focus.set(Focus::Workbench);
+        }
+    }
+
+    fn confirm(&self) {
+        self.save_value();
+        let path = self.path.get_untracked();
+        let mut breakpoint = self.breakpoint.get_untracked();
+        breakpoint.active = true;
+        self.debug.breakpoints.update(|breakpoints| {
+            breakpoints
+                .entry(path.clone())
+                .or_default()
+                .insert(breakpoint.line, breakpoint);
+        });
+        self.debug.sync_breakpoints(&path, &self.common.proxy);
+        self.cancel();
+    }
 }
 
 #[derive(Clone, PartialEq, Eq)]
@@ -180,6 +857,395 @@ pub struct DapVariable {
     pub children_expanded_count: usize,
 }
 
+/// An entry of the debug console
+#[derive(Clone)]
+pub enum DebugConsoleEntry {
+    /// What the program or the debug adapter printed, with the category of the
+    /// output, e.g. `stdout`, `stderr`, `console` or `important`
+    Output {
+        category: Option<String>,
+        output: String,
+    },
+    /// An expression typed in the console
+    Expression(String),
+    /// The result of an expression, whose structured values can be expanded,
+    /// with the scope its tree was created in
+    Result { scope: Scope, tree: VariableTree },
+    /// The error the debug adapter replied with when evaluating an expression
+    Error(String),
+}
+
+impl DebugConsoleEntry {
+    /// The number of lines the entry takes in the console
+    fn len(&self) -> usize {
+        match self {
+            DebugConsoleEntry::Result { tree, .. } => {
+                tree.root.with(|root| root.total_len())
+            }
+            _ => 1,
+        }
+    }
+}
+
+/// The entries of the debug console, of which only the latest are kept
+#[derive(Clone, Default)]
+pub struct DebugConsole {
+    /// The entries with their ids, which stay the same when the oldest entries
+    /// are dropped
+    entries: im::Vector<(usize, DebugConsoleEntry)>,
+    next_id: usize,
+}
+
+impl DebugConsole {
+    pub fn push(&mut self, entry: DebugConsoleEntry) {
+        self.entries.push_back((self.next_id, entry));
+        self.next_id += 1;
+        if self.entries.len() > MAX_CONSOLE_ENTRIES {
+            if let Some((_, DebugConsoleEntry::Result { scope, .. })) =
+                self.entries.pop_front()
+            {
+                scope.dispose();
+            }
+        }
+    }
+}
+
+/// A line of the debug console
+#[derive(Clone)]
+pub enum DebugConsoleLine {
+    /// An output, expression or error, which takes a single line
+    Entry { id: usize, entry: DebugConsoleEntry },
+    /// A node of the tree of an expression result, at `index` in the tree
+    Variable {
+        id: usize,
+        index: usize,
+        tree: VariableTree,
+        node: DapVariableViewdata,
+    },
+}
+
+impl VirtualVector<DebugConsoleLine> for DebugConsole {
+    fn total_len(&self) -> usize {
+        self.entries.iter().map(|(_, entry)| entry.len()).sum()
+    }
+
+    fn slice(
+        &mut self,
+        range: std::ops::Range<usize>,
+    ) -> impl Iterator<Item = DebugConsoleLine> {
+        let mut lines = Vec::new();
+        let mut start = 0;
+        for (id, entry) in self.entries.iter() {
+            if start >= range.end {
+                break;
+            }
+            let len = entry.len();
+            if start + len > range.start {
+                let skip = range.start.saturating_sub(start);
+                let take = (range.end - start).min(len) - skip;
+                match entry {
+                    DebugConsoleEntry::Result { tree, .. } => {
+                        let mut root = tree.root.get();
+                        let nodes = root.slice(0..len).enumerate().skip(skip);
+                        lines.extend(nodes.take(take).map(|(index, node)| {
+                            DebugConsoleLine::Variable {
+                                id: *id,
+                                index,
+                                tree: *tree,
+                                node,
+                            }
+                        }));
+                    }
+                    _ => lines.push(DebugConsoleLine::Entry {
+                        id: *id,
+                        entry: entry.clone(),
+                    }),
+                }
+            }
+            start += len;
+        }
+        lines.into_iter()
+    }
+}
+
+/// A tree of variables which is expanded on its own, like the result of an
+/// expression
+#[derive(Clone, Copy)]
+pub struct VariableTree {
+    /// Bumped whenever the tree changes
+    pub id: RwSignal<usize>,
+    pub root: RwSignal<DapVariable>,
+}
+
+impl VariableTree {
+    pub fn new(cx: Scope, root: DapVariable) -> Self {
+        Self {
+            id: cx.create_rw_signal(0),
+            root: cx.create_rw_signal(root),
+        }
+    }
+}
+
+/// The value of a watch expression in a debug session
+#[derive(Clone)]
+pub enum WatchValue {
+    /// The result of the expression, whose structured values can be expanded,
+    /// with the scope its tree was created in
+    Result { scope: Scope, tree: VariableTree },
+    /// The error the debug adapter replied with when evaluating the expression
+    Error(String),
+}
+
+impl WatchValue {
+    /// Dispose the tree of the result, once the value was replaced or removed
+    pub fn dispose(&self) {
+        if let WatchValue::Result { scope, .. } = self {
+            scope.dispose();
+        }
+    }
+}
+
+/// The inputs of the debug panel, which take the keyboard when the panel is
+/// focused
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DebugInput {
+    /// The input of the debug console
+    Console,
+    /// The input adding a watch expression
+    Watch,
+    /// The inline editor of the value of a variable
+    Variable,
+    /// The input adding a function breakpoint
+    FunctionBreakpoint,
+}
+
+/// The variable whose value is being edited in the variables tree
+#[derive(Clone, PartialEq, Eq)]
+pub struct VariableEdit {
+    pub dap_id: DapId,
+    pub parent: Vec<usize>,
+    pub name: String,
+    pub evaluate_name: Option<String>,
+}
+
+/// The inputs of the debug panel: the debug console, the watch expressions,
+/// the values of variables and the function breakpoints
+#[derive(Clone)]
+pub struct DebugPanelData {
+    pub input: RwSignal<DebugInput>,
+    pub console_editor: EditorData,
+    pub watch_editor: EditorData,
+    pub variable_editor: EditorData,
+    pub function_breakpoint_editor: EditorData,
+    pub variable_edit: RwSignal<Option<VariableEdit>>,
+    pub terminal: TerminalPanelData,
+}
+
+impl KeyPressFocus for DebugPanelData {
+    fn get_mode(&self) -> Mode {
+        Mode::Insert
+    }
+
+    fn check_condition(&self, condition: Condition) -> bool {
+        match condition {
+            Condition::PanelFocus => true,
+            Condition::DebugConsoleFocus => {
+                self.input.get_untracked() == DebugInput::Console
+            }
+            Condition::ModalFocus => {
+                self.input.get_untracked() == DebugInput::Variable
+            }
+            _ => false,
+        }
+    }
+
+    fn run_command(
+        &self,
+        command: &LapceCommand,
+        count: Option<usize>,
+        mods: ModifiersState,
+    ) -> CommandExecuted {
+        let input = self.input.get_untracked();
+        match &command.kind {
+            CommandKind::Workbench(LapceWorkbenchCommand::DebugConsoleEvaluate) => {
+                self.evaluate();
+                CommandExecuted::Yes
+            }
+            CommandKind::Focus(FocusCommand::ModalClose)
+                if input == DebugInput::Variable =>
+            {
+                self.cancel_variable_edit();
+                CommandExecuted::Yes
+            }
+            CommandKind::Edit(EditCommand::InsertNewLine)
+                if input != DebugInput::Console =>
+            {
+                match input {
+                    DebugInput::Watch => self.add_watch(),
+                    DebugInput::Variable => self.confirm_variable_edit(),
+                    DebugInput::FunctionBreakpoint => self.add_function_breakpoint(),
+                    DebugInput::Console => {}
+                }
+                CommandExecuted::Yes
+            }
+            CommandKind::Edit(_)
+            | CommandKind::Move(_)
+            | CommandKind::MultiSelection(_) => {
+                self.editor().run_command(command, count, mods)
+            }
+            _ => CommandExecuted::No,
+        }
+    }
+
+    fn receive_char(&self, c: &str) {
+        self.editor().receive_char(c);
+    }
+}
+
+impl DebugPanelData {
+    pub fn new(
+        cx: Scope,
+        editors: RwSignal<im::HashMap<EditorId, Rc<EditorData>>>,
+        terminal: TerminalPanelData,
+        common: Rc<CommonData>,
+    ) -> Self {
+        Self {
+            input: cx.create_rw_signal(DebugInput::Console),
+            console_editor: EditorData::new_local(cx, editors, common.clone()),
+            watch_editor: EditorData::new_local(cx, editors, common.clone()),
+            variable_editor: EditorData::new_local(cx, editors, common.clone()),
+            function_breakpoint_editor: EditorData::new_local(cx, editors, common),
+            variable_edit: cx.create_rw_signal(None),
+            terminal,
+        }
+    }
+
+    fn editor(&self) -> &EditorData {
+        match self.input.get_untracked() {
+            DebugInput::Console => &self.console_editor,
+            DebugInput::Watch => &self.watch_editor,
+            DebugInput::Variable => &self.variable_editor,
+            DebugInput::FunctionBreakpoint => &self.function_breakpoint_editor,
+        }
+    }
+
+    /// Give the keyboard to one of the inputs, which stops editing a variable
+    /// when it's another one
+    pub fn focus_input(&self, input: DebugInput) {
+        if input != DebugInput::Variable {
+            self.variable_edit.set(None);
+        }
+        self.input.set(input);
+        self.terminal
+            .common
+            .focus
+            .set(Focus::Panel(PanelKind::Debug));
+    }
+
+    /// Send the expression typed in the console to the active debug session,
+    /// leaving the input empty
+    pub fn evaluate(&self) {
+        let Some(dap) = self.terminal.get_active_dap(false) else {
+            return;
+        };
+        let expression = self
+            .console_editor
+            .doc()
+            .buffer
+            .with_untracked(|buffer| buffer.to_string());
+        let expression = expression.trim();
+        if expression.is_empty() {
+            return;
+        }
+        dap.evaluate(expression.to_string());
+        self.console_editor.reset();
+    }
+
+    /// Add the expression typed in the watch input to the watch expressions,
+    /// leaving the input empty
+    pub fn add_watch(&self) {
+        let expression = self
+            .watch_editor
+            .doc()
+            .buffer
+            .with_untracked(|buffer| buffer.to_string());
+        let expression = expression.trim();
+        if expression.is_empty() {
+            return;
+        }
+        self.terminal.debug.add_watch(expression.to_string());
+        self.watch_editor.reset();
+    }
+
+    /// Add a breakpoint on the function typed in the input, leaving the input
+    /// empty
+    pub fn add_function_breakpoint(&self) {
+        let name = self
+            .function_breakpoint_editor
+            .doc()
+            .buffer
+            .with_untracked(|buffer| buffer.to_string());
+        let name = name.trim();
+        if name.is_empty() {
+            return;
+        }
+        self.terminal
+            .debug
+            .add_function_breakpoint(name.to_string(), &self.terminal.common.proxy);
+        self.function_breakpoint_editor.reset();
+    }
+
+    /// Edit the value of the variable inline, if the debug adapter can change it
+    pub fn start_variable_edit(
+        &self,
+        dap: &DapData,
+        parent: Vec<usize>,
+        var: &Variable,
+    ) {
+        if !dap.can_set_variable(&parent, var) {
+            return;
+        }
+        self.variable_edit.set(Some(VariableEdit {
+            dap_id: dap.dap_id,
+            parent,
+            name: var.name.clone(),
+            evaluate_name: var.evaluate_name.clone(),
+        }));
+        self.variable_editor
+            .doc()
+            .reload(Rope::from(&var.value), true);
+        self.variable_editor.cursor().update(|cursor| {
+            cursor.set_insert(Selection::region(0, var.value.len()))
+        });
+        self.focus_input(DebugInput::Variable);
+    }
+
+    fn cancel_variable_edit(&self) {
+        self.variable_edit.set(None);
+        self.input.set(DebugInput::Console);
+    }
+
+    fn confirm_variable_edit(&self) {
+        let Some(edit) = self.variable_edit.get_untracked() else {
+            return;
+        };
+        let value = self
+            .variable_editor
+            .doc()
+            .buffer
+            .with_untracked(|buffer| buffer.to_string());
+        let dap = self
+            .terminal
+            .debug
+            .daps
+            .with_untracked(|daps| daps.get(&edit.dap_id).cloned());
+        if let Some(dap) = dap {
+            dap.set_variable(edit, value, self.terminal.debug.watches);
+        }
+        self.cancel_variable_edit();
+    }
+}
+
 #[derive(Clone)]
 pub struct DapData {
     pub term_id: TermId,
@@ -187,8 +1253,14 @@ pub struct DapData {
     pub stopped: RwSignal<bool>,
     pub thread_id: RwSignal<Option<ThreadId>>,
     pub stack_traces: RwSignal<BTreeMap<ThreadId, StackTraceData>>,
+    /// The stack frame the variables are shown for and expressions are
+    /// evaluated in
+    pub frame_id: RwSignal<Option<usize>>,
     pub variables_id: RwSignal<usize>,
     pub variables: RwSignal<DapVariable>,
+    pub console: RwSignal<DebugConsole>,
+    pub watch_values: RwSignal<im::HashMap<String, WatchValue>>,
+    pub capabilities: RwSignal<Option<DebuggerCapabilities>>,
     pub breakline: Memo<Option<(usize, PathBuf)>>,
     pub common: Rc<CommonData>,
 }
@@ -233,6 +1305,7 @@ pub fn new(
             stopped,
             thread_id,
             stack_traces,
+            frame_id: cx.create_rw_signal(None),
             variables_id: cx.create_rw_signal(0),
             variables: cx.create_rw_signal(DapVariable {
                 item: ScopeOrVar::Scope(dap_types::Scope::default()),
@@ -242,6 +1315,9 @@ pub fn new(
                 children: Vec::new(),
                 children_expanded_count: 0,
             }),
+            console: cx.create_rw_signal(DebugConsole::default()),
+            watch_values: cx.create_rw_signal(im::HashMap::new()),
+            capabilities: cx.create_rw_signal(None),
             breakline,
             common,
         }
@@ -265,6 +1341,7 @@ pub fn stopped(
         for (thread_id, frames) in stack_traces {
             let is_main_thread = main_thread_id.as_ref() == Some(thread_id);
             if is_main_thread {
+                self.frame_id.set(frames.first().map(|frame| frame.id));
                 if let Some(frame) = frames.first() {
                     if let Some(path) =
                         frame.source.as_ref().and_then(|source| source.path.clone())
@@ -334,10 +1411,25 @@ pub fn stopped(
     }
 
     pub fn toggle_expand(&self, parent: Vec<usize>, reference: usize) {
-        self.variables_id.update(|id| {
+        let tree = VariableTree {
+            id: self.variables_id,
+            root: self.variables,
+        };
+        self.toggle_var_expand(tree, parent, reference);
+    }
+
+    /// Expand or collapse a variable of the tree, reading its children from the
+    /// debug adapter the first time
+    pub fn toggle_var_expand(
+        &self,
+        tree: VariableTree,
+        parent: Vec<usize>,
+        reference: usize,
+    ) {
+        tree.id.update(|id| {
             *id += 1;
         });
-        self.variables.update(|variables| {
+        tree.root.update(|variables| {
             if let Some(var) = variables.get_var_mut(&parent, reference) {
                 if var.expanded {
                     var.expanded = false;
@@ -346,7 +1438,7 @@ pub fn toggle_expand(&self, parent: Vec<usize>, reference: usize) {
                     var.expanded = true;
                     if !var.read {
                         var.read = true;
-                        self.read_var_children(&parent, reference);
+                        self.read_var_children(tree, &parent, reference);
                     } else {
                         variables.update_count_recursive(&parent, reference);
                     }
@@ -355,17 +1447,20 @@ pub fn toggle_expand(&self, parent: Vec<usize>, reference: usize) {
         });
     }
 
-    fn read_var_children(&self, parent: &[usize], reference: usize) {
-        let root = self.variables;
+    fn read_var_children(
+        &self,
+        tree: VariableTree,
+        parent: &[usize],
+        reference: usize,
+    ) {
         let parent = parent.to_vec();
-        let variables_id = self.variables_id;
 
         let send = create_ext_action(self.common.scope, move |result| {
             if let Ok(ProxyResponse::DapVariableResponse { varialbes }) = result {
-                variables_id.update(|id| {
+                tree.id.update(|id| {
                     *id += 1;
                 });
-                root.update(|root| {
+                tree.root.update(|root| {
                     if let Some(var) = root.get_var_mut(&parent, reference) {
                         let mut new_parent = parent.clone();
                         new_parent.push(reference);
@@ -392,8 +1487,232 @@ fn read_var_children(&self, parent: &[usize], reference: usize) {
                 send(result);
             });
     }
+
+    pub fn output(&self, output: &Output) {
+        // Telemetry isn't meant to be shown to the user
+        if output.category.as_deref() == Some("telemetry") {
+            return;
+        }
+        let text = output.output.trim_end_matches(['\r', '\n']);
+        self.console.update(|console| {
+            for line in text.split('\n') {
+                console.push(DebugConsoleEntry::Output {
+                    category: output.category.clone(),
+                    output: line.trim_end_matches('\r').to_string(),
+                });
+            }
+        });
+    }
+
+    /// Evaluate the expression in the selected stack frame and print the
+    /// result in the debug console
+    pub fn evaluate(&self, expression: String) {
+        self.console.update(|console| {
+            console.push(DebugConsoleEntry::Expression(expression.clone()));
+        });
+
+        let console = self.console;
+        let cx = self.common.scope;
+        let send = create_ext_action(self.common.scope, move |result| {
+            let entry = match result {
+                Ok(ProxyResponse::DapEvaluateResponse { result }) => {
+                    // The tree goes away with the entry, when the console drops
+                    // the oldest entries
+                    let scope = cx.create_child();
+                    let tree = VariableTree::new(
+                        scope,
+                        DapVariable::evaluate_result(String::new(), result),
+                    );
+                    DebugConsoleEntry::Result { scope, tree }
+                }
+                Err(err) => DebugConsoleEntry::Error(err.message),
+                Ok(_) => return,
+            };
+            console.update(|console| {
+                console.push(entry);
+            });
+        });
+        let frame_id = if self.stopped.get_untracked() {
+            self.frame_id.get_untracked()
+        } else {
+            None
+        };
+        self.common.proxy.dap_evaluate(
+            self.dap_id,
+            expression,
+            frame_id,
+            Some("repl".to_string()),
+            move |result| {
+                send(result);
+            },
+        );
+    }
+
+    /// Evaluate the watch expressions in the selected stack frame, forgetting
+    /// the values of the ones that were removed
+    pub fn evaluate_watches(&self, watches: &im::Vector<String>) {
+        let mut removed = Vec::new();
+        self.watch_values.update(|values| {
+            values.retain(|expression, value| {
+                let keep = watches.contains(expression);
+                if !keep {
+                    removed.push(value.clone());
+                }
+                keep
+            });
+        });
+        for value in removed {
+            value.dispose();
+        }
+        for expression in watches.iter() {
+            self.evaluate_watch(expression.clone());
+        }
+    }
+
+    /// Evaluate a watch expression, which is only possible while stopped
+    pub fn evaluate_watch(&self, expression: String) {
+        if !self.stopped.get_untracked() {
+            return;
+        }
+
+        let watch_values = self.watch_values;
+        let cx = self.common.scope;
+        let name = expression.clone();
+        let send = create_ext_action(self.common.scope, move |result| {
+            let value = match result {
+                Ok(ProxyResponse::DapEvaluateResponse { result }) => {
+                    let var = DapVariable::evaluate_result(name.clone(), result);
+                    let current = watch_values
+                        .with_untracked(|values| values.get(&name).cloned());
+                    // Keep the signal of the previous result so that only the
+                    // tree is updated
+                    if let Some(WatchValue::Result { tree, .. }) = current {
+                        tree.id.update(|id| {
+                            *id += 1;
+                        });
+                        tree.root.set(var);
+                        return;
+                    }
+                    let scope = cx.create_child();
+                    let tree = VariableTree::new(scope, var);
+                    WatchValue::Result { scope, tree }
+                }
+                Err(err) => WatchValue::Error(err.message),
+                Ok(_) => return,
+            };
+            let previous = watch_values
+                .try_update(|values| values.insert(name, value))
+                .flatten();
+            if let Some(previous) = previous {
+                previous.dispose();
+            }
+        });
+        self.common.proxy.dap_evaluate(
+            self.dap_id,
+            expression,
+            self.frame_id.get_untracked(),
+            Some("watch".to_string()),
+            move |result| {
+                send(result);
+            },
+        );
+    }
+
+    /// Whether the value of the variable can be changed, with `setVariable` on
+    /// the variable that contains it or with `setExpression` on its evaluate name
+    pub fn can_set_variable(&self, parent: &[usize], var: &Variable) -> bool {
+        self.capabilities.with_untracked(|capabilities| {
+            let Some(capabilities) = capabilities.as_ref() else {
+                return false;
+            };
+            (capabilities.supports_set_variable.unwrap_or(false)
+                && !parent.is_empty())
+                || (capabilities.supports_set_expression.unwrap_or(false)
+                    && var.evaluate_name.is_some())
+        })
+    }
+
+    /// Change the value of a variable, updating it in the variables tree and
+    /// evaluating the watch expressions again since they may depend on it
+    pub fn set_variable(
+        &self,
+        edit: VariableEdit,
+        value: String,
+        watches: RwSignal<im::Vector<String>>,
+    ) {
+        let (supports_set_variable, supports_set_expression) =
+            self.capabilities.with_untracked(|capabilities| {
+                capabilities
+                    .as_ref()
+                    .map(|c| {
+                        (
+                            c.supports_set_variable.unwrap_or(false),
+                            c.supports_set_expression.unwrap_or(false),
+                        )
+                    })
+                    .unwrap_or_default()
+            });
+
+        let dap = self.clone();
+        let parent = edit.parent.clone();
+        let name = edit.name.clone();
+        let send = create_ext_action(self.common.scope, move |result| {
+            let (value, ty, reference) = match result {
+                Ok(ProxyResponse::DapSetVariableResponse { result }) => (
+                    result.value,
+                    result.ty,
+                    result.variables_reference.unwrap_or(0),
+                ),
+                Ok(ProxyResponse::DapSetExpressionResponse { result }) => (
+                    result.value,
+                    result.ty,
+                    result.variables_reference.unwrap_or(0),
+                ),
+                Err(err) => {
+                    dap.console.update(|console| {
+                        console.push(DebugConsoleEntry::Error(err.message));
+                    });
+                    return;
+                }
+                Ok(_) => return,
+            };
+            dap.variables_id.update(|id| {
+                *id += 1;
+            });
+            dap.variables.update(|variables| {
+                variables.update_value(&parent, &name, value, ty, reference);
+            });
+            dap.evaluate_watches(&watches.get_untracked());
+        });
+
+        let container = edit.parent.last().copied();
+        if let Some(reference) = container.filter(|_| supports_set_variable) {
+            self.common.proxy.dap_set_variable(
+                self.dap_id,
+                reference,
+                edit.name,
+                value,
+                move |result| {
+                    send(result);
+                },
+            );
+        } else if let Some(expression) =
+            edit.evaluate_name.filter(|_| supports_set_expression)
+        {
+            self.common.proxy.dap_set_expression(
+                self.dap_id,
+                expression,
+                value,
+                self.frame_id.get_untracked(),
+                move |result| {
+                    send(result);
+                },
+            );
+        }
+    }
 }
 
+#[derive(Clone)]
 pub struct DapVariableViewdata {
     pub item: ScopeOrVar,
     pub parent: Vec<usize>,
@@ -426,6 +1745,36 @@ fn slice(
 }
 
 impl DapVariable {
+    /// The root of the tree of an evaluation result, which has the result as its
+    /// only child so that it can be expanded like a variable
+    fn evaluate_result(name: String, result: EvaluateResponse) -> Self {
+        DapVariable {
+            item: ScopeOrVar::Scope(dap_types::Scope::default()),
+            parent: Vec::new(),
+            expanded: true,
+            read: true,
+            children: vec![DapVariable {
+                item: ScopeOrVar::Var(Variable {
+                    name,
+                    value: result.result,
+                    ty: result.ty,
+                    presentation_hint: result.presentation_hint,
+                    evaluate_name: None,
+                    variables_reference: result.variables_reference,
+                    named_variables: result.named_variables,
+                    indexed_variables: result.indexed_variables,
+                    memory_reference: result.memory_reference,
+                }),
+                parent: Vec::new(),
+                expanded: false,
+                read: false,
+                children: Vec::new(),
+                children_expanded_count: 0,
+            }],
+            children_expanded_count: 1,
+        }
+    }
+
     pub fn append_view_slice(
         &self,
         view_items: &mut Vec<DapVariableViewdata>,
@@ -482,6 +1831,43 @@ pub fn get_var_mut(
             .find(|c| c.item.reference() == reference)
     }
 
+    /// Replace the value of the variable named `name` under `parent`, dropping
+    /// the children that were read for its old value
+    pub fn update_value(
+        &mut self,
+        parent: &[usize],
+        name: &str,
+        value: String,
+        ty: Option<String>,
+        variables_reference: usize,
+    ) -> Option<()> {
+        let container = parent.iter().try_fold(&mut *self, |item, parent| {
+            item.children
+                .iter_mut()
+                .find(|c| c.item.reference() == *parent)
+        })?;
+        let var = container
+            .children
+            .iter_mut()
+            .find(|c| c.item.name() == name)?;
+        if let ScopeOrVar::Var(item) = &mut var.item {
+            item.value = value;
+            if ty.is_some() {
+                item.ty = ty;
+            }
+            item.variables_reference = variables_reference;
+        }
+        var.expanded = false;
+        var.read = false;
+        var.children.clear();
+        var.children_expanded_count = 0;
+
+        if let Some((reference, parent)) = parent.split_last() {
+            self.update_count_recursive(parent, *reference);
+        }
+        Some(())
+    }
+
     pub fn update_count_recursive(&mut self, parent: &[usize], reference: usize) {
         let mut parent = parent.to_vec();
         self.update_count(&parent, reference);
@@ -515,9 +1901,18 @@ pub fn update_count(
 
 #[cfg(test)]
 mod tests {
-    use lapce_rpc::dap_types::{Scope, Variable};
+    use floem::views::VirtualVector;
+    use std::collections::{BTreeMap, HashMap};
+
+    use lapce_rpc::dap_types::{
+        Breakpoint, DapId, EvaluateResponse, ExceptionBreakpointsFilter, Scope,
+        Variable,
+    };
 
-    use super::{DapVariable, ScopeOrVar};
+    use super::{
+        runnable_debug_config, runnable_run_config, update_changed_breakpoint,
+        DapVariable, LapceBreakpoint, RunDebugConfigs, ScopeOrVar,
+    };
 
     #[test]
     fn test_update_count() {
@@ -637,4 +2032,344 @@ fn test_update_count() {
         assert_eq!(var.children_expanded_count, 4);
         assert_eq!(root.children_expanded_count, 11);
     }
+
+    #[test]
+    fn test_evaluate_result() {
+        let mut root = DapVariable::evaluate_result(
+            String::new(),
+            EvaluateResponse {
+                result: "Foo { a: 1, b: 2 }".to_string(),
+                ty: Some("Foo".to_string()),
+                presentation_hint: None,
+                variables_reference: 5,
+                named_variables: None,
+                indexed_variables: None,
+                memory_reference: None,
+            },
+        );
+        assert_eq!(root.total_len(), 1);
+        let items = root.slice(0..1).collect::<Vec<_>>();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].item.value(), Some("Foo { a: 1, b: 2 }"));
+        assert_eq!(items[0].item.reference(), 5);
+
+        let var = root.get_var_mut(&[], 5).unwrap();
+        var.expanded = true;
+        var.read = true;
+        var.children = ["a", "b"]
+            .iter()
+            .enumerate()
+            .map(|(i, name)| DapVariable {
+                item: ScopeOrVar::Var(Variable {
+                    name: name.to_string(),
+                    variables_reference: 6 + i,
+                    ..Default::default()
+                }),
+                parent: vec![5],
+                expanded: false,
+                read: false,
+                children: Vec::new(),
+                children_expanded_count: 0,
+            })
+            .collect();
+        root.update_count_recursive(&[], 5);
+        assert_eq!(root.total_len(), 3);
+        let items = root.slice(0..3).collect::<Vec<_>>();
+        assert_eq!(items[1].item.name(), "a");
+        assert_eq!(items[2].level, 1);
+    }
+
+    #[test]
+    fn test_update_value() {
+        let mut root = DapVariable::evaluate_result(
+            "foo".to_string(),
+            EvaluateResponse {
+                result: "Foo { a: 1 }".to_string(),
+                ty: Some("Foo".to_string()),
+                presentation_hint: None,
+                variables_reference: 5,
+                named_variables: None,
+                indexed_variables: None,
+                memory_reference: None,
+            },
+        );
+        let var = root.get_var_mut(&[], 5).unwrap();
+        var.expanded = true;
+        var.read = true;
+        var.children = vec![DapVariable {
+            item: ScopeOrVar::Var(Variable {
+                name: "a".to_string(),
+                value: "1".to_string(),
+                ..Default::default()
+            }),
+            parent: vec![5],
+            expanded: false,
+            read: false,
+            children: Vec::new(),
+            children_expanded_count: 0,
+        }];
+        root.update_count_recursive(&[], 5);
+        assert_eq!(root.total_len(), 2);
+
+        root.update_value(&[5], "a", "2".to_string(), None, 0)
+            .unwrap();
+        let items = root.slice(0..2).collect::<Vec<_>>();
+        assert_eq!(items[1].item.value(), Some("2"));
+        assert_eq!(root.total_len(), 2);
+
+        // A new value drops the children read for the old one
+        root.update_value(&[], "foo", "Foo { a: 3 }".to_string(), None, 7)
+            .unwrap();
+        assert_eq!(root.total_len(), 1);
+        let items = root.slice(0..1).collect::<Vec<_>>();
+        assert_eq!(items[0].item.value(), Some("Foo { a: 3 }"));
+        assert_eq!(items[0].item.ty(), Some("Foo"));
+        assert_eq!(items[0].item.reference(), 7);
+        assert!(!items[0].expanded);
+
+        assert!(root
+            .update_value(&[], "bar", String::new(), None, 0)
+            .is_none());
+    }
+
+    #[test]
+    fn test_source_breakpoint() {
+        // Breakpoints saved before they had conditions are still loaded
+        let breakpoint: LapceBreakpoint =
+            serde_json::from_value(serde_json::json!({
+                "id": null,
+                "verified": false,
+                "message": null,
+                "line": 9,
+                "offset": 120,
+                "dap_line": null,
+                "active": true,
+            }))
+            .unwrap();
+        assert!(!breakpoint.is_conditional());
+        assert!(!breakpoint.is_logpoint());
+        let source_breakpoint = breakpoint.source_breakpoint();
+        assert_eq!(source_breakpoint.line, 10);
+        assert_eq!(source_breakpoint.condition, None);
+
+        let breakpoint = LapceBreakpoint {
+            hit_condition: Some(">= 3".to_string()),
+            log_message: Some("x = {x}".to_string()),
+            ..LapceBreakpoint::new(9, 120)
+        };
+        assert!(breakpoint.is_conditional());
+        assert!(breakpoint.is_logpoint());
+        let source_breakpoint = breakpoint.source_breakpoint();
+        assert_eq!(source_breakpoint.hit_condition.as_deref(), Some(">= 3"));
+        assert_eq!(source_breakpoint.log_message.as_deref(), Some("x = {x}"));
+    }
+
+    #[test]
+    fn test_runnable_run_config() {
+        let runnable = serde_json::json!({
+            "label": "test tests::it_works",
+            "kind": "cargo",
+            "args": {
+                "workspaceRoot": "/ws",
+                "cargoArgs": ["test", "--package", "foo", "--lib"],
+                "cargoExtraArgs": [],
+                "executableArgs": ["tests::it_works", "--exact", "--nocapture"],
+            },
+        });
+        let config = runnable_run_config(runnable).unwrap();
+        assert_eq!(config.name, "test tests::it_works");
+        assert_eq!(config.program, "cargo");
+        assert_eq!(
+            config.args.unwrap(),
+            vec![
+                "test",
+                "--package",
+                "foo",
+                "--lib",
+                "--",
+                "tests::it_works",
+                "--exact",
+                "--nocapture"
+            ]
+        );
+        assert_eq!(config.cwd.as_deref(), Some("/ws"));
+
+        let runnable = serde_json::json!({
+            "label": "run foo",
+            "kind": "cargo",
+            "args": {
+                "workspaceRoot": "/ws",
+                "cwd": "/ws/foo",
+                "cargoArgs": ["run", "--package", "foo", "--bin", "foo"],
+            },
+        });
+        let config = runnable_run_config(runnable).unwrap();
+        assert_eq!(
+            config.args.unwrap(),
+            vec!["run", "--package", "foo", "--bin", "foo"]
+        );
+        assert_eq!(config.cwd.as_deref(), Some("/ws/foo"));
+
+        let runnable = serde_json::json!({
+            "label": "run",
+            "kind": "shell",
+            "args": { "cargoArgs": [] },
+        });
+        assert!(runnable_run_config(runnable).is_none());
+    }
+
+    #[test]
+    fn test_runnable_debug_config() {
+        let runnable = serde_json::json!({
+            "label": "test tests::it_works",
+            "kind": "cargo",
+            "args": {
+                "workspaceRoot": "/ws",
+                "cargoArgs": ["test", "--package", "foo", "--lib"],
+                "executableArgs": ["tests::it_works", "--exact", "--nocapture"],
+            },
+        });
+        let (cargo_args, config) = runnable_debug_config(runnable).unwrap();
+        assert_eq!(cargo_args, vec!["test", "--package", "foo", "--lib"]);
+        assert_eq!(config.ty.as_deref(), Some("lldb"));
+        assert_eq!(
+            config.args.unwrap(),
+            vec!["tests::it_works", "--exact", "--nocapture"]
+        );
+        assert_eq!(config.cwd.as_deref(), Some("/ws"));
+    }
+
+    #[test]
+    fn test_attach_run_configs() {
+        let configs: RunDebugConfigs = toml::from_str(
+            r#"
+            [[configs]]
+            name = "run"
+            program = "cargo"
+
+            [[configs]]
+            name = "attach"
+            type = "lldb"
+            request = "attach"
+
+            [[configs]]
+            name = "remote"
+            type = "debugpy"
+            request = "attach"
+            host = "10.0.0.2"
+            port = 5678
+            "#,
+        )
+        .unwrap();
+        let [run, attach, remote] = &configs.configs[..] else {
+            panic!("expected three configs");
+        };
+
+        assert!(!run.is_attach());
+        assert!(!run.needs_process());
+
+        assert!(attach.is_attach());
+        assert!(attach.program.is_empty());
+        assert!(attach.needs_process());
+        let mut attach = attach.clone();
+        attach.pid = Some(42);
+        assert!(!attach.needs_process());
+        assert_eq!(attach.target(), "pid 42");
+
+        assert!(!remote.needs_process());
+        assert_eq!(remote.target(), "10.0.0.2:5678");
+    }
+
+    #[test]
+    fn test_update_changed_breakpoint() {
+        let dap_id = DapId(1);
+        let other_dap_id = DapId(2);
+        // The offset of a line when every line has 10 characters
+        let offset_of_line = |line: usize| Some(line * 10);
+        let mut breakpoints = BTreeMap::new();
+        for line in [3, 10] {
+            let mut breakpoint = LapceBreakpoint::new(line, line * 10);
+            breakpoint.ids.insert(dap_id, line);
+            breakpoint.ids.insert(other_dap_id, line + 1);
+            breakpoints.insert(line, breakpoint);
+        }
+        let changed: Breakpoint = serde_json::from_value(serde_json::json!({
+            "id": 3,
+            "verified": true,
+            "line": 6,
+        }))
+        .unwrap();
+
+        // The id belongs to the breakpoint on line 3 in this session only
+        assert!(!update_changed_breakpoint(
+            &mut breakpoints,
+            DapId(3),
+            false,
+            &changed,
+            offset_of_line
+        ));
+
+        // Verified and moved to the line the adapter put it on
+        assert!(update_changed_breakpoint(
+            &mut breakpoints,
+            dap_id,
+            false,
+            &changed,
+            offset_of_line
+        ));
+        assert_eq!(breakpoints.keys().copied().collect::<Vec<_>>(), [5, 10]);
+        assert!(breakpoints[&5].verified);
+        assert_eq!(breakpoints[&5].line, 5);
+        assert_eq!(breakpoints[&5].offset, 50);
+        assert!(!breakpoints[&10].verified);
+
+        // Not moved onto another breakpoint
+        let mut changed = changed;
+        changed.line = Some(11);
+        assert!(update_changed_breakpoint(
+            &mut breakpoints,
+            dap_id,
+            false,
+            &changed,
+            offset_of_line
+        ));
+        assert_eq!(breakpoints.keys().copied().collect::<Vec<_>>(), [5, 10]);
+        assert_eq!(breakpoints[&5].offset, 50);
+
+        assert!(update_changed_breakpoint(
+            &mut breakpoints,
+            dap_id,
+            true,
+            &changed,
+            offset_of_line
+        ));
+        assert!(!breakpoints[&5].verified);
+        assert_eq!(breakpoints[&5].ids.get(&dap_id), None);
+        assert_eq!(breakpoints[&5].ids.get(&other_dap_id), Some(&4));
+
+        assert!(!update_changed_breakpoint(
+            &mut breakpoints,
+            dap_id,
+            false,
+            &changed,
+            offset_of_line
+        ));
+    }
+
+    #[test]
+    fn test_exception_filter_enabled() {
+        let filter: ExceptionBreakpointsFilter =
+            serde_json::from_value(serde_json::json!({
+                "filter": "raised",
+                "label": "Raised Exceptions",
+                "default": true,
+            }))
+            .unwrap();
+        let mut choices = HashMap::new();
+        assert!(filter.enabled(&choices));
+        choices.insert("uncaught".to_string(), false);
+        assert!(filter.enabled(&choices));
+        choices.insert("raised".to_string(), false);
+        assert!(!filter.enabled(&choices));
+    }
 }
@@ -44,6 +44,7 @@
     editor::{Action, EditConf, EditType},
     indent::IndentStyle,
     language::LapceLanguage,
+    lens::{CodeLensId, CodeLenses},
     line_ending::LineEnding,
     mode::MotionMode,
     register::Register,
@@ -56,7 +57,8 @@
 use lapce_rpc::{
     buffer::BufferId,
     plugin::PluginId,
-    proxy::ProxyResponse,
+    proxy::{ProxyCallback, ProxyResponse, ProxyRpcHandler},
+    source_control::BlameHunk,
     style::{LineStyle, LineStyles, Style},
 };
 use lapce_xi_rope::{
@@ -64,7 +66,8 @@
     Interval, Rope, RopeDelta, Transformer,
 };
 use lsp_types::{
-    CodeActionResponse, Diagnostic, DiagnosticSeverity, InlayHint, InlayHintLabel,
+    CodeActionResponse, CodeLens, Diagnostic, DiagnosticSeverity, InlayHint,
+    InlayHintLabel,
 };
 use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
@@ -74,8 +77,10 @@
     config::{color::LapceColor, editor::WrapStyle, LapceConfig},
     editor::{compute_screen_lines, EditorData},
     find::{Find, FindProgress, FindResult},
+    folding::{Folding, FoldingRange},
     history::DocumentHistory,
     keypress::KeyPressFocus,
+    merge_conflict::{find_conflicts, ConflictResolution, MergeConflict},
     panel::kind::PanelKind,
     window_tab::{CommonData, Focus},
     workspace::LapceWorkspace,
@@ -99,6 +104,37 @@ pub struct DocHistory {
     pub version: String,
 }
 
+impl DocHistory {
+    /// The version of the text in the clipboard, which a file is compared with
+    pub const CLIPBOARD: &'static str = "Clipboard";
+    /// The version of the inline mode of a diff editor, which has the removed
+    /// lines of the changes above the added ones
+    pub const INLINE: &'static str = "Inline";
+
+    /// Whether the content of this version is in the source control, rather
+    /// than made by the editor
+    pub fn is_source_control(&self) -> bool {
+        self.version != Self::CLIPBOARD && self.version != Self::INLINE
+    }
+
+    /// Get the content of the file at this version, which is either the index,
+    /// or a git revision such as `HEAD` or a commit id.
+    pub fn retrieve(
+        &self,
+        proxy: &ProxyRpcHandler,
+        f: impl ProxyCallback + 'static,
+    ) {
+        if !self.is_source_control() {
+            return;
+        }
+        if self.version == "index" {
+            proxy.get_buffer_head(self.path.clone(), f);
+        } else {
+            proxy.git_get_file_revision(self.path.clone(), self.version.clone(), f);
+        }
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum DocContent {
     /// A file at some location. This can be a remote path.
@@ -145,11 +181,45 @@ pub struct DocInfo {
     pub path: PathBuf,
     pub scroll_offset: (f64, f64),
     pub cursor_offset: usize,
+    #[serde(default)]
+    pub folded: Vec<FoldingRange>,
 }
 
 /// (Offset -> (Plugin the code actions are from, Code Actions))
 pub type CodeActions = im::HashMap<usize, Arc<(PluginId, CodeActionResponse)>>;
 
+/// The text displayed after a line whose following lines are folded.
+pub const FOLD_PLACEHOLDER: &str = " ⋯ ";
+
+/// The separator between the code lenses displayed above the same line.
+pub const CODE_LENS_SEPARATOR: &str = " | ";
+
+/// How long to wait after an edit before blaming the file again, as blaming is
+/// slow for files with a long history.
+const BLAME_DELAY: Duration = Duration::from_millis(500);
+
+/// What is displayed on the virtual line above a line
+pub enum LineLenses {
+    /// The actions to resolve the conflict which starts on the line, which
+    /// take the place of its code lenses
+    Conflict(MergeConflict),
+    CodeLens(Vec<(CodeLensId, lsp_types::Command)>),
+}
+
+impl LineLenses {
+    pub fn titles(&self) -> Vec<&str> {
+        match self {
+            LineLenses::Conflict(_) => {
+                ConflictResolution::ALL.iter().map(|r| r.title()).collect()
+            }
+            LineLenses::CodeLens(commands) => commands
+                .iter()
+                .map(|(_, command)| command.title.as_str())
+                .collect(),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Doc {
     pub scope: Scope,
@@ -178,9 +248,19 @@ pub struct Doc {
     /// (Offset -> (Plugin the code actions are from, Code Actions))
     pub code_actions: RwSignal<CodeActions>,
 
+    /// Code lenses for the document, such as rust-analyzer's `Run | Debug`
+    pub code_lens: RwSignal<CodeLenses>,
+
+    /// The foldable ranges of the document, and which of them are folded
+    pub folding: RwSignal<Folding>,
+
     /// Stores information about different versions of the document from source control.
     histories: RwSignal<im::HashMap<String, DocumentHistory>>,
     pub head_changes: RwSignal<im::Vector<DiffLines>>,
+    /// The commits which last changed the lines of the document, ordered by line
+    pub blame: RwSignal<im::Vector<BlameHunk>>,
+    /// The conflicts left in the document by a merge or rebase, ordered by line
+    pub conflicts: RwSignal<im::Vector<MergeConflict>>,
 
     line_styles: Rc<RefCell<LineStyles>>,
     pub parser: Rc<RefCell<BracketParser>>,
@@ -221,6 +301,8 @@ pub fn new(
             ))),
             semantic_styles: cx.create_rw_signal(None),
             inlay_hints: cx.create_rw_signal(None),
+            code_lens: cx.create_rw_signal(CodeLenses::default()),
+            folding: cx.create_rw_signal(Folding::default()),
             diagnostics,
             completion_lens: cx.create_rw_signal(None),
             completion_pos: cx.create_rw_signal((0, 0)),
@@ -234,6 +316,8 @@ pub fn new(
             loaded: cx.create_rw_signal(false),
             histories: cx.create_rw_signal(im::HashMap::new()),
             head_changes: cx.create_rw_signal(im::Vector::new()),
+            blame: cx.create_rw_signal(im::Vector::new()),
+            conflicts: cx.create_rw_signal(im::Vector::new()),
             sticky_headers: Rc::new(RefCell::new(HashMap::new())),
             code_actions: cx.create_rw_signal(im::HashMap::new()),
             find_result: FindResult::new(cx),
@@ -272,6 +356,8 @@ pub fn new_content(
             ))),
             semantic_styles: cx.create_rw_signal(None),
             inlay_hints: cx.create_rw_signal(None),
+            code_lens: cx.create_rw_signal(CodeLenses::default()),
+            folding: cx.create_rw_signal(Folding::default()),
             diagnostics: DiagnosticData {
                 expanded: cx.create_rw_signal(true),
                 diagnostics: cx.create_rw_signal(im::Vector::new()),
@@ -284,6 +370,8 @@ pub fn new_content(
             content: cx.create_rw_signal(content),
             histories: cx.create_rw_signal(im::HashMap::new()),
             head_changes: cx.create_rw_signal(im::Vector::new()),
+            blame: cx.create_rw_signal(im::Vector::new()),
+            conflicts: cx.create_rw_signal(im::Vector::new()),
             sticky_headers: Rc::new(RefCell::new(HashMap::new())),
             loaded: cx.create_rw_signal(true),
             find_result: FindResult::new(cx),
@@ -319,6 +407,8 @@ pub fn new_history(
             ))),
             semantic_styles: cx.create_rw_signal(None),
             inlay_hints: cx.create_rw_signal(None),
+            code_lens: cx.create_rw_signal(CodeLenses::default()),
+            folding: cx.create_rw_signal(Folding::default()),
             diagnostics: DiagnosticData {
                 expanded: cx.create_rw_signal(true),
                 diagnostics: cx.create_rw_signal(im::Vector::new()),
@@ -333,6 +423,8 @@ pub fn new_history(
             loaded: cx.create_rw_signal(true),
             histories: cx.create_rw_signal(im::HashMap::new()),
             head_changes: cx.create_rw_signal(im::Vector::new()),
+            blame: cx.create_rw_signal(im::Vector::new()),
+            conflicts: cx.create_rw_signal(im::Vector::new()),
             code_actions: cx.create_rw_signal(im::HashMap::new()),
             find_result: FindResult::new(cx),
             preedit: PreeditData::new(cx),
@@ -614,8 +706,10 @@ pub fn apply_deltas(&self, deltas: &[(Rope, RopeDelta, InvalLines)]) {
         let rev = self.rev() - deltas.len() as u64;
         batch(|| {
             for (i, (_, delta, inval)) in deltas.iter().enumerate() {
+                self.update_folding(inval);
                 self.update_styles(delta);
                 self.update_inlay_hints(delta);
+                self.update_code_lens(delta);
                 self.update_diagnostics(delta);
                 self.update_completion_lens(delta);
                 self.update_find_result(delta);
@@ -665,9 +759,13 @@ fn on_update(&self, edits: Option<&[SyntaxEdit]>) {
             self.trigger_syntax_change(edits);
             self.clear_sticky_headers_cache();
             self.trigger_head_change();
+            self.get_blame();
+            self.find_conflicts();
             self.check_auto_save();
             self.get_semantic_styles();
             self.get_inlay_hints();
+            self.get_code_lens();
+            self.get_folding_range();
             self.find_result.reset();
             self.do_bracket_colorization();
         });
@@ -747,6 +845,35 @@ fn update_inlay_hints(&self, delta: &RopeDelta) {
         });
     }
 
+    /// Update the code lenses so their positions are correct after an edit.
+    fn update_code_lens(&self, delta: &RopeDelta) {
+        if self
+            .code_lens
+            .with_untracked(|code_lens| code_lens.is_empty())
+        {
+            return;
+        }
+        self.code_lens
+            .update(|code_lens| code_lens.apply_delta(delta));
+    }
+
+    /// Update the folding ranges so their lines are correct after an edit.
+    fn update_folding(&self, inval: &InvalLines) {
+        if self
+            .folding
+            .with_untracked(|f| f.ranges.is_empty() && f.folded.is_empty())
+        {
+            return;
+        }
+        self.folding.update(|folding| {
+            folding.apply_inval(
+                inval.start_line,
+                inval.inval_count,
+                inval.new_count,
+            );
+        });
+    }
+
     pub fn trigger_syntax_change(&self, edits: Option<&[SyntaxEdit]>) {
         let (rev, text) =
             self.buffer.with_untracked(|b| (b.rev(), b.text().clone()));
@@ -915,6 +1042,183 @@ fn get_inlay_hints(&self) {
         });
     }
 
+    /// Request code lenses for the buffer from the LSP through the proxy. Each
+    /// plugin's response comes back through `Doc::receive_code_lens`.
+    fn get_code_lens(&self) {
+        if !self.loaded() {
+            return;
+        }
+
+        let path =
+            if let DocContent::File { path, .. } = self.content.get_untracked() {
+                path
+            } else {
+                return;
+            };
+
+        self.common.proxy.get_code_lens(path, self.rev());
+    }
+
+    /// Replace the code lenses of the plugin, if they're for the current
+    /// revision of the buffer.
+    pub fn receive_code_lens(
+        &self,
+        rev: u64,
+        plugin_id: PluginId,
+        lenses: Vec<CodeLens>,
+    ) {
+        if self.rev() != rev {
+            return;
+        }
+        let lenses = self.buffer.with_untracked(|buffer| {
+            lenses
+                .into_iter()
+                .map(|lens| (buffer.offset_of_position(&lens.range.start), lens))
+                .collect()
+        });
+        self.code_lens
+            .update(|code_lens| code_lens.set(plugin_id, lenses));
+        self.clear_text_cache();
+    }
+
+    /// Get the foldable ranges from the language server, falling back to the
+    /// ranges of the syntax tree when no language server provides them.
+    fn get_folding_range(&self) {
+        if !self.loaded() {
+            return;
+        }
+
+        let path =
+            if let DocContent::File { path, .. } = self.content.get_untracked() {
+                path
+            } else {
+                return;
+            };
+
+        let rev = self.rev();
+        let doc = self.clone();
+        let send = create_ext_action(self.scope, move |ranges| {
+            if doc.rev() != rev {
+                return;
+            }
+            let ranges = ranges.unwrap_or_else(|| {
+                doc.syntax.with_untracked(|syntax| {
+                    syntax
+                        .folding_ranges()
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|(start, end)| FoldingRange::new(start, end))
+                        .collect()
+                })
+            });
+            doc.folding.update(|folding| folding.set_ranges(ranges));
+        });
+
+        self.common.proxy.get_folding_range(path, move |result| {
+            if let Ok(ProxyResponse::GetFoldingRangeResponse { ranges }) = result {
+                let ranges: Vec<FoldingRange> = ranges
+                    .into_iter()
+                    .map(|r| {
+                        FoldingRange::new(r.start_line as usize, r.end_line as usize)
+                    })
+                    .collect();
+                send(Some(ranges));
+            } else {
+                send(None);
+            }
+        });
+    }
+
+    /// Resolve the code lenses on the given lines which don't have a command yet,
+    /// as servers such as rust-analyzer leave computing them until they're shown.
+    pub fn resolve_code_lens(&self, lines: Range<usize>) {
+        let (start, end, rev) = self.buffer.with_untracked(|b| {
+            (
+                b.offset_of_line(lines.start),
+                b.offset_of_line(lines.end),
+                b.rev(),
+            )
+        });
+        let unresolved: Vec<(CodeLensId, CodeLens)> =
+            self.code_lens.with_untracked(|code_lens| {
+                code_lens
+                    .range(start..end)
+                    .into_iter()
+                    .filter(|(_, lens)| lens.command.is_none())
+                    .map(|(id, lens)| (id, lens.clone()))
+                    .collect()
+            });
+        if unresolved.is_empty() {
+            return;
+        }
+
+        let mut to_resolve = Vec::new();
+        self.code_lens.update(|code_lens| {
+            to_resolve = unresolved
+                .into_iter()
+                .filter(|(id, _)| code_lens.start_resolve(*id))
+                .collect();
+        });
+        for (id, lens) in to_resolve {
+            let doc = self.clone();
+            let send = create_ext_action(self.scope, move |lens| {
+                if doc.rev() != rev {
+                    return;
+                }
+                doc.code_lens
+                    .update(|code_lens| code_lens.resolve(id, lens));
+                doc.clear_text_cache();
+            });
+            self.common
+                .proxy
+                .code_lens_resolve(id.plugin_id, lens, move |result| {
+                    if let Ok(ProxyResponse::CodeLensResolveResponse { code_lens }) =
+                        result
+                    {
+                        send(code_lens);
+                    }
+                });
+        }
+    }
+
+    /// What is displayed on the virtual line above the line, if anything.
+    pub fn line_lenses(&self, line: usize) -> Option<LineLenses> {
+        if let Some(conflict) = self.conflict_at(line) {
+            return Some(LineLenses::Conflict(conflict));
+        }
+
+        if !self
+            .common
+            .config
+            .get_untracked()
+            .editor
+            .enable_lsp_code_lens
+        {
+            return None;
+        }
+        let (start, end) = self.buffer.with_untracked(|b| {
+            (b.offset_of_line(line), b.offset_of_line(line + 1))
+        });
+        let commands: Vec<(CodeLensId, lsp_types::Command)> =
+            self.code_lens.with_untracked(|code_lens| {
+                code_lens
+                    .range(start..end)
+                    .into_iter()
+                    .filter_map(|(id, lens)| Some((id, lens.command.clone()?)))
+                    .collect()
+            });
+        (!commands.is_empty()).then_some(LineLenses::CodeLens(commands))
+    }
+
+    /// The indentation of the line, which the lenses above it are aligned with.
+    pub fn line_indent(&self, line: usize) -> String {
+        self.buffer.with_untracked(|b| {
+            let start = b.offset_of_line(line);
+            let end = b.first_non_blank_character_on_line(line).max(start);
+            b.slice_to_cow(start..end).to_string()
+        })
+    }
+
     pub fn diagnostics(&self) -> &DiagnosticData {
         &self.diagnostics
     }
@@ -1208,6 +1512,16 @@ pub fn head_changes(&self) -> RwSignal<im::Vector<DiffLines>> {
         self.head_changes
     }
 
+    /// The text of the `head` version of the file, which the head changes are
+    /// against
+    pub fn head_text(&self) -> Option<Rope> {
+        self.histories.with_untracked(|histories| {
+            histories
+                .get("head")
+                .map(|history| history.buffer.text().clone())
+        })
+    }
+
     /// Retrieve the `head` version of the buffer
     pub fn retrieve_head(&self) {
         if let DocContent::File { path, .. } = self.content.get_untracked() {
@@ -1245,6 +1559,136 @@ pub fn retrieve_head(&self) {
         }
     }
 
+    /// Get the content of a history document again, for when that version of
+    /// the file changed, such as the index after staging.
+    pub fn retrieve_history(&self) {
+        let DocContent::History(history) = self.content.get_untracked() else {
+            return;
+        };
+        let send = {
+            let doc = self.clone();
+            create_ext_action(self.scope, move |result| {
+                if let Ok(ProxyResponse::BufferHeadResponse { content, .. }) = result
+                {
+                    doc.reload(Rope::from(content), true);
+                }
+            })
+        };
+        history.retrieve(&self.common.proxy, move |result| {
+            send(result);
+        });
+    }
+
+    /// Look for the conflict markers left in the file by a merge or rebase, if
+    /// git has conflicts in it
+    pub fn find_conflicts(&self) {
+        let conflicted = self.content.with_untracked(|content| {
+            content.path().is_some_and(|path| {
+                self.common
+                    .conflicted_files
+                    .with_untracked(|files| files.contains(path))
+            })
+        });
+        let conflicts = if conflicted {
+            self.buffer
+                .with_untracked(|b| find_conflicts(b.text().lines(0..b.len())))
+        } else {
+            Vec::new()
+        };
+        let conflicts: im::Vector<MergeConflict> = conflicts.into();
+        if self.conflicts.with_untracked(|c| *c != conflicts) {
+            self.conflicts.set(conflicts);
+            // The actions of the conflicts are displayed above their lines
+            self.clear_text_cache();
+        }
+    }
+
+    /// The conflict which starts on the line, which is where its actions are
+    pub fn conflict_at(&self, line: usize) -> Option<MergeConflict> {
+        self.conflicts.with_untracked(|conflicts| {
+            conflicts
+                .iter()
+                .find(|conflict| conflict.start == line)
+                .copied()
+        })
+    }
+
+    /// Replace the conflict, markers included, with the side or sides to keep
+    pub fn resolve_conflict(
+        &self,
+        conflict: MergeConflict,
+        resolution: ConflictResolution,
+    ) {
+        let (selection, text) = self.buffer.with_untracked(|b| {
+            let text: String = conflict
+                .resolved_lines(resolution)
+                .into_iter()
+                .map(|lines| {
+                    b.slice_to_cow(
+                        b.offset_of_line(lines.start)..b.offset_of_line(lines.end),
+                    )
+                })
+                .collect();
+            let selection = Selection::region(
+                b.offset_of_line(conflict.start),
+                b.offset_of_line(conflict.end + 1),
+            );
+            (selection, text)
+        });
+        self.do_raw_edit(&[(selection, text.as_str())], EditType::Other);
+    }
+
+    /// Request the blame of the buffer from the proxy once there have been no
+    /// edits for a bit, if it's displayed anywhere.
+    pub fn get_blame(&self) {
+        if !self.loaded() {
+            return;
+        }
+        if !self.common.config.get_untracked().editor.inline_blame
+            && !self.common.show_blame.get_untracked()
+        {
+            return;
+        }
+        let DocContent::File { path, .. } = self.content.get_untracked() else {
+            return;
+        };
+
+        let rev = self.rev();
+        let doc = self.clone();
+        exec_after(BLAME_DELAY, move |_| {
+            let current_rev = match doc
+                .buffer
+                .try_with_untracked(|b| b.as_ref().map(|b| b.rev()))
+            {
+                Some(rev) => rev,
+                None => return,
+            };
+            if current_rev != rev {
+                return;
+            }
+
+            let send = {
+                let doc = doc.clone();
+                create_ext_action(doc.scope, move |result| {
+                    if doc.rev() != rev {
+                        return;
+                    }
+                    // A file which isn't in the repository has no blame
+                    let hunks = match result {
+                        Ok(ProxyResponse::GitBlameResponse { hunks }) => {
+                            im::Vector::from(hunks)
+                        }
+                        _ => im::Vector::new(),
+                    };
+                    doc.blame.set(hunks);
+                })
+            };
+            doc.common.proxy.git_blame(path, move |result| {
+                send(result);
+            });
+        });
+    }
+
     pub fn trigger_head_change(&self) {
         let history = if let Some(text) =
             self.histories.with_untracked(|histories| {
@@ -1610,6 +2054,20 @@ fn phantom_text(
             });
         let mut diag_text: SmallVec<[PhantomText; 6]> = diag_text.collect();
 
+        // A folded line has a placeholder at its end for the lines it hides,
+        // before the diagnostics
+        if self.folding.with_untracked(|f| f.folded_at(line).is_some()) {
+            text.push(PhantomText {
+                kind: PhantomTextKind::InlayHint,
+                col: end_offset - start_offset,
+                text: FOLD_PLACEHOLDER.to_string(),
+                fg: Some(config.color(LapceColor::EDITOR_DIM)),
+                font_size: None,
+                bg: None,
+                under_line: None,
+            });
+        }
+
         text.append(&mut diag_text);
 
         let (completion_line, completion_col) = self.completion_pos.get_untracked();
@@ -1675,6 +2133,31 @@ fn phantom_text(
             }
         });
 
+        // The code lenses, or the actions to resolve a conflict, go on a virtual
+        // line above the line, so they're first of the text at its start
+        if let Some(lenses) = self.line_lenses(line) {
+            let fg = match lenses {
+                LineLenses::Conflict(_) => LapceColor::EDITOR_LINK,
+                LineLenses::CodeLens(_) => LapceColor::EDITOR_DIM,
+            };
+            text.insert(
+                0,
+                PhantomText {
+                    kind: PhantomTextKind::InlayHint,
+                    col: 0,
+                    text: format!(
+                        "{}{}\n",
+                        self.line_indent(line),
+                        lenses.titles().join(CODE_LENS_SEPARATOR)
+                    ),
+                    fg: Some(config.color(fg)),
+                    font_size: Some(config.editor.code_lens_font_size()),
+                    bg: None,
+                    under_line: None,
+                },
+            );
+        }
+
         PhantomTextLine { text }
     }
 
@@ -2036,6 +2519,7 @@ fn should_blink(
                 | Focus::Panel(PanelKind::Plugin)
                 | Focus::Panel(PanelKind::Search)
                 | Focus::Panel(PanelKind::SourceControl)
+                | Focus::Panel(PanelKind::Debug)
         ) {
             return true;
         }
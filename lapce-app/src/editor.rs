@@ -1,5 +1,6 @@
 use std::{
     collections::{HashMap, HashSet},
+    path::PathBuf,
     rc::Rc,
     str::FromStr,
     sync::Arc,
@@ -22,7 +23,7 @@
         view::{
             DiffSection, DiffSectionKind, LineInfo, ScreenLines, ScreenLinesBase,
         },
-        visual_line::{Lines, TextLayoutProvider, VLine, VLineInfo},
+        visual_line::{Lines, RVLine, TextLayoutProvider, VLine, VLineInfo},
         Editor,
     },
 };
@@ -41,12 +42,19 @@
     mode::{Mode, MotionMode},
     rope_text_pos::RopeTextPosition,
     selection::{InsertDrift, SelRegion, Selection},
+    word::WordCursor,
 };
-use lapce_rpc::{buffer::BufferId, plugin::PluginId, proxy::ProxyResponse};
-use lapce_xi_rope::{Rope, RopeDelta, Transformer};
+use lapce_rpc::{
+    buffer::BufferId,
+    plugin::PluginId,
+    proxy::{ProxyRequest, ProxyResponse},
+    source_control::GitHunk,
+};
+use lapce_xi_rope::{find::CaseMatching, Rope, RopeDelta, Transformer};
 use lsp_types::{
-    CompletionItem, CompletionTextEdit, GotoDefinitionResponse, HoverContents,
-    InlineCompletionTriggerKind, Location, MarkedString, MarkupKind, TextEdit,
+    CompletionItem, CompletionTextEdit, DocumentHighlight, DocumentHighlightKind,
+    GotoDefinitionResponse, HoverContents, InlineCompletionTriggerKind, Location,
+    MarkedString, MarkupKind, Position, Range, TextEdit,
 };
 use serde::{Deserialize, Serialize};
 
@@ -55,17 +63,23 @@
     completion::CompletionStatus,
     config::LapceConfig,
     db::LapceDb,
-    doc::{Doc, DocContent},
+    debug::{runnable_debug_config, runnable_run_config, RunDebugMode},
+    doc::{Doc, DocContent, LineLenses, CODE_LENS_SEPARATOR},
     editor_tab::EditorTabChild,
+    find::{Find, FindSearchString},
+    folding,
     id::{DiffEditorId, EditorTabId},
     inline_completion::{InlineCompletionItem, InlineCompletionStatus},
     keypress::{condition::Condition, KeyPressFocus},
+    listener::Listener,
     main_split::{MainSplitData, SplitDirection, SplitMoveDirection},
     markdown::{
         from_marked_string, from_plaintext, parse_markdown, MarkdownContent,
     },
+    merge_conflict::ConflictResolution,
     proxy::path_from_url,
     snippet::Snippet,
+    source_control::{diff_hunks, hunk_at_line},
     window_tab::{CommonData, Focus, WindowTabData},
 };
 
@@ -79,12 +93,22 @@
 pub mod location;
 pub mod view;
 
+const DOCUMENT_HIGHLIGHT_DELAY: Duration = Duration::from_millis(150);
+
 #[derive(Clone, Debug)]
 pub enum InlineFindDirection {
     Left,
     Right,
 }
 
+#[derive(Clone, PartialEq)]
+pub struct DocumentHighlights {
+    /// The revision of the buffer the highlights were computed for
+    pub rev: u64,
+    /// (Start offset, end offset, kind)
+    pub ranges: Vec<(usize, usize, DocumentHighlightKind)>,
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct EditorInfo {
     pub content: DocContent,
@@ -200,6 +224,9 @@ pub struct EditorData {
     pub editor: Rc<Editor>,
     pub kind: RwSignal<EditorViewKind>,
     pub sticky_header_height: RwSignal<f64>,
+    /// The other reads and writes of the symbol under the cursor
+    pub document_highlights: RwSignal<Option<DocumentHighlights>>,
+    document_highlight_timer: RwSignal<TimerToken>,
     pub common: Rc<CommonData>,
 }
 impl PartialEq for EditorData {
@@ -219,7 +246,7 @@ fn new(
         let cx = cx.create_child();
 
         let confirmed = confirmed.unwrap_or_else(|| cx.create_rw_signal(false));
-        EditorData {
+        let editor_data = EditorData {
             scope: cx,
             editor_tab_id: cx.create_rw_signal(editor_tab_id),
             diff_editor_id: cx.create_rw_signal(diff_editor_id),
@@ -231,8 +258,13 @@ fn new(
             editor: Rc::new(editor),
             kind: cx.create_rw_signal(EditorViewKind::Normal),
             sticky_header_height: cx.create_rw_signal(0.0),
+            document_highlights: cx.create_rw_signal(None),
+            document_highlight_timer: cx.create_rw_signal(TimerToken::INVALID),
             common,
-        }
+        };
+        editor_data.watch_document_highlights();
+        editor_data.watch_folded_cursor();
+        editor_data
     }
 
     pub fn new_local(
@@ -412,6 +444,18 @@ fn run_edit_command(&self, cmd: &EditCommand) -> CommandExecuted {
             self.cancel_inline_completion();
         }
 
+        if *cmd == EditCommand::InsertNewLine {
+            self.on_type_format("\n");
+        } else if *cmd == EditCommand::ClipboardPaste
+            && !deltas.is_empty()
+            && self
+                .common
+                .config
+                .with_untracked(|config| config.editor.format_on_paste)
+        {
+            self.format_pasted(&deltas);
+        }
+
         self.apply_deltas(&deltas);
         if let EditCommand::NormalMode = cmd {
             self.snippet.set(None);
@@ -1233,6 +1277,264 @@ enum DefinitionOrReferece {
         );
     }
 
+    /// The file path of the document and the LSP position of the cursor, along
+    /// with its offset.
+    pub fn cursor_lsp_position(&self) -> Option<(PathBuf, usize, Position)> {
+        let doc = self.doc();
+        if !doc.loaded() {
+            return None;
+        }
+        let path = doc.content.with_untracked(|c| c.path().cloned())?;
+        let offset = self.cursor().with_untracked(|c| c.offset());
+        let position = doc
+            .buffer
+            .with_untracked(|buffer| buffer.offset_to_position(offset));
+        Some((path, offset, position))
+    }
+
+    /// Update the document highlights once the cursor has rested for a moment.
+    fn watch_document_highlights(&self) {
+        let editor = self.clone();
+        let cursor = self.cursor();
+        let doc = self.doc_signal();
+        let timer = self.document_highlight_timer;
+        self.scope.create_effect(move |_| {
+            cursor.track();
+            doc.with(|doc| doc.buffer.track());
+            let editor = editor.clone();
+            let token = exec_after(DOCUMENT_HIGHLIGHT_DELAY, move |token| {
+                if timer.try_get_untracked() == Some(token)
+                    && editor.editor_tab_id.try_get_untracked().flatten().is_some()
+                {
+                    editor.update_document_highlights();
+                }
+            });
+            timer.set(token);
+        });
+    }
+
+    /// Request the highlights of the symbol under the cursor, falling back to the
+    /// occurrences of the word under the cursor when no language server provides
+    /// them.
+    fn update_document_highlights(&self) {
+        let config = self.common.config.get_untracked();
+        if !config.editor.highlight_selection_occurrences {
+            self.document_highlights.set(None);
+            return;
+        }
+        let Some((path, offset, position)) = self.cursor_lsp_position() else {
+            return;
+        };
+
+        let doc = self.doc();
+        let rev = doc.rev();
+        let cursor = self.cursor().read_only();
+        let document_highlights = self.document_highlights;
+        let send = create_ext_action(self.scope, move |highlights| {
+            if doc.rev() != rev || cursor.with_untracked(|c| c.offset()) != offset {
+                return;
+            }
+            let ranges = match highlights {
+                Some(highlights) => lsp_highlight_ranges(&doc, highlights),
+                None => word_highlight_ranges(&doc, offset),
+            };
+            document_highlights.set(Some(DocumentHighlights { rev, ranges }));
+        });
+        self.common
+            .proxy
+            .get_document_highlight(path, position, move |result| {
+                if let Ok(ProxyResponse::GetDocumentHighlightResponse {
+                    highlights,
+                }) = result
+                {
+                    send(Some(highlights));
+                } else {
+                    send(None);
+                }
+            });
+    }
+
+    pub fn go_to_implementation(&self) {
+        self.go_to_locations(
+            |path, position| ProxyRequest::GetImplementation { path, position },
+            |response| match response {
+                ProxyResponse::GetImplementationResponse { implementation } => {
+                    Some(implementation)
+                }
+                _ => None,
+            },
+        );
+    }
+
+    pub fn go_to_declaration(&self) {
+        self.go_to_locations(
+            |path, position| ProxyRequest::GetDeclaration { path, position },
+            |response| match response {
+                ProxyResponse::GetDeclarationResponse { declaration } => {
+                    Some(declaration)
+                }
+                _ => None,
+            },
+        );
+    }
+
+    /// Send the `request` for the symbol under the cursor and jump to the
+    /// locations which `locations` takes out of its response.
+    fn go_to_locations(
+        &self,
+        request: impl FnOnce(PathBuf, Position) -> ProxyRequest,
+        locations: impl FnOnce(ProxyResponse) -> Option<GotoDefinitionResponse>
+            + Send
+            + 'static,
+    ) {
+        let Some((path, offset, position)) = self.cursor_lsp_position() else {
+            return;
+        };
+
+        let internal_command = self.common.internal_command;
+        let cursor = self.cursor().read_only();
+        let send = create_ext_action(self.scope, move |locations| {
+            if cursor.with_untracked(|c| c.offset()) == offset {
+                jump_to_locations(internal_command, locations);
+            }
+        });
+        self.common
+            .proxy
+            .request_async(request(path, position), move |result| {
+                if let Some(response) = result.ok().and_then(locations) {
+                    send(goto_response_locations(response));
+                }
+            });
+    }
+
+    pub fn run_fold_command(&self, cmd: &LapceWorkbenchCommand) {
+        let doc = self.doc();
+        let offset = self.cursor().with_untracked(|c| c.offset());
+        let line = doc.buffer.with_untracked(|b| b.line_of_offset(offset));
+        doc.folding.update(|folding| match cmd {
+            LapceWorkbenchCommand::Fold => {
+                folding.fold(line);
+            }
+            LapceWorkbenchCommand::Unfold => {
+                folding.unfold(line);
+            }
+            LapceWorkbenchCommand::ToggleFold => {
+                if !folding.unfold(line) {
+                    folding.fold(line);
+                }
+            }
+            LapceWorkbenchCommand::FoldAll => folding.fold_all(),
+            LapceWorkbenchCommand::UnfoldAll => folding.unfold_all(),
+            LapceWorkbenchCommand::FoldLevel1 => folding.fold_level(1),
+            LapceWorkbenchCommand::FoldLevel2 => folding.fold_level(2),
+            LapceWorkbenchCommand::FoldLevel3 => folding.fold_level(3),
+            LapceWorkbenchCommand::FoldLevel4 => folding.fold_level(4),
+            LapceWorkbenchCommand::FoldLevel5 => folding.fold_level(5),
+            LapceWorkbenchCommand::FoldLevel6 => folding.fold_level(6),
+            LapceWorkbenchCommand::FoldLevel7 => folding.fold_level(7),
+            _ => {}
+        });
+        self.move_cursor_out_of_folds();
+    }
+
+    /// Fold or unfold the range starting on the line, as done by clicking on the
+    /// chevron in the gutter.
+    pub fn toggle_fold(&self, line: usize) {
+        self.doc().folding.update(|folding| folding.toggle(line));
+        self.move_cursor_out_of_folds();
+    }
+
+    /// Move the cursor onto the line which folds it, if it has just been folded
+    /// away.
+    fn move_cursor_out_of_folds(&self) {
+        let doc = self.doc();
+        let offset = self.cursor().with_untracked(|c| c.offset());
+        let (line, col) =
+            doc.buffer.with_untracked(|b| b.offset_to_line_col(offset));
+        let Some(range) = doc.folding.with_untracked(|folding| {
+            folding.hidden().into_iter().find(|r| r.hides(line))
+        }) else {
+            return;
+        };
+        let offset = doc
+            .buffer
+            .with_untracked(|b| b.offset_of_line_col(range.start_line, col));
+        self.cursor()
+            .update(|cursor| cursor.set_offset(offset, false, false));
+    }
+
+    /// Keep the cursor out of the folded lines. Moving down onto a fold skips
+    /// over it and moving up onto one goes to the line which folds it, while any
+    /// other way of getting there, such as a jump, unfolds the lines.
+    fn watch_folded_cursor(&self) {
+        let editor = self.clone();
+        let cursor = self.cursor();
+        self.scope.create_effect(move |prev_line: Option<usize>| {
+            let offset = cursor.with(|c| c.offset());
+            let doc = editor.doc();
+            let (line, col) =
+                doc.buffer.with_untracked(|b| b.offset_to_line_col(offset));
+            let Some(range) = doc.folding.with_untracked(|folding| {
+                folding.hidden().into_iter().find(|r| r.hides(line))
+            }) else {
+                return line;
+            };
+
+            let last_line = doc.buffer.with_untracked(|b| b.last_line());
+            let target = match prev_line {
+                Some(prev_line) if prev_line == range.start_line => {
+                    if range.end_line < last_line {
+                        Some(range.end_line + 1)
+                    } else {
+                        Some(range.start_line)
+                    }
+                }
+                Some(prev_line) if prev_line == range.end_line + 1 => {
+                    Some(range.start_line)
+                }
+                _ => None,
+            };
+            if let Some(target) = target {
+                let offset = doc
+                    .buffer
+                    .with_untracked(|b| b.offset_of_line_col(target, col));
+                cursor.update(|cursor| {
+                    let modify = matches!(cursor.mode, CursorMode::Visual { .. });
+                    cursor.set_offset(offset, modify, false);
+                });
+                return target;
+            }
+
+            doc.folding.update(|folding| {
+                folding.reveal(line);
+            });
+            line
+        });
+    }
+
+    /// The visual lines hidden by folding, as `(first hidden vline, count)`
+    pub fn hidden_vlines(&self) -> Vec<(usize, usize)> {
+        let hidden = self.doc().folding.with_untracked(|f| f.hidden());
+        if hidden.is_empty() {
+            return Vec::new();
+        }
+        folding::hidden_vlines(
+            &hidden,
+            self.editor.last_line(),
+            self.editor.last_vline().get() + 1,
+            |line| self.editor.vline_of_line(line).get(),
+        )
+    }
+
+    /// The index the visual line is displayed at, with the folded lines left out
+    pub fn displayed_vline(&self, vline: VLine) -> VLine {
+        let hidden_vlines = self.hidden_vlines();
+        if hidden_vlines.is_empty() {
+            return vline;
+        }
+        VLine(folding::displayed_vline(&hidden_vlines, vline.get()))
+    }
+
     fn scroll(&self, down: bool, count: usize, mods: ModifiersState) {
         self.editor.scroll(
             self.sticky_header_height.get_untracked(),
@@ -1847,6 +2149,10 @@ fn do_go_to_location(
             let db: Arc<LapceDb> = use_context().unwrap();
             if let Ok(info) = db.get_doc_info(&self.common.workspace, &location.path)
             {
+                let doc = self.doc();
+                if doc.folding.with_untracked(|f| f.folded.is_empty()) {
+                    doc.folding.update(|f| f.set_folded(info.folded));
+                }
                 self.go_to_position(
                     EditorPosition::Offset(info.cursor_offset),
                     Some(Vec2::new(info.scroll_offset.0, info.scroll_offset.1)),
@@ -2091,6 +2397,253 @@ pub fn format(&self) {
         }
     }
 
+    /// Format the selected text, or the lines of the cursors when nothing is
+    /// selected, rather than the whole document.
+    pub fn format_selection(&self) {
+        let mut ranges = self.doc().buffer.with_untracked(|buffer| {
+            let selection = self.cursor().get_untracked().edit_selection(buffer);
+            selection
+                .regions()
+                .iter()
+                .map(|region| {
+                    let (start, end) = if region.is_caret() {
+                        let line = buffer.line_of_offset(region.start);
+                        (
+                            buffer.offset_of_line(line),
+                            buffer.offset_of_line(line + 1),
+                        )
+                    } else {
+                        (region.min(), region.max())
+                    };
+                    Range {
+                        start: buffer.offset_to_position(start),
+                        end: buffer.offset_to_position(end),
+                    }
+                })
+                .collect::<Vec<_>>()
+        });
+        ranges.dedup();
+        self.format_ranges(ranges);
+    }
+
+    /// Format the text which was just inserted by a paste.
+    fn format_pasted(&self, deltas: &[(Rope, RopeDelta, InvalLines)]) {
+        let Some((start, end)) = deltas
+            .iter()
+            .map(|(_, delta, _)| {
+                let (iv, new_len) = delta.summary();
+                (iv.start(), iv.start() + new_len)
+            })
+            .reduce(|(start, end), (s, e)| (start.min(s), end.max(e)))
+        else {
+            return;
+        };
+        let range = self.doc().buffer.with_untracked(|buffer| Range {
+            start: buffer.offset_to_position(start),
+            end: buffer.offset_to_position(end),
+        });
+        self.format_ranges(vec![range]);
+    }
+
+    fn format_ranges(&self, ranges: Vec<Range>) {
+        let doc = self.doc();
+        let rev = doc.rev();
+        let DocContent::File { path, .. } = doc.content.get_untracked() else {
+            return;
+        };
+        if ranges.is_empty() {
+            return;
+        }
+
+        let editor = self.clone();
+        let send = create_ext_action(self.scope, move |edits: Vec<TextEdit>| {
+            if !edits.is_empty() && editor.doc().rev() == rev {
+                editor.do_text_edit(&edits);
+            }
+        });
+
+        let proxy = self.common.proxy.clone();
+        std::thread::spawn(move || {
+            let mut edits = Vec::new();
+            for range in ranges {
+                let (tx, rx) = crossbeam_channel::bounded(1);
+                proxy.get_document_range_formatting(
+                    path.clone(),
+                    range,
+                    move |result| {
+                        let _ = tx.send(result);
+                    },
+                );
+                if let Ok(Ok(ProxyResponse::GetDocumentFormatting {
+                    edits: range_edits,
+                })) = rx.recv_timeout(std::time::Duration::from_secs(1))
+                {
+                    edits.extend(range_edits);
+                }
+            }
+            send(edits);
+        });
+    }
+
+    /// Let the language server format after a character was typed, if it's a
+    /// trigger character of one of them. The proxy only sends it on to the
+    /// servers which have the character as a trigger character.
+    fn on_type_format(&self, ch: &str) {
+        let is_trigger =
+            self.common
+                .on_type_format_triggers
+                .with_untracked(|plugins| {
+                    plugins.values().flatten().any(|trigger| trigger == ch)
+                });
+        if !is_trigger {
+            return;
+        }
+
+        let Some((path, offset, position)) = self.cursor_lsp_position() else {
+            return;
+        };
+        let rev = self.doc().rev();
+
+        let editor = self.clone();
+        let send = create_ext_action(self.scope, move |edits: Vec<TextEdit>| {
+            if editor.doc().rev() == rev
+                && editor.cursor().with_untracked(|c| c.offset()) == offset
+            {
+                editor.do_text_edit(&edits);
+            }
+        });
+        self.common.proxy.get_document_on_type_formatting(
+            path,
+            position,
+            ch.to_string(),
+            move |result| {
+                if let Ok(ProxyResponse::GetDocumentFormatting { edits }) = result {
+                    if !edits.is_empty() {
+                        send(edits);
+                    }
+                }
+            },
+        );
+    }
+
+    pub fn cursor_line(&self) -> usize {
+        let offset = self.cursor().with_untracked(|c| c.offset());
+        self.doc()
+            .buffer
+            .with_untracked(|buffer| buffer.line_of_offset(offset))
+    }
+
+    /// The hunk of the changes in the working tree that aren't staged, which has
+    /// the line in it.
+    fn unstaged_hunk(&self, line: usize) -> Option<GitHunk> {
+        let doc = self.doc();
+        if !doc.content.with_untracked(|content| content.is_file()) {
+            return None;
+        }
+        let changes = doc.head_changes().get_untracked();
+        let hunks = diff_hunks(&changes.into_iter().collect::<Vec<_>>());
+        hunk_at_line(&hunks, line)
+    }
+
+    /// The hunk of the staged changes which has the line in it, when this is the
+    /// index side of the diff of the staged changes of a file.
+    fn staged_hunk(&self, line: usize) -> Option<(PathBuf, GitHunk)> {
+        let DocContent::History(history) = self.doc().content.get_untracked() else {
+            return None;
+        };
+        if history.version != "index" {
+            return None;
+        }
+        let EditorViewKind::Diff(diff) = self.kind.get_untracked() else {
+            return None;
+        };
+        if !diff.is_right {
+            return None;
+        }
+        let hunk = hunk_at_line(&diff_hunks(&diff.changes), line)?;
+        Some((history.path, hunk))
+    }
+
+    /// Put the change at the line in the index.
+    pub fn stage_hunk(&self, line: usize) {
+        let Some(hunk) = self.unstaged_hunk(line) else {
+            return;
+        };
+        let doc = self.doc();
+        let Some(path) = doc.content.with_untracked(|c| c.path().cloned()) else {
+            return;
+        };
+        let content = doc.buffer.with_untracked(|buffer| {
+            let start = buffer.offset_of_line(hunk.new_start);
+            let end = buffer.offset_of_line(hunk.new_start + hunk.new_lines);
+            buffer.slice_to_cow(start..end).to_string()
+        });
+        self.common.proxy.git_stage_hunk(path, hunk, content);
+    }
+
+    /// Take the staged change at the line back out of the index.
+    pub fn unstage_hunk(&self, line: usize) {
+        if let Some((path, hunk)) = self.staged_hunk(line) {
+            self.common.proxy.git_unstage_hunk(path, hunk);
+        }
+    }
+
+    /// Undo the unstaged change at the line, by putting back the lines from the
+    /// index in the buffer.
+    pub fn revert_hunk(&self, line: usize) {
+        let Some(hunk) = self.unstaged_hunk(line) else {
+            return;
+        };
+        let doc = self.doc();
+        let Some(head) = doc.head_text() else {
+            return;
+        };
+        let old = head
+            .slice_to_cow(
+                head.offset_of_line(hunk.old_start)
+                    ..head.offset_of_line(hunk.old_start + hunk.old_lines),
+            )
+            .to_string();
+        let selection = doc.buffer.with_untracked(|buffer| {
+            Selection::region(
+                buffer.offset_of_line(hunk.new_start),
+                buffer.offset_of_line(hunk.new_start + hunk.new_lines),
+            )
+        });
+        self.do_edit(&selection, &[(selection.clone(), old.as_str())]);
+    }
+
+    /// The actions on the change at the line, for the context menus of the
+    /// editor and of its gutter.
+    fn hunk_menu_items(&self, line: usize) -> Vec<MenuItem> {
+        if self.unstaged_hunk(line).is_some() {
+            let editor = self.clone();
+            let stage = move || editor.stage_hunk(line);
+            let editor = self.clone();
+            let revert = move || editor.revert_hunk(line);
+            vec![
+                MenuItem::new("Stage Change").action(stage),
+                MenuItem::new("Revert Change").action(revert),
+            ]
+        } else if self.staged_hunk(line).is_some() {
+            let editor = self.clone();
+            let unstage = move || editor.unstage_hunk(line);
+            vec![MenuItem::new("Unstage Change").action(unstage)]
+        } else {
+            Vec::new()
+        }
+    }
+
+    pub fn show_hunk_menu(&self, line: usize) {
+        let items = self.hunk_menu_items(line);
+        if !items.is_empty() {
+            let menu = items
+                .into_iter()
+                .fold(Menu::new(""), |menu, item| menu.entry(item));
+            show_context_menu(menu, None);
+        }
+    }
+
     fn search_whole_word_forward(&self, mods: ModifiersState) {
         let offset = self.cursor().with_untracked(|c| c.offset());
         let (word, buffer) = self.doc().buffer.with_untracked(|buffer| {
@@ -2188,6 +2741,7 @@ pub fn save_doc_position(&self) {
 
         let cursor_offset = self.cursor().with_untracked(|c| c.offset());
         let scroll_offset = self.viewport().with_untracked(|v| v.origin().to_vec2());
+        let folded = doc.folding.with_untracked(|f| f.folded.clone());
 
         let db: Arc<LapceDb> = use_context().unwrap();
         db.save_doc_position(
@@ -2195,6 +2749,7 @@ pub fn save_doc_position(&self) {
             path,
             cursor_offset,
             scroll_offset,
+            folded,
         );
     }
 
@@ -2364,7 +2919,9 @@ pub fn pointer_down(&self, pointer_event: &PointerInputEvent) {
     fn left_click(&self, pointer_event: &PointerInputEvent) {
         match pointer_event.count {
             1 => {
-                self.single_click(pointer_event);
+                if !self.click_code_lens(pointer_event) {
+                    self.single_click(pointer_event);
+                }
             }
             2 => {
                 self.double_click(pointer_event);
@@ -2376,6 +2933,134 @@ fn left_click(&self, pointer_event: &PointerInputEvent) {
         }
     }
 
+    /// Run the code lens, or the action to resolve a conflict, under the
+    /// pointer, returning whether there was one.
+    fn click_code_lens(&self, pointer_event: &PointerInputEvent) -> bool {
+        let doc = self.doc();
+        let mode = self.cursor().with_untracked(|c| c.get_mode());
+        let (offset, _) = self.editor.offset_of_point(mode, pointer_event.pos);
+        let line = doc.buffer.with_untracked(|b| b.line_of_offset(offset));
+        let Some(lenses) = doc.line_lenses(line) else {
+            return false;
+        };
+        let Some(i) = self.code_lens_at(line, &lenses.titles(), pointer_event.pos)
+        else {
+            return false;
+        };
+
+        match lenses {
+            LineLenses::Conflict(conflict) => {
+                doc.resolve_conflict(conflict, ConflictResolution::ALL[i]);
+            }
+            LineLenses::CodeLens(mut commands) => {
+                let (id, command) = commands.swap_remove(i);
+                self.run_code_lens(id.plugin_id, command);
+            }
+        }
+        true
+    }
+
+    /// The index of the lens under the point, of the ones with the titles
+    /// displayed on the virtual line above the line, see `Doc::phantom_text`.
+    fn code_lens_at(
+        &self,
+        line: usize,
+        titles: &[&str],
+        point: Point,
+    ) -> Option<usize> {
+        let y = self
+            .screen_lines()
+            .with_untracked(|screen_lines| screen_lines.info_for_line(line))?
+            .y;
+        let line_height = self.common.config.get_untracked().editor.line_height();
+        if point.y < y || point.y >= y + line_height as f64 {
+            return None;
+        }
+
+        // The lenses are the start of the text layout of the line
+        let text_layout = self.editor.text_layout(line);
+        let mut start = self.doc().line_indent(line).len();
+        for (i, title) in titles.iter().enumerate() {
+            let end = start + title.len();
+            let x0 = text_layout.text.hit_position(start).point.x;
+            let x1 = text_layout.text.hit_position(end).point.x;
+            if x0 <= point.x && point.x < x1 {
+                return Some(i);
+            }
+            start = end + CODE_LENS_SEPARATOR.len();
+        }
+        None
+    }
+
+    /// Run the command of a code lens. The run, debug and references commands of
+    /// rust-analyzer are up to the editor to handle, the others are executed by
+    /// the language server.
+    fn run_code_lens(&self, plugin_id: PluginId, command: lsp_types::Command) {
+        let arguments = command.arguments.clone().unwrap_or_default();
+        match command.command.as_str() {
+            "rust-analyzer.runSingle" => {
+                if let Some(config) =
+                    arguments.into_iter().next().and_then(runnable_run_config)
+                {
+                    self.common.internal_command.send(
+                        InternalCommand::RunAndDebug {
+                            mode: RunDebugMode::Run,
+                            config,
+                        },
+                    );
+                }
+            }
+            "rust-analyzer.debugSingle" => {
+                let Some((cargo_args, mut config)) =
+                    arguments.into_iter().next().and_then(runnable_debug_config)
+                else {
+                    return;
+                };
+                // The debugger launches the executable rather than cargo, so it
+                // has to be built first to know where it is
+                let (cwd, env) = (config.cwd.clone(), config.env.clone());
+                let internal_command = self.common.internal_command;
+                let send = create_ext_action(self.scope, move |path: PathBuf| {
+                    config.program = path.to_string_lossy().to_string();
+                    internal_command.send(InternalCommand::RunAndDebug {
+                        mode: RunDebugMode::Debug,
+                        config,
+                    });
+                });
+                self.common.proxy.cargo_executable(
+                    cargo_args,
+                    cwd,
+                    env,
+                    move |result| {
+                        if let Ok(ProxyResponse::CargoExecutableResponse { path }) =
+                            result
+                        {
+                            send(path);
+                        }
+                    },
+                );
+            }
+            "rust-analyzer.showReferences" => {
+                // The arguments are the uri, the position and the locations
+                let locations = arguments
+                    .into_iter()
+                    .nth(2)
+                    .and_then(|l| serde_json::from_value::<Vec<Location>>(l).ok())
+                    .unwrap_or_default();
+                jump_to_locations(self.common.internal_command, locations);
+            }
+            _ => {
+                self.common
+                    .proxy
+                    .execute_command(plugin_id, command, |result| {
+                        if let Err(err) = result {
+                            tracing::error!("{:?}", err);
+                        }
+                    });
+            }
+        }
+    }
+
     fn single_click(&self, pointer_event: &PointerInputEvent) {
         self.editor.single_click(pointer_event);
     }
@@ -2462,10 +3147,23 @@ fn right_click(&self, pointer_event: &PointerInputEvent) {
 
         let is_file = doc.content.with_untracked(|content| content.is_file());
         let mut menu = Menu::new("");
+        let hunk_items = self.hunk_menu_items(self.cursor_line());
+        if !hunk_items.is_empty() {
+            for item in hunk_items {
+                menu = menu.entry(item);
+            }
+            menu = menu.separator();
+        }
         let cmds = if is_file {
             vec![
                 Some(CommandKind::Focus(FocusCommand::GotoDefinition)),
                 Some(CommandKind::Focus(FocusCommand::GotoTypeDefinition)),
+                Some(CommandKind::Workbench(
+                    LapceWorkbenchCommand::GotoImplementation,
+                )),
+                Some(CommandKind::Workbench(
+                    LapceWorkbenchCommand::GotoDeclaration,
+                )),
                 None,
                 Some(CommandKind::Focus(FocusCommand::Rename)),
                 None,
@@ -2710,6 +3408,10 @@ fn receive_char(&self, c: &str) {
                     InlineCompletionTriggerKind::Automatic,
                 );
 
+                if c.chars().count() == 1 {
+                    self.on_type_format(c);
+                }
+
                 self.apply_deltas(&deltas);
             } else if let Some(direction) = self.inline_find.get_untracked() {
                 self.inline_find(direction.clone(), c);
@@ -2855,7 +3557,30 @@ pub(crate) fn compute_screen_lines(
             let mut rvlines = Vec::new();
             let mut info = HashMap::new();
 
-            let Some(min_info) = *min_info else {
+            // The lines hidden by folding are skipped, so the viewport is in terms
+            // of the displayed lines rather than the actual visual lines
+            let hidden = doc.folding.with(|folding| folding.hidden());
+            let last_line = doc.buffer.with_untracked(|b| b.last_line());
+            let hidden_vlines = if hidden.is_empty() {
+                Vec::new()
+            } else {
+                folding::hidden_vlines(
+                    &hidden,
+                    last_line,
+                    lines.num_vlines(&text_prov),
+                    |line| lines.vline_of_line(&text_prov, line).get(),
+                )
+            };
+
+            let min_info = if hidden_vlines.is_empty() {
+                *min_info
+            } else {
+                let vline = folding::real_vline(&hidden_vlines, min_vline.get());
+                lines
+                    .iter_vlines(text_prov.clone(), false, VLine(vline))
+                    .next()
+            };
+            let Some(min_info) = min_info else {
                 return ScreenLines {
                     lines: Rc::new(rvlines),
                     info: Rc::new(info),
@@ -2867,33 +3592,55 @@ pub(crate) fn compute_screen_lines(
             // TODO: the original was min_line..max_line + 1, are we iterating too little now?
             // the iterator is from min_vline..max_vline
             let count = max_vline.get() - min_vline.get();
-            let iter = lines
-                .iter_rvlines_init(
-                    text_prov,
-                    cache_rev,
-                    config.id,
-                    min_info.rvline,
-                    false,
-                )
-                .take(count);
-
-            for (i, vline_info) in iter.enumerate() {
-                rvlines.push(vline_info.rvline);
-
-                let y_idx = min_vline.get() + i;
-                let vline_y = y_idx * line_height;
-                let line_y = vline_y - vline_info.rvline.line_index * line_height;
-
-                // Add the information to make it cheap to get in the future.
-                // This y positions are shifted by the baseline y0
-                info.insert(
-                    vline_info.rvline,
-                    LineInfo {
-                        y: line_y as f64 - y0,
-                        vline_y: vline_y as f64 - y0,
-                        vline_info,
-                    },
-                );
+            let mut start_rvline = min_info.rvline;
+            'folds: while rvlines.len() < count {
+                let mut iter = lines
+                    .iter_rvlines_init(
+                        text_prov.clone(),
+                        cache_rev,
+                        config.id,
+                        start_rvline,
+                        false,
+                    )
+                    .peekable();
+
+                while let Some(vline_info) = iter.next() {
+                    rvlines.push(vline_info.rvline);
+
+                    let y_idx = min_vline.get() + rvlines.len() - 1;
+                    let vline_y = y_idx * line_height;
+                    let line_y =
+                        vline_y - vline_info.rvline.line_index * line_height;
+
+                    // Add the information to make it cheap to get in the future.
+                    // This y positions are shifted by the baseline y0
+                    info.insert(
+                        vline_info.rvline,
+                        LineInfo {
+                            y: line_y as f64 - y0,
+                            vline_y: vline_y as f64 - y0,
+                            vline_info,
+                        },
+                    );
+
+                    if rvlines.len() >= count {
+                        break 'folds;
+                    }
+
+                    // Continue after the folded range if the next line is hidden
+                    let next_line = iter.peek().map(|info| info.rvline.line);
+                    if let Some(range) = next_line.and_then(|next_line| {
+                        hidden.iter().find(|r| r.hides(next_line))
+                    }) {
+                        if range.end_line >= last_line {
+                            break 'folds;
+                        }
+                        start_rvline = RVLine::new(range.end_line + 1, 0);
+                        continue 'folds;
+                    }
+                }
+
+                break;
             }
 
             ScreenLines {
@@ -2914,6 +3661,8 @@ pub(crate) fn compute_screen_lines(
             let mut last_change: Option<&DiffLines> = None;
             let mut changes = diff_info.changes.iter().peekable();
             let is_right = diff_info.is_right;
+            // The inline editor shows both the removed and the added lines
+            let inline = diff_info.inline;
 
             let line_y = |info: VLineInfo<()>, vline_y: usize| -> usize {
                 vline_y - info.rvline.line_index * line_height
@@ -2921,7 +3670,7 @@ pub(crate) fn compute_screen_lines(
 
             while let Some(change) = changes.next() {
                 match (is_right, change) {
-                    (true, DiffLines::Left(range)) => {
+                    (true, DiffLines::Left(range)) if !inline => {
                         if let Some(DiffLines::Right(_)) = changes.peek() {
                         } else {
                             let len = range.len();
@@ -2948,15 +3697,14 @@ pub(crate) fn compute_screen_lines(
                             y_idx += len;
                         }
                     }
-                    (true, DiffLines::Right(range))
-                    | (false, DiffLines::Left(range)) => {
+                    (_, DiffLines::Left(range) | DiffLines::Right(range)) => {
                         // TODO: count vline count in the range instead
                         let height = range.len();
 
                         diff_sections.push(DiffSection {
                             y_idx,
                             height,
-                            kind: if is_right {
+                            kind: if matches!(change, DiffLines::Right(_)) {
                                 DiffSectionKind::Added
                             } else {
                                 DiffSectionKind::Removed
@@ -2968,7 +3716,7 @@ pub(crate) fn compute_screen_lines(
                         y_idx += height;
 
                         if y_idx < min_vline.get() {
-                            if is_right {
+                            if is_right && !inline {
                                 if let Some(DiffLines::Left(r)) = last_change {
                                     // TODO: count vline count in the other editor since this is skipping an amount dependent on those vlines
                                     let len = r.len() - r.len().min(range.len());
@@ -3029,7 +3777,7 @@ pub(crate) fn compute_screen_lines(
                             }
                         }
 
-                        if is_right {
+                        if is_right && !inline {
                             if let Some(DiffLines::Left(r)) = last_change {
                                 // TODO: count vline count in the other editor since this is skipping an amount dependent on those vlines
                                 let len = r.len() - r.len().min(range.len());
@@ -3155,3 +3903,96 @@ fn parse_hover_resp(
         },
     }
 }
+
+fn goto_response_locations(response: GotoDefinitionResponse) -> Vec<Location> {
+    match response {
+        GotoDefinitionResponse::Scalar(location) => vec![location],
+        GotoDefinitionResponse::Array(locations) => locations,
+        GotoDefinitionResponse::Link(location_links) => location_links
+            .into_iter()
+            .map(|link| Location {
+                uri: link.target_uri,
+                range: link.target_selection_range,
+            })
+            .collect(),
+    }
+}
+
+fn lsp_highlight_ranges(
+    doc: &Doc,
+    highlights: Vec<DocumentHighlight>,
+) -> Vec<(usize, usize, DocumentHighlightKind)> {
+    doc.buffer.with_untracked(|buffer| {
+        highlights
+            .into_iter()
+            .map(|h| {
+                (
+                    buffer.offset_of_position(&h.range.start),
+                    buffer.offset_of_position(&h.range.end),
+                    h.kind.unwrap_or(DocumentHighlightKind::TEXT),
+                )
+            })
+            .collect()
+    })
+}
+
+/// The whole word occurrences of the word at `offset`.
+fn word_highlight_ranges(
+    doc: &Doc,
+    offset: usize,
+) -> Vec<(usize, usize, DocumentHighlightKind)> {
+    let text = doc.buffer.with_untracked(|buffer| buffer.text().clone());
+    let (start, end) = WordCursor::new(&text, offset).select_word();
+    let word = text.slice_to_cow(start..end);
+    if word.is_empty() || !word.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        return Vec::new();
+    }
+
+    let search = FindSearchString {
+        content: word.to_string(),
+        regex: None,
+    };
+    let mut occurrences = Selection::new();
+    Find::find(
+        &text,
+        &search,
+        0,
+        text.len(),
+        CaseMatching::Exact,
+        true,
+        false,
+        &mut occurrences,
+    );
+    occurrences
+        .regions()
+        .iter()
+        .map(|region| (region.min(), region.max(), DocumentHighlightKind::TEXT))
+        .collect()
+}
+
+/// Jump straight to the location if there's only one, otherwise let the user
+/// pick one of them in the palette.
+fn jump_to_locations(
+    internal_command: Listener<InternalCommand>,
+    locations: Vec<Location>,
+) {
+    let mut locations: Vec<EditorLocation> = locations
+        .into_iter()
+        .map(|l| EditorLocation {
+            path: path_from_url(&l.uri),
+            position: Some(EditorPosition::Position(l.range.start)),
+            scroll_offset: None,
+            ignore_unconfirmed: false,
+            same_editor_tab: false,
+        })
+        .collect();
+    match locations.len() {
+        0 => {}
+        1 => internal_command.send(InternalCommand::JumpToLocation {
+            location: locations.remove(0),
+        }),
+        _ => internal_command.send(InternalCommand::PaletteReferences {
+            references: locations,
+        }),
+    }
+}
@@ -1,17 +1,22 @@
-use std::{rc::Rc, sync::atomic};
+use std::{
+    ops::Range,
+    rc::Rc,
+    sync::{atomic, Arc},
+};
 
 use floem::{
     event::EventListener,
     ext_event::create_ext_action,
-    reactive::{RwSignal, Scope},
+    reactive::{create_rw_signal, ReadSignal, RwSignal, Scope},
     style::CursorStyle,
     view::View,
     views::{
-        clip, dyn_stack, editor::id::EditorId, empty, label, stack, svg, Decorators,
+        clip, container, dyn_stack, editor::id::EditorId, empty, label, stack, svg,
+        Decorators,
     },
 };
 use lapce_core::buffer::{
-    diff::{expand_diff_lines, rope_diff, DiffExpand, DiffLines},
+    diff::{expand_diff_lines, rope_diff, DiffBothInfo, DiffExpand, DiffLines},
     rope_text::RopeText,
 };
 use lapce_rpc::{buffer::BufferId, proxy::ProxyResponse};
@@ -19,26 +24,70 @@
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    config::{color::LapceColor, icon::LapceIcons},
-    doc::{Doc, DocContent},
+    app::clickable_icon,
+    config::{color::LapceColor, icon::LapceIcons, LapceConfig},
+    doc::{Doc, DocContent, DocHistory},
     id::{DiffEditorId, EditorTabId},
     main_split::MainSplitData,
+    settings::checkbox,
+    source_control::diff_hunks,
     wave::wave_box,
-    window_tab::CommonData,
+    window_tab::{CommonData, WindowTabData},
+    workspace::LapceWorkspace,
+};
+
+use super::{
+    location::EditorPosition, view::editor_container_view, EditorData,
+    EditorViewKind,
 };
 
-use super::{EditorData, EditorViewKind};
+/// The most tokens of a changed block for which the changed words are looked
+/// for, as the cost grows with the product of the tokens of both sides
+const WORD_DIFF_MAX_TOKENS: usize = 1000;
+
+/// The changed words of the left side and of the right side of a diff, as byte
+/// ranges
+type ChangedWords = (Vec<Range<usize>>, Vec<Range<usize>>);
 
 #[derive(Clone)]
 pub struct DiffInfo {
     pub is_right: bool,
+    /// Whether this is the editor of the inline mode, whose document has both
+    /// sides of each change, the removed lines above the added ones
+    pub inline: bool,
     pub changes: Vec<DiffLines>,
+    /// The words which changed in the removed lines, as offsets in the document
+    pub removed_words: Vec<Range<usize>>,
+    /// The words which changed in the added lines, as offsets in the document
+    pub added_words: Vec<Range<usize>>,
+}
+
+impl DiffInfo {
+    /// The first line of each change in the document of this editor, which is
+    /// where a removal would be for the side without the removed lines
+    pub fn change_lines(&self) -> Vec<usize> {
+        diff_hunks(&self.changes)
+            .into_iter()
+            .map(|hunk| {
+                if self.inline {
+                    hunk.old_start.min(hunk.new_start)
+                } else if self.is_right {
+                    hunk.new_start
+                } else {
+                    hunk.old_start
+                }
+            })
+            .collect()
+    }
 }
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct DiffEditorInfo {
     pub left_content: DocContent,
     pub right_content: DocContent,
+    /// The working file of a merge editor
+    #[serde(default)]
+    pub result_content: Option<DocContent>,
 }
 
 impl DiffEditorInfo {
@@ -82,12 +131,9 @@ pub fn to_data(
                                 doc.init_content(Rope::from(content));
                             }
                         });
-                        common.proxy.get_buffer_head(
-                            history.path.clone(),
-                            move |result| {
-                                send(result);
-                            },
-                        );
+                        history.retrieve(&common.proxy, move |result| {
+                            send(result);
+                        });
                     }
 
                     doc
@@ -114,6 +160,7 @@ pub fn to_data(
 
         let left_doc = new_doc(&self.left_content);
         let right_doc = new_doc(&self.right_content);
+        let result_doc = self.result_content.as_ref().map(new_doc);
 
         let diff_editor_data = DiffEditorData::new(
             cx,
@@ -121,6 +168,8 @@ pub fn to_data(
             editor_tab_id,
             left_doc,
             right_doc,
+            result_doc,
+            data.editors,
             data.common.clone(),
         );
 
@@ -139,23 +188,59 @@ pub struct DiffEditorData {
     pub scope: Scope,
     pub left: Rc<EditorData>,
     pub right: Rc<EditorData>,
+    /// The editor of the working file in a merge editor, where the conflicts
+    /// between the current side on the left and the incoming side on the right
+    /// are resolved
+    pub result: Option<Rc<EditorData>>,
+    /// The read only editor of the inline mode, which has the removed lines
+    /// above the added ones
+    pub inline: Rc<EditorData>,
     pub confirmed: RwSignal<bool>,
     pub focus_right: RwSignal<bool>,
+    /// Whether the result editor has the focus rather than one of the sides
+    pub focus_result: RwSignal<bool>,
+    /// Whether the changes are shown inline instead of side by side
+    pub show_inline: RwSignal<bool>,
+    /// Whether lines which only differ in whitespace are left out of the diff
+    pub ignore_whitespace: RwSignal<bool>,
 }
 
 impl DiffEditorData {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         cx: Scope,
         id: DiffEditorId,
         editor_tab_id: EditorTabId,
         left_doc: Rc<Doc>,
         right_doc: Rc<Doc>,
+        result_doc: Option<Rc<Doc>>,
+        editors: RwSignal<im::HashMap<EditorId, Rc<EditorData>>>,
         common: Rc<CommonData>,
     ) -> Self {
         let cx = cx.create_child();
         let confirmed = cx.create_rw_signal(false);
 
-        let [left, right] = [left_doc, right_doc].map(|doc| {
+        // The inline document is a version of the file, so it gets the syntax
+        // highlighting of the file
+        let path = [&right_doc, &left_doc]
+            .into_iter()
+            .find_map(|doc| match doc.content.get_untracked() {
+                DocContent::File { path, .. } => Some(path),
+                DocContent::History(history) => Some(history.path),
+                DocContent::Local | DocContent::Scratch { .. } => None,
+            })
+            .unwrap_or_default();
+        let inline_doc = Rc::new(Doc::new_history(
+            cx,
+            DocContent::History(DocHistory {
+                path,
+                version: DocHistory::INLINE.to_string(),
+            }),
+            editors,
+            common.clone(),
+        ));
+
+        let new_editor = |doc| {
             let editor_data = EditorData::new_doc(
                 cx,
                 doc,
@@ -166,7 +251,10 @@ pub fn new(
             );
 
             Rc::new(editor_data)
-        });
+        };
+        let [left, right, inline] =
+            [left_doc, right_doc, inline_doc].map(new_editor);
+        let result = result_doc.map(new_editor);
 
         let data = Self {
             id,
@@ -174,8 +262,13 @@ pub fn new(
             scope: cx,
             left,
             right,
+            focus_result: cx.create_rw_signal(result.is_some()),
+            result,
+            inline,
             confirmed,
             focus_right: cx.create_rw_signal(true),
+            show_inline: cx.create_rw_signal(false),
+            ignore_whitespace: cx.create_rw_signal(false),
         };
 
         data.listen_diff_changes();
@@ -187,6 +280,62 @@ pub fn diff_editor_info(&self) -> DiffEditorInfo {
         DiffEditorInfo {
             left_content: self.left.doc().content.get_untracked(),
             right_content: self.right.doc().content.get_untracked(),
+            result_content: self
+                .result
+                .as_ref()
+                .map(|result| result.doc().content.get_untracked()),
+        }
+    }
+
+    /// The editor which has the focus, of the sides and the result
+    pub fn focused_editor(&self, tracked: bool) -> Rc<EditorData> {
+        let get = |signal: RwSignal<bool>| {
+            if tracked {
+                signal.get()
+            } else {
+                signal.get_untracked()
+            }
+        };
+        match &self.result {
+            Some(result) if get(self.focus_result) => result.clone(),
+            _ if get(self.show_inline) => self.inline.clone(),
+            _ if get(self.focus_right) => self.right.clone(),
+            _ => self.left.clone(),
+        }
+    }
+
+    /// All the editors, which are the sides, the inline editor and the result
+    /// of a merge editor
+    pub fn editors(&self) -> impl Iterator<Item = &Rc<EditorData>> {
+        [&self.left, &self.right, &self.inline]
+            .into_iter()
+            .chain(self.result.iter())
+    }
+
+    /// Switch between showing the changes side by side and inline. A merge
+    /// editor always has its sides next to each other.
+    pub fn toggle_inline(&self) {
+        if self.result.is_none() {
+            self.show_inline
+                .update(|show_inline| *show_inline = !*show_inline);
+        }
+    }
+
+    pub fn toggle_ignore_whitespace(&self) {
+        self.ignore_whitespace
+            .update(|ignore_whitespace| *ignore_whitespace = !*ignore_whitespace);
+    }
+
+    /// Move the cursor of the focused editor to the start of the next change,
+    /// or the previous one, going around at the end of the file
+    pub fn go_to_change(&self, forward: bool) {
+        let editor = self.focused_editor(false);
+        let EditorViewKind::Diff(diff_info) = editor.kind.get_untracked() else {
+            return;
+        };
+        let lines = diff_info.change_lines();
+        if let Some(line) = next_change_line(&lines, editor.cursor_line(), forward) {
+            editor.go_to_position(EditorPosition::Line(line), None, None);
         }
     }
 
@@ -199,7 +348,7 @@ pub fn copy(
         let cx = cx.create_child();
         let confirmed = cx.create_rw_signal(true);
 
-        let [left, right] = [&self.left, &self.right].map(|editor_data| {
+        let copy_editor = |editor_data: &Rc<EditorData>| {
             let editor_data = editor_data.copy(
                 cx,
                 None,
@@ -208,15 +357,24 @@ pub fn copy(
             );
 
             Rc::new(editor_data)
-        });
+        };
+        let [left, right, inline] =
+            [&self.left, &self.right, &self.inline].map(copy_editor);
+        let result = self.result.as_ref().map(copy_editor);
 
         let diff_editor = DiffEditorData {
             scope: cx,
             id: diff_editor_id,
             editor_tab_id: cx.create_rw_signal(editor_tab_id),
             focus_right: cx.create_rw_signal(true),
+            focus_result: cx.create_rw_signal(result.is_some()),
+            show_inline: cx.create_rw_signal(self.show_inline.get_untracked()),
+            ignore_whitespace: cx
+                .create_rw_signal(self.ignore_whitespace.get_untracked()),
             left,
             right,
+            inline,
+            result,
             confirmed,
         };
 
@@ -245,6 +403,10 @@ fn listen_diff_changes(&self) {
             })
         };
 
+        let inline = self.inline.clone();
+        let show_inline = self.show_inline;
+        let ignore_whitespace = self.ignore_whitespace;
+
         cx.create_effect(move |_| {
             let (_, left_rev) = left_doc_rev.get();
             let (left_editor_view, left_doc) = (left.kind, left.doc());
@@ -260,11 +422,15 @@ fn listen_diff_changes(&self) {
                     (buffer.atomic_rev(), buffer.text().clone())
                 });
 
+            let show_inline = show_inline.get();
+            let ignore_whitespace = ignore_whitespace.get();
+            let (inline_editor_view, inline_doc) = (inline.kind, inline.doc());
+
             let send = {
                 let right_atomic_rev = right_atomic_rev.clone();
-                create_ext_action(cx, move |changes: Option<Vec<DiffLines>>| {
-                    let changes = if let Some(changes) = changes {
-                        changes
+                create_ext_action(cx, move |diff: Option<DiffResult>| {
+                    let diff = if let Some(diff) = diff {
+                        diff
                     } else {
                         return;
                     };
@@ -278,31 +444,517 @@ fn listen_diff_changes(&self) {
                         return;
                     }
 
+                    let (removed_words, added_words) = diff.words;
                     left_editor_view.set(EditorViewKind::Diff(DiffInfo {
                         is_right: false,
-                        changes: changes.clone(),
+                        inline: false,
+                        changes: diff.changes.clone(),
+                        removed_words,
+                        added_words: Vec::new(),
                     }));
                     right_editor_view.set(EditorViewKind::Diff(DiffInfo {
                         is_right: true,
-                        changes,
+                        inline: false,
+                        changes: diff.changes,
+                        removed_words: Vec::new(),
+                        added_words,
                     }));
+                    if let Some(inline) = diff.inline {
+                        inline_doc.reload(inline.text, true);
+                        let (removed_words, added_words) = inline.words;
+                        inline_editor_view.set(EditorViewKind::Diff(DiffInfo {
+                            is_right: true,
+                            inline: true,
+                            changes: inline.changes,
+                            removed_words,
+                            added_words,
+                        }));
+                    }
                 })
             };
 
             rayon::spawn(move || {
-                let changes = rope_diff(
-                    left_rope,
-                    right_rope,
-                    right_rev,
-                    right_atomic_rev.clone(),
-                    Some(3),
-                );
-                send(changes);
+                let changes = if ignore_whitespace {
+                    rope_diff(
+                        without_whitespace_changes(&left_rope),
+                        without_whitespace_changes(&right_rope),
+                        right_rev,
+                        right_atomic_rev.clone(),
+                        Some(3),
+                    )
+                } else {
+                    rope_diff(
+                        left_rope.clone(),
+                        right_rope.clone(),
+                        right_rev,
+                        right_atomic_rev.clone(),
+                        Some(3),
+                    )
+                };
+                let diff = changes.map(|changes| {
+                    let words = word_changes(
+                        &left_rope,
+                        &right_rope,
+                        &changes,
+                        ignore_whitespace,
+                    );
+                    let inline = show_inline.then(|| {
+                        inline_diff(
+                            &left_rope,
+                            &right_rope,
+                            &changes,
+                            ignore_whitespace,
+                        )
+                    });
+                    DiffResult {
+                        changes,
+                        words,
+                        inline,
+                    }
+                });
+                send(diff);
             });
         });
     }
 }
 
+/// The changes between the sides of a diff editor
+struct DiffResult {
+    changes: Vec<DiffLines>,
+    words: ChangedWords,
+    /// The document of the inline mode, when it is shown
+    inline: Option<InlineDiff>,
+}
+
+/// The document of the inline mode, with the removed lines of each change above
+/// the added ones
+struct InlineDiff {
+    text: Rope,
+    /// The changes, whose both sides are lines of this document
+    changes: Vec<DiffLines>,
+    /// The changed words of the removed lines and of the added lines
+    words: ChangedWords,
+}
+
+/// The text with the whitespace of each line collapsed, so that lines which
+/// only differ in whitespace are the same. It has the same lines as the text.
+fn without_whitespace_changes(text: &Rope) -> Rope {
+    let mut result = String::with_capacity(text.len());
+    for line in text.lines_raw(..) {
+        result.push_str(&line.split_whitespace().collect::<Vec<_>>().join(" "));
+        if line.ends_with('\n') {
+            result.push('\n');
+        }
+    }
+    Rope::from(result)
+}
+
+/// The text of the lines, with a line break at the end
+fn lines_text(text: &Rope, lines: &Range<usize>) -> String {
+    let mut result = text
+        .slice_to_cow(
+            text.offset_of_line(lines.start)..text.offset_of_line(lines.end),
+        )
+        .to_string();
+    if !result.is_empty() && !result.ends_with('\n') {
+        result.push('\n');
+    }
+    result
+}
+
+/// The changed words of the blocks of removed lines which are replaced by added
+/// lines, as offsets in the left text and in the right text
+fn word_changes(
+    left: &Rope,
+    right: &Rope,
+    changes: &[DiffLines],
+    ignore_whitespace: bool,
+) -> ChangedWords {
+    let mut removed = Vec::new();
+    let mut added = Vec::new();
+    for (change, next) in changes.iter().zip(changes.iter().skip(1)) {
+        let (DiffLines::Left(left_lines), DiffLines::Right(right_lines)) =
+            (change, next)
+        else {
+            continue;
+        };
+        let left_start = left.offset_of_line(left_lines.start);
+        let right_start = right.offset_of_line(right_lines.start);
+        let Some((left_words, right_words)) = word_diff(
+            &left.slice_to_cow(left_start..left.offset_of_line(left_lines.end)),
+            &right.slice_to_cow(right_start..right.offset_of_line(right_lines.end)),
+            ignore_whitespace,
+        ) else {
+            continue;
+        };
+        removed.extend(
+            left_words
+                .into_iter()
+                .map(|range| range.start + left_start..range.end + left_start),
+        );
+        added.extend(
+            right_words
+                .into_iter()
+                .map(|range| range.start + right_start..range.end + right_start),
+        );
+    }
+    (removed, added)
+}
+
+/// The document of the inline mode, which has the unchanged lines and the added
+/// lines of the right side, with the removed lines of the left side above the
+/// lines which replace them
+fn inline_diff(
+    left: &Rope,
+    right: &Rope,
+    changes: &[DiffLines],
+    ignore_whitespace: bool,
+) -> InlineDiff {
+    let mut text = String::new();
+    let mut line = 0;
+    let mut inline_changes = Vec::new();
+    for change in changes {
+        let (rope, lines) = match change {
+            DiffLines::Left(range) => (left, range),
+            DiffLines::Right(range) => (right, range),
+            DiffLines::Both(info) => (right, &info.right),
+        };
+        text.push_str(&lines_text(rope, lines));
+        let range = line..line + lines.len();
+        line = range.end;
+        inline_changes.push(match change {
+            DiffLines::Left(_) => DiffLines::Left(range),
+            DiffLines::Right(_) => DiffLines::Right(range),
+            DiffLines::Both(info) => DiffLines::Both(DiffBothInfo {
+                left: range.clone(),
+                right: range,
+                skip: info.skip.clone(),
+            }),
+        });
+    }
+    let text = Rope::from(text);
+    let words = word_changes(&text, &text, &inline_changes, ignore_whitespace);
+    InlineDiff {
+        text,
+        changes: inline_changes,
+        words,
+    }
+}
+
+/// The line of the next change after the line, or of the previous one before
+/// it, going around at the end
+pub fn next_change_line(
+    lines: &[usize],
+    line: usize,
+    forward: bool,
+) -> Option<usize> {
+    if forward {
+        lines
+            .iter()
+            .find(|change| **change > line)
+            .or_else(|| lines.first())
+            .copied()
+    } else {
+        lines
+            .iter()
+            .rev()
+            .find(|change| **change < line)
+            .or_else(|| lines.last())
+            .copied()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum TokenKind {
+    Word,
+    Whitespace,
+    LineBreak,
+    Symbol,
+}
+
+impl TokenKind {
+    fn of(c: char) -> TokenKind {
+        if c == '\n' {
+            TokenKind::LineBreak
+        } else if c.is_whitespace() {
+            TokenKind::Whitespace
+        } else if c.is_alphanumeric() || c == '_' {
+            TokenKind::Word
+        } else {
+            TokenKind::Symbol
+        }
+    }
+}
+
+/// Split the text into words, runs of whitespace, line breaks and the other
+/// characters one by one
+fn tokenize(text: &str, ignore_whitespace: bool) -> Vec<(TokenKind, Range<usize>)> {
+    let mut tokens: Vec<(TokenKind, Range<usize>)> = Vec::new();
+    for (i, c) in text.char_indices() {
+        let kind = TokenKind::of(c);
+        let end = i + c.len_utf8();
+        match tokens.last_mut() {
+            Some((last, range))
+                if *last == kind
+                    && matches!(kind, TokenKind::Word | TokenKind::Whitespace)
+                    && range.end == i =>
+            {
+                range.end = end;
+            }
+            _ => tokens.push((kind, i..end)),
+        }
+    }
+    if ignore_whitespace {
+        tokens.retain(|(kind, _)| *kind != TokenKind::Whitespace);
+    }
+    tokens
+}
+
+/// The byte ranges of the words which differ between the two texts, in the
+/// left text and in the right text. Adjacent changed words on a line make up
+/// one range. It's `None` when the texts are too long to compare.
+pub fn word_diff(
+    left: &str,
+    right: &str,
+    ignore_whitespace: bool,
+) -> Option<ChangedWords> {
+    let left_tokens = tokenize(left, ignore_whitespace);
+    let right_tokens = tokenize(right, ignore_whitespace);
+    if left_tokens.len() > WORD_DIFF_MAX_TOKENS
+        || right_tokens.len() > WORD_DIFF_MAX_TOKENS
+    {
+        return None;
+    }
+
+    // The longest common subsequence of the tokens, where lengths[i][j] is the
+    // length of the one of the tokens from i on the left and j on the right
+    let (n, m) = (left_tokens.len(), right_tokens.len());
+    let same = |i: usize, j: usize| {
+        let (left_kind, left_range) = &left_tokens[i];
+        let (right_kind, right_range) = &right_tokens[j];
+        left_kind == right_kind
+            && left[left_range.clone()] == right[right_range.clone()]
+    };
+    let mut lengths = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lengths[i][j] = if same(i, j) {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut left_matched = vec![false; n];
+    let mut right_matched = vec![false; m];
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if same(i, j) {
+            left_matched[i] = true;
+            right_matched[j] = true;
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+
+    Some((
+        changed_ranges(&left_tokens, &left_matched),
+        changed_ranges(&right_tokens, &right_matched),
+    ))
+}
+
+/// The ranges of the tokens which aren't matched. The ones with only whitespace
+/// between them are merged, but not across line breaks, which are never changes.
+fn changed_ranges(
+    tokens: &[(TokenKind, Range<usize>)],
+    matched: &[bool],
+) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    let mut extend = false;
+    for ((kind, range), matched) in tokens.iter().zip(matched) {
+        if *matched && *kind == TokenKind::Whitespace {
+            continue;
+        }
+        if *matched || *kind == TokenKind::LineBreak {
+            extend = false;
+            continue;
+        }
+        match ranges.last_mut() {
+            Some(last) if extend => last.end = range.end,
+            _ => ranges.push(range.clone()),
+        }
+        extend = true;
+    }
+    ranges
+}
+
+/// The bar above a diff editor, with the number of changes, the buttons to go
+/// through them and the options of how they are shown
+pub fn diff_editor_header_view(diff_editor: DiffEditorData) -> impl View {
+    let config = diff_editor.right.common.config;
+    let right_editor_view = diff_editor.right.kind;
+    let show_inline = diff_editor.show_inline;
+    let ignore_whitespace = diff_editor.ignore_whitespace;
+
+    stack((
+        label(move || {
+            let changes = right_editor_view.with(|kind| match kind {
+                EditorViewKind::Diff(diff_info) => diff_info.change_lines().len(),
+                EditorViewKind::Normal => 0,
+            });
+            match changes {
+                0 => "No changes".to_string(),
+                1 => "1 change".to_string(),
+                n => format!("{n} changes"),
+            }
+        })
+        .style(|s| s.flex_grow(1.0).text_ellipsis().min_width(0.0)),
+        {
+            let diff_editor = diff_editor.clone();
+            diff_option(
+                "Inline",
+                move || show_inline.get(),
+                move || diff_editor.toggle_inline(),
+                config,
+            )
+        },
+        {
+            let diff_editor = diff_editor.clone();
+            diff_option(
+                "Ignore Whitespace",
+                move || ignore_whitespace.get(),
+                move || diff_editor.toggle_ignore_whitespace(),
+                config,
+            )
+        },
+        {
+            let diff_editor = diff_editor.clone();
+            clickable_icon(
+                || LapceIcons::SEARCH_BACKWARD,
+                move || diff_editor.go_to_change(false),
+                || false,
+                || false,
+                || "Previous Change",
+                config,
+            )
+        },
+        clickable_icon(
+            || LapceIcons::SEARCH_FORWARD,
+            move || diff_editor.go_to_change(true),
+            || false,
+            || false,
+            || "Next Change",
+            config,
+        ),
+    ))
+    .style(move |s| {
+        let config = config.get();
+        s.items_center()
+            .padding_left(10.0)
+            .padding_right(6.0)
+            .width_full()
+            .line_height(1.6)
+            .border_bottom(1.0)
+            .border_color(config.color(LapceColor::LAPCE_BORDER))
+            .background(config.color(LapceColor::PANEL_BACKGROUND))
+    })
+}
+
+/// An option of how the changes are shown, which is switched on and off by
+/// clicking it
+fn diff_option(
+    text: &'static str,
+    checked: impl Fn() -> bool + 'static,
+    on_click: impl Fn() + 'static,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    stack((
+        checkbox(checked, config),
+        label(move || text.to_string()).style(|s| s.margin_left(6.0)),
+    ))
+    .on_click_stop(move |_| on_click())
+    .style(|s| {
+        s.items_center()
+            .margin_left(10.0)
+            .cursor(CursorStyle::Pointer)
+    })
+}
+
+/// The result pane of a merge editor, which is the editor of the working file
+/// with a header to complete the merge of the file once its conflicts are
+/// resolved
+pub fn merge_result_view(
+    window_tab_data: Rc<WindowTabData>,
+    workspace: Arc<LapceWorkspace>,
+    result: Rc<EditorData>,
+    is_active: impl Fn(bool) -> bool + 'static + Copy,
+) -> impl View {
+    let config = window_tab_data.common.config;
+    let source_control = window_tab_data.source_control.clone();
+    let doc = result.doc_signal();
+    let result_editor = create_rw_signal(result);
+
+    stack((
+        stack((
+            label(
+                move || match doc.with(|doc| doc.conflicts.with(|c| c.len())) {
+                    0 => "Result".to_string(),
+                    1 => "Result, 1 conflict left".to_string(),
+                    n => format!("Result, {n} conflicts left"),
+                },
+            )
+            .style(|s| s.flex_grow(1.0).text_ellipsis().min_width(0.0)),
+            label(|| "Complete Merge".to_string())
+                .on_click_stop(move |_| {
+                    source_control.complete_merge(doc.get_untracked());
+                })
+                .style(move |s| {
+                    let config = config.get();
+                    s.padding_horiz(10.0)
+                        .border(1.0)
+                        .border_radius(6.0)
+                        .border_color(config.color(LapceColor::LAPCE_BORDER))
+                        .hover(|s| {
+                            s.cursor(CursorStyle::Pointer).background(
+                                config.color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                            )
+                        })
+                        .active(|s| {
+                            s.background(
+                                config.color(
+                                    LapceColor::PANEL_HOVERED_ACTIVE_BACKGROUND,
+                                ),
+                            )
+                        })
+                }),
+        ))
+        .style(move |s| {
+            let config = config.get();
+            s.items_center()
+                .padding_horiz(10.0)
+                .padding_vert(4.0)
+                .width_full()
+                .line_height(1.6)
+                .border_bottom(1.0)
+                .border_color(config.color(LapceColor::LAPCE_BORDER))
+                .background(config.color(LapceColor::PANEL_BACKGROUND))
+        }),
+        container(editor_container_view(
+            window_tab_data,
+            workspace,
+            is_active,
+            result_editor,
+        ))
+        .style(|s| s.width_full().flex_grow(1.0).flex_basis(0.0)),
+    ))
+    .style(|s| s.flex_col())
+}
+
 struct DiffShowMoreSection {
     left_actual_line: usize,
     right_actual_line: usize,
@@ -310,11 +962,13 @@ struct DiffShowMoreSection {
     lines: usize,
 }
 
+/// The bars of the hidden unchanged lines, which expand them. In the inline mode
+/// the inline editor is the right editor, without a left one.
 pub fn diff_show_more_section_view(
-    left_editor: Rc<EditorData>,
+    left_editor: Option<Rc<EditorData>>,
     right_editor: Rc<EditorData>,
 ) -> impl View {
-    let left_editor_view = left_editor.kind;
+    let left_editor_view = left_editor.map(|editor| editor.kind);
     let right_editor_view = right_editor.kind;
     let viewport = right_editor.viewport();
     let config = right_editor.common.config;
@@ -336,8 +990,11 @@ pub fn diff_show_more_section_view(
             while let Some(change) = changes.next() {
                 match change {
                     DiffLines::Left(range) => {
-                        if let Some(DiffLines::Right(_)) = changes.peek() {
-                        } else {
+                        // Removed lines are next to the lines replacing them,
+                        // apart from in the inline mode where they are above
+                        let replaced =
+                            matches!(changes.peek(), Some(DiffLines::Right(_)));
+                        if diff_info.inline || !replaced {
                             let len = range.len();
                             visual_line += len;
                         }
@@ -346,7 +1003,9 @@ pub fn diff_show_more_section_view(
                         let len = range.len();
                         visual_line += len;
 
-                        if let Some(DiffLines::Left(r)) = last_change {
+                        if let (Some(DiffLines::Left(r)), false) =
+                            (last_change, diff_info.inline)
+                        {
                             let len = r.len() - r.len().min(range.len());
                             if len > 0 {
                                 visual_line += len;
@@ -405,16 +1064,18 @@ pub fn diff_show_more_section_view(
             ))
             .on_event_stop(EventListener::PointerDown, move |_| {})
             .on_click_stop(move |_event| {
-                left_editor_view.update(|editor_view| {
-                    if let EditorViewKind::Diff(diff_info) = editor_view {
-                        expand_diff_lines(
-                            &mut diff_info.changes,
-                            section.left_actual_line,
-                            DiffExpand::All,
-                            false,
-                        );
-                    }
-                });
+                if let Some(left_editor_view) = left_editor_view {
+                    left_editor_view.update(|editor_view| {
+                        if let EditorViewKind::Diff(diff_info) = editor_view {
+                            expand_diff_lines(
+                                &mut diff_info.changes,
+                                section.left_actual_line,
+                                DiffExpand::All,
+                                false,
+                            );
+                        }
+                    });
+                }
                 right_editor_view.update(|editor_view| {
                     if let EditorViewKind::Diff(diff_info) = editor_view {
                         expand_diff_lines(
@@ -446,16 +1107,18 @@ pub fn diff_show_more_section_view(
             ))
             .on_event_stop(EventListener::PointerDown, move |_| {})
             .on_click_stop(move |_event| {
-                left_editor_view.update(|editor_view| {
-                    if let EditorViewKind::Diff(diff_info) = editor_view {
-                        expand_diff_lines(
-                            &mut diff_info.changes,
-                            section.left_actual_line,
-                            DiffExpand::Up(10),
-                            false,
-                        );
-                    }
-                });
+                if let Some(left_editor_view) = left_editor_view {
+                    left_editor_view.update(|editor_view| {
+                        if let EditorViewKind::Diff(diff_info) = editor_view {
+                            expand_diff_lines(
+                                &mut diff_info.changes,
+                                section.left_actual_line,
+                                DiffExpand::Up(10),
+                                false,
+                            );
+                        }
+                    });
+                }
                 right_editor_view.update(|editor_view| {
                     if let EditorViewKind::Diff(diff_info) = editor_view {
                         expand_diff_lines(
@@ -487,16 +1150,18 @@ pub fn diff_show_more_section_view(
             ))
             .on_event_stop(EventListener::PointerDown, move |_| {})
             .on_click_stop(move |_event| {
-                left_editor_view.update(|editor_view| {
-                    if let EditorViewKind::Diff(diff_info) = editor_view {
-                        expand_diff_lines(
-                            &mut diff_info.changes,
-                            section.left_actual_line,
-                            DiffExpand::Down(10),
-                            false,
-                        );
-                    }
-                });
+                if let Some(left_editor_view) = left_editor_view {
+                    left_editor_view.update(|editor_view| {
+                        if let EditorViewKind::Diff(diff_info) = editor_view {
+                            expand_diff_lines(
+                                &mut diff_info.changes,
+                                section.left_actual_line,
+                                DiffExpand::Down(10),
+                                false,
+                            );
+                        }
+                    });
+                }
                 right_editor_view.update(|editor_view| {
                     if let EditorViewKind::Diff(diff_info) = editor_view {
                         expand_diff_lines(
@@ -542,3 +1207,50 @@ pub fn diff_show_more_section_view(
     ))
     .style(|s| s.absolute().flex_col().size_pct(100.0, 100.0))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{next_change_line, word_diff};
+
+    #[test]
+    fn test_word_diff() {
+        let (left, right) =
+            word_diff("let x = foo(a, b);\n", "let y = foo(a, c);\n", false)
+                .unwrap();
+        assert_eq!(left, vec![4..5, 15..16]);
+        assert_eq!(right, vec![4..5, 15..16]);
+
+        // Changed words next to each other make up one range
+        let (left, right) = word_diff("a b c d", "a x y d", false).unwrap();
+        assert_eq!(left, vec![2..5]);
+        assert_eq!(right, vec![2..5]);
+
+        // Which isn't the case across lines
+        let (left, right) = word_diff("a b\nc d", "a x\ny d", false).unwrap();
+        assert_eq!(left, vec![2..3, 4..5]);
+        assert_eq!(right, vec![2..3, 4..5]);
+
+        let (left, right) = word_diff("a  b", "a b ", false).unwrap();
+        assert_eq!(left, vec![1..3]);
+        assert_eq!(right, vec![1..2, 3..4]);
+        let (left, right) = word_diff("a  b", "a b ", true).unwrap();
+        assert!(left.is_empty());
+        assert!(right.is_empty());
+
+        // Offsets are in bytes
+        let (left, right) = word_diff("é = 1", "é = 2", false).unwrap();
+        assert_eq!(left, vec![5..6]);
+        assert_eq!(right, vec![5..6]);
+    }
+
+    #[test]
+    fn test_next_change_line() {
+        let lines = [3, 10, 20];
+        assert_eq!(next_change_line(&lines, 0, true), Some(3));
+        assert_eq!(next_change_line(&lines, 3, true), Some(10));
+        assert_eq!(next_change_line(&lines, 20, true), Some(3));
+        assert_eq!(next_change_line(&lines, 10, false), Some(3));
+        assert_eq!(next_change_line(&lines, 3, false), Some(20));
+        assert_eq!(next_change_line(&[], 3, false), None);
+    }
+}
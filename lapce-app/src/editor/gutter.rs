@@ -5,15 +5,36 @@
     cosmic_text::{Attrs, AttrsList, FamilyOwned, TextLayout},
     id::Id,
     peniko::kurbo::{Point, Rect, Size},
+    reactive::create_effect,
     view::{AnyWidget, View, ViewData, Widget},
     Renderer,
 };
 use lapce_core::{buffer::rope_text::RopeText, mode::Mode};
 
-use crate::config::{color::LapceColor, LapceConfig};
+use crate::{
+    config::{color::LapceColor, LapceConfig},
+    source_control::{blame_at_line, blame_gutter_text},
+};
 
 use super::{view::changes_colors_screen, EditorData};
 
+/// How many characters of the blame fit in the blame column of the gutter
+const BLAME_GUTTER_CHARS: f64 = 30.0;
+/// The space between the blame column and the line numbers
+const BLAME_GUTTER_PADDING: f64 = 10.0;
+
+/// The width of the blame column, which is before the line numbers
+pub fn blame_gutter_width(config: &LapceConfig) -> f64 {
+    let family: Vec<FamilyOwned> =
+        FamilyOwned::parse_list(&config.editor.font_family).collect();
+    let attrs = Attrs::new()
+        .family(&family)
+        .font_size(config.editor.font_size() as f32);
+    let mut text_layout = TextLayout::new();
+    text_layout.set_text("0", AttrsList::new(attrs));
+    text_layout.size().width * BLAME_GUTTER_CHARS + BLAME_GUTTER_PADDING
+}
+
 pub struct EditorGutterView {
     id: Id,
     data: ViewData,
@@ -24,6 +45,15 @@ pub struct EditorGutterView {
 pub fn editor_gutter_view(editor: Rc<EditorData>) -> EditorGutterView {
     let id = Id::next();
 
+    // The blame of the file arrives after the gutter has been painted
+    let doc = editor.doc_signal();
+    let show_blame = editor.common.show_blame;
+    create_effect(move |_| {
+        show_blame.track();
+        doc.with(|doc| doc.blame).track();
+        id.request_paint();
+    });
+
     EditorGutterView {
         id,
         data: ViewData::new(id),
@@ -48,7 +78,7 @@ fn paint_head_changes(
         let changes = e_data.doc().head_changes().get_untracked();
         let line_height = config.editor.line_height() as f64;
 
-        let changes = changes_colors_screen(config, &e_data.editor, changes);
+        let changes = changes_colors_screen(config, e_data, changes);
         for (y, height, removed, color) in changes {
             let height = if removed {
                 10.0
@@ -69,6 +99,47 @@ fn paint_head_changes(
         }
     }
 
+    /// Paint who last changed the lines, once at the start of each blame hunk
+    fn paint_blame(
+        &self,
+        cx: &mut PaintCx,
+        viewport: Rect,
+        attrs_list: &AttrsList,
+        config: &LapceConfig,
+    ) {
+        if !self.editor.common.show_blame.get_untracked() {
+            return;
+        }
+
+        let blame = self.editor.doc().blame.get_untracked();
+        let line_height = config.editor.line_height() as f64;
+        let width = blame_gutter_width(config) - BLAME_GUTTER_PADDING;
+
+        cx.save();
+        cx.clip(&Rect::new(0.0, 0.0, width, viewport.height()));
+        self.editor.screen_lines().with_untracked(|screen_lines| {
+            for (i, (line, y)) in screen_lines.iter_lines_y().enumerate() {
+                let Some(hunk) = blame_at_line(&blame, line) else {
+                    continue;
+                };
+                // The first line on screen is labelled even in the middle of a
+                // hunk, so it's clear which commit the lines are from
+                if hunk.start_line != line && i > 0 {
+                    continue;
+                }
+
+                let mut text_layout = TextLayout::new();
+                text_layout.set_text(&blame_gutter_text(hunk), attrs_list.clone());
+                let height = text_layout.size().height;
+                cx.draw_text(
+                    &text_layout,
+                    Point::new(0.0, y + (line_height - height) / 2.0 - viewport.y0),
+                );
+            }
+        });
+        cx.restore();
+    }
+
     fn paint_sticky_headers(
         &self,
         cx: &mut PaintCx,
@@ -207,6 +278,7 @@ fn paint(&mut self, cx: &mut floem::context::PaintCx) {
             }
         });
 
+        self.paint_blame(cx, viewport, &attrs_list, &config);
         self.paint_head_changes(cx, &self.editor, viewport, kind_is_normal, &config);
         self.paint_sticky_headers(cx, kind_is_normal, &config);
     }
@@ -1,11 +1,13 @@
 use std::{cmp, path::PathBuf, rc::Rc, sync::Arc};
 
 use floem::{
-    action::{set_ime_allowed, set_ime_cursor_area},
+    action::{set_ime_allowed, set_ime_cursor_area, show_context_menu},
     context::PaintCx,
+    cosmic_text::{Attrs, AttrsList, FamilyOwned, TextLayout},
     event::{Event, EventListener},
     id::Id,
     keyboard::ModifiersState,
+    menu::{Menu, MenuItem},
     peniko::{
         kurbo::{Line, Point, Rect, Size},
         Color,
@@ -35,16 +37,21 @@
     buffer::{diff::DiffLines, rope_text::RopeText},
     cursor::{CursorAffinity, CursorMode},
 };
-use lapce_rpc::dap_types::{DapId, SourceBreakpoint};
 use lapce_xi_rope::find::CaseMatching;
+use lsp_types::DocumentHighlightKind;
 
-use super::{gutter::editor_gutter_view, EditorData};
+use super::{
+    gutter::{blame_gutter_width, editor_gutter_view},
+    EditorData, EditorViewKind,
+};
 use crate::{
     app::clickable_icon,
     command::InternalCommand,
     config::{color::LapceColor, icon::LapceIcons, LapceConfig},
-    debug::LapceBreakpoint,
+    debug::{BreakpointEditKind, LapceBreakpoint},
     doc::DocContent,
+    folding::{self, FoldingRange},
+    source_control::{blame_annotation, blame_at_line},
     text_input::text_input,
     window_tab::{Focus, WindowTabData},
     workspace::LapceWorkspace,
@@ -80,14 +87,28 @@ pub fn editor_view(
     let view_kind = e_data.kind;
     let screen_lines = e_data.screen_lines();
     create_effect(move |_| {
-        doc.track();
+        doc.with(|doc| doc.folding.track());
         view_kind.track();
         id.request_layout();
     });
 
+    // Redo the text layouts when the folded ranges change, as the folded lines
+    // have a placeholder after them
+    create_effect(move |last_folded: Option<Vec<FoldingRange>>| {
+        let doc = doc.get();
+        let folded = doc.folding.with(|folding| folding.folded.clone());
+        if last_folded.is_some_and(|last_folded| last_folded != folded) {
+            doc.clear_text_cache();
+        }
+        folded
+    });
+
     let hide_cursor = e_data.common.window_common.hide_cursor;
+    let document_highlights = e_data.document_highlights;
     create_effect(move |_| {
         hide_cursor.track();
+        document_highlights.track();
+        doc.with(|doc| doc.blame).track();
         let occurrences = doc.with(|doc| doc.find_result.occurrences);
         occurrences.track();
         id.request_paint();
@@ -103,6 +124,20 @@ pub fn editor_view(
         rev
     });
 
+    // Resolve the code lenses as they scroll into view
+    create_effect(move |_| {
+        let doc = doc.get();
+        doc.code_lens.track();
+        let lines = screen_lines.with(|screen_lines| {
+            let first = screen_lines.lines.first()?.line;
+            let last = screen_lines.lines.last()?.line;
+            Some(first..last + 1)
+        });
+        if let Some(lines) = lines {
+            doc.resolve_code_lens(lines);
+        }
+    });
+
     let config = e_data.common.config;
     let sticky_header_height_signal = e_data.sticky_header_height;
     let editor2 = e_data.clone();
@@ -275,6 +310,54 @@ fn paint_diff_sections(
         }
     }
 
+    /// Paint the words which changed within the changed lines of a diff, over
+    /// the background of the lines
+    fn paint_diff_words(
+        &self,
+        cx: &mut PaintCx,
+        screen_lines: &ScreenLines,
+        config: &LapceConfig,
+    ) {
+        if screen_lines.lines.is_empty() {
+            return;
+        }
+        let min_vline = *screen_lines.lines.first().unwrap();
+        let max_vline = *screen_lines.lines.last().unwrap();
+        let min_line = screen_lines.info(min_vline).unwrap().vline_info.rvline.line;
+        let max_line = screen_lines.info(max_vline).unwrap().vline_info.rvline.line;
+
+        let ed = &self.editor.editor;
+        let start = ed.offset_of_line(min_line);
+        let end = ed.offset_of_line(max_line + 1);
+        let line_height = config.editor.line_height() as f64;
+
+        self.editor.kind.with_untracked(|kind| {
+            let EditorViewKind::Diff(diff_info) = kind else {
+                return;
+            };
+            for (words, color) in [
+                (&diff_info.removed_words, LapceColor::SOURCE_CONTROL_REMOVED),
+                (&diff_info.added_words, LapceColor::SOURCE_CONTROL_ADDED),
+            ] {
+                let color = config.color(color).with_alpha_factor(0.4);
+                for range in words
+                    .iter()
+                    .filter(|range| range.end > start && range.start < end)
+                {
+                    for rect in range_rects(
+                        ed,
+                        screen_lines,
+                        range.start,
+                        range.end,
+                        line_height,
+                    ) {
+                        cx.fill(&rect, color, 0.0);
+                    }
+                }
+            }
+        });
+    }
+
     fn paint_diff_no_code(
         &self,
         cx: &mut PaintCx,
@@ -406,6 +489,53 @@ fn paint_cursor(
         });
     }
 
+    /// Paint the reads and writes of the symbol under the cursor.
+    fn paint_document_highlights(
+        &self,
+        cx: &mut PaintCx,
+        screen_lines: &ScreenLines,
+    ) {
+        let e_data = &self.editor;
+        let ed = &e_data.editor;
+        let Some(highlights) = e_data.document_highlights.get_untracked() else {
+            return;
+        };
+        if highlights.rev != e_data.doc().rev() {
+            return;
+        }
+        let (Some(min_vline), Some(max_vline)) =
+            (screen_lines.lines.first(), screen_lines.lines.last())
+        else {
+            return;
+        };
+        let start = ed.offset_of_line(min_vline.line);
+        let end = ed.offset_of_line(max_vline.line + 1);
+
+        let config = e_data.common.config.get_untracked();
+        let line_height = config.editor.line_height() as f64;
+        let read_color = config.color(LapceColor::EDITOR_DOCUMENT_HIGHLIGHT_READ);
+        let write_color = config.color(LapceColor::EDITOR_DOCUMENT_HIGHLIGHT_WRITE);
+        for (highlight_start, highlight_end, kind) in highlights.ranges {
+            if highlight_end < start || highlight_start > end {
+                continue;
+            }
+            let color = if kind == DocumentHighlightKind::WRITE {
+                write_color
+            } else {
+                read_color
+            };
+            for rect in range_rects(
+                ed,
+                screen_lines,
+                highlight_start,
+                highlight_end,
+                line_height,
+            ) {
+                cx.fill(&rect, color, 0.0);
+            }
+        }
+    }
+
     fn paint_find(&self, cx: &mut PaintCx, screen_lines: &ScreenLines) {
         let visual = self.editor.common.find.visual;
         if !visual.get_untracked() {
@@ -440,62 +570,13 @@ fn paint_find(&self, cx: &mut PaintCx, screen_lines: &ScreenLines) {
         for region in occurrences.with_untracked(|selection| {
             selection.regions_in_range(start, end).to_vec()
         }) {
-            let start = region.min();
-            let end = region.max();
-
-            // TODO(minor): the proper affinity here should probably be tracked by selregion
-            let (start_rvline, start_col) =
-                ed.rvline_col_of_offset(start, CursorAffinity::Forward);
-            let (end_rvline, end_col) =
-                ed.rvline_col_of_offset(end, CursorAffinity::Backward);
-
-            for line_info in screen_lines.iter_line_info() {
-                let rvline_info = line_info.vline_info;
-                let rvline = rvline_info.rvline;
-                let line = rvline.line;
-
-                if rvline < start_rvline {
-                    continue;
-                }
-
-                if rvline > end_rvline {
-                    break;
-                }
-
-                let phantom_text = ed.phantom_text(line);
-
-                let left_col = if rvline == start_rvline { start_col } else { 0 };
-                let (right_col, _vline_end) = if rvline == end_rvline {
-                    let max_col = ed.last_col(rvline_info, true);
-                    (end_col.min(max_col), false)
-                } else {
-                    (ed.last_col(rvline_info, true), true)
-                };
-
-                // Shift it by the phantom text
-                let left_col = phantom_text.col_after(left_col, false);
-                let right_col = phantom_text.col_after(right_col, false);
-
-                // TODO(minor): sel region should have the affinity of the start/end
-                let x0 = ed
-                    .line_point_of_line_col(line, left_col, CursorAffinity::Forward)
-                    .x;
-                let x1 = ed
-                    .line_point_of_line_col(
-                        line,
-                        right_col,
-                        CursorAffinity::Backward,
-                    )
-                    .x;
-
-                if !rvline_info.is_empty() && start != end && left_col != right_col {
-                    rects.push(
-                        Size::new(x1 - x0, line_height)
-                            .to_rect()
-                            .with_origin(Point::new(x0, line_info.vline_y)),
-                    );
-                }
-            }
+            rects.extend(range_rects(
+                ed,
+                screen_lines,
+                region.min(),
+                region.max(),
+                line_height,
+            ));
         }
 
         let color = config.color(LapceColor::EDITOR_FOREGROUND);
@@ -504,6 +585,60 @@ fn paint_find(&self, cx: &mut PaintCx, screen_lines: &ScreenLines) {
         }
     }
 
+    /// Paint who last changed the cursor line, and when, after the end of it
+    fn paint_inline_blame(
+        &self,
+        cx: &mut PaintCx,
+        screen_lines: &ScreenLines,
+        config: &LapceConfig,
+    ) {
+        if !config.editor.inline_blame || !self.is_active.get_untracked() {
+            return;
+        }
+        let e_data = &self.editor;
+        if !e_data.kind.with_untracked(|kind| kind.is_normal()) {
+            return;
+        }
+
+        let doc = e_data.doc();
+        let offset = e_data.cursor().with_untracked(|cursor| cursor.offset());
+        let line = doc.buffer.with_untracked(|b| b.line_of_offset(offset));
+        let blame = doc.blame.get_untracked();
+        let Some(hunk) = blame_at_line(&blame, line) else {
+            return;
+        };
+        let Some(info) = screen_lines.info_for_line(line) else {
+            return;
+        };
+
+        // The annotation goes after the last visual line of a wrapped line
+        let text_layout = e_data.editor.text_layout(line);
+        let Some((index, run)) = text_layout.text.layout_runs().enumerate().last()
+        else {
+            return;
+        };
+        let line_height = config.editor.line_height() as f64;
+
+        let family: Vec<FamilyOwned> =
+            FamilyOwned::parse_list(&config.editor.font_family).collect();
+        let attrs = Attrs::new()
+            .family(&family)
+            .color(config.color(LapceColor::EDITOR_DIM))
+            .font_size(config.editor.inlay_hint_font_size() as f32);
+        let now = chrono::Local::now().timestamp();
+        let mut annotation = TextLayout::new();
+        annotation.set_text(
+            &format!("    {}", blame_annotation(hunk, now)),
+            AttrsList::new(attrs),
+        );
+        let height = annotation.size().height;
+        let y = info.y + (index as f64) * line_height;
+        cx.draw_text(
+            &annotation,
+            Point::new(run.line_w as f64, y + (line_height - height) / 2.0),
+        );
+    }
+
     fn paint_sticky_headers(
         &self,
         cx: &mut PaintCx,
@@ -636,7 +771,16 @@ fn paint_scroll_bar(
         }
 
         let doc = self.editor.doc();
-        let total_len = doc.buffer.with_untracked(|buffer| buffer.last_line());
+        let hidden_len: usize = doc
+            .folding
+            .with_untracked(|f| f.hidden())
+            .iter()
+            .map(|r| r.end_line - r.start_line)
+            .sum();
+        let total_len = doc
+            .buffer
+            .with_untracked(|buffer| buffer.last_line())
+            .saturating_sub(hidden_len);
         let changes = doc.head_changes().get_untracked();
         let total_height = viewport.height();
         let total_width = viewport.width();
@@ -647,7 +791,7 @@ fn paint_scroll_bar(
             (total_len * line_height) as f64
         };
 
-        let colors = changes_colors_all(&config, &self.editor.editor, changes);
+        let colors = changes_colors_all(&config, &self.editor, changes);
         for (y, height, _, color) in colors {
             let y = y / content_height * total_height;
             let height = ((height * line_height) as f64 / content_height
@@ -962,7 +1106,8 @@ fn layout(
             let line_height = config.editor.line_height() as f64;
 
             let width = e_data.editor.max_line_width() + 20.0;
-            let height = line_height * e_data.editor.last_vline().get() as f64;
+            let last_vline = e_data.displayed_vline(e_data.editor.last_vline());
+            let height = line_height * last_vline.get() as f64;
 
             let style = Style::new().width(width).height(height).to_taffy_style();
             cx.set_style(inner_node, style);
@@ -1003,17 +1148,84 @@ fn paint(&mut self, cx: &mut PaintCx) {
         let screen_lines = ed.screen_lines.get_untracked();
         self.paint_diff_sections(cx, viewport, &screen_lines, &config);
         let screen_lines = ed.screen_lines.get_untracked();
+        self.paint_diff_words(cx, &screen_lines, &config);
+        let screen_lines = ed.screen_lines.get_untracked();
+        self.paint_document_highlights(cx, &screen_lines);
+        let screen_lines = ed.screen_lines.get_untracked();
         self.paint_find(cx, &screen_lines);
         let screen_lines = ed.screen_lines.get_untracked();
         self.paint_bracket_highlights_scope_lines(cx, viewport, &screen_lines);
         let screen_lines = ed.screen_lines.get_untracked();
         FloemEditorView::paint_text(cx, ed, viewport, &screen_lines);
         let screen_lines = ed.screen_lines.get_untracked();
+        self.paint_inline_blame(cx, &screen_lines, &config);
+        let screen_lines = ed.screen_lines.get_untracked();
         self.paint_sticky_headers(cx, viewport, &screen_lines);
         self.paint_scroll_bar(cx, viewport, is_local, config);
     }
 }
 
+/// The rects covering the text between `start` and `end` on the screen lines.
+fn range_rects(
+    ed: &Editor,
+    screen_lines: &ScreenLines,
+    start: usize,
+    end: usize,
+    line_height: f64,
+) -> Vec<Rect> {
+    let mut rects = Vec::new();
+    // TODO(minor): the proper affinity here should probably be tracked by selregion
+    let (start_rvline, start_col) =
+        ed.rvline_col_of_offset(start, CursorAffinity::Forward);
+    let (end_rvline, end_col) =
+        ed.rvline_col_of_offset(end, CursorAffinity::Backward);
+
+    for line_info in screen_lines.iter_line_info() {
+        let rvline_info = line_info.vline_info;
+        let rvline = rvline_info.rvline;
+        let line = rvline.line;
+
+        if rvline < start_rvline {
+            continue;
+        }
+
+        if rvline > end_rvline {
+            break;
+        }
+
+        let phantom_text = ed.phantom_text(line);
+
+        let left_col = if rvline == start_rvline { start_col } else { 0 };
+        let (right_col, _vline_end) = if rvline == end_rvline {
+            let max_col = ed.last_col(rvline_info, true);
+            (end_col.min(max_col), false)
+        } else {
+            (ed.last_col(rvline_info, true), true)
+        };
+
+        // Shift it by the phantom text
+        let left_col = phantom_text.col_after(left_col, false);
+        let right_col = phantom_text.col_after(right_col, false);
+
+        // TODO(minor): sel region should have the affinity of the start/end
+        let x0 = ed
+            .line_point_of_line_col(line, left_col, CursorAffinity::Forward)
+            .x;
+        let x1 = ed
+            .line_point_of_line_col(line, right_col, CursorAffinity::Backward)
+            .x;
+
+        if !rvline_info.is_empty() && start != end && left_col != right_col {
+            rects.push(
+                Size::new(x1 - x0, line_height)
+                    .to_rect()
+                    .with_origin(Point::new(x0, line_info.vline_y)),
+            );
+        }
+    }
+    rects
+}
+
 fn get_sticky_header_info(
     editor_data: &EditorData,
     viewport: RwSignal<Rect>,
@@ -1227,8 +1439,10 @@ fn editor_gutter(
     e_data: RwSignal<Rc<EditorData>>,
     is_active: impl Fn(bool) -> bool + 'static + Copy,
 ) -> impl View {
-    let breakpoints = window_tab_data.terminal.debug.breakpoints;
-    let daps = window_tab_data.terminal.debug.daps;
+    let debug = window_tab_data.terminal.debug.clone();
+    let breakpoints = debug.breakpoints;
+    let breakpoint_edit = window_tab_data.breakpoint_edit.clone();
+    let show_blame = window_tab_data.common.show_blame;
 
     let padding_left = 25.0;
     let padding_right = 30.0;
@@ -1257,8 +1471,9 @@ fn editor_gutter(
                 .code_actions()
                 .with(|c| c.get(&offset).map(|c| !c.1.is_empty()).unwrap_or(false));
             if has_code_actions {
+                doc.folding.track();
                 let vline = ed.vline_of_offset(offset, affinity);
-                Some(vline)
+                Some(e_data.with_untracked(|e| e.displayed_vline(vline)))
             } else {
                 None
             }
@@ -1272,6 +1487,12 @@ fn editor_gutter(
 
     let breakpoints_view = move |i: usize| {
         let hovered = create_rw_signal(false);
+        let debug = debug.clone();
+        let breakpoint_edit = breakpoint_edit.clone();
+        let line_of_view = move || {
+            let screen_lines = screen_lines.get_untracked();
+            screen_lines.lines.get(i).map(|r| r.line).unwrap_or(0)
+        };
         container(
             svg(move || config.get().ui_svg(LapceIcons::DEBUG_BREAKPOINT)).style(
                 move |s| {
@@ -1283,33 +1504,25 @@ fn editor_gutter(
                 },
             ),
         )
-        .on_click_stop(move |_| {
-            let screen_lines = screen_lines.get_untracked();
-            let line = screen_lines.lines.get(i).map(|r| r.line).unwrap_or(0);
-            // let line = (viewport.get_untracked().y0
-            //     / config.get_untracked().editor.line_height() as f64)
-            //     .floor() as usize
-            //     + i;
-            let e_data = e_data.get_untracked();
-            let doc = e_data.doc();
-            let offset = doc.buffer.with_untracked(|b| b.offset_of_line(line));
-            if let Some(path) = doc.content.get_untracked().path() {
-                let path_breakpoints = breakpoints
-                    .try_update(|breakpoints| {
+        .on_click_stop({
+            let debug = debug.clone();
+            move |_| {
+                let line = line_of_view();
+                // let line = (viewport.get_untracked().y0
+                //     / config.get_untracked().editor.line_height() as f64)
+                //     .floor() as usize
+                //     + i;
+                let e_data = e_data.get_untracked();
+                let doc = e_data.doc();
+                let offset = doc.buffer.with_untracked(|b| b.offset_of_line(line));
+                if let Some(path) = doc.content.get_untracked().path() {
+                    breakpoints.update(|breakpoints| {
                         let breakpoints =
                             breakpoints.entry(path.clone()).or_default();
                         if let std::collections::btree_map::Entry::Vacant(e) =
                             breakpoints.entry(line)
                         {
-                            e.insert(LapceBreakpoint {
-                                id: None,
-                                verified: false,
-                                message: None,
-                                line,
-                                offset,
-                                dap_line: None,
-                                active: true,
-                            });
+                            e.insert(LapceBreakpoint::new(line, offset));
                         } else {
                             let mut toggle_active = false;
                             if let Some(breakpint) = breakpoints.get_mut(&line) {
@@ -1322,36 +1535,107 @@ fn editor_gutter(
                                 breakpoints.remove(&line);
                             }
                         }
-                        breakpoints.clone()
-                    })
-                    .unwrap();
-                let source_breakpoints: Vec<SourceBreakpoint> = path_breakpoints
-                    .iter()
-                    .filter_map(|(_, b)| {
-                        if b.active {
-                            Some(SourceBreakpoint {
-                                line: b.line + 1,
-                                column: None,
-                                condition: None,
-                                hit_condition: None,
-                                log_message: None,
-                            })
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
-                let daps: Vec<DapId> =
-                    daps.with_untracked(|daps| daps.keys().cloned().collect());
-                for dap_id in daps {
-                    e_data.common.proxy.dap_set_breakpoints(
-                        dap_id,
-                        path.to_path_buf(),
-                        source_breakpoints.clone(),
-                    );
+                    });
+                    debug.sync_breakpoints(path, &e_data.common.proxy);
                 }
             }
         })
+        .on_secondary_click_stop(move |_| {
+            let line = line_of_view();
+            let e_data = e_data.get_untracked();
+            let doc = e_data.doc();
+            let offset = doc.buffer.with_untracked(|b| b.offset_of_line(line));
+            let Some(path) = doc.content.get_untracked().path().cloned() else {
+                return;
+            };
+            let editor_id = e_data.id();
+            let breakpoint = breakpoints.with_untracked(|breakpoints| {
+                breakpoints
+                    .get(&path)
+                    .and_then(|breakpoints| breakpoints.get(&line))
+                    .cloned()
+            });
+            let edit = |kind: BreakpointEditKind| {
+                let breakpoint_edit = breakpoint_edit.clone();
+                let path = path.clone();
+                move || {
+                    breakpoint_edit.start(
+                        editor_id,
+                        path.clone(),
+                        line,
+                        offset,
+                        kind,
+                    )
+                }
+            };
+            let menu = match breakpoint {
+                Some(breakpoint) => {
+                    let kind = if breakpoint.is_logpoint() {
+                        BreakpointEditKind::LogMessage
+                    } else if breakpoint.condition.is_none()
+                        && breakpoint.hit_condition.is_some()
+                    {
+                        BreakpointEditKind::HitCount
+                    } else {
+                        BreakpointEditKind::Condition
+                    };
+                    let toggle = {
+                        let debug = debug.clone();
+                        let path = path.clone();
+                        let proxy = e_data.common.proxy.clone();
+                        move || {
+                            breakpoints.update(|breakpoints| {
+                                if let Some(breakpoint) =
+                                    breakpoints.get_mut(&path).and_then(
+                                        |breakpoints| breakpoints.get_mut(&line),
+                                    )
+                                {
+                                    breakpoint.active = !breakpoint.active;
+                                }
+                            });
+                            debug.sync_breakpoints(&path, &proxy);
+                        }
+                    };
+                    let remove = {
+                        let debug = debug.clone();
+                        let path = path.clone();
+                        let proxy = e_data.common.proxy.clone();
+                        move || {
+                            breakpoints.update(|breakpoints| {
+                                if let Some(breakpoints) = breakpoints.get_mut(&path)
+                                {
+                                    breakpoints.remove(&line);
+                                }
+                            });
+                            debug.sync_breakpoints(&path, &proxy);
+                        }
+                    };
+                    Menu::new("")
+                        .entry(
+                            MenuItem::new("Edit Breakpoint...").action(edit(kind)),
+                        )
+                        .entry(
+                            MenuItem::new(if breakpoint.active {
+                                "Disable Breakpoint"
+                            } else {
+                                "Enable Breakpoint"
+                            })
+                            .action(toggle),
+                        )
+                        .entry(MenuItem::new("Remove Breakpoint").action(remove))
+                }
+                None => Menu::new("")
+                    .entry(
+                        MenuItem::new("Add Conditional Breakpoint...")
+                            .action(edit(BreakpointEditKind::Condition)),
+                    )
+                    .entry(
+                        MenuItem::new("Add Logpoint...")
+                            .action(edit(BreakpointEditKind::LogMessage)),
+                    ),
+            };
+            show_context_menu(menu, None);
+        })
         .on_event_stop(EventListener::PointerEnter, move |_| {
             hovered.set(true);
         })
@@ -1370,6 +1654,14 @@ fn editor_gutter(
     stack((
         stack((
             empty().style(move |s| s.width(padding_left)),
+            empty().style(move |s| {
+                let width = if show_blame.get() {
+                    blame_gutter_width(&config.get())
+                } else {
+                    0.0
+                };
+                s.width(width as f32)
+            }),
             label(move || {
                 let doc = doc.get();
                 doc.buffer.with(|b| b.last_line() + 1).to_string()
@@ -1408,18 +1700,24 @@ fn editor_gutter(
                         };
                         breakpoints.into_iter()
                     },
-                    move |(line, b)| (*line, b.active),
+                    move |(line, b)| {
+                        (*line, b.active, b.is_logpoint(), b.is_conditional())
+                    },
                     move |(line, breakpoint)| {
                         let active = breakpoint.active;
+                        let icon = if breakpoint.is_logpoint() {
+                            LapceIcons::DEBUG_BREAKPOINT_LOG
+                        } else if breakpoint.is_conditional() {
+                            LapceIcons::DEBUG_BREAKPOINT_CONDITIONAL
+                        } else {
+                            LapceIcons::DEBUG_BREAKPOINT
+                        };
                         let line_y = screen_lines
                             .with_untracked(|s| s.info_for_line(line))
                             .map(|l| l.y)
                             .unwrap_or_default();
-                        container(
-                            svg(move || {
-                                config.get().ui_svg(LapceIcons::DEBUG_BREAKPOINT)
-                            })
-                            .style(move |s| {
+                        container(svg(move || config.get().ui_svg(icon)).style(
+                            move |s| {
                                 let config = config.get();
                                 let size = config.ui.icon_size() as f32 + 2.0;
                                 let color = if active {
@@ -1429,8 +1727,8 @@ fn editor_gutter(
                                 };
                                 let color = config.color(color);
                                 s.size(size, size).color(color)
-                            }),
-                        )
+                            },
+                        ))
                         .style(move |s| {
                             let config = config.get();
                             s.absolute()
@@ -1462,7 +1760,91 @@ fn editor_gutter(
                             scroll_delta.set(pointer_event.delta);
                         }
                     })
+                    .on_event_cont(EventListener::PointerDown, move |event| {
+                        if let Event::PointerDown(pointer_event) = event {
+                            if pointer_event.button.is_secondary() {
+                                let e_data = e_data.get_untracked();
+                                let mode =
+                                    e_data.cursor().with_untracked(|c| c.get_mode());
+                                let y = pointer_event.pos.y
+                                    + viewport.get_untracked().y0;
+                                let (offset, _) = e_data
+                                    .editor
+                                    .offset_of_point(mode, Point::new(0.0, y));
+                                let line = e_data
+                                    .doc()
+                                    .buffer
+                                    .with_untracked(|b| b.line_of_offset(offset));
+                                e_data.show_hunk_menu(line);
+                            }
+                        }
+                    })
                     .style(|s| s.size_pct(100.0, 100.0)),
+                dyn_stack(
+                    move || {
+                        let doc = doc.get();
+                        let folding = doc.folding.get();
+                        screen_lines.with(|screen_lines| {
+                            screen_lines
+                                .lines
+                                .iter()
+                                .filter(|rvline| rvline.line_index == 0)
+                                .filter_map(|rvline| {
+                                    let line = rvline.line;
+                                    if folding.folded_at(line).is_some() {
+                                        Some((line, true))
+                                    } else if folding.range_at(line).is_some() {
+                                        Some((line, false))
+                                    } else {
+                                        None
+                                    }
+                                })
+                                .collect::<Vec<_>>()
+                        })
+                    },
+                    |(line, folded)| (*line, *folded),
+                    move |(line, folded)| {
+                        let icon = if folded {
+                            LapceIcons::ITEM_CLOSED
+                        } else {
+                            LapceIcons::ITEM_OPENED
+                        };
+                        container(svg(move || config.get().ui_svg(icon)).style(
+                            move |s| {
+                                let config = config.get();
+                                let size = config.ui.icon_size() as f32;
+                                s.size(size, size)
+                                    .color(config.color(LapceColor::EDITOR_DIM))
+                            },
+                        ))
+                        .on_click_stop(move |_| {
+                            e_data.get_untracked().toggle_fold(line);
+                        })
+                        .style(move |s| {
+                            let config = config.get();
+                            let size = config.ui.icon_size() as f32;
+                            let line_height = config.editor.line_height() as f32;
+                            let line_y = screen_lines
+                                .with(|s| s.info_for_line(line))
+                                .map(|l| l.y);
+                            s.absolute()
+                                .size(size, line_height)
+                                .items_center()
+                                .margin_left(
+                                    gutter_width.get() as f32 + padding_right
+                                        - size
+                                        - 2.0,
+                                )
+                                .margin_top(
+                                    line_y.unwrap_or_default() as f32
+                                        - viewport.get().y0 as f32,
+                                )
+                                .cursor(CursorStyle::Pointer)
+                                .apply_if(line_y.is_none(), |s| s.hide())
+                        })
+                    },
+                )
+                .style(|s| s.absolute().size_pct(100.0, 100.0)),
                 container(
                     svg(move || config.get().ui_svg(LapceIcons::LIGHTBULB)).style(
                         move |s| {
@@ -1724,7 +2106,9 @@ fn editor_content(
         let config = config.get_untracked();
         let line_height = config.editor.line_height();
         // TODO: is there a good way to avoid the calculation of the vline here?
+        e_data.doc().folding.track();
         let vline = e_data.editor.vline_of_rvline(rvline);
+        let vline = e_data.displayed_vline(vline);
         let rect = Rect::from_origin_size(
             (x, (vline.get() * line_height) as f64),
             (width, line_height as f64),
@@ -2119,9 +2503,10 @@ fn changes_color_iter<'a>(
 /// Returns `(y, height_idx, removed, color)`
 pub fn changes_colors_screen(
     config: &LapceConfig,
-    editor: &Editor,
+    e_data: &EditorData,
     changes: im::Vector<DiffLines>,
 ) -> Vec<(f64, usize, bool, Color)> {
+    let editor = &e_data.editor;
     let screen_lines = editor.screen_lines.get_untracked();
 
     let Some((min, max)) = screen_lines.rvline_range() else {
@@ -2146,7 +2531,7 @@ pub fn changes_colors_screen(
             }
 
             let rvline = editor.rvline_of_line(pre_line);
-            let vline = editor.vline_of_line(pre_line);
+            let vline = e_data.displayed_vline(editor.vline_of_line(pre_line));
             let y = (vline.0 * line_height) as f64;
             let height = {
                 // Accumulate the number of line indices each potentially wrapped line spans
@@ -2173,10 +2558,18 @@ pub fn changes_colors_screen(
 /// Returns `(y, height_idx, removed, color)`
 pub fn changes_colors_all(
     config: &LapceConfig,
-    ed: &Editor,
+    e_data: &EditorData,
     changes: im::Vector<DiffLines>,
 ) -> Vec<(f64, usize, bool, Color)> {
+    let ed = &e_data.editor;
     let line_height = config.editor.line_height();
+    // The lines hidden by folding take up no space on the scrollbar
+    let hidden_vlines = e_data.hidden_vlines();
+    let is_hidden = |vline: usize| {
+        hidden_vlines
+            .iter()
+            .any(|(start, count)| *start <= vline && vline < start + count)
+    };
 
     let mut line = 0;
     let mut colors = Vec::new();
@@ -2204,11 +2597,13 @@ pub fn changes_colors_all(
                 continue;
             };
 
-            let y = info.vline.get() * line_height;
+            let vline = folding::displayed_vline(&hidden_vlines, info.vline.get());
+            let y = vline * line_height;
             let end_line = info.rvline.line + len;
             let height = vline_iter
                 .by_ref()
                 .peeking_take_while(|info| info.rvline.line < end_line)
+                .filter(|info| !is_hidden(info.vline.get()))
                 .count();
             let removed = len == 0;
 
@@ -121,8 +121,16 @@ pub fn to_data(
 }
 
 pub enum EditorTabChildSource {
-    Editor { path: PathBuf, doc: Rc<Doc> },
-    DiffEditor { left: Rc<Doc>, right: Rc<Doc> },
+    Editor {
+        path: PathBuf,
+        doc: Rc<Doc>,
+    },
+    DiffEditor {
+        left: Rc<Doc>,
+        right: Rc<Doc>,
+        /// The working file of a merge editor
+        result: Option<Rc<Doc>>,
+    },
     NewFileEditor,
     Settings,
     ThemeColorSettings,
@@ -264,6 +272,30 @@ pub fn view_info(
                     .with(|diff_editors| diff_editors.get(&diff_editor_id).cloned());
                 let confirmed = diff_editor_data.as_ref().map(|d| d.confirmed);
 
+                // A merge editor is named after the file the result goes to
+                if let Some(result) =
+                    diff_editor_data.as_ref().and_then(|d| d.result.clone())
+                {
+                    let (content, is_pristine) = result.doc_signal().with(|doc| {
+                        (doc.content.get(), doc.buffer.with(|b| b.is_pristine()))
+                    });
+                    if let DocContent::File { path, .. } = content {
+                        let (icon, color) = config.file_svg(&path);
+                        return EditorTabChildViewInfo {
+                            icon,
+                            color,
+                            path: format!(
+                                "{} (Merge)",
+                                path.file_name()
+                                    .unwrap_or_default()
+                                    .to_string_lossy()
+                            ),
+                            confirmed,
+                            is_pristine,
+                        };
+                    }
+                }
+
                 let info = diff_editor_data
                     .map(|diff_editor_data| {
                         [diff_editor_data.left, diff_editor_data.right].map(|data| {
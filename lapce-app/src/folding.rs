@@ -0,0 +1,393 @@
+use serde::{Deserialize, Serialize};
+
+/// A foldable region of a document.
+/// When folded, `start_line` stays visible and the lines after it up to and
+/// including `end_line` are hidden.
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub struct FoldingRange {
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+impl FoldingRange {
+    pub fn new(start_line: usize, end_line: usize) -> Self {
+        Self {
+            start_line,
+            end_line,
+        }
+    }
+
+    pub fn contains(&self, line: usize) -> bool {
+        self.start_line <= line && line <= self.end_line
+    }
+
+    /// Whether the line is hidden when this range is folded
+    pub fn hides(&self, line: usize) -> bool {
+        self.start_line < line && line <= self.end_line
+    }
+
+    /// Move the range after `inval_count` lines starting at `start_line` were
+    /// replaced by `new_count` lines.
+    /// Returns `None` if the edit removed the range or overlapped its first line
+    /// together with lines outside of it.
+    fn apply_inval(
+        self,
+        start_line: usize,
+        inval_count: usize,
+        new_count: usize,
+    ) -> Option<Self> {
+        let inval_end = start_line + inval_count;
+        let shift = |line: usize| (line + new_count).checked_sub(inval_count);
+        if self.end_line < start_line {
+            Some(self)
+        } else if self.start_line >= inval_end {
+            Some(Self::new(shift(self.start_line)?, shift(self.end_line)?))
+        } else if self.start_line <= start_line && inval_end <= self.end_line + 1 {
+            let end_line = shift(self.end_line)?;
+            (end_line > self.start_line)
+                .then_some(Self::new(self.start_line, end_line))
+        } else {
+            None
+        }
+    }
+}
+
+/// The folding state of a document
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Folding {
+    /// The ranges which can be folded, sorted by their start line
+    pub ranges: Vec<FoldingRange>,
+    /// The ranges which are folded, sorted by their start line.
+    /// These can be nested, in which case the outermost range decides what's hidden.
+    pub folded: Vec<FoldingRange>,
+}
+
+impl Folding {
+    pub fn set_ranges(&mut self, mut ranges: Vec<FoldingRange>) {
+        ranges.retain(|r| r.end_line > r.start_line);
+        ranges.sort();
+        ranges.dedup();
+        self.ranges = ranges;
+    }
+
+    pub fn set_folded(&mut self, mut folded: Vec<FoldingRange>) {
+        folded.retain(|r| r.end_line > r.start_line);
+        folded.sort();
+        folded.dedup();
+        self.folded = folded;
+    }
+
+    /// The largest foldable range starting on the line
+    pub fn range_at(&self, line: usize) -> Option<FoldingRange> {
+        self.ranges
+            .iter()
+            .filter(|r| r.start_line == line)
+            .max_by_key(|r| r.end_line)
+            .copied()
+    }
+
+    /// The largest folded range starting on the line
+    pub fn folded_at(&self, line: usize) -> Option<FoldingRange> {
+        self.folded
+            .iter()
+            .filter(|r| r.start_line == line)
+            .max_by_key(|r| r.end_line)
+            .copied()
+    }
+
+    pub fn is_hidden(&self, line: usize) -> bool {
+        self.folded.iter().any(|r| r.hides(line))
+    }
+
+    /// Fold the range starting on the line, or otherwise the innermost unfolded
+    /// range containing it. Returns whether anything was folded.
+    pub fn fold(&mut self, line: usize) -> bool {
+        let range = self
+            .range_at(line)
+            .filter(|r| !self.folded.contains(r))
+            .or_else(|| {
+                self.ranges
+                    .iter()
+                    .filter(|r| r.contains(line) && !self.folded.contains(r))
+                    .min_by_key(|r| r.end_line - r.start_line)
+                    .copied()
+            });
+        let Some(range) = range else {
+            return false;
+        };
+        let idx = self.folded.partition_point(|r| *r < range);
+        self.folded.insert(idx, range);
+        true
+    }
+
+    /// Unfold the range starting on the line, or otherwise the innermost folded
+    /// range containing it. Returns whether anything was unfolded.
+    pub fn unfold(&mut self, line: usize) -> bool {
+        let range = self.folded_at(line).or_else(|| {
+            self.folded
+                .iter()
+                .filter(|r| r.contains(line))
+                .min_by_key(|r| r.end_line - r.start_line)
+                .copied()
+        });
+        let Some(range) = range else {
+            return false;
+        };
+        self.folded.retain(|r| *r != range);
+        true
+    }
+
+    /// Fold or unfold the range starting on the line
+    pub fn toggle(&mut self, line: usize) {
+        if let Some(range) = self.folded_at(line) {
+            self.folded.retain(|r| *r != range);
+        } else if self.range_at(line).is_some() {
+            self.fold(line);
+        }
+    }
+
+    /// Unfold every range which hides the line.
+    /// Returns whether anything was unfolded.
+    pub fn reveal(&mut self, line: usize) -> bool {
+        let len = self.folded.len();
+        self.folded.retain(|r| !r.hides(line));
+        self.folded.len() != len
+    }
+
+    pub fn fold_all(&mut self) {
+        self.folded = self.ranges.clone();
+    }
+
+    pub fn unfold_all(&mut self) {
+        self.folded.clear();
+    }
+
+    /// Fold every range nested `level` deep, where the ranges which aren't inside
+    /// any other range are at level 1.
+    pub fn fold_level(&mut self, level: usize) {
+        let mut parents: Vec<FoldingRange> = Vec::new();
+        let mut folded = std::mem::take(&mut self.folded);
+        // Sort by start line, and put the outer ranges first when they start on
+        // the same line
+        let mut ranges = self.ranges.clone();
+        ranges.sort_by_key(|r| (r.start_line, std::cmp::Reverse(r.end_line)));
+        for range in ranges {
+            while parents
+                .last()
+                .is_some_and(|p| p.end_line < range.start_line)
+            {
+                parents.pop();
+            }
+            if parents.len() + 1 == level {
+                folded.push(range);
+            }
+            parents.push(range);
+        }
+        self.set_folded(folded);
+    }
+
+    /// The outermost folded ranges, which are what decides the hidden lines
+    pub fn hidden(&self) -> Vec<FoldingRange> {
+        let mut hidden: Vec<FoldingRange> = Vec::new();
+        for range in self.folded.iter() {
+            match hidden.last_mut() {
+                Some(last) if last.contains(range.start_line) => {
+                    last.end_line = last.end_line.max(range.end_line);
+                }
+                _ => hidden.push(*range),
+            }
+        }
+        hidden
+    }
+
+    /// Update the ranges after `inval_count` lines starting at `start_line` were
+    /// replaced by `new_count` lines.
+    pub fn apply_inval(
+        &mut self,
+        start_line: usize,
+        inval_count: usize,
+        new_count: usize,
+    ) {
+        self.ranges = self
+            .ranges
+            .iter()
+            .filter_map(|r| r.apply_inval(start_line, inval_count, new_count))
+            .collect();
+        self.folded = self
+            .folded
+            .iter()
+            .filter_map(|r| r.apply_inval(start_line, inval_count, new_count))
+            .collect();
+    }
+}
+
+/// Get the hidden visual lines, as `(first hidden vline, count)`, of the
+/// outermost folded ranges.
+pub fn hidden_vlines(
+    hidden: &[FoldingRange],
+    last_line: usize,
+    num_vlines: usize,
+    vline_of_line: impl Fn(usize) -> usize,
+) -> Vec<(usize, usize)> {
+    hidden
+        .iter()
+        .filter(|r| r.start_line < last_line)
+        .map(|r| {
+            let start = vline_of_line(r.start_line + 1);
+            let end = if r.end_line < last_line {
+                vline_of_line(r.end_line + 1)
+            } else {
+                num_vlines
+            };
+            (start, end.saturating_sub(start))
+        })
+        .collect()
+}
+
+/// Convert the index of a visual line as it's displayed, with the folded lines
+/// left out, into the actual visual line.
+pub fn real_vline(hidden_vlines: &[(usize, usize)], vline: usize) -> usize {
+    let mut real = vline;
+    for (start, count) in hidden_vlines {
+        if *start <= real {
+            real += count;
+        } else {
+            break;
+        }
+    }
+    real
+}
+
+/// Convert a visual line into the index it's displayed at, with the folded
+/// lines left out.
+/// A hidden line is displayed at the line which folds it.
+pub fn displayed_vline(hidden_vlines: &[(usize, usize)], vline: usize) -> usize {
+    let mut displayed = vline;
+    for (start, count) in hidden_vlines {
+        if *start > vline {
+            break;
+        }
+        if vline < start + count {
+            return displayed - (vline - start) - 1;
+        }
+        displayed -= count;
+    }
+    displayed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(start_line: usize, end_line: usize) -> FoldingRange {
+        FoldingRange::new(start_line, end_line)
+    }
+
+    fn folding() -> Folding {
+        let mut folding = Folding::default();
+        folding.set_ranges(vec![
+            range(0, 10),
+            range(1, 4),
+            range(2, 3),
+            range(6, 9),
+            range(12, 14),
+        ]);
+        folding
+    }
+
+    #[test]
+    fn test_fold_and_unfold() {
+        let mut folding = folding();
+        assert!(folding.fold(1));
+        assert_eq!(folding.folded, vec![range(1, 4)]);
+        // Inside a range which doesn't start on the line, the innermost is used
+        assert!(folding.fold(7));
+        assert_eq!(folding.folded, vec![range(1, 4), range(6, 9)]);
+        assert!(folding.fold(5));
+        assert_eq!(folding.folded, vec![range(0, 10), range(1, 4), range(6, 9)]);
+        assert!(!folding.fold(11));
+
+        assert!(folding.unfold(0));
+        assert_eq!(folding.folded, vec![range(1, 4), range(6, 9)]);
+        assert!(folding.unfold(8));
+        assert_eq!(folding.folded, vec![range(1, 4)]);
+
+        folding.toggle(1);
+        assert!(folding.folded.is_empty());
+        folding.toggle(1);
+        assert_eq!(folding.folded, vec![range(1, 4)]);
+        assert!(folding.reveal(3));
+        assert!(folding.folded.is_empty());
+    }
+
+    #[test]
+    fn test_fold_level() {
+        let mut folding = folding();
+        folding.fold_level(1);
+        assert_eq!(folding.folded, vec![range(0, 10), range(12, 14)]);
+        folding.unfold_all();
+        folding.fold_level(2);
+        assert_eq!(folding.folded, vec![range(1, 4), range(6, 9)]);
+        folding.unfold_all();
+        folding.fold_level(3);
+        assert_eq!(folding.folded, vec![range(2, 3)]);
+    }
+
+    #[test]
+    fn test_hidden() {
+        let mut folding = folding();
+        folding.fold_all();
+        assert_eq!(folding.hidden(), vec![range(0, 10), range(12, 14)]);
+        assert!(folding.is_hidden(10));
+        assert!(!folding.is_hidden(0));
+        assert!(!folding.is_hidden(11));
+    }
+
+    #[test]
+    fn test_apply_inval() {
+        let mut folding = folding();
+        folding.fold(6);
+        // Insert two lines before the ranges
+        folding.apply_inval(11, 1, 3);
+        assert_eq!(
+            folding.ranges,
+            vec![
+                range(0, 10),
+                range(1, 4),
+                range(2, 3),
+                range(6, 9),
+                range(14, 16)
+            ]
+        );
+        // Remove a line inside of a range
+        folding.apply_inval(7, 2, 1);
+        assert_eq!(folding.folded, vec![range(6, 8)]);
+        // Edit the start line of a range together with the line before it
+        folding.apply_inval(5, 2, 1);
+        assert!(folding.folded.is_empty());
+    }
+
+    #[test]
+    fn test_vline_mapping() {
+        // Lines 3..=5 and 9..=9 hidden, without any wrapping
+        let vlines = hidden_vlines(&[range(2, 5), range(8, 9)], 20, 21, |line| line);
+        assert_eq!(vlines, vec![(3, 3), (9, 1)]);
+
+        assert_eq!(real_vline(&vlines, 2), 2);
+        assert_eq!(real_vline(&vlines, 3), 6);
+        assert_eq!(real_vline(&vlines, 5), 8);
+        assert_eq!(real_vline(&vlines, 6), 10);
+
+        assert_eq!(displayed_vline(&vlines, 2), 2);
+        assert_eq!(displayed_vline(&vlines, 4), 2);
+        assert_eq!(displayed_vline(&vlines, 6), 3);
+        assert_eq!(displayed_vline(&vlines, 9), 5);
+        assert_eq!(displayed_vline(&vlines, 10), 6);
+
+        // A range reaching the end of the document
+        let vlines = hidden_vlines(&[range(18, 20)], 20, 21, |line| line);
+        assert_eq!(vlines, vec![(19, 2)]);
+    }
+}
@@ -0,0 +1,443 @@
+use std::{ops::Range, path::PathBuf, rc::Rc};
+
+use floem::{
+    ext_event::create_ext_action,
+    reactive::{RwSignal, Scope},
+    views::VirtualVector,
+};
+use lapce_rpc::{
+    proxy::ProxyResponse,
+    source_control::{FileDiff, GitCommit},
+};
+
+use crate::{command::InternalCommand, window_tab::CommonData};
+
+/// How many commits are requested from the proxy at a time
+const PAGE_SIZE: usize = 200;
+
+/// The part of the commit graph in the row of a commit. The lanes are the
+/// columns the lines of the branches go down in, and a line goes from one lane
+/// to another when branches split or merge.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GraphRow {
+    /// The lane of the dot of the commit, which the rows of the changed files
+    /// of a commit don't have
+    pub lane: Option<usize>,
+    /// The lines in the upper half of the row, from a lane at the top to a lane
+    /// in the middle
+    pub top: Vec<(usize, usize)>,
+    /// The lines in the lower half of the row, from a lane in the middle to a
+    /// lane at the bottom
+    pub bottom: Vec<(usize, usize)>,
+}
+
+impl GraphRow {
+    /// The row below this one which only has the lanes going through it
+    fn below(&self) -> GraphRow {
+        let mut lanes = self.bottom.iter().map(|(_, to)| *to).collect::<Vec<_>>();
+        lanes.sort();
+        lanes.dedup();
+        let lines = lanes
+            .into_iter()
+            .map(|lane| (lane, lane))
+            .collect::<Vec<_>>();
+        GraphRow {
+            lane: None,
+            top: lines.clone(),
+            bottom: lines,
+        }
+    }
+
+    /// The number of lanes the row draws in
+    pub fn width(&self) -> usize {
+        self.top
+            .iter()
+            .chain(self.bottom.iter())
+            .flat_map(|(from, to)| [*from, *to])
+            .chain(self.lane)
+            .max()
+            .map(|lane| lane + 1)
+            .unwrap_or(0)
+    }
+}
+
+/// Lay out the graph of the commits, which are ordered newest first with the
+/// children of a commit before it.
+pub fn commit_graph(commits: &[GitCommit]) -> Vec<GraphRow> {
+    // The commit each lane goes down to
+    let mut lanes: Vec<Option<&str>> = Vec::new();
+    let free_lane = |lanes: &mut Vec<Option<&str>>| {
+        lanes
+            .iter()
+            .position(|lane| lane.is_none())
+            .unwrap_or_else(|| {
+                lanes.push(None);
+                lanes.len() - 1
+            })
+    };
+
+    let mut rows = Vec::new();
+    for commit in commits {
+        let lane = lanes
+            .iter()
+            .position(|lane| *lane == Some(commit.id.as_str()))
+            .unwrap_or_else(|| free_lane(&mut lanes));
+
+        let mut top = Vec::new();
+        for (i, next) in lanes.iter_mut().enumerate() {
+            match next {
+                Some(id) if *id == commit.id => {
+                    top.push((i, lane));
+                    *next = None;
+                }
+                Some(_) => top.push((i, i)),
+                None => {}
+            }
+        }
+
+        let mut bottom = lanes
+            .iter()
+            .enumerate()
+            .filter(|(_, next)| next.is_some())
+            .map(|(i, _)| (i, i))
+            .collect::<Vec<_>>();
+        for (n, parent) in commit.parents.iter().enumerate() {
+            let parent_lane = lanes
+                .iter()
+                .position(|lane| *lane == Some(parent.as_str()))
+                .unwrap_or_else(|| {
+                    // The first parent continues the lane of the commit
+                    if n == 0 && lanes[lane].is_none() {
+                        lane
+                    } else {
+                        free_lane(&mut lanes)
+                    }
+                });
+            lanes[parent_lane] = Some(parent.as_str());
+            bottom.push((lane, parent_lane));
+        }
+
+        while lanes.last() == Some(&None) {
+            lanes.pop();
+        }
+        rows.push(GraphRow {
+            lane: Some(lane),
+            top,
+            bottom,
+        });
+    }
+    rows
+}
+
+#[derive(Clone)]
+pub struct GitLogCommit {
+    pub commit: GitCommit,
+    pub graph: GraphRow,
+    pub expanded: bool,
+    /// The changed files, which are `None` until they were requested
+    pub diffs: Option<Vec<FileDiff>>,
+}
+
+/// A visible row of the log, which is a commit or one of its changed files
+#[derive(Clone)]
+pub struct GitLogViewItem {
+    /// The index of the commit in the log
+    pub index: usize,
+    pub commit: GitCommit,
+    /// The changed file the row is for, or `None` for the row of the commit
+    pub file: Option<FileDiff>,
+    pub expanded: bool,
+    pub graph: GraphRow,
+}
+
+#[derive(Clone, Default)]
+pub struct GitLog {
+    pub commits: im::Vector<GitLogCommit>,
+}
+
+impl GitLog {
+    /// Add the next page of commits, and lay out the graph again when there is
+    /// one, as the lanes of the new commits depend on the ones before
+    fn append(&mut self, commits: Vec<GitCommit>, with_graph: bool) {
+        self.commits
+            .extend(commits.into_iter().map(|commit| GitLogCommit {
+                commit,
+                graph: GraphRow::default(),
+                expanded: false,
+                diffs: None,
+            }));
+        if with_graph {
+            let commits = self
+                .commits
+                .iter()
+                .map(|commit| commit.commit.clone())
+                .collect::<Vec<_>>();
+            for (commit, graph) in
+                self.commits.iter_mut().zip(commit_graph(&commits))
+            {
+                commit.graph = graph;
+            }
+        }
+    }
+}
+
+impl VirtualVector<GitLogViewItem> for GitLog {
+    fn total_len(&self) -> usize {
+        self.commits
+            .iter()
+            .map(|commit| {
+                let files = if commit.expanded {
+                    commit.diffs.as_ref().map(|diffs| diffs.len()).unwrap_or(0)
+                } else {
+                    0
+                };
+                files + 1
+            })
+            .sum()
+    }
+
+    fn slice(
+        &mut self,
+        range: Range<usize>,
+    ) -> impl Iterator<Item = GitLogViewItem> {
+        let mut view_items = Vec::new();
+        let mut i = 0;
+        for (index, commit) in self.commits.iter().enumerate() {
+            if i >= range.end {
+                break;
+            }
+            if i >= range.start {
+                view_items.push(GitLogViewItem {
+                    index,
+                    commit: commit.commit.clone(),
+                    file: None,
+                    expanded: commit.expanded,
+                    graph: commit.graph.clone(),
+                });
+            }
+            i += 1;
+
+            if !commit.expanded {
+                continue;
+            }
+            let below = commit.graph.below();
+            for diff in commit.diffs.iter().flatten() {
+                if i >= range.start && i < range.end {
+                    view_items.push(GitLogViewItem {
+                        index,
+                        commit: commit.commit.clone(),
+                        file: Some(diff.clone()),
+                        expanded: false,
+                        graph: below.clone(),
+                    });
+                }
+                i += 1;
+            }
+        }
+        view_items.into_iter()
+    }
+}
+
+#[derive(Clone)]
+pub struct GitLogData {
+    /// The file the log is for, or `None` for the whole repository
+    pub path: RwSignal<Option<PathBuf>>,
+    pub log: RwSignal<GitLog>,
+    /// Increased for every new log, so that responses for an old one are dropped
+    pub log_id: RwSignal<usize>,
+    pub loading: RwSignal<bool>,
+    /// Whether the last page was full, so there may be more commits
+    pub has_more: RwSignal<bool>,
+    pub common: Rc<CommonData>,
+}
+
+impl GitLogData {
+    pub fn new(cx: Scope, common: Rc<CommonData>) -> Self {
+        Self {
+            path: cx.create_rw_signal(None),
+            log: cx.create_rw_signal(GitLog::default()),
+            log_id: cx.create_rw_signal(0),
+            loading: cx.create_rw_signal(false),
+            has_more: cx.create_rw_signal(false),
+            common,
+        }
+    }
+
+    /// Show the log of the file, or of the whole repository without a path
+    pub fn show(&self, path: Option<PathBuf>) {
+        self.log_id.update(|id| *id += 1);
+        self.path.set(path);
+        self.log.set(GitLog::default());
+        self.has_more.set(false);
+        self.loading.set(false);
+        self.load_more();
+    }
+
+    /// Read the log again, such as after a commit
+    pub fn refresh(&self) {
+        self.show(self.path.get_untracked());
+    }
+
+    /// Request the next page of commits
+    pub fn load_more(&self) {
+        if self.loading.get_untracked() {
+            return;
+        }
+        self.loading.set(true);
+
+        let log_id = self.log_id.get_untracked();
+        let path = self.path.get_untracked();
+        // The commits of a file skip the ones in between, so their parents
+        // aren't in the log and there's no graph
+        let with_graph = path.is_none();
+        let skip = self.log.with_untracked(|log| log.commits.len());
+        let data = self.clone();
+        let send = create_ext_action(self.common.scope, move |commits| {
+            if data.log_id.get_untracked() != log_id {
+                return;
+            }
+            data.loading.set(false);
+            let Some(commits): Option<Vec<GitCommit>> = commits else {
+                data.has_more.set(false);
+                return;
+            };
+            data.has_more.set(commits.len() == PAGE_SIZE);
+            data.log.update(|log| log.append(commits, with_graph));
+        });
+        self.common
+            .proxy
+            .git_log(path, skip, PAGE_SIZE, move |result| {
+                if let Ok(ProxyResponse::GitLogResponse { commits }) = result {
+                    send(Some(commits));
+                } else {
+                    send(None);
+                }
+            });
+    }
+
+    /// Show or hide the changed files of the commit
+    pub fn toggle_expand(&self, index: usize) {
+        let mut request = None;
+        self.log.update(|log| {
+            if let Some(commit) = log.commits.get_mut(index) {
+                commit.expanded = !commit.expanded;
+                if commit.expanded && commit.diffs.is_none() {
+                    request = Some(commit.commit.id.clone());
+                }
+            }
+        });
+        let Some(id) = request else {
+            return;
+        };
+
+        let log_id = self.log_id.get_untracked();
+        let log_id_signal = self.log_id;
+        let log = self.log;
+        let send = create_ext_action(self.common.scope, move |diffs| {
+            if log_id_signal.get_untracked() != log_id {
+                return;
+            }
+            log.update(|log| {
+                if let Some(commit) = log.commits.get_mut(index) {
+                    commit.diffs = Some(diffs);
+                }
+            });
+        });
+        self.common.proxy.git_commit_diffs(id, move |result| {
+            if let Ok(ProxyResponse::GitCommitDiffsResponse { diffs }) = result {
+                send(diffs);
+            } else {
+                send(Vec::new());
+            }
+        });
+    }
+
+    /// Open the changes the commit made to the file in a diff editor
+    pub fn open_file_changes(&self, commit: &GitCommit, diff: &FileDiff) {
+        self.common
+            .internal_command
+            .send(InternalCommand::OpenCommitFileChanges {
+                commit: commit.id.clone(),
+                diff: diff.clone(),
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit(id: &str, parents: &[&str]) -> GitCommit {
+        GitCommit {
+            id: id.to_string(),
+            parents: parents.iter().map(|p| p.to_string()).collect(),
+            author: String::new(),
+            email: String::new(),
+            time: 0,
+            summary: String::new(),
+        }
+    }
+
+    fn row(
+        lane: usize,
+        top: &[(usize, usize)],
+        bottom: &[(usize, usize)],
+    ) -> GraphRow {
+        GraphRow {
+            lane: Some(lane),
+            top: top.to_vec(),
+            bottom: bottom.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_commit_graph() {
+        // A merge of the branch `c` into `b`, and a branch `e` which isn't
+        // merged
+        let commits = [
+            commit("e", &["a"]),
+            commit("m", &["b", "c"]),
+            commit("b", &["a"]),
+            commit("c", &["a"]),
+            commit("a", &[]),
+        ];
+        assert_eq!(
+            commit_graph(&commits),
+            vec![
+                row(0, &[], &[(0, 0)]),
+                row(1, &[(0, 0)], &[(0, 0), (1, 1), (1, 2)]),
+                row(1, &[(0, 0), (1, 1), (2, 2)], &[(0, 0), (2, 2), (1, 0)]),
+                row(2, &[(0, 0), (2, 2)], &[(0, 0), (2, 0)]),
+                row(0, &[(0, 0)], &[]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_slice_expanded() {
+        let mut log = GitLog::default();
+        log.append(vec![commit("b", &["a"]), commit("a", &[])], true);
+        assert_eq!(log.total_len(), 2);
+
+        log.commits[0].expanded = true;
+        log.commits[0].diffs = Some(vec![
+            FileDiff::Added(PathBuf::from("x")),
+            FileDiff::Modified(PathBuf::from("y")),
+        ]);
+        assert_eq!(log.total_len(), 4);
+
+        let items = log.slice(1..4).collect::<Vec<_>>();
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[0].file, Some(FileDiff::Added(PathBuf::from("x"))));
+        assert_eq!(
+            items[0].graph,
+            GraphRow {
+                lane: None,
+                top: vec![(0, 0)],
+                bottom: vec![(0, 0)],
+            }
+        );
+        assert_eq!(items[2].index, 1);
+        assert!(items[2].file.is_none());
+    }
+}
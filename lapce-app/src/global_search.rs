@@ -1,4 +1,8 @@
-use std::{ops::Range, path::PathBuf, rc::Rc};
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
 
 use floem::{
     ext_event::create_ext_action,
@@ -7,13 +11,24 @@
     views::VirtualVector,
 };
 use indexmap::IndexMap;
-use lapce_core::{mode::Mode, selection::Selection};
-use lapce_rpc::proxy::{ProxyResponse, SearchMatch};
+use lapce_core::{
+    command::FocusCommand, editor::EditType, mode::Mode,
+    rope_text_pos::RopeTextPosition, selection::Selection,
+};
+use lapce_proxy::search::{
+    apply_line_replacements, build_search_regex, line_replacements,
+};
+use lapce_rpc::proxy::{ProxyResponse, ReplacedFile, SearchMatch};
 use lapce_xi_rope::Rope;
+use lsp_types::TextEdit;
 
 use crate::{
     command::{CommandExecuted, CommandKind},
-    editor::EditorData,
+    doc::Doc,
+    editor::{
+        location::{EditorLocation, EditorPosition},
+        EditorData,
+    },
     keypress::{condition::Condition, KeyPressFocus},
     main_split::MainSplitData,
     window_tab::CommonData,
@@ -38,9 +53,36 @@ pub fn height(&self) -> f64 {
     }
 }
 
+/// The inputs of the search panel which can have the keyboard focus.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GlobalSearchInput {
+    Search,
+    Replace,
+    Include,
+    Exclude,
+}
+
+/// What the last replace in files changed, so that it can be undone as a whole.
+#[derive(Clone)]
+pub struct GlobalReplaceUndo {
+    /// The open documents which were edited, with the edits that revert the
+    /// replace and their revision right after it
+    docs: Vec<(Rc<Doc>, Vec<(Selection, String)>, u64)>,
+    /// The files which were replaced on disk
+    files: IndexMap<PathBuf, ReplacedFile>,
+}
+
 #[derive(Clone)]
 pub struct GlobalSearchData {
     pub editor: EditorData,
+    pub replace_editor: EditorData,
+    /// The glob patterns of the files to search, separated by commas
+    pub include_editor: EditorData,
+    /// The glob patterns of the files to leave out, separated by commas
+    pub exclude_editor: EditorData,
+    pub replace_active: RwSignal<bool>,
+    pub focused_input: RwSignal<GlobalSearchInput>,
+    pub last_replace: RwSignal<Option<GlobalReplaceUndo>>,
     pub search_result: RwSignal<IndexMap<PathBuf, SearchMatchData>>,
     pub main_split: MainSplitData,
     pub common: Rc<CommonData>,
@@ -52,7 +94,18 @@ fn get_mode(&self) -> Mode {
     }
 
     fn check_condition(&self, condition: Condition) -> bool {
-        matches!(condition, Condition::PanelFocus)
+        match condition {
+            Condition::PanelFocus => true,
+            Condition::SearchFocus => {
+                self.replace_active.get_untracked()
+                    && self.focused_input.get_untracked()
+                        == GlobalSearchInput::Search
+            }
+            Condition::ReplaceFocus => {
+                self.focused_input.get_untracked() == GlobalSearchInput::Replace
+            }
+            _ => false,
+        }
     }
 
     fn run_command(
@@ -64,11 +117,21 @@ fn run_command(
         match &command.kind {
             CommandKind::Workbench(_) => {}
             CommandKind::Scroll(_) => {}
+            CommandKind::Focus(FocusCommand::FocusReplaceEditor) => {
+                if self.replace_active.get_untracked() {
+                    self.focused_input.set(GlobalSearchInput::Replace);
+                    return CommandExecuted::Yes;
+                }
+            }
+            CommandKind::Focus(FocusCommand::FocusFindEditor) => {
+                self.focused_input.set(GlobalSearchInput::Search);
+                return CommandExecuted::Yes;
+            }
             CommandKind::Focus(_) => {}
             CommandKind::Edit(_)
             | CommandKind::Move(_)
             | CommandKind::MultiSelection(_) => {
-                return self.editor.run_command(command, count, mods);
+                return self.active_editor().run_command(command, count, mods);
             }
             CommandKind::MotionMode(_) => {}
         }
@@ -76,7 +139,7 @@ fn run_command(
     }
 
     fn receive_char(&self, c: &str) {
-        self.editor.receive_char(c);
+        self.active_editor().receive_char(c);
     }
 }
 
@@ -108,10 +171,22 @@ impl GlobalSearchData {
     pub fn new(cx: Scope, main_split: MainSplitData) -> Self {
         let common = main_split.common.clone();
         let editor = EditorData::new_local(cx, main_split.editors, common.clone());
+        let replace_editor =
+            EditorData::new_local(cx, main_split.editors, common.clone());
+        let include_editor =
+            EditorData::new_local(cx, main_split.editors, common.clone());
+        let exclude_editor =
+            EditorData::new_local(cx, main_split.editors, common.clone());
         let search_result = cx.create_rw_signal(IndexMap::new());
 
         let global_search = Self {
             editor,
+            replace_editor,
+            include_editor,
+            exclude_editor,
+            replace_active: cx.create_rw_signal(false),
+            focused_input: cx.create_rw_signal(GlobalSearchInput::Search),
+            last_replace: cx.create_rw_signal(None),
             search_result,
             main_split,
             common,
@@ -119,35 +194,8 @@ pub fn new(cx: Scope, main_split: MainSplitData) -> Self {
 
         {
             let global_search = global_search.clone();
-            let buffer = global_search.editor.doc().buffer;
             cx.create_effect(move |_| {
-                let pattern = buffer.with(|buffer| buffer.to_string());
-                if pattern.is_empty() {
-                    global_search.search_result.update(|r| r.clear());
-                    return;
-                }
-                let case_sensitive = global_search.common.find.case_sensitive(true);
-                let whole_word = global_search.common.find.whole_words.get();
-                let is_regex = global_search.common.find.is_regex.get();
-                let send = {
-                    let global_search = global_search.clone();
-                    create_ext_action(cx, move |result| {
-                        if let Ok(ProxyResponse::GlobalSearchResponse { matches }) =
-                            result
-                        {
-                            global_search.update_matches(matches);
-                        }
-                    })
-                };
-                global_search.common.proxy.global_search(
-                    pattern,
-                    case_sensitive,
-                    whole_word,
-                    is_regex,
-                    move |result| {
-                        send(result);
-                    },
-                );
+                global_search.search();
             });
         }
 
@@ -163,6 +211,40 @@ pub fn new(cx: Scope, main_split: MainSplitData) -> Self {
         global_search
     }
 
+    /// Run the search for the current pattern and options. Reading them here
+    /// makes the search re-run whenever they change.
+    fn search(&self) {
+        let pattern = self.editor.doc().buffer.with(|buffer| buffer.to_string());
+        if pattern.is_empty() {
+            self.search_result.update(|r| r.clear());
+            return;
+        }
+        let case_sensitive = self.common.find.case_sensitive(true);
+        let whole_word = self.common.find.whole_words.get();
+        let is_regex = self.common.find.is_regex.get();
+        let include = glob_patterns(&self.include_editor);
+        let exclude = glob_patterns(&self.exclude_editor);
+        let send = {
+            let global_search = self.clone();
+            create_ext_action(self.common.scope, move |result| {
+                if let Ok(ProxyResponse::GlobalSearchResponse { matches }) = result {
+                    global_search.update_matches(matches);
+                }
+            })
+        };
+        self.common.proxy.global_search(
+            pattern,
+            case_sensitive,
+            whole_word,
+            is_regex,
+            include,
+            exclude,
+            move |result| {
+                send(result);
+            },
+        );
+    }
+
     fn update_matches(&self, matches: IndexMap<PathBuf, Vec<SearchMatch>>) {
         let current = self.search_result.get_untracked();
 
@@ -197,4 +279,311 @@ pub fn set_pattern(&self, pattern: String) {
             .cursor()
             .update(|cursor| cursor.set_insert(Selection::region(0, pattern_len)));
     }
+
+    fn active_editor(&self) -> &EditorData {
+        match self.focused_input.get_untracked() {
+            GlobalSearchInput::Search => &self.editor,
+            GlobalSearchInput::Replace => &self.replace_editor,
+            GlobalSearchInput::Include => &self.include_editor,
+            GlobalSearchInput::Exclude => &self.exclude_editor,
+        }
+    }
+
+    /// The text to replace the matches with.
+    pub fn replacement(&self) -> String {
+        self.replace_editor
+            .doc()
+            .buffer
+            .with_untracked(|buffer| buffer.to_string())
+    }
+
+    /// The content of the match's line with the replacement applied, along
+    /// with the range of the replaced text, if the replace input is shown.
+    pub fn replace_preview(
+        &self,
+        m: &SearchMatch,
+    ) -> Option<(String, usize, usize)> {
+        if !self.replace_active.get() {
+            return None;
+        }
+        let pattern = self.editor.doc().buffer.with(|buffer| buffer.to_string());
+        let replacement = self
+            .replace_editor
+            .doc()
+            .buffer
+            .with(|buffer| buffer.to_string());
+        let is_regex = self.common.find.is_regex.get();
+        let regex = build_search_regex(
+            &pattern,
+            self.common.find.case_sensitive(true),
+            self.common.find.whole_words.get(),
+            is_regex,
+        )
+        .ok()?;
+        let replacements = line_replacements(
+            &regex,
+            &m.line_content,
+            &[(m.start, m.end)],
+            &replacement,
+            is_regex,
+        );
+        let (start, _, text) = replacements.first()?;
+        let end = start + text.len();
+        Some((
+            apply_line_replacements(&m.line_content, &replacements),
+            *start,
+            end,
+        ))
+    }
+
+    /// Remove a single match from the results, so that it's left out of
+    /// "Replace All".
+    pub fn dismiss_match(&self, path: &Path, m: &SearchMatch) {
+        self.remove_matches(&IndexMap::from([(
+            path.to_path_buf(),
+            vec![m.clone()],
+        )]));
+    }
+
+    /// Remove all the matches of a file from the results.
+    pub fn dismiss_file(&self, path: &Path) {
+        self.search_result.update(|result| {
+            result.shift_remove(path);
+        });
+    }
+
+    fn remove_matches(&self, matches: &IndexMap<PathBuf, Vec<SearchMatch>>) {
+        self.search_result.update(|result| {
+            for (path, matches) in matches {
+                let Some(match_data) = result.get(path) else {
+                    continue;
+                };
+                match_data.matches.update(|current| {
+                    current.retain(|m| {
+                        !matches.iter().any(|removed| {
+                            removed.line == m.line
+                                && removed.start == m.start
+                                && removed.end == m.end
+                        })
+                    });
+                });
+                if match_data.matches.with_untracked(|m| m.is_empty()) {
+                    result.shift_remove(path);
+                }
+            }
+        });
+    }
+
+    pub fn replace_match(&self, path: &Path, m: &SearchMatch) {
+        self.replace(IndexMap::from([(path.to_path_buf(), vec![m.clone()])]));
+    }
+
+    pub fn replace_file(&self, path: &Path) {
+        let Some(matches) = self.search_result.with_untracked(|result| {
+            result
+                .get(path)
+                .map(|match_data| match_data.matches.get_untracked())
+        }) else {
+            return;
+        };
+        self.replace(IndexMap::from([(
+            path.to_path_buf(),
+            matches.into_iter().collect(),
+        )]));
+    }
+
+    pub fn replace_all(&self) {
+        let matches = self.search_result.with_untracked(|result| {
+            result
+                .iter()
+                .map(|(path, match_data)| {
+                    (
+                        path.clone(),
+                        match_data.matches.get_untracked().into_iter().collect(),
+                    )
+                })
+                .collect()
+        });
+        self.replace(matches);
+    }
+
+    /// Ask the proxy to replace the given matches. Files which aren't open are
+    /// replaced on disk by the proxy itself, while the edits for the open
+    /// ones are applied to their documents once the response comes back.
+    fn replace(&self, matches: IndexMap<PathBuf, Vec<SearchMatch>>) {
+        let pattern = self
+            .editor
+            .doc()
+            .buffer
+            .with_untracked(|buffer| buffer.to_string());
+        if pattern.is_empty() || matches.values().all(|m| m.is_empty()) {
+            return;
+        }
+        let case_sensitive = self.common.find.case_sensitive(false);
+        let whole_word = self.common.find.whole_words.get_untracked();
+        let is_regex = self.common.find.is_regex.get_untracked();
+
+        self.remove_matches(&matches);
+
+        let send = {
+            let global_search = self.clone();
+            create_ext_action(self.common.scope, move |result| {
+                if let Ok(ProxyResponse::GlobalReplaceResponse { edits, files }) =
+                    result
+                {
+                    global_search.apply_replace(edits, files);
+                }
+            })
+        };
+        self.common.proxy.global_replace(
+            pattern,
+            case_sensitive,
+            whole_word,
+            is_regex,
+            self.replacement(),
+            matches,
+            move |result| {
+                send(result);
+            },
+        );
+    }
+
+    fn apply_replace(
+        &self,
+        edits: IndexMap<PathBuf, Vec<TextEdit>>,
+        files: IndexMap<PathBuf, ReplacedFile>,
+    ) {
+        let mut docs = Vec::new();
+        for (path, edits) in edits {
+            let doc = self
+                .main_split
+                .docs
+                .with_untracked(|docs| docs.get(&path).cloned());
+            let Some(doc) = doc else {
+                // The proxy has the file open, but we don't have its document,
+                // so open it which applies the edits once it's loaded.
+                let location = EditorLocation {
+                    path,
+                    position: edits
+                        .first()
+                        .map(|edit| EditorPosition::Position(edit.range.start)),
+                    scroll_offset: None,
+                    ignore_unconfirmed: false,
+                    same_editor_tab: false,
+                };
+                self.main_split.jump_to_location(location, Some(edits));
+                continue;
+            };
+
+            let (regions, reverts) = doc.buffer.with_untracked(|buffer| {
+                let mut regions = edits
+                    .iter()
+                    .map(|edit| {
+                        (
+                            buffer.offset_of_position(&edit.range.start),
+                            buffer.offset_of_position(&edit.range.end),
+                            edit.new_text.as_str(),
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                regions.sort_by_key(|(start, _, _)| *start);
+                let reverts = revert_edits(buffer.text(), &regions);
+                (regions, reverts)
+            });
+            let selections = regions
+                .iter()
+                .map(|(start, end, new_text)| {
+                    (Selection::region(*start, *end), *new_text)
+                })
+                .collect::<Vec<_>>();
+            if doc.do_raw_edit(&selections, EditType::Other).is_some() {
+                docs.push((doc.clone(), reverts, doc.rev()));
+            }
+        }
+
+        if !docs.is_empty() || !files.is_empty() {
+            self.last_replace
+                .set(Some(GlobalReplaceUndo { docs, files }));
+        }
+    }
+
+    /// Revert the last replace in files. Documents which were edited since are
+    /// left untouched.
+    pub fn undo_replace(&self) {
+        let Some(undo) = self.last_replace.get_untracked() else {
+            return;
+        };
+        self.last_replace.set(None);
+
+        for (doc, reverts, rev) in undo.docs {
+            if doc.rev() == rev {
+                let edits = reverts
+                    .iter()
+                    .map(|(selection, text)| (selection, text.as_str()))
+                    .collect::<Vec<_>>();
+                doc.do_raw_edit(&edits, EditType::Other);
+            }
+        }
+        if !undo.files.is_empty() {
+            self.common.proxy.global_replace_undo(undo.files);
+        }
+
+        // Bring the replaced matches back into the results
+        self.search();
+    }
+}
+
+/// The edits which turn the text back into `text` after the sorted, non
+/// overlapping `regions` of it were replaced with their new text.
+fn revert_edits(
+    text: &Rope,
+    regions: &[(usize, usize, &str)],
+) -> Vec<(Selection, String)> {
+    let mut shift = 0isize;
+    regions
+        .iter()
+        .map(|(start, end, new_text)| {
+            let new_start = (*start as isize + shift) as usize;
+            shift += new_text.len() as isize - (end - start) as isize;
+            (
+                Selection::region(new_start, new_start + new_text.len()),
+                text.slice_to_cow(*start..*end).to_string(),
+            )
+        })
+        .collect()
+}
+
+/// The comma separated glob patterns of a "files to include / exclude" input.
+fn glob_patterns(editor: &EditorData) -> Vec<String> {
+    editor.doc().buffer.with(|buffer| {
+        buffer
+            .to_string()
+            .split(',')
+            .map(|pattern| pattern.trim())
+            .filter(|pattern| !pattern.is_empty())
+            .map(|pattern| pattern.to_string())
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use lapce_xi_rope::Rope;
+
+    use super::revert_edits;
+
+    #[test]
+    fn test_revert_edits() {
+        let text = Rope::from("foo bar foo");
+        let reverts = revert_edits(&text, &[(0, 3, "quux"), (8, 11, "b")]);
+        let reverts = reverts
+            .iter()
+            .map(|(selection, text)| {
+                let region = selection.regions()[0];
+                (region.start, region.end, text.as_str())
+            })
+            .collect::<Vec<_>>();
+        // "quux bar b"
+        assert_eq!(reverts, vec![(0, 4, "foo"), (9, 10, "foo")]);
+    }
 }
@@ -0,0 +1,560 @@
+use std::{ops::Range, path::PathBuf, rc::Rc};
+
+use floem::{
+    ext_event::create_ext_action,
+    reactive::{RwSignal, Scope},
+    views::VirtualVector,
+};
+use lapce_rpc::{plugin::PluginId, proxy::ProxyResponse};
+use lsp_types::{CallHierarchyItem, Position, SymbolKind, TypeHierarchyItem, Url};
+
+use crate::{
+    command::InternalCommand,
+    editor::location::{EditorLocation, EditorPosition},
+    proxy::path_from_url,
+    window_tab::CommonData,
+};
+
+/// What the hierarchy panel shows for the symbol it was opened on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HierarchyKind {
+    IncomingCalls,
+    OutgoingCalls,
+    Supertypes,
+    Subtypes,
+}
+
+impl HierarchyKind {
+    pub fn title(&self) -> &'static str {
+        match self {
+            HierarchyKind::IncomingCalls => "Incoming Calls",
+            HierarchyKind::OutgoingCalls => "Outgoing Calls",
+            HierarchyKind::Supertypes => "Supertypes",
+            HierarchyKind::Subtypes => "Subtypes",
+        }
+    }
+
+    pub fn is_call(&self) -> bool {
+        matches!(
+            self,
+            HierarchyKind::IncomingCalls | HierarchyKind::OutgoingCalls
+        )
+    }
+
+    /// The other direction of the same hierarchy
+    pub fn reversed(&self) -> Self {
+        match self {
+            HierarchyKind::IncomingCalls => HierarchyKind::OutgoingCalls,
+            HierarchyKind::OutgoingCalls => HierarchyKind::IncomingCalls,
+            HierarchyKind::Supertypes => HierarchyKind::Subtypes,
+            HierarchyKind::Subtypes => HierarchyKind::Supertypes,
+        }
+    }
+}
+
+/// An item from the language server, which is passed back to it to get the
+/// children of the item.
+#[derive(Clone, Debug)]
+pub enum HierarchyItem {
+    Call(CallHierarchyItem),
+    Type(TypeHierarchyItem),
+}
+
+impl HierarchyItem {
+    pub fn name(&self) -> &str {
+        match self {
+            HierarchyItem::Call(item) => &item.name,
+            HierarchyItem::Type(item) => &item.name,
+        }
+    }
+
+    pub fn kind(&self) -> SymbolKind {
+        match self {
+            HierarchyItem::Call(item) => item.kind,
+            HierarchyItem::Type(item) => item.kind,
+        }
+    }
+
+    pub fn detail(&self) -> Option<&str> {
+        match self {
+            HierarchyItem::Call(item) => item.detail.as_deref(),
+            HierarchyItem::Type(item) => item.detail.as_deref(),
+        }
+    }
+
+    pub fn uri(&self) -> &Url {
+        match self {
+            HierarchyItem::Call(item) => &item.uri,
+            HierarchyItem::Type(item) => &item.uri,
+        }
+    }
+
+    pub fn selection_range(&self) -> lsp_types::Range {
+        match self {
+            HierarchyItem::Call(item) => item.selection_range,
+            HierarchyItem::Type(item) => item.selection_range,
+        }
+    }
+}
+
+impl From<CallHierarchyItem> for HierarchyItem {
+    fn from(item: CallHierarchyItem) -> Self {
+        HierarchyItem::Call(item)
+    }
+}
+
+impl From<TypeHierarchyItem> for HierarchyItem {
+    fn from(item: TypeHierarchyItem) -> Self {
+        HierarchyItem::Type(item)
+    }
+}
+
+/// An item in the hierarchy tree.
+#[derive(Clone)]
+pub struct HierarchyNode {
+    pub item: HierarchyItem,
+    /// Where to jump to in the file of the item. For incoming calls this is the
+    /// first call site, otherwise the name of the item.
+    pub position: Position,
+    pub expanded: bool,
+    /// `None` until the children were requested from the language server
+    pub children: Option<Vec<HierarchyNode>>,
+    pub children_expanded_count: usize,
+}
+
+impl HierarchyNode {
+    pub fn new(item: impl Into<HierarchyItem>, position: Position) -> Self {
+        Self {
+            item: item.into(),
+            position,
+            expanded: false,
+            children: None,
+            children_expanded_count: 0,
+        }
+    }
+
+    fn update_count(&mut self) {
+        self.children_expanded_count = if self.expanded {
+            self.children
+                .iter()
+                .flatten()
+                .map(|child| child.children_expanded_count + 1)
+                .sum()
+        } else {
+            0
+        };
+    }
+
+    /// Push the visible rows of this node and its children which fall in the
+    /// range, where `current` is the row of this node.
+    /// Returns the row after the last one of this node.
+    fn append_view_slice(
+        &self,
+        view_items: &mut Vec<HierarchyViewItem>,
+        range: &Range<usize>,
+        current: usize,
+        path: Vec<usize>,
+    ) -> usize {
+        let end = current + 1 + self.children_expanded_count;
+        if current >= range.end || end <= range.start {
+            return end;
+        }
+
+        let level = path.len() - 1;
+        if current >= range.start {
+            view_items.push(HierarchyViewItem {
+                item: self.item.clone(),
+                position: self.position,
+                path: path.clone(),
+                expanded: self.expanded,
+                leaf: self.children.as_ref().is_some_and(|c| c.is_empty()),
+                level,
+            });
+        }
+
+        if self.expanded {
+            let mut i = current + 1;
+            for (index, child) in self.children.iter().flatten().enumerate() {
+                if i >= range.end {
+                    break;
+                }
+                let mut path = path.clone();
+                path.push(index);
+                i = child.append_view_slice(view_items, range, i, path);
+            }
+        }
+        end
+    }
+}
+
+/// A visible row of the hierarchy tree
+#[derive(Clone)]
+pub struct HierarchyViewItem {
+    pub item: HierarchyItem,
+    pub position: Position,
+    /// The indices leading to the node from the roots
+    pub path: Vec<usize>,
+    pub expanded: bool,
+    /// Whether the node is known to have no children
+    pub leaf: bool,
+    pub level: usize,
+}
+
+#[derive(Clone, Default)]
+pub struct HierarchyTree {
+    pub roots: Vec<HierarchyNode>,
+    pub children_expanded_count: usize,
+}
+
+impl HierarchyTree {
+    pub fn new(roots: Vec<HierarchyNode>) -> Self {
+        let mut tree = Self {
+            roots,
+            children_expanded_count: 0,
+        };
+        tree.update_count(&[]);
+        tree
+    }
+
+    pub fn node_mut(&mut self, path: &[usize]) -> Option<&mut HierarchyNode> {
+        let (first, rest) = path.split_first()?;
+        rest.iter()
+            .try_fold(self.roots.get_mut(*first)?, |node, index| {
+                node.children.as_mut()?.get_mut(*index)
+            })
+    }
+
+    /// Recount the visible rows of the node at the path and of its parents
+    pub fn update_count(&mut self, path: &[usize]) {
+        for len in (1..=path.len()).rev() {
+            if let Some(node) = self.node_mut(&path[..len]) {
+                node.update_count();
+            }
+        }
+        self.children_expanded_count = self
+            .roots
+            .iter()
+            .map(|node| node.children_expanded_count + 1)
+            .sum();
+    }
+}
+
+impl VirtualVector<HierarchyViewItem> for HierarchyTree {
+    fn total_len(&self) -> usize {
+        self.children_expanded_count
+    }
+
+    fn slice(
+        &mut self,
+        range: Range<usize>,
+    ) -> impl Iterator<Item = HierarchyViewItem> {
+        let mut view_items = Vec::new();
+        let mut i = 0;
+        for (index, node) in self.roots.iter().enumerate() {
+            if i >= range.end {
+                break;
+            }
+            i = node.append_view_slice(&mut view_items, &range, i, vec![index]);
+        }
+        view_items.into_iter()
+    }
+}
+
+#[derive(Clone)]
+pub struct HierarchyData {
+    pub kind: RwSignal<HierarchyKind>,
+    /// The plugin which prepared the hierarchy, which is also asked for the
+    /// children of the items
+    pub plugin_id: RwSignal<Option<PluginId>>,
+    /// The items the hierarchy was prepared for
+    pub items: RwSignal<Vec<HierarchyItem>>,
+    pub tree: RwSignal<HierarchyTree>,
+    /// Increased for every new tree, so that responses for an old one are dropped
+    pub tree_id: RwSignal<usize>,
+    pub loading: RwSignal<bool>,
+    pub common: Rc<CommonData>,
+}
+
+impl HierarchyData {
+    pub fn new(cx: Scope, common: Rc<CommonData>) -> Self {
+        Self {
+            kind: cx.create_rw_signal(HierarchyKind::IncomingCalls),
+            plugin_id: cx.create_rw_signal(None),
+            items: cx.create_rw_signal(Vec::new()),
+            tree: cx.create_rw_signal(HierarchyTree::default()),
+            tree_id: cx.create_rw_signal(0),
+            loading: cx.create_rw_signal(false),
+            common,
+        }
+    }
+
+    /// Prepare the hierarchy of the symbol at the position and show it
+    pub fn prepare(&self, kind: HierarchyKind, path: PathBuf, position: Position) {
+        let tree_id = self.new_tree_id();
+        self.kind.set(kind);
+        self.plugin_id.set(None);
+        self.items.set(Vec::new());
+        self.tree.set(HierarchyTree::default());
+        self.loading.set(true);
+
+        let hierarchy = self.clone();
+        let send = create_ext_action(self.common.scope, move |result| {
+            if hierarchy.tree_id.get_untracked() != tree_id {
+                return;
+            }
+            hierarchy.loading.set(false);
+            if let Some((plugin_id, items)) = result {
+                hierarchy.plugin_id.set(Some(plugin_id));
+                hierarchy.items.set(items);
+                hierarchy.show_items();
+            }
+        });
+        if kind.is_call() {
+            self.common.proxy.prepare_call_hierarchy(
+                path,
+                position,
+                move |result| {
+                    if let Ok(ProxyResponse::PrepareCallHierarchyResponse {
+                        plugin_id,
+                        items,
+                    }) = result
+                    {
+                        send(Some((plugin_id, into_items(items))));
+                    } else {
+                        send(None);
+                    }
+                },
+            );
+        } else {
+            self.common.proxy.prepare_type_hierarchy(
+                path,
+                position,
+                move |result| {
+                    if let Ok(ProxyResponse::PrepareTypeHierarchyResponse {
+                        plugin_id,
+                        items,
+                    }) = result
+                    {
+                        send(Some((plugin_id, into_items(items))));
+                    } else {
+                        send(None);
+                    }
+                },
+            );
+        }
+    }
+
+    /// Switch between incoming and outgoing calls, or between supertypes and
+    /// subtypes, of the same items
+    pub fn reverse(&self) {
+        self.kind.update(|kind| *kind = kind.reversed());
+        self.new_tree_id();
+        self.show_items();
+    }
+
+    fn new_tree_id(&self) -> usize {
+        self.tree_id.update(|id| *id += 1);
+        self.tree_id.get_untracked()
+    }
+
+    /// Rebuild the tree from the prepared items, with their children expanded
+    fn show_items(&self) {
+        let roots = self.items.with_untracked(|items| {
+            items
+                .iter()
+                .map(|item| {
+                    HierarchyNode::new(item.clone(), item.selection_range().start)
+                })
+                .collect::<Vec<_>>()
+        });
+        let len = roots.len();
+        self.tree.set(HierarchyTree::new(roots));
+        for index in 0..len {
+            self.toggle_expand(&[index]);
+        }
+    }
+
+    pub fn toggle_expand(&self, path: &[usize]) {
+        let mut request = None;
+        self.tree.update(|tree| {
+            if let Some(node) = tree.node_mut(path) {
+                node.expanded = !node.expanded;
+                if node.expanded && node.children.is_none() {
+                    request = Some(node.item.clone());
+                }
+            }
+            tree.update_count(path);
+        });
+        if let Some(item) = request {
+            self.read_children(path.to_vec(), item);
+        }
+    }
+
+    fn read_children(&self, path: Vec<usize>, item: HierarchyItem) {
+        let Some(plugin_id) = self.plugin_id.get_untracked() else {
+            return;
+        };
+        let tree_id = self.tree_id.get_untracked();
+        let tree_id_signal = self.tree_id;
+        let tree = self.tree;
+        let send = create_ext_action(self.common.scope, move |children| {
+            if tree_id_signal.get_untracked() != tree_id {
+                return;
+            }
+            tree.update(|tree| {
+                if let Some(node) = tree.node_mut(&path) {
+                    node.children = Some(children);
+                }
+                tree.update_count(&path);
+            });
+        });
+
+        let proxy = &self.common.proxy;
+        let kind = self.kind.get_untracked();
+        match (kind, item) {
+            (HierarchyKind::IncomingCalls, HierarchyItem::Call(item)) => proxy
+                .call_hierarchy_incoming_calls(plugin_id, item, move |result| {
+                    let children = match result {
+                        Ok(ProxyResponse::CallHierarchyIncomingCallsResponse {
+                            calls,
+                        }) => calls
+                            .into_iter()
+                            .map(|call| {
+                                let position = call
+                                    .from_ranges
+                                    .first()
+                                    .map(|range| range.start)
+                                    .unwrap_or(call.from.selection_range.start);
+                                HierarchyNode::new(call.from, position)
+                            })
+                            .collect(),
+                        _ => Vec::new(),
+                    };
+                    send(children);
+                }),
+            (HierarchyKind::OutgoingCalls, HierarchyItem::Call(item)) => proxy
+                .call_hierarchy_outgoing_calls(plugin_id, item, move |result| {
+                    let children = match result {
+                        Ok(ProxyResponse::CallHierarchyOutgoingCallsResponse {
+                            calls,
+                        }) => calls
+                            .into_iter()
+                            .map(|call| {
+                                let position = call.to.selection_range.start;
+                                HierarchyNode::new(call.to, position)
+                            })
+                            .collect(),
+                        _ => Vec::new(),
+                    };
+                    send(children);
+                }),
+            (
+                HierarchyKind::Supertypes | HierarchyKind::Subtypes,
+                HierarchyItem::Type(item),
+            ) => {
+                let callback = move |result| {
+                    let children = match result {
+                        Ok(ProxyResponse::TypeHierarchyItemsResponse { items }) => {
+                            items
+                                .into_iter()
+                                .map(|item| {
+                                    let position = item.selection_range.start;
+                                    HierarchyNode::new(item, position)
+                                })
+                                .collect()
+                        }
+                        _ => Vec::new(),
+                    };
+                    send(children);
+                };
+                if kind == HierarchyKind::Supertypes {
+                    proxy.type_hierarchy_supertypes(plugin_id, item, callback);
+                } else {
+                    proxy.type_hierarchy_subtypes(plugin_id, item, callback);
+                }
+            }
+            // The items were prepared for the other kind of hierarchy
+            _ => send(Vec::new()),
+        }
+    }
+
+    pub fn jump_to(&self, item: &HierarchyViewItem) {
+        self.common
+            .internal_command
+            .send(InternalCommand::JumpToLocation {
+                location: EditorLocation {
+                    path: path_from_url(item.item.uri()),
+                    position: Some(EditorPosition::Position(item.position)),
+                    scroll_offset: None,
+                    ignore_unconfirmed: false,
+                    same_editor_tab: false,
+                },
+            });
+    }
+}
+
+fn into_items<T: Into<HierarchyItem>>(items: Vec<T>) -> Vec<HierarchyItem> {
+    items.into_iter().map(Into::into).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(name: &str, children: Option<Vec<HierarchyNode>>) -> HierarchyNode {
+        let item = CallHierarchyItem {
+            name: name.to_string(),
+            kind: SymbolKind::FUNCTION,
+            tags: None,
+            detail: None,
+            uri: Url::parse("file:///main.rs").unwrap(),
+            range: lsp_types::Range::default(),
+            selection_range: lsp_types::Range::default(),
+            data: None,
+        };
+        let mut node = HierarchyNode::new(item, Position::default());
+        node.children = children;
+        node
+    }
+
+    fn names(tree: &mut HierarchyTree, range: Range<usize>) -> Vec<String> {
+        tree.slice(range)
+            .map(|item| item.item.name().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_expand_and_slice() {
+        let mut tree = HierarchyTree::new(vec![
+            node(
+                "a",
+                Some(vec![
+                    node("a0", Some(vec![node("a00", None)])),
+                    node("a1", None),
+                ]),
+            ),
+            node("b", None),
+        ]);
+        assert_eq!(tree.total_len(), 2);
+        assert_eq!(names(&mut tree, 0..10), vec!["a", "b"]);
+
+        tree.node_mut(&[0]).unwrap().expanded = true;
+        tree.update_count(&[0]);
+        assert_eq!(tree.total_len(), 4);
+        assert_eq!(names(&mut tree, 0..10), vec!["a", "a0", "a1", "b"]);
+
+        tree.node_mut(&[0, 0]).unwrap().expanded = true;
+        tree.update_count(&[0, 0]);
+        assert_eq!(tree.total_len(), 5);
+        assert_eq!(names(&mut tree, 2..4), vec!["a00", "a1"]);
+
+        let items: Vec<_> = tree.slice(2..3).collect();
+        assert_eq!(items[0].path, vec![0, 0, 0]);
+        assert_eq!(items[0].level, 2);
+        assert!(!items[0].leaf);
+
+        tree.node_mut(&[0]).unwrap().expanded = false;
+        tree.update_count(&[0]);
+        assert_eq!(names(&mut tree, 1..10), vec!["b"]);
+    }
+}
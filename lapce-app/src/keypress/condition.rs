@@ -59,10 +59,14 @@ pub enum Condition {
     TerminalFocus,
     #[strum(serialize = "source_control_focus")]
     SourceControlFocus,
+    #[strum(serialize = "debug_console_focus")]
+    DebugConsoleFocus,
     #[strum(serialize = "panel_focus")]
     PanelFocus,
     #[strum(serialize = "rename_focus")]
     RenameFocus,
+    #[strum(serialize = "breakpoint_edit_focus")]
+    BreakpointEditFocus,
     #[strum(serialize = "search_active")]
     SearchActive,
     #[strum(serialize = "search_focus")]
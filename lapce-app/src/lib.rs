@@ -13,7 +13,10 @@
 pub mod file_explorer;
 pub mod find;
 pub mod focus_text;
+pub mod folding;
+pub mod git_log;
 pub mod global_search;
+pub mod hierarchy;
 pub mod history;
 pub mod hover;
 pub mod id;
@@ -23,6 +26,7 @@
 pub mod listener;
 pub mod main_split;
 pub mod markdown;
+pub mod merge_conflict;
 pub mod palette;
 pub mod panel;
 pub mod plugin;
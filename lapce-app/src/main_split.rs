@@ -4,6 +4,8 @@
     rc::Rc,
 };
 
+use anyhow::{anyhow, bail, Result};
+
 use floem::{
     action::save_as,
     ext_event::create_ext_action,
@@ -11,22 +13,25 @@
     keyboard::ModifiersState,
     peniko::kurbo::{Point, Rect, Vec2},
     reactive::{Memo, RwSignal, Scope},
-    views::editor::id::EditorId,
+    views::editor::{id::EditorId, text::SystemClipboard},
 };
 use itertools::Itertools;
 use lapce_core::{
     buffer::rope_text::RopeText, command::FocusCommand, cursor::Cursor,
-    rope_text_pos::RopeTextPosition, selection::Selection, syntax::Syntax,
+    register::Clipboard, rope_text_pos::RopeTextPosition, selection::Selection,
+    syntax::Syntax,
 };
 use lapce_rpc::{
     buffer::BufferId,
     plugin::{PluginId, VoltID},
     proxy::ProxyResponse,
+    source_control::FileDiff,
 };
 use lapce_xi_rope::Rope;
 use lsp_types::{
-    CodeAction, CodeActionOrCommand, DiagnosticSeverity, DocumentChangeOperation,
-    DocumentChanges, OneOf, Position, TextEdit, Url, WorkspaceEdit,
+    CodeAction, CodeActionOrCommand, Command, DiagnosticSeverity,
+    DocumentChangeOperation, DocumentChanges, OneOf, Position, TextEdit, Url,
+    WorkspaceEdit,
 };
 use serde::{Deserialize, Serialize};
 use tracing::warn;
@@ -281,11 +286,7 @@ pub fn new(cx: Scope, common: Rc<CommonData>) -> Self {
                     let diff_editor = diff_editors.with(|diff_editors| {
                         diff_editors.get(&diff_editor_id).cloned()
                     })?;
-                    if diff_editor.focus_right.get() {
-                        diff_editor.right
-                    } else {
-                        diff_editor.left
-                    }
+                    diff_editor.focused_editor(true)
                 }
                 _ => return None,
             };
@@ -349,11 +350,7 @@ pub fn key_down<'a>(
                     self.diff_editors.with_untracked(|diff_editors| {
                         diff_editors.get(&diff_editor_id).cloned()
                     })?;
-                let editor = if diff_editor.focus_right.get_untracked() {
-                    diff_editor.right
-                } else {
-                    diff_editor.left
-                };
+                let editor = diff_editor.focused_editor(false);
                 let processed = keypress.key_down(event, &*editor);
                 editor.get_code_actions();
                 Some(processed)
@@ -514,34 +511,161 @@ pub fn go_to_location(
         }
     }
 
-    pub fn open_file_changes(&self, path: PathBuf) {
-        let (right, _) = self.get_doc(path.clone());
-        let left = Doc::new_history(
+    /// A document of the file at a version from source control, which is empty
+    /// when the file doesn't exist at that version
+    fn history_doc(&self, path: PathBuf, version: String) -> Rc<Doc> {
+        let history = DocHistory { path, version };
+        let doc = Rc::new(Doc::new_history(
             self.scope,
-            DocContent::History(DocHistory {
-                path: path.clone(),
-                version: "head".to_string(),
-            }),
+            DocContent::History(history.clone()),
             self.editors,
             self.common.clone(),
-        );
-        let left = Rc::new(left);
+        ));
 
         let send = {
-            let left = left.clone();
+            let doc = doc.clone();
             create_ext_action(self.scope, move |result| {
-                if let Ok(ProxyResponse::BufferHeadResponse { content, .. }) = result
-                {
-                    left.init_content(Rope::from(content));
-                }
+                let content = match result {
+                    Ok(ProxyResponse::BufferHeadResponse { content, .. }) => content,
+                    _ => String::new(),
+                };
+                doc.init_content(Rope::from(content));
             })
         };
-        self.common.proxy.get_buffer_head(path, move |result| {
+        history.retrieve(&self.common.proxy, move |result| {
             send(result);
         });
 
+        doc
+    }
+
+    /// Open the diff of the changes in the working tree against the index, or of
+    /// the staged changes in the index against `HEAD`.
+    pub fn open_file_changes(&self, path: PathBuf, staged: bool) {
+        let (left, right) = if staged {
+            (
+                self.history_doc(path.clone(), "HEAD".to_string()),
+                self.history_doc(path, "index".to_string()),
+            )
+        } else {
+            (
+                self.history_doc(path.clone(), "index".to_string()),
+                self.get_doc(path).0,
+            )
+        };
+
+        self.get_editor_tab_child(
+            EditorTabChildSource::DiffEditor {
+                left,
+                right,
+                result: None,
+            },
+            false,
+            false,
+        );
+    }
+
+    /// Open the changes the commit made to the file, against the first parent of
+    /// the commit
+    pub fn open_commit_file_changes(&self, commit: String, diff: FileDiff) {
+        let (old_path, new_path) = match diff {
+            FileDiff::Renamed(new, old) => (old, new),
+            FileDiff::Modified(path)
+            | FileDiff::Added(path)
+            | FileDiff::Deleted(path)
+            | FileDiff::Conflicted(path) => (path.clone(), path),
+        };
+        let left = self.history_doc(old_path, format!("{commit}^"));
+        let right = self.history_doc(new_path, commit);
+
+        self.get_editor_tab_child(
+            EditorTabChildSource::DiffEditor {
+                left,
+                right,
+                result: None,
+            },
+            false,
+            false,
+        );
+    }
+
+    /// Open the diff of the file against a revision, which is a commit, a branch
+    /// or a tag
+    pub fn open_revision_changes(&self, path: PathBuf, revision: String) {
+        let left = self.history_doc(path.clone(), revision);
+        let right = self.get_doc(path).0;
+        self.open_diff_editor(left, right);
+    }
+
+    /// Open the diff of the document of the active editor against the document
+    /// of another editor, which can have changes that aren't saved
+    pub fn compare_with_editor(&self, editor_id: EditorId) {
+        let Some(active_editor) = self.active_editor.get_untracked() else {
+            return;
+        };
+        let Some(editor) = self
+            .editors
+            .with_untracked(|editors| editors.get(&editor_id).cloned())
+        else {
+            return;
+        };
+        self.open_diff_editor(editor.doc(), active_editor.doc());
+    }
+
+    /// Open the diff of the document of the active editor against the text in
+    /// the clipboard
+    pub fn compare_with_clipboard(&self) {
+        let Some(active_editor) = self.active_editor.get_untracked() else {
+            return;
+        };
+        let mut clipboard = SystemClipboard::new();
+        let Some(text) = clipboard.get_string() else {
+            return;
+        };
+        let right = active_editor.doc();
+        let path = right
+            .content
+            .with_untracked(|content| content.path().cloned())
+            .unwrap_or_default();
+        let left = Rc::new(Doc::new_history(
+            self.scope,
+            DocContent::History(DocHistory {
+                path,
+                version: DocHistory::CLIPBOARD.to_string(),
+            }),
+            self.editors,
+            self.common.clone(),
+        ));
+        left.init_content(Rope::from(text));
+        self.open_diff_editor(left, right);
+    }
+
+    fn open_diff_editor(&self, left: Rc<Doc>, right: Rc<Doc>) {
         self.get_editor_tab_child(
-            EditorTabChildSource::DiffEditor { left, right },
+            EditorTabChildSource::DiffEditor {
+                left,
+                right,
+                result: None,
+            },
+            false,
+            false,
+        );
+    }
+
+    /// Open the merge editor of a file with conflicts, which has the current
+    /// and the incoming side of the merge from the index above the working
+    /// file, where the conflicts are resolved
+    pub fn open_merge_editor(&self, path: PathBuf) {
+        let left = self.history_doc(path.clone(), ":2".to_string());
+        let right = self.history_doc(path.clone(), ":3".to_string());
+        let result = Some(self.get_doc(path).0);
+
+        self.get_editor_tab_child(
+            EditorTabChildSource::DiffEditor {
+                left,
+                right,
+                result,
+            },
             false,
             false,
         );
@@ -551,7 +675,11 @@ pub fn open_diff_files(&self, left_path: PathBuf, right_path: PathBuf) {
         let [left, right] = [left_path, right_path].map(|path| self.get_doc(path).0);
 
         self.get_editor_tab_child(
-            EditorTabChildSource::DiffEditor { left, right },
+            EditorTabChildSource::DiffEditor {
+                left,
+                right,
+                result: None,
+            },
             false,
             false,
         );
@@ -623,7 +751,10 @@ fn get_editor_tab_child(
         };
 
         let is_same_diff_editor =
-            |diff_editor_id: &DiffEditorId, left: &Rc<Doc>, right: &Rc<Doc>| {
+            |diff_editor_id: &DiffEditorId,
+             left: &Rc<Doc>,
+             right: &Rc<Doc>,
+             result: &Option<Rc<Doc>>| {
                 diff_editors
                     .get(diff_editor_id)
                     .map(|diff_editor| {
@@ -631,6 +762,10 @@ fn get_editor_tab_child(
                             == diff_editor.left.doc().content.get_untracked()
                             && right.content.get_untracked()
                                 == diff_editor.right.doc().content.get_untracked()
+                            && result.as_ref().map(|doc| doc.content.get_untracked())
+                                == diff_editor.result.as_ref().map(|result| {
+                                    result.doc().content.get_untracked()
+                                })
                     })
                     .unwrap_or(false)
             };
@@ -665,20 +800,25 @@ fn get_editor_tab_child(
                             }
                         }
                         EditorTabChild::DiffEditor(diff_editor_id) => {
-                            if let EditorTabChildSource::DiffEditor { left, right } =
-                                &source
+                            if let EditorTabChildSource::DiffEditor {
+                                left,
+                                right,
+                                result,
+                            } = &source
                             {
-                                is_same_diff_editor(diff_editor_id, left, right)
-                                    || diff_editors
-                                        .get(diff_editor_id)
-                                        .map(|diff_editor| {
-                                            diff_editor.left.doc().is_pristine()
-                                                && diff_editor
-                                                    .right
-                                                    .doc()
-                                                    .is_pristine()
-                                        })
-                                        .unwrap_or(false)
+                                is_same_diff_editor(
+                                    diff_editor_id,
+                                    left,
+                                    right,
+                                    result,
+                                ) || diff_editors
+                                    .get(diff_editor_id)
+                                    .map(|diff_editor| {
+                                        diff_editor
+                                            .editors()
+                                            .all(|editor| editor.doc().is_pristine())
+                                    })
+                                    .unwrap_or(false)
                             } else {
                                 false
                             }
@@ -715,14 +855,23 @@ fn get_editor_tab_child(
                                 }
                             })
                     }),
-                EditorTabChildSource::DiffEditor { left, right } => {
+                EditorTabChildSource::DiffEditor {
+                    left,
+                    right,
+                    result,
+                } => {
                     if let Some(index) =
                         active_editor_tab.with_untracked(|editor_tab| {
                             editor_tab.children.iter().position(|(_, _, child)| {
                                 if let EditorTabChild::DiffEditor(diff_editor_id) =
                                     child
                                 {
-                                    is_same_diff_editor(diff_editor_id, left, right)
+                                    is_same_diff_editor(
+                                        diff_editor_id,
+                                        left,
+                                        right,
+                                        result,
+                                    )
                                 } else {
                                     false
                                 }
@@ -919,7 +1068,11 @@ fn get_editor_tab_child(
                 EditorTabChildSource::Volt(id) => {
                     EditorTabChild::Volt(VoltViewId::next(), id.to_owned())
                 }
-                EditorTabChildSource::DiffEditor { left, right } => {
+                EditorTabChildSource::DiffEditor {
+                    left,
+                    right,
+                    result,
+                } => {
                     let diff_editor_id = DiffEditorId::next();
                     let diff_editor = DiffEditorData::new(
                         self.scope,
@@ -927,6 +1080,8 @@ fn get_editor_tab_child(
                         editor_tab_id,
                         left.clone(),
                         right.clone(),
+                        result.clone(),
+                        self.editors,
                         self.common.clone(),
                     );
                     self.diff_editors.update(|diff_editors| {
@@ -979,16 +1134,30 @@ fn get_editor_tab_child(
                 }
                 (
                     EditorTabChild::DiffEditor(diff_editor_id),
-                    EditorTabChildSource::DiffEditor { left, right },
-                ) => {
-                    if !is_same_diff_editor(diff_editor_id, left, right) {
-                        if let Some(diff_editor) = diff_editors.get(diff_editor_id) {
+                    EditorTabChildSource::DiffEditor {
+                        left,
+                        right,
+                        result,
+                    },
+                ) => match diff_editors.get(diff_editor_id) {
+                    // Only a merge editor has an editor for the result
+                    Some(diff_editor)
+                        if diff_editor.result.is_some() == result.is_some() =>
+                    {
+                        if !is_same_diff_editor(diff_editor_id, left, right, result)
+                        {
                             diff_editor.left.update_doc(left.clone());
                             diff_editor.right.update_doc(right.clone());
+                            if let (Some(editor), Some(result)) =
+                                (&diff_editor.result, result)
+                            {
+                                editor.update_doc(result.clone());
+                            }
                         }
+                        true
                     }
-                    true
-                }
+                    _ => false,
+                },
                 (EditorTabChild::Settings(_), EditorTabChildSource::Settings) => {
                     true
                 }
@@ -1042,24 +1211,27 @@ fn get_editor_tab_child(
                             EditorTabChildSource::Editor { path, .. } => editor_tab
                                 .get_editor(&editors, path)
                                 .map(|(index, _)| index),
-                            EditorTabChildSource::DiffEditor { left, right } => {
-                                editor_tab.children.iter().position(
-                                    |(_, _, child)| {
-                                        if let EditorTabChild::DiffEditor(
+                            EditorTabChildSource::DiffEditor {
+                                left,
+                                right,
+                                result,
+                            } => editor_tab.children.iter().position(
+                                |(_, _, child)| {
+                                    if let EditorTabChild::DiffEditor(
+                                        diff_editor_id,
+                                    ) = child
+                                    {
+                                        is_same_diff_editor(
                                             diff_editor_id,
-                                        ) = child
-                                        {
-                                            is_same_diff_editor(
-                                                diff_editor_id,
-                                                left,
-                                                right,
-                                            )
-                                        } else {
-                                            false
-                                        }
-                                    },
-                                )
-                            }
+                                            left,
+                                            right,
+                                            result,
+                                        )
+                                    } else {
+                                        false
+                                    }
+                                },
+                            ),
                             EditorTabChildSource::Settings => editor_tab
                                 .children
                                 .iter()
@@ -1784,6 +1956,23 @@ pub fn editor_tab_child_close_active(&self) -> Option<()> {
         Some(())
     }
 
+    /// The diff editor of the active editor tab, if that's what it shows
+    pub fn active_diff_editor(&self) -> Option<DiffEditorData> {
+        let active_editor_tab = self.active_editor_tab.get_untracked()?;
+        let editor_tab = self.editor_tabs.with_untracked(|editor_tabs| {
+            editor_tabs.get(&active_editor_tab).copied()
+        })?;
+        let (_, _, child) = editor_tab.with_untracked(|editor_tab| {
+            editor_tab.children.get(editor_tab.active).cloned()
+        })?;
+        let EditorTabChild::DiffEditor(diff_editor_id) = child else {
+            return None;
+        };
+        self.diff_editors.with_untracked(|diff_editors| {
+            diff_editors.get(&diff_editor_id).cloned()
+        })
+    }
+
     pub fn editor_tab_child_close(
         &self,
         editor_tab_id: EditorTabId,
@@ -1965,70 +2154,109 @@ pub fn editor_tab_update_layout(
 
     pub fn run_code_action(&self, plugin_id: PluginId, action: CodeActionOrCommand) {
         match action {
-            CodeActionOrCommand::Command(_) => {}
+            CodeActionOrCommand::Command(command) => {
+                self.execute_command(plugin_id, command);
+            }
             CodeActionOrCommand::CodeAction(action) => {
-                if let Some(edit) = action.edit.as_ref() {
-                    self.apply_workspace_edit(edit);
-                } else {
+                if action.edit.is_none() && action.command.is_none() {
                     self.resolve_code_action(plugin_id, action);
+                } else {
+                    self.apply_code_action(plugin_id, action);
                 }
             }
         }
     }
 
-    /// Resolve a code action and apply its held workspace edit
+    /// Apply the workspace edit of a code action and then run its command
+    fn apply_code_action(&self, plugin_id: PluginId, action: CodeAction) {
+        if let Some(edit) = action.edit.as_ref() {
+            if let Err(err) = self.apply_workspace_edit(edit) {
+                warn!("failed to apply code action: {err}");
+            }
+        }
+        if let Some(command) = action.command {
+            self.execute_command(plugin_id, command);
+        }
+    }
+
+    /// Resolve a code action and apply its held workspace edit and command
     fn resolve_code_action(&self, plugin_id: PluginId, action: CodeAction) {
         let main_split = self.clone();
-        let send = create_ext_action(self.scope, move |edit| {
-            main_split.apply_workspace_edit(&edit);
+        let send = create_ext_action(self.scope, move |action| {
+            main_split.apply_code_action(plugin_id, action);
         });
         self.common
             .proxy
             .code_action_resolve(action, plugin_id, move |result| {
                 if let Ok(ProxyResponse::CodeActionResolveResponse { item }) = result
                 {
-                    if let Some(edit) = item.edit {
-                        send(edit);
-                    }
+                    send(*item);
+                }
+            });
+    }
+
+    /// Ask the language server to run a command. The server sends back any
+    /// changes it makes with a `workspace/applyEdit` request.
+    fn execute_command(&self, plugin_id: PluginId, command: Command) {
+        self.common
+            .proxy
+            .execute_command(plugin_id, command, |result| {
+                if let Err(err) = result {
+                    warn!("failed to execute command: {:?}", err);
                 }
             });
     }
 
     /// Perform a workspace edit, which are from the LSP (such as code actions, or symbol renaming)
-    pub fn apply_workspace_edit(&self, edit: &WorkspaceEdit) {
-        if let Some(DocumentChanges::Operations(_op)) =
+    /// Nothing is applied if any of the edits can't be, such as an edit to a
+    /// url which isn't a file, or creating or renaming a file.
+    pub fn apply_workspace_edit(&self, edit: &WorkspaceEdit) -> Result<()> {
+        if let Some(DocumentChanges::Operations(ops)) =
             edit.document_changes.as_ref()
         {
-            // TODO
+            if ops
+                .iter()
+                .any(|op| matches!(op, DocumentChangeOperation::Op(_)))
+            {
+                bail!("resource operations aren't supported");
+            }
         }
 
         if let Some(edits) = workspace_edits(edit) {
-            for (url, edits) in edits {
-                if let Ok(path) = url.to_file_path() {
-                    let active_path = self
-                        .active_editor
-                        .get_untracked()
-                        .map(|editor| editor.doc())
-                        .map(|doc| doc.content.get_untracked())
-                        .and_then(|content| content.path().cloned());
-                    let position = if active_path.as_ref() == Some(&path) {
-                        None
-                    } else {
-                        edits
-                            .first()
-                            .map(|edit| EditorPosition::Position(edit.range.start))
-                    };
-                    let location = EditorLocation {
-                        path,
-                        position,
-                        scroll_offset: None,
-                        ignore_unconfirmed: true,
-                        same_editor_tab: false,
-                    };
-                    self.jump_to_location(location, Some(edits));
-                }
+            let edits = edits
+                .into_iter()
+                .map(|(url, edits)| {
+                    let path = url
+                        .to_file_path()
+                        .map_err(|_| anyhow!("{url} isn't a file"))?;
+                    Ok((path, edits))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            for (path, edits) in edits {
+                let active_path = self
+                    .active_editor
+                    .get_untracked()
+                    .map(|editor| editor.doc())
+                    .map(|doc| doc.content.get_untracked())
+                    .and_then(|content| content.path().cloned());
+                let position = if active_path.as_ref() == Some(&path) {
+                    None
+                } else {
+                    edits
+                        .first()
+                        .map(|edit| EditorPosition::Position(edit.range.start))
+                };
+                let location = EditorLocation {
+                    path,
+                    position,
+                    scroll_offset: None,
+                    ignore_unconfirmed: true,
+                    same_editor_tab: false,
+                };
+                self.jump_to_location(location, Some(edits));
             }
         }
+        Ok(())
     }
 
     pub fn next_error(&self) {
@@ -2444,14 +2672,11 @@ fn editor_tab_child_set_parent(
                         diff_editors.get(diff_editor_id).cloned()
                     })?;
                 diff_editor.editor_tab_id.set(editor_tab_id);
-                diff_editor
-                    .left
-                    .diff_editor_id
-                    .set(Some((editor_tab_id, *diff_editor_id)));
-                diff_editor
-                    .right
-                    .diff_editor_id
-                    .set(Some((editor_tab_id, *diff_editor_id)));
+                for editor in diff_editor.editors() {
+                    editor
+                        .diff_editor_id
+                        .set(Some((editor_tab_id, *diff_editor_id)));
+                }
             }
             EditorTabChild::Settings(_) => {}
             EditorTabChild::ThemeColorSettings(_) => {}
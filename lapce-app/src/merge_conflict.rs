@@ -0,0 +1,191 @@
+use std::ops::Range;
+
+const START_MARKER: &str = "<<<<<<<";
+const BASE_MARKER: &str = "|||||||";
+const SEPARATOR_MARKER: &str = "=======";
+const END_MARKER: &str = ">>>>>>>";
+
+/// A conflict left in a file by a merge or rebase, with the zero based lines of
+/// its markers. The current side is between the `<<<<<<<` and the `=======`
+/// lines, or the `|||||||` line of the common ancestor when there is one, and
+/// the incoming side is between the `=======` and the `>>>>>>>` lines.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MergeConflict {
+    pub start: usize,
+    pub base: Option<usize>,
+    pub separator: usize,
+    pub end: usize,
+}
+
+/// Which side of a conflict to keep
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConflictResolution {
+    Current,
+    Incoming,
+    Both,
+}
+
+impl ConflictResolution {
+    pub const ALL: [ConflictResolution; 3] = [
+        ConflictResolution::Current,
+        ConflictResolution::Incoming,
+        ConflictResolution::Both,
+    ];
+
+    /// The title of the action shown on the first line of a conflict
+    pub fn title(&self) -> &'static str {
+        match self {
+            ConflictResolution::Current => "Accept Current",
+            ConflictResolution::Incoming => "Accept Incoming",
+            ConflictResolution::Both => "Accept Both",
+        }
+    }
+}
+
+impl MergeConflict {
+    pub fn contains(&self, line: usize) -> bool {
+        self.start <= line && line <= self.end
+    }
+
+    /// The lines of the current side, which is `HEAD` for a merge
+    pub fn current(&self) -> Range<usize> {
+        self.start + 1..self.base.unwrap_or(self.separator)
+    }
+
+    /// The lines of the incoming side, which is the branch being merged
+    pub fn incoming(&self) -> Range<usize> {
+        self.separator + 1..self.end
+    }
+
+    /// The lines which replace the whole conflict, markers included, when it
+    /// is resolved
+    pub fn resolved_lines(
+        &self,
+        resolution: ConflictResolution,
+    ) -> Vec<Range<usize>> {
+        match resolution {
+            ConflictResolution::Current => vec![self.current()],
+            ConflictResolution::Incoming => vec![self.incoming()],
+            ConflictResolution::Both => vec![self.current(), self.incoming()],
+        }
+    }
+}
+
+/// Whether the line is the marker, which is followed by nothing but an
+/// optional label like the branch name
+fn is_marker(line: &str, marker: &str) -> bool {
+    line.strip_prefix(marker)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace))
+}
+
+/// Find the conflicts in the lines of a file. Markers which don't make up a
+/// whole conflict are ignored, like a `=======` line used as a heading.
+pub fn find_conflicts<S: AsRef<str>>(
+    lines: impl IntoIterator<Item = S>,
+) -> Vec<MergeConflict> {
+    let mut conflicts = Vec::new();
+    let mut start = None;
+    let mut base = None;
+    let mut separator = None;
+    for (i, line) in lines.into_iter().enumerate() {
+        let line = line.as_ref();
+        if is_marker(line, START_MARKER) {
+            start = Some(i);
+            base = None;
+            separator = None;
+        } else if start.is_none() {
+            continue;
+        } else if is_marker(line, BASE_MARKER)
+            && base.is_none()
+            && separator.is_none()
+        {
+            base = Some(i);
+        } else if line.trim_end() == SEPARATOR_MARKER && separator.is_none() {
+            separator = Some(i);
+        } else if is_marker(line, END_MARKER) {
+            if let (Some(start), Some(separator)) = (start, separator) {
+                conflicts.push(MergeConflict {
+                    start,
+                    base,
+                    separator,
+                    end: i,
+                });
+            }
+            start = None;
+            base = None;
+            separator = None;
+        }
+    }
+    conflicts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_conflicts, ConflictResolution, MergeConflict};
+
+    #[test]
+    fn test_find_conflicts() {
+        let text = "a
+<<<<<<< HEAD
+b
+=======
+c
+d
+>>>>>>> topic
+e
+<<<<<<< ours
+f
+||||||| base
+g
+=======
+>>>>>>> theirs
+";
+        let conflicts = find_conflicts(text.lines());
+        assert_eq!(
+            conflicts,
+            vec![
+                MergeConflict {
+                    start: 1,
+                    base: None,
+                    separator: 3,
+                    end: 6,
+                },
+                MergeConflict {
+                    start: 8,
+                    base: Some(10),
+                    separator: 12,
+                    end: 13,
+                },
+            ]
+        );
+        assert_eq!(conflicts[0].current(), 2..3);
+        assert_eq!(conflicts[0].incoming(), 4..6);
+        assert_eq!(conflicts[1].current(), 9..10);
+        assert_eq!(conflicts[1].incoming(), 13..13);
+        assert_eq!(
+            conflicts[0].resolved_lines(ConflictResolution::Both),
+            vec![2..3, 4..6]
+        );
+    }
+
+    #[test]
+    fn test_incomplete_conflicts() {
+        // A separator without a start, and a start without an end
+        assert!(find_conflicts(["a", "=======", "b", ">>>>>>> x"]).is_empty());
+        assert!(find_conflicts(["<<<<<<< x", "a", "=======", "b"]).is_empty());
+        // Lines which only start like a marker
+        assert!(
+            find_conflicts(["<<<<<<<<", "a", "=======", "b", ">>>>>>>>"]).is_empty()
+        );
+        // A new start replaces an unfinished one
+        assert_eq!(
+            find_conflicts(["<<<<<<<", "<<<<<<<", "a", "=======", ">>>>>>>"]),
+            vec![MergeConflict {
+                start: 1,
+                base: None,
+                separator: 3,
+                end: 4,
+            }]
+        );
+    }
+}
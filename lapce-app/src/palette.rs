@@ -1,7 +1,7 @@
 use std::{
     cell::RefCell,
     collections::{HashMap, HashSet},
-    path::PathBuf,
+    path::{Path, PathBuf},
     rc::Rc,
     sync::{
         atomic::{AtomicU64, Ordering},
@@ -23,7 +23,9 @@
     line_ending::LineEnding, mode::Mode, movement::Movement, selection::Selection,
     syntax::Syntax,
 };
-use lapce_rpc::proxy::ProxyResponse;
+use lapce_rpc::{
+    dap_types::RunDebugConfig, proxy::ProxyResponse, source_control::DiffInfo,
+};
 use lapce_xi_rope::Rope;
 use lsp_types::DocumentSymbolResponse;
 use nucleo::Utf32Str;
@@ -36,10 +38,12 @@
 };
 use crate::{
     command::{
-        CommandExecuted, CommandKind, InternalCommand, LapceCommand, WindowCommand,
+        CommandExecuted, CommandKind, InternalCommand, LapceCommand,
+        LapceWorkbenchCommand, WindowCommand,
     },
     db::LapceDb,
     debug::{RunDebugConfigs, RunDebugMode},
+    doc::DocContent,
     editor::{
         location::{EditorLocation, EditorPosition},
         EditorData,
@@ -57,6 +61,9 @@
 
 const DEFAULT_RUN_TOML: &str = include_str!("../../defaults/run.toml");
 
+/// The most commits which changed a file listed to compare the file with
+const COMPARE_COMMITS_LIMIT: usize = 100;
+
 #[derive(Clone, PartialEq, Eq)]
 pub enum PaletteStatus {
     Inactive,
@@ -64,6 +71,40 @@ pub enum PaletteStatus {
     Done,
 }
 
+/// What selecting a reference in the [`PaletteKind::SCMReferences`] palette does
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SCMReferenceAction {
+    Checkout,
+    /// Create a branch starting at the reference
+    BranchFrom,
+    Rename,
+    Delete,
+    /// Merge the reference into the branch which is checked out
+    Merge,
+    /// Compare the file of the active editor with it
+    Compare,
+}
+
+/// What the text typed in the [`PaletteKind::SCMInput`] palette is for
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SCMInputAction {
+    /// The name of a new branch, which starts at the reference, or at `HEAD`
+    /// without one
+    CreateBranch { start: Option<String> },
+    /// The new name of the branch
+    RenameBranch { branch: String },
+    /// The message of a new stash, which is optional
+    StashMessage,
+}
+
+/// What selecting a stash in the [`PaletteKind::SCMStashes`] palette does
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SCMStashAction {
+    Pop,
+    Apply,
+    Drop,
+}
+
 #[derive(Clone, Debug)]
 pub struct PaletteInput {
     pub input: String,
@@ -103,6 +144,11 @@ pub struct PaletteData {
     pub source_control: SourceControlData,
     pub common: Rc<CommonData>,
     left_diff_path: RwSignal<Option<PathBuf>>,
+    scm_reference_action: RwSignal<SCMReferenceAction>,
+    scm_input_action: RwSignal<SCMInputAction>,
+    scm_stash_action: RwSignal<SCMStashAction>,
+    /// The attach config waiting for a process to be picked
+    attach_config: RwSignal<Option<RunDebugConfig>>,
 }
 
 impl PaletteData {
@@ -198,6 +244,11 @@ pub fn new(
 
         let clicked_index = cx.create_rw_signal(Option::<usize>::None);
         let left_diff_path = cx.create_rw_signal(None);
+        let scm_reference_action = cx.create_rw_signal(SCMReferenceAction::Checkout);
+        let scm_input_action =
+            cx.create_rw_signal(SCMInputAction::CreateBranch { start: None });
+        let scm_stash_action = cx.create_rw_signal(SCMStashAction::Pop);
+        let attach_config = cx.create_rw_signal(None);
 
         let palette = Self {
             run_id_counter,
@@ -222,6 +273,10 @@ pub fn new(
             source_control,
             common,
             left_diff_path,
+            scm_reference_action,
+            scm_input_action,
+            scm_stash_action,
+            attach_config,
         };
 
         {
@@ -325,16 +380,68 @@ pub fn run(&self, kind: PaletteKind) {
             .update(|cursor| cursor.set_insert(Selection::caret(symbol.len())));
     }
 
+    /// Start the palette with the references of the repository, where selecting
+    /// one does the action.
+    pub fn run_scm_references(&self, action: SCMReferenceAction) {
+        self.scm_reference_action.set(action);
+        self.run(PaletteKind::SCMReferences);
+    }
+
+    /// Start the palette for typing a branch name or a stash message.
+    pub fn run_scm_input(&self, action: SCMInputAction) {
+        self.scm_input_action.set(action);
+        self.run(PaletteKind::SCMInput);
+    }
+
+    /// Start the palette with the stashes of the repository, where selecting
+    /// one does the action.
+    pub fn run_scm_stashes(&self, action: SCMStashAction) {
+        self.scm_stash_action.set(action);
+        self.run(PaletteKind::SCMStashes);
+    }
+
+    /// Start the palette with the running processes, where selecting one
+    /// debugs the attach config with that process.
+    pub fn run_attach_process(&self, config: RunDebugConfig) {
+        self.attach_config.set(Some(config));
+        self.run(PaletteKind::AttachProcess);
+    }
+
     /// Get the placeholder text to use in the palette input field.
     pub fn placeholder_text(&self) -> &'static str {
-        if self.kind.get() == PaletteKind::DiffFiles {
-            if self.left_diff_path.with(Option::is_some) {
-                "Select right file"
-            } else {
-                "Seleft left file"
+        match self.kind.get() {
+            PaletteKind::DiffFiles => {
+                if self.left_diff_path.with(Option::is_some) {
+                    "Select right file"
+                } else {
+                    "Seleft left file"
+                }
             }
-        } else {
-            ""
+            PaletteKind::SCMReferences => match self.scm_reference_action.get() {
+                SCMReferenceAction::Checkout => "",
+                SCMReferenceAction::BranchFrom => "Select the start of the branch",
+                SCMReferenceAction::Rename => "Select the branch to rename",
+                SCMReferenceAction::Delete => "Select the branch to delete",
+                SCMReferenceAction::Merge => "Select what to merge into the branch",
+                SCMReferenceAction::Compare => {
+                    "Select the revision to compare the file with"
+                }
+            },
+            PaletteKind::SCMInput => {
+                self.scm_input_action.with(|action| match action {
+                    SCMInputAction::CreateBranch { .. } => "Name of the new branch",
+                    SCMInputAction::RenameBranch { .. } => "New name of the branch",
+                    SCMInputAction::StashMessage => "Stash message (optional)",
+                })
+            }
+            PaletteKind::SCMStashes => match self.scm_stash_action.get() {
+                SCMStashAction::Pop => "Select the stash to pop",
+                SCMStashAction::Apply => "Select the stash to apply",
+                SCMStashAction::Drop => "Select the stash to drop",
+            },
+            PaletteKind::OpenEditors => "Select the editor to compare the file with",
+            PaletteKind::AttachProcess => "Select the process to attach to",
+            _ => "",
         }
     }
 
@@ -379,6 +486,9 @@ fn run_inner(&self, kind: PaletteKind) {
             PaletteKind::RunAndDebug => {
                 self.get_run_configs();
             }
+            PaletteKind::AttachProcess => {
+                self.get_processes();
+            }
             PaletteKind::ColorTheme => {
                 self.get_color_themes();
             }
@@ -394,6 +504,15 @@ fn run_inner(&self, kind: PaletteKind) {
             PaletteKind::SCMReferences => {
                 self.get_scm_references();
             }
+            PaletteKind::SCMInput => {
+                self.items.update(|items| items.clear());
+            }
+            PaletteKind::SCMStashes => {
+                self.get_scm_stashes();
+            }
+            PaletteKind::OpenEditors => {
+                self.get_open_editors();
+            }
             PaletteKind::TerminalProfile => self.get_terminal_profiles(),
         }
     }
@@ -831,24 +950,27 @@ fn set_run_configs(&self, content: String) {
         let mut items = Vec::new();
         if let Some(configs) = configs.as_ref() {
             for config in &configs.configs {
-                items.push((
-                    executed_run_configs
-                        .get(&(RunDebugMode::Run, config.name.clone())),
-                    PaletteItem {
-                        content: PaletteItemContent::RunAndDebug {
-                            mode: RunDebugMode::Run,
-                            config: config.clone(),
+                // There is nothing to run for a config that attaches to an
+                // existing process
+                if !config.is_attach() {
+                    items.push((
+                        executed_run_configs
+                            .get(&(RunDebugMode::Run, config.name.clone())),
+                        PaletteItem {
+                            content: PaletteItemContent::RunAndDebug {
+                                mode: RunDebugMode::Run,
+                                config: config.clone(),
+                            },
+                            filter_text: format!(
+                                "Run {} {}",
+                                config.name,
+                                config.target()
+                            ),
+                            score: 0,
+                            indices: vec![],
                         },
-                        filter_text: format!(
-                            "Run {} {} {}",
-                            config.name,
-                            config.program,
-                            config.args.clone().unwrap_or_default().join(" ")
-                        ),
-                        score: 0,
-                        indices: vec![],
-                    },
-                ));
+                    ));
+                }
                 if config.ty.is_some() {
                     items.push((
                         executed_run_configs
@@ -859,10 +981,9 @@ fn set_run_configs(&self, content: String) {
                                 config: config.clone(),
                             },
                             filter_text: format!(
-                                "Debug {} {} {}",
+                                "Debug {} {}",
                                 config.name,
-                                config.program,
-                                config.args.clone().unwrap_or_default().join(" ")
+                                config.target()
                             ),
                             score: 0,
                             indices: vec![],
@@ -985,8 +1106,40 @@ fn get_line_endings(&self) {
     }
 
     fn get_scm_references(&self) {
-        let branches = self.source_control.branches.get_untracked();
-        let tags = self.source_control.tags.get_untracked();
+        let action = self.scm_reference_action.get_untracked();
+        // The file is compared with the references of its own repository
+        let file_repository = if action == SCMReferenceAction::Compare {
+            self.active_file()
+                .and_then(|path| self.file_repository(&path))
+        } else {
+            None
+        };
+        let (head, mut branches, tags) = match file_repository {
+            Some(info) => (
+                info.head,
+                info.branches.into_iter().collect(),
+                info.tags.into_iter().collect(),
+            ),
+            None => (
+                self.source_control.branch.get_untracked(),
+                self.source_control.branches.get_untracked(),
+                self.source_control.tags.get_untracked(),
+            ),
+        };
+        // The branch which is checked out can't be deleted or merged into itself
+        if matches!(
+            action,
+            SCMReferenceAction::Delete | SCMReferenceAction::Merge
+        ) {
+            branches.retain(|branch| branch != &head);
+        }
+        // Only branches can be renamed or deleted
+        let tags = match action {
+            SCMReferenceAction::Rename | SCMReferenceAction::Delete => {
+                im::Vector::new()
+            }
+            _ => tags,
+        };
         let mut items: im::Vector<PaletteItem> = im::Vector::new();
         for refs in branches.into_iter() {
             items.push_back(PaletteItem {
@@ -1009,6 +1162,169 @@ fn get_scm_references(&self) {
             });
         }
         self.items.set(items);
+
+        // The file can also be compared with the commits which changed it
+        if action == SCMReferenceAction::Compare {
+            if let Some(path) = self.active_file() {
+                self.get_file_commits(path);
+            }
+        }
+    }
+
+    /// Add the latest commits which changed the file after the references
+    fn get_file_commits(&self, path: PathBuf) {
+        let items = self.items;
+        let run_id = self.run_id;
+        let current_run_id = run_id.get_untracked();
+        let send = create_ext_action(self.common.scope, move |result| {
+            // The palette may have been started again since
+            if run_id.get_untracked() != current_run_id {
+                return;
+            }
+            if let Ok(ProxyResponse::GitLogResponse { commits }) = result {
+                items.update(|items| {
+                    items.extend(commits.into_iter().map(|commit| PaletteItem {
+                        filter_text: format!(
+                            "{} {}",
+                            commit.id.get(..8).unwrap_or(&commit.id),
+                            commit.summary
+                        ),
+                        content: PaletteItemContent::SCMCommit { id: commit.id },
+                        score: 0,
+                        indices: Vec::new(),
+                    }))
+                });
+            }
+        });
+        self.common.proxy.git_log(
+            Some(path),
+            0,
+            COMPARE_COMMITS_LIMIT,
+            move |result| {
+                send(result);
+            },
+        );
+    }
+
+    /// The repository which contains the file, the innermost one if they're nested
+    fn file_repository(&self, path: &Path) -> Option<DiffInfo> {
+        self.source_control
+            .repositories
+            .with_untracked(|repositories| {
+                repositories
+                    .iter()
+                    .filter(|info| path.starts_with(&info.path))
+                    .max_by_key(|info| info.path.components().count())
+                    .cloned()
+            })
+    }
+
+    /// The file of the active editor, which is what is compared with a revision
+    fn active_file(&self) -> Option<PathBuf> {
+        let editor = self.main_split.active_editor.get_untracked()?;
+        let doc = editor.doc();
+        doc.content.with_untracked(|content| match content {
+            DocContent::File { path, .. } => Some(path.clone()),
+            _ => None,
+        })
+    }
+
+    /// The documents open in the editors other than the active one, which the
+    /// document of the active editor can be compared with
+    fn get_open_editors(&self) {
+        let active_doc = self
+            .main_split
+            .active_editor
+            .get_untracked()
+            .map(|editor| editor.doc());
+        let editors = self.main_split.editors.get_untracked();
+        let mut seen = HashSet::new();
+        let mut items = im::Vector::new();
+        for (editor_id, editor) in editors.into_iter() {
+            let doc = editor.doc();
+            if active_doc
+                .as_ref()
+                .is_some_and(|active_doc| Rc::ptr_eq(active_doc, &doc))
+            {
+                continue;
+            }
+            let name = match doc.content.get_untracked() {
+                DocContent::File { path, .. } => self
+                    .workspace
+                    .path
+                    .as_ref()
+                    .and_then(|workspace| path.strip_prefix(workspace).ok())
+                    .unwrap_or(&path)
+                    .to_string_lossy()
+                    .to_string(),
+                DocContent::Scratch { name, .. } => name,
+                DocContent::Local | DocContent::History(_) => continue,
+            };
+            if !seen.insert(name.clone()) {
+                continue;
+            }
+            items.push_back(PaletteItem {
+                content: PaletteItemContent::OpenEditor { editor_id },
+                filter_text: name,
+                score: 0,
+                indices: Vec::new(),
+            });
+        }
+        self.items.set(items);
+    }
+
+    fn get_scm_stashes(&self) {
+        let set_items = self.items.write_only();
+        let send = create_ext_action(self.common.scope, move |result| {
+            if let Ok(ProxyResponse::GitStashListResponse { stashes }) = result {
+                let items: im::Vector<PaletteItem> = stashes
+                    .into_iter()
+                    .map(|stash| PaletteItem {
+                        content: PaletteItemContent::SCMStash { index: stash.index },
+                        filter_text: format!(
+                            "stash@{{{}}}: {}",
+                            stash.index, stash.message
+                        ),
+                        score: 0,
+                        indices: Vec::new(),
+                    })
+                    .collect();
+                set_items.set(items);
+            } else {
+                set_items.update(|items| items.clear());
+            }
+        });
+
+        self.common.proxy.git_stash_list(move |result| {
+            send(result);
+        });
+    }
+
+    fn get_processes(&self) {
+        let set_items = self.items.write_only();
+        let send = create_ext_action(self.common.scope, move |result| {
+            if let Ok(ProxyResponse::ListProcessesResponse { processes }) = result {
+                let items: im::Vector<PaletteItem> = processes
+                    .into_iter()
+                    .map(|process| PaletteItem {
+                        content: PaletteItemContent::Process { pid: process.pid },
+                        filter_text: format!(
+                            "{} {} {}",
+                            process.pid, process.name, process.command
+                        ),
+                        score: 0,
+                        indices: Vec::new(),
+                    })
+                    .collect();
+                set_items.set(items);
+            } else {
+                set_items.update(|items| items.clear());
+            }
+        });
+
+        self.common.proxy.list_processes(move |result| {
+            send(result);
+        });
     }
 
     fn get_terminal_profiles(&self) {
@@ -1251,14 +1567,68 @@ fn select(&self) {
                     });
                 }
                 PaletteItemContent::SCMReference { name } => {
-                    self.common
-                        .lapce_command
-                        .send(crate::command::LapceCommand {
-                        kind: CommandKind::Workbench(
-                            crate::command::LapceWorkbenchCommand::CheckoutReference,
-                        ),
-                        data: Some(serde_json::json!(name.to_owned())),
-                    });
+                    match self.scm_reference_action.get_untracked() {
+                        SCMReferenceAction::Checkout => {
+                            self.common.lapce_command.send(LapceCommand {
+                                kind: CommandKind::Workbench(
+                                    LapceWorkbenchCommand::CheckoutReference,
+                                ),
+                                data: Some(serde_json::json!(name.to_owned())),
+                            });
+                        }
+                        SCMReferenceAction::BranchFrom => {
+                            self.run_scm_input(SCMInputAction::CreateBranch {
+                                start: Some(name.clone()),
+                            });
+                        }
+                        SCMReferenceAction::Rename => {
+                            self.run_scm_input(SCMInputAction::RenameBranch {
+                                branch: name.clone(),
+                            });
+                        }
+                        SCMReferenceAction::Delete => {
+                            self.source_control.delete_branch(name.clone(), false);
+                        }
+                        SCMReferenceAction::Merge => {
+                            self.source_control.merge(name.clone());
+                        }
+                        SCMReferenceAction::Compare => {
+                            if let Some(path) = self.active_file() {
+                                self.main_split
+                                    .open_revision_changes(path, name.clone());
+                            }
+                        }
+                    }
+                }
+                PaletteItemContent::SCMCommit { id } => {
+                    if let Some(path) = self.active_file() {
+                        self.main_split.open_revision_changes(path, id.clone());
+                    }
+                }
+                PaletteItemContent::OpenEditor { editor_id } => {
+                    self.main_split.compare_with_editor(*editor_id);
+                }
+                PaletteItemContent::Process { pid } => {
+                    if let Some(mut config) = self.attach_config.get_untracked() {
+                        config.pid = Some(*pid);
+                        self.common.internal_command.send(
+                            InternalCommand::RunAndDebug {
+                                mode: RunDebugMode::Debug,
+                                config,
+                            },
+                        );
+                    }
+                }
+                PaletteItemContent::SCMStash { index } => {
+                    match self.scm_stash_action.get_untracked() {
+                        SCMStashAction::Pop => self.source_control.stash_pop(*index),
+                        SCMStashAction::Apply => {
+                            self.source_control.stash_apply(*index)
+                        }
+                        SCMStashAction::Drop => {
+                            self.source_control.stash_drop(*index)
+                        }
+                    }
                 }
                 PaletteItemContent::TerminalProfile { name: _, profile } => self
                     .common
@@ -1279,6 +1649,26 @@ fn select(&self) {
                     },
                 },
             );
+        } else if self.kind.get_untracked() == PaletteKind::SCMInput {
+            let input = self
+                .input
+                .with_untracked(|input| input.input.trim().to_string());
+            match self.scm_input_action.get_untracked() {
+                SCMInputAction::CreateBranch { start } => {
+                    if !input.is_empty() {
+                        self.source_control.create_branch(input, start);
+                    }
+                }
+                SCMInputAction::RenameBranch { branch } => {
+                    if !input.is_empty() {
+                        self.source_control.rename_branch(branch, input);
+                    }
+                }
+                SCMInputAction::StashMessage => {
+                    self.source_control
+                        .stash((!input.is_empty()).then_some(input));
+                }
+            }
         }
     }
 
@@ -1397,6 +1787,10 @@ fn preview(&self) {
                         save: false,
                     }),
                 PaletteItemContent::SCMReference { .. } => {}
+                PaletteItemContent::SCMCommit { .. } => {}
+                PaletteItemContent::SCMStash { .. } => {}
+                PaletteItemContent::Process { .. } => {}
+                PaletteItemContent::OpenEditor { .. } => {}
                 PaletteItemContent::TerminalProfile { .. } => {}
             }
         }
@@ -1414,6 +1808,7 @@ fn cancel(&self) {
         }
 
         self.left_diff_path.set(None);
+        self.attach_config.set(None);
         self.close();
     }
 
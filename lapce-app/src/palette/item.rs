@@ -1,5 +1,6 @@
 use std::path::PathBuf;
 
+use floem::views::editor::id::EditorId;
 use lapce_core::line_ending::LineEnding;
 use lapce_rpc::dap_types::RunDebugConfig;
 use lsp_types::{Range, SymbolKind};
@@ -65,6 +66,10 @@ pub enum PaletteItemContent {
         mode: RunDebugMode,
         config: RunDebugConfig,
     },
+    /// A running process that a debugger can attach to
+    Process {
+        pid: u32,
+    },
     ColorTheme {
         name: String,
     },
@@ -80,6 +85,17 @@ pub enum PaletteItemContent {
     SCMReference {
         name: String,
     },
+    /// A commit which changed the file being compared
+    SCMCommit {
+        id: String,
+    },
+    SCMStash {
+        index: usize,
+    },
+    /// An editor whose document the active one is compared with
+    OpenEditor {
+        editor_id: EditorId,
+    },
     TerminalProfile {
         name: String,
         profile: lapce_rpc::terminal::TerminalProfile,
@@ -16,13 +16,17 @@ pub enum PaletteKind {
     #[cfg(windows)]
     WslHost,
     RunAndDebug,
+    AttachProcess,
     ColorTheme,
     IconTheme,
     Language,
     LineEnding,
     SCMReferences,
+    SCMInput,
+    SCMStashes,
     TerminalProfile,
     DiffFiles,
+    OpenEditors,
 }
 
 impl PaletteKind {
@@ -41,12 +45,16 @@ pub fn symbol(&self) -> &'static str {
             | PaletteKind::Reference
             | PaletteKind::SshHost
             | PaletteKind::RunAndDebug
+            | PaletteKind::AttachProcess
             | PaletteKind::ColorTheme
             | PaletteKind::IconTheme
             | PaletteKind::Language
             | PaletteKind::LineEnding
             | PaletteKind::SCMReferences
-            | PaletteKind::DiffFiles => "",
+            | PaletteKind::SCMInput
+            | PaletteKind::SCMStashes
+            | PaletteKind::DiffFiles
+            | PaletteKind::OpenEditors => "",
             #[cfg(windows)]
             PaletteKind::WslHost => "",
         }
@@ -87,6 +95,8 @@ pub fn command(self) -> Option<LapceWorkbenchCommand> {
             PaletteKind::RunAndDebug => {
                 Some(LapceWorkbenchCommand::PaletteRunAndDebug)
             }
+            // Opened when debugging an attach config without a pid
+            PaletteKind::AttachProcess => None,
             PaletteKind::ColorTheme => Some(LapceWorkbenchCommand::ChangeColorTheme),
             PaletteKind::IconTheme => Some(LapceWorkbenchCommand::ChangeIconTheme),
             PaletteKind::Language => Some(LapceWorkbenchCommand::ChangeFileLanguage),
@@ -96,8 +106,13 @@ pub fn command(self) -> Option<LapceWorkbenchCommand> {
             PaletteKind::SCMReferences => {
                 Some(LapceWorkbenchCommand::PaletteSCMReferences)
             }
+            // Opened by the branch and stash commands
+            PaletteKind::SCMInput | PaletteKind::SCMStashes => None,
             PaletteKind::TerminalProfile => None, // InternalCommand::NewTerminal
             PaletteKind::DiffFiles => Some(LapceWorkbenchCommand::DiffFiles),
+            PaletteKind::OpenEditors => {
+                Some(LapceWorkbenchCommand::CompareWithOpenEditor)
+            }
         }
     }
 
@@ -120,12 +135,16 @@ pub fn get_input<'a>(&self, input: &'a str) -> &'a str {
             | PaletteKind::Reference
             | PaletteKind::SshHost
             | PaletteKind::RunAndDebug
+            | PaletteKind::AttachProcess
             | PaletteKind::ColorTheme
             | PaletteKind::IconTheme
             | PaletteKind::Language
             | PaletteKind::LineEnding
             | PaletteKind::SCMReferences
-            | PaletteKind::DiffFiles => input,
+            | PaletteKind::SCMInput
+            | PaletteKind::SCMStashes
+            | PaletteKind::DiffFiles
+            | PaletteKind::OpenEditors => input,
             PaletteKind::PaletteHelp
             | PaletteKind::Command
             | PaletteKind::Workspace
@@ -1,15 +1,20 @@
 use std::{rc::Rc, sync::Arc};
 
 use floem::{
+    action::show_context_menu,
     cosmic_text::Style as FontStyle,
     event::EventListener,
-    peniko::Color,
+    menu::{Menu, MenuItem},
+    peniko::{
+        kurbo::{Point, Rect},
+        Color,
+    },
     reactive::{create_rw_signal, ReadSignal, RwSignal},
     style::CursorStyle,
     view::View,
     views::{
-        container, dyn_stack, label, scroll, stack, svg, text, virtual_stack,
-        Decorators, VirtualDirection, VirtualItemSize,
+        container, dyn_container, dyn_stack, empty, label, scroll, stack, svg, text,
+        virtual_stack, Decorators, VirtualDirection, VirtualItemSize, VirtualVector,
     },
 };
 use lapce_rpc::{
@@ -17,17 +22,22 @@
     terminal::TermId,
 };
 
-use super::{position::PanelPosition, view::panel_header};
+use super::{kind::PanelKind, position::PanelPosition, view::panel_header};
 use crate::{
     app::clickable_icon,
     command::InternalCommand,
     config::{color::LapceColor, icon::LapceIcons, LapceConfig},
-    debug::{DapVariable, RunDebugMode, StackTraceData},
+    debug::{
+        DapVariable, DapVariableViewdata, DebugConsoleEntry, DebugConsoleLine,
+        DebugInput, RunDebugMode, ScopeOrVar, StackTraceData, VariableTree,
+        WatchValue,
+    },
     editor::location::{EditorLocation, EditorPosition},
     listener::Listener,
     settings::checkbox,
     terminal::panel::TerminalPanelData,
-    window_tab::WindowTabData,
+    text_input::text_input,
+    window_tab::{Focus, WindowTabData},
 };
 
 pub fn debug_panel(
@@ -52,6 +62,11 @@ pub fn debug_panel(
             variables_view(window_tab_data.clone()),
         ))
         .style(|s| s.width_pct(100.0).flex_grow(1.0).flex_basis(0.0).flex_col()),
+        stack((
+            panel_header("Watch".to_string(), config),
+            watch_view(window_tab_data.clone()),
+        ))
+        .style(|s| s.width_pct(100.0).flex_grow(1.0).flex_basis(0.0).flex_col()),
         stack((
             panel_header("Stack Frames".to_string(), config),
             debug_stack_traces(terminal, internal_command, config),
@@ -62,6 +77,11 @@ pub fn debug_panel(
             breakpoints_view(window_tab_data.clone()),
         ))
         .style(|s| s.width_pct(100.0).flex_col().height(150.0)),
+        stack((
+            panel_header("Debug Console".to_string(), config),
+            debug_console_view(window_tab_data.clone()),
+        ))
+        .style(|s| s.width_pct(100.0).flex_grow(1.0).flex_basis(0.0).flex_col()),
     ))
     .style(move |s| {
         s.width_pct(100.0)
@@ -335,8 +355,10 @@ fn debug_processes(
 fn variables_view(window_tab_data: Rc<WindowTabData>) -> impl View {
     let terminal = window_tab_data.terminal.clone();
     let local_terminal = window_tab_data.terminal.clone();
+    let debug_panel = window_tab_data.debug_panel.clone();
     let ui_line_height = window_tab_data.common.ui_line_height;
     let config = window_tab_data.common.config;
+    let focus = window_tab_data.common.focus;
     container(
         scroll(
             virtual_stack(
@@ -349,11 +371,8 @@ fn variables_view(window_tab_data: Rc<WindowTabData>) -> impl View {
                             return DapVariable::default();
                         }
                         let process_stopped = terminal
-                            .get_terminal(&dap.term_id)
-                            .and_then(|t| {
-                                t.run_debug.with(|r| r.as_ref().map(|r| r.stopped))
-                            })
-                            .unwrap_or(true);
+                            .get_run_debug(&dap.term_id, true)
+                            .map_or(true, |r| r.stopped);
                         if process_stopped {
                             return DapVariable::default();
                         }
@@ -372,11 +391,49 @@ fn variables_view(window_tab_data: Rc<WindowTabData>) -> impl View {
                 },
                 move |node| {
                     let local_terminal = local_terminal.clone();
+                    let debug_panel = debug_panel.clone();
                     let level = node.level;
                     let reference = node.item.reference();
                     let name = node.item.name();
                     let ty = node.item.ty();
                     let type_exists = ty.map(|ty| !ty.is_empty()).unwrap_or(false);
+                    let value = node.item.value().unwrap_or("").to_string();
+                    let var = match &node.item {
+                        ScopeOrVar::Var(var) => Some(var.clone()),
+                        ScopeOrVar::Scope(_) => None,
+                    };
+                    let dap_id =
+                        local_terminal.get_active_dap(false).map(|dap| dap.dap_id);
+                    let is_editing = {
+                        let variable_edit = debug_panel.variable_edit;
+                        let parent = node.parent.clone();
+                        let name = name.to_string();
+                        move || {
+                            variable_edit.with(|edit| {
+                                edit.as_ref().is_some_and(|edit| {
+                                    Some(edit.dap_id) == dap_id
+                                        && edit.parent == parent
+                                        && edit.name == name
+                                })
+                            })
+                        }
+                    };
+                    let start_edit = {
+                        let local_terminal = local_terminal.clone();
+                        let debug_panel = debug_panel.clone();
+                        let parent = node.parent.clone();
+                        let var = var.clone();
+                        move || {
+                            let dap = local_terminal.get_active_dap(false);
+                            if let (Some(dap), Some(var)) = (dap, var.as_ref()) {
+                                debug_panel.start_variable_edit(
+                                    &dap,
+                                    parent.clone(),
+                                    var,
+                                );
+                            }
+                        }
+                    };
                     stack((
                         svg(move || {
                             let config = config.get();
@@ -407,20 +464,77 @@ fn variables_view(window_tab_data: Rc<WindowTabData>) -> impl View {
                                     s.hide()
                                 })
                         }),
-                        text(format!(" = {}", node.item.value().unwrap_or("")))
-                            .style(move |s| s.apply_if(reference > 0, |s| s.hide())),
+                        {
+                            let variable_editor =
+                                debug_panel.variable_editor.clone();
+                            let input = debug_panel.input;
+                            dyn_container(is_editing, move |editing| {
+                                if editing {
+                                    stack((
+                                        text(" = "),
+                                        text_input(
+                                            variable_editor.clone(),
+                                            move || {
+                                                focus.get()
+                                                    == Focus::Panel(PanelKind::Debug)
+                                                    && input.get()
+                                                        == DebugInput::Variable
+                                            },
+                                        )
+                                        .style(|s| s.min_width(150.0)),
+                                    ))
+                                    .any()
+                                } else {
+                                    text(format!(" = {value}"))
+                                        .style(move |s| {
+                                            s.apply_if(reference > 0, |s| s.hide())
+                                        })
+                                        .any()
+                                }
+                            })
+                        },
                     ))
+                    .on_double_click_stop({
+                        let start_edit = start_edit.clone();
+                        move |_| start_edit()
+                    })
+                    .on_secondary_click_stop({
+                        let local_terminal = local_terminal.clone();
+                        let parent = node.parent.clone();
+                        move |_| {
+                            let Some(var) = var.clone() else {
+                                return;
+                            };
+                            let Some(dap) = local_terminal.get_active_dap(false)
+                            else {
+                                return;
+                            };
+                            let mut menu = Menu::new("");
+                            if dap.can_set_variable(&parent, &var) {
+                                let start_edit = start_edit.clone();
+                                menu = menu.entry(
+                                    MenuItem::new("Set Value")
+                                        .action(move || start_edit()),
+                                );
+                            }
+                            if let Some(expression) = var.evaluate_name.clone() {
+                                let debug = local_terminal.debug.clone();
+                                menu = menu.entry(
+                                    MenuItem::new("Add to Watch").action(
+                                        move || debug.add_watch(expression.clone()),
+                                    ),
+                                );
+                            }
+                            show_context_menu(menu, None);
+                        }
+                    })
                     .on_click_stop(move |_| {
                         if reference > 0 {
                             let dap = local_terminal.get_active_dap(false);
                             if let Some(dap) = dap {
                                 let process_stopped = local_terminal
-                                    .get_terminal(&dap.term_id)
-                                    .and_then(|t| {
-                                        t.run_debug
-                                            .with(|r| r.as_ref().map(|r| r.stopped))
-                                    })
-                                    .unwrap_or(true);
+                                    .get_run_debug(&dap.term_id, false)
+                                    .map_or(true, |r| r.stopped);
                                 if !process_stopped {
                                     dap.toggle_expand(
                                         node.parent.clone(),
@@ -454,11 +568,116 @@ fn variables_view(window_tab_data: Rc<WindowTabData>) -> impl View {
     .style(|s| s.width_full().line_height(1.6).flex_grow(1.0).flex_basis(0))
 }
 
+fn watch_view(window_tab_data: Rc<WindowTabData>) -> impl View {
+    let terminal = window_tab_data.terminal.clone();
+    let debug = terminal.debug.clone();
+    let debug_panel = window_tab_data.debug_panel.clone();
+    let editor = debug_panel.watch_editor.clone();
+    let input = debug_panel.input;
+    let config = window_tab_data.common.config;
+    let focus = window_tab_data.common.focus;
+    let is_focused = move || {
+        focus.get() == Focus::Panel(PanelKind::Debug)
+            && input.get() == DebugInput::Watch
+    };
+
+    stack((
+        container(
+            scroll(
+                dyn_stack(
+                    move || debug.watches.get(),
+                    |expression| expression.clone(),
+                    move |expression| {
+                        watch_item(terminal.clone(), expression, config)
+                    },
+                )
+                .style(|s| s.flex_col().min_width_full()),
+            )
+            .style(|s| s.absolute().size_full()),
+        )
+        .style(|s| {
+            s.width_full()
+                .line_height(1.6)
+                .flex_grow(1.0)
+                .flex_basis(0.0)
+        }),
+        text_input(editor, is_focused)
+            .placeholder(|| "Add Expression".to_string())
+            .on_event_cont(EventListener::PointerDown, move |_| {
+                debug_panel.focus_input(DebugInput::Watch);
+            })
+            .style(move |s| {
+                let config = config.get();
+                s.width_full()
+                    .padding_vert(4.0)
+                    .padding_horiz(10.0)
+                    .cursor(CursorStyle::Text)
+                    .border_top(1.0)
+                    .border_color(config.color(LapceColor::LAPCE_BORDER))
+            }),
+    ))
+    .style(|s| s.size_full().flex_col())
+}
+
+fn watch_item(
+    terminal: TerminalPanelData,
+    expression: String,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    let debug = terminal.debug.clone();
+    let value = {
+        let terminal = terminal.clone();
+        let expression = expression.clone();
+        move || {
+            terminal.get_active_dap(true).and_then(|dap| {
+                dap.watch_values
+                    .with(|values| values.get(&expression).cloned())
+            })
+        }
+    };
+
+    stack((
+        {
+            let expression = expression.clone();
+            dyn_container(value, move |value| match value {
+                Some(WatchValue::Result { tree, .. }) => {
+                    variable_tree(terminal.clone(), tree, config).any()
+                }
+                Some(WatchValue::Error(message)) => {
+                    text(format!("{expression}: {message}"))
+                        .style(move |s| {
+                            s.color(config.get().color(LapceColor::LAPCE_ERROR))
+                        })
+                        .any()
+                }
+                None => text(format!("{expression}: not available"))
+                    .style(move |s| {
+                        s.color(config.get().color(LapceColor::EDITOR_DIM))
+                    })
+                    .any(),
+            })
+            .style(|s| s.flex_grow(1.0))
+        },
+        clickable_icon(
+            || LapceIcons::CLOSE,
+            move || {
+                debug.remove_watch(&expression);
+            },
+            || false,
+            || false,
+            || "Remove Expression",
+            config,
+        ),
+    ))
+    .style(|s| s.items_start().min_width_full().padding_horiz(10.0))
+}
+
 fn debug_stack_frames(
     dap_id: DapId,
     thread_id: ThreadId,
     stack_trace: StackTraceData,
     stopped: RwSignal<bool>,
+    frame_id: RwSignal<Option<usize>>,
     internal_command: Listener<InternalCommand>,
     config: ReadSignal<Arc<LapceConfig>>,
 ) -> impl View {
@@ -502,6 +721,7 @@ fn debug_stack_frames(
                     .to_string();
                 let has_source = !source_path.is_empty();
                 let source_path = format!("{source_path}:{}", frame.line);
+                let id = frame.id;
 
                 container(stack((
                     label(move || frame.name.clone()).style(move |s| {
@@ -550,6 +770,11 @@ fn debug_stack_frames(
                         .apply_if(!has_source, |s| {
                             s.color(config.color(LapceColor::EDITOR_DIM))
                         })
+                        .apply_if(frame_id.get() == Some(id), |s| {
+                            s.background(
+                                config.color(LapceColor::PANEL_CURRENT_BACKGROUND),
+                            )
+                        })
                         .hover(|s| {
                             s.background(
                                 config.color(LapceColor::PANEL_HOVERED_BACKGROUND),
@@ -577,11 +802,8 @@ fn debug_stack_traces(
                     let dap = local_terminal.get_active_dap(true);
                     if let Some(dap) = dap {
                         let process_stopped = local_terminal
-                            .get_terminal(&dap.term_id)
-                            .and_then(|t| {
-                                t.run_debug.with(|r| r.as_ref().map(|r| r.stopped))
-                            })
-                            .unwrap_or(true);
+                            .get_run_debug(&dap.term_id, true)
+                            .map_or(true, |r| r.stopped);
                         if process_stopped {
                             return Vec::new();
                         }
@@ -590,24 +812,33 @@ fn debug_stack_traces(
                         let mut traces = stack_traces
                             .into_iter()
                             .map(|(thread_id, stack_trace)| {
-                                (dap.dap_id, dap.stopped, thread_id, stack_trace)
+                                (
+                                    dap.dap_id,
+                                    dap.stopped,
+                                    dap.frame_id,
+                                    thread_id,
+                                    stack_trace,
+                                )
                             })
                             .collect::<Vec<_>>();
-                        traces.sort_by_key(|(_, _, id, _)| main_thread != Some(*id));
+                        traces.sort_by_key(|(_, _, _, id, _)| {
+                            main_thread != Some(*id)
+                        });
                         traces
                     } else {
                         Vec::new()
                     }
                 },
-                |(dap_id, stopped, thread_id, _)| {
+                |(dap_id, stopped, _, thread_id, _)| {
                     (*dap_id, *thread_id, stopped.get_untracked())
                 },
-                move |(dap_id, stopped, thread_id, stack_trace)| {
+                move |(dap_id, stopped, frame_id, thread_id, stack_trace)| {
                     debug_stack_frames(
                         dap_id,
                         thread_id,
                         stack_trace,
                         stopped,
+                        frame_id,
                         internal_command,
                         config,
                     )
@@ -626,136 +857,500 @@ fn debug_stack_traces(
 }
 
 fn breakpoints_view(window_tab_data: Rc<WindowTabData>) -> impl View {
-    let breakpoints = window_tab_data.terminal.debug.breakpoints;
+    let debug_panel = window_tab_data.debug_panel.clone();
+    let editor = debug_panel.function_breakpoint_editor.clone();
+    let input = debug_panel.input;
     let config = window_tab_data.common.config;
-    let workspace = window_tab_data.common.workspace.clone();
+    let focus = window_tab_data.common.focus;
+    let is_focused = move || {
+        focus.get() == Focus::Panel(PanelKind::Debug)
+            && input.get() == DebugInput::FunctionBreakpoint
+    };
     let available_width = create_rw_signal(0.0);
-    let internal_command = window_tab_data.common.internal_command;
-    container(
-        scroll(
-            dyn_stack(
-                move || {
-                    breakpoints
-                        .get()
-                        .into_iter()
-                        .flat_map(|(path, breakpoints)| {
-                            breakpoints.into_values().map(move |b| (path.clone(), b))
-                        })
-                },
-                move |(path, breakpoint)| {
-                    (path.clone(), breakpoint.line, breakpoint.active)
-                },
-                move |(path, breakpoint)| {
-                    let line = breakpoint.line;
-                    let full_path = path.clone();
-                    let full_path_for_jump = path.clone();
-                    let full_path_for_close = path.clone();
-                    let path = if let Some(workspace_path) = workspace.path.as_ref()
-                    {
-                        path.strip_prefix(workspace_path)
-                            .unwrap_or(&full_path)
-                            .to_path_buf()
-                    } else {
-                        path
-                    };
 
-                    let file_name =
-                        path.file_name().and_then(|s| s.to_str()).unwrap_or("");
-                    let folder =
-                        path.parent().and_then(|s| s.to_str()).unwrap_or("");
-                    let folder_empty = folder.is_empty();
+    stack((
+        container(
+            scroll(
+                stack((
+                    exception_filters_view(window_tab_data.clone()),
+                    function_breakpoints_view(window_tab_data.clone()),
+                    source_breakpoints_view(
+                        window_tab_data.clone(),
+                        available_width,
+                    ),
+                ))
+                .style(|s| s.flex_col().line_height(1.6).width_pct(100.0)),
+            )
+            .on_resize(move |rect| {
+                let width = rect.width();
+                if available_width.get_untracked() != width {
+                    available_width.set(width);
+                }
+            })
+            .style(|s| s.absolute().size_pct(100.0, 100.0)),
+        )
+        .style(|s| s.width_full().flex_grow(1.0).flex_basis(0.0)),
+        text_input(editor, is_focused)
+            .placeholder(|| "Add Function Breakpoint".to_string())
+            .on_event_cont(EventListener::PointerDown, move |_| {
+                debug_panel.focus_input(DebugInput::FunctionBreakpoint);
+            })
+            .style(move |s| {
+                let config = config.get();
+                s.width_full()
+                    .padding_vert(4.0)
+                    .padding_horiz(10.0)
+                    .cursor(CursorStyle::Text)
+                    .border_top(1.0)
+                    .border_color(config.color(LapceColor::LAPCE_BORDER))
+            }),
+    ))
+    .style(|s| s.size_full().flex_col())
+}
 
-                    stack((
-                        clickable_icon(
-                            move || LapceIcons::CLOSE,
-                            move || {
-                                breakpoints.update(|breakpoints| {
-                                    if let Some(breakpoints) =
-                                        breakpoints.get_mut(&full_path_for_close)
-                                    {
-                                        breakpoints.remove(&line);
-                                    }
-                                });
-                            },
-                            || false,
-                            || false,
-                            || "Remove",
-                            config,
+/// The exception filters of the active debug session, which make it stop
+/// when an exception is thrown
+fn exception_filters_view(window_tab_data: Rc<WindowTabData>) -> impl View {
+    let terminal = window_tab_data.terminal.clone();
+    let debug = terminal.debug.clone();
+    let choices = debug.exception_filters;
+    let proxy = window_tab_data.common.proxy.clone();
+    let config = window_tab_data.common.config;
+
+    dyn_stack(
+        move || {
+            terminal
+                .get_active_dap(true)
+                .and_then(|dap| dap.capabilities.get())
+                .and_then(|c| c.exception_breakpoint_filters)
+                .unwrap_or_default()
+        },
+        |filter| filter.filter.clone(),
+        move |filter| {
+            let debug = debug.clone();
+            let proxy = proxy.clone();
+            let label = filter.label.clone();
+            let enabled = {
+                let filter = filter.clone();
+                move || choices.with(|choices| filter.enabled(choices))
+            };
+            stack((
+                checkbox(enabled, config)
+                    .style(|s| s.margin_right(6.0).cursor(CursorStyle::Pointer)),
+                text(label).style(|s| s.text_ellipsis().min_width(0.0)),
+            ))
+            .style(move |s| {
+                s.items_center()
+                    .padding_horiz(10.0)
+                    .width_pct(100.0)
+                    .hover(|s| {
+                        s.background(
+                            config.get().color(LapceColor::PANEL_HOVERED_BACKGROUND),
                         )
-                        .on_event_stop(EventListener::PointerDown, |_| {}),
-                        checkbox(move || breakpoint.active, config)
-                            .style(|s| {
-                                s.margin_right(6.0).cursor(CursorStyle::Pointer)
-                            })
-                            .on_click_stop(move |_| {
-                                breakpoints.update(|breakpoints| {
-                                    if let Some(breakpoints) =
-                                        breakpoints.get_mut(&full_path)
-                                    {
-                                        if let Some(breakpoint) =
-                                            breakpoints.get_mut(&line)
-                                        {
-                                            breakpoint.active = !breakpoint.active;
-                                        }
-                                    }
-                                });
-                            }),
-                        text(format!("{file_name}:{}", breakpoint.line + 1)).style(
-                            move |s| {
-                                let size = config.get().ui.icon_size() as f32;
-                                s.text_ellipsis().max_width(
-                                    available_width.get() as f32
-                                        - 20.0
-                                        - size
-                                        - 6.0
-                                        - size
-                                        - 8.0,
-                                )
-                            },
-                        ),
-                        text(folder).style(move |s| {
-                            s.text_ellipsis()
-                                .flex_grow(1.0)
-                                .flex_basis(0.0)
-                                .color(config.get().color(LapceColor::EDITOR_DIM))
-                                .min_width(0.0)
-                                .margin_left(6.0)
-                                .apply_if(folder_empty, |s| s.hide())
-                        }),
-                    ))
-                    .style(move |s| {
-                        s.items_center().padding_horiz(10.0).width_pct(100.0).hover(
-                            |s| {
-                                s.background(
-                                    config
-                                        .get()
-                                        .color(LapceColor::PANEL_HOVERED_BACKGROUND),
-                                )
-                            },
+                    })
+            })
+            .on_click_stop(move |_| {
+                debug.toggle_exception_filter(&filter, &proxy);
+            })
+        },
+    )
+    .style(|s| s.flex_col().width_pct(100.0))
+}
+
+fn function_breakpoints_view(window_tab_data: Rc<WindowTabData>) -> impl View {
+    let debug = window_tab_data.terminal.debug.clone();
+    let daps = debug.daps;
+    let function_breakpoints = debug.function_breakpoints;
+    let proxy = window_tab_data.common.proxy.clone();
+    let config = window_tab_data.common.config;
+
+    dyn_stack(
+        move || function_breakpoints.get(),
+        |breakpoint| {
+            (
+                breakpoint.name.clone(),
+                breakpoint.active,
+                breakpoint.verified,
+            )
+        },
+        move |breakpoint| {
+            let name = breakpoint.name.clone();
+            let debug_for_remove = debug.clone();
+            let proxy_for_remove = proxy.clone();
+            let name_for_remove = name.clone();
+            let debug = debug.clone();
+            let proxy = proxy.clone();
+            // Only a running debug session can tell whether the function exists
+            let verified = breakpoint.verified;
+            let unverified = move || !verified && daps.with(|daps| !daps.is_empty());
+
+            stack((
+                clickable_icon(
+                    || LapceIcons::CLOSE,
+                    move || {
+                        debug_for_remove.remove_function_breakpoint(
+                            &name_for_remove,
+                            &proxy_for_remove,
+                        );
+                    },
+                    || false,
+                    || false,
+                    || "Remove",
+                    config,
+                )
+                .on_event_stop(EventListener::PointerDown, |_| {}),
+                checkbox(move || breakpoint.active, config)
+                    .style(|s| s.margin_right(6.0).cursor(CursorStyle::Pointer)),
+                text(&name).style(move |s| {
+                    s.text_ellipsis()
+                        .min_width(0.0)
+                        .apply_if(unverified(), |s| {
+                            s.color(config.get().color(LapceColor::EDITOR_DIM))
+                        })
+                }),
+            ))
+            .style(move |s| {
+                s.items_center()
+                    .padding_horiz(10.0)
+                    .width_pct(100.0)
+                    .hover(|s| {
+                        s.background(
+                            config.get().color(LapceColor::PANEL_HOVERED_BACKGROUND),
                         )
                     })
+            })
+            .on_click_stop(move |_| {
+                debug.toggle_function_breakpoint(&name, &proxy);
+            })
+        },
+    )
+    .style(|s| s.flex_col().width_pct(100.0))
+}
+
+fn source_breakpoints_view(
+    window_tab_data: Rc<WindowTabData>,
+    available_width: RwSignal<f64>,
+) -> impl View {
+    let breakpoints = window_tab_data.terminal.debug.breakpoints;
+    let daps = window_tab_data.terminal.debug.daps;
+    let config = window_tab_data.common.config;
+    let workspace = window_tab_data.common.workspace.clone();
+    let internal_command = window_tab_data.common.internal_command;
+    dyn_stack(
+        move || {
+            breakpoints
+                .get()
+                .into_iter()
+                .flat_map(|(path, breakpoints)| {
+                    breakpoints.into_values().map(move |b| (path.clone(), b))
+                })
+        },
+        move |(path, breakpoint)| {
+            (
+                path.clone(),
+                breakpoint.line,
+                breakpoint.active,
+                breakpoint.verified,
+            )
+        },
+        move |(path, breakpoint)| {
+            let line = breakpoint.line;
+            let verified = breakpoint.verified;
+            let unverified = move || !verified && daps.with(|daps| !daps.is_empty());
+            let full_path = path.clone();
+            let full_path_for_jump = path.clone();
+            let full_path_for_close = path.clone();
+            let path = if let Some(workspace_path) = workspace.path.as_ref() {
+                path.strip_prefix(workspace_path)
+                    .unwrap_or(&full_path)
+                    .to_path_buf()
+            } else {
+                path
+            };
+
+            let file_name = path.file_name().and_then(|s| s.to_str()).unwrap_or("");
+            let folder = path.parent().and_then(|s| s.to_str()).unwrap_or("");
+            let folder_empty = folder.is_empty();
+
+            stack((
+                clickable_icon(
+                    move || LapceIcons::CLOSE,
+                    move || {
+                        breakpoints.update(|breakpoints| {
+                            if let Some(breakpoints) =
+                                breakpoints.get_mut(&full_path_for_close)
+                            {
+                                breakpoints.remove(&line);
+                            }
+                        });
+                    },
+                    || false,
+                    || false,
+                    || "Remove",
+                    config,
+                )
+                .on_event_stop(EventListener::PointerDown, |_| {}),
+                checkbox(move || breakpoint.active, config)
+                    .style(|s| s.margin_right(6.0).cursor(CursorStyle::Pointer))
                     .on_click_stop(move |_| {
-                        internal_command.send(InternalCommand::JumpToLocation {
-                            location: EditorLocation {
-                                path: full_path_for_jump.clone(),
-                                position: Some(EditorPosition::Line(line)),
-                                scroll_offset: None,
-                                ignore_unconfirmed: false,
-                                same_editor_tab: false,
-                            },
+                        breakpoints.update(|breakpoints| {
+                            if let Some(breakpoints) =
+                                breakpoints.get_mut(&full_path)
+                            {
+                                if let Some(breakpoint) = breakpoints.get_mut(&line)
+                                {
+                                    breakpoint.active = !breakpoint.active;
+                                }
+                            }
                         });
+                    }),
+                text(format!("{file_name}:{}", breakpoint.line + 1)).style(
+                    move |s| {
+                        let config = config.get();
+                        let size = config.ui.icon_size() as f32;
+                        s.text_ellipsis()
+                            .max_width(
+                                available_width.get() as f32
+                                    - 20.0
+                                    - size
+                                    - 6.0
+                                    - size
+                                    - 8.0,
+                            )
+                            .apply_if(unverified(), |s| {
+                                s.color(config.color(LapceColor::EDITOR_DIM))
+                            })
+                    },
+                ),
+                text(folder).style(move |s| {
+                    s.text_ellipsis()
+                        .flex_grow(1.0)
+                        .flex_basis(0.0)
+                        .color(config.get().color(LapceColor::EDITOR_DIM))
+                        .min_width(0.0)
+                        .margin_left(6.0)
+                        .apply_if(folder_empty, |s| s.hide())
+                }),
+            ))
+            .style(move |s| {
+                s.items_center()
+                    .padding_horiz(10.0)
+                    .width_pct(100.0)
+                    .hover(|s| {
+                        s.background(
+                            config.get().color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                        )
                     })
-                },
+            })
+            .on_click_stop(move |_| {
+                internal_command.send(InternalCommand::JumpToLocation {
+                    location: EditorLocation {
+                        path: full_path_for_jump.clone(),
+                        position: Some(EditorPosition::Line(line)),
+                        scroll_offset: None,
+                        ignore_unconfirmed: false,
+                        same_editor_tab: false,
+                    },
+                });
+            })
+        },
+    )
+    .style(|s| s.flex_col().width_pct(100.0))
+}
+
+fn debug_console_view(window_tab_data: Rc<WindowTabData>) -> impl View {
+    let terminal = window_tab_data.terminal.clone();
+    let local_terminal = window_tab_data.terminal.clone();
+    let debug_panel = window_tab_data.debug_panel.clone();
+    let editor = debug_panel.console_editor.clone();
+    let input = debug_panel.input;
+    let ui_line_height = window_tab_data.common.ui_line_height;
+    let config = window_tab_data.common.config;
+    let focus = window_tab_data.common.focus;
+    let is_focused = move || {
+        focus.get() == Focus::Panel(PanelKind::Debug)
+            && input.get() == DebugInput::Console
+    };
+    let content_height = create_rw_signal(0.0);
+
+    stack((
+        container(
+            scroll(
+                virtual_stack(
+                    VirtualDirection::Vertical,
+                    VirtualItemSize::Fixed(Box::new(move || ui_line_height.get())),
+                    move || {
+                        terminal
+                            .get_active_dap(true)
+                            .map(|dap| dap.console.get())
+                            .unwrap_or_default()
+                    },
+                    |line| match line {
+                        DebugConsoleLine::Entry { id, .. } => (*id, 0, 0),
+                        DebugConsoleLine::Variable {
+                            id, index, tree, ..
+                        } => (*id, tree.id.get_untracked(), *index),
+                    },
+                    move |line| {
+                        debug_console_line(local_terminal.clone(), line, config)
+                    },
+                )
+                .on_resize(move |rect| {
+                    let height = rect.height();
+                    if content_height.get_untracked() != height {
+                        content_height.set(height);
+                    }
+                })
+                .style(|s| s.flex_col().min_width_full().padding_horiz(10.0)),
             )
-            .style(|s| s.flex_col().line_height(1.6).width_pct(100.0)),
+            // Follow the output as it's printed
+            .ensure_visible(move || {
+                let height = content_height.get();
+                Rect::from_origin_size(Point::new(0.0, height), (0.0, 0.0))
+            })
+            .style(|s| s.absolute().size_full()),
         )
-        .on_resize(move |rect| {
-            let width = rect.width();
-            if available_width.get_untracked() != width {
-                available_width.set(width);
-            }
-        })
-        .style(|s| s.absolute().size_pct(100.0, 100.0)),
+        .style(|s| {
+            s.width_full()
+                .line_height(1.6)
+                .flex_grow(1.0)
+                .flex_basis(0.0)
+        }),
+        text_input(editor, is_focused)
+            .on_event_cont(EventListener::PointerDown, move |_| {
+                debug_panel.focus_input(DebugInput::Console);
+            })
+            .style(move |s| {
+                let config = config.get();
+                s.width_full()
+                    .padding_vert(4.0)
+                    .padding_horiz(10.0)
+                    .cursor(CursorStyle::Text)
+                    .border_top(1.0)
+                    .border_color(config.color(LapceColor::LAPCE_BORDER))
+            }),
+    ))
+    .style(|s| s.size_full().flex_col())
+}
+
+fn debug_console_line(
+    terminal: TerminalPanelData,
+    line: DebugConsoleLine,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    let entry = match line {
+        DebugConsoleLine::Entry { entry, .. } => entry,
+        DebugConsoleLine::Variable { tree, node, .. } => {
+            return variable_node(terminal, tree, node, config).any();
+        }
+    };
+    match entry {
+        DebugConsoleEntry::Output { category, output } => text(output)
+            .style(move |s| {
+                let config = config.get();
+                let color = match category.as_deref() {
+                    Some("stderr") => config.color(LapceColor::LAPCE_ERROR),
+                    Some("important") => config.color(LapceColor::LAPCE_WARN),
+                    Some("console") => config.color(LapceColor::EDITOR_DIM),
+                    _ => config.color(LapceColor::EDITOR_FOREGROUND),
+                };
+                s.color(color)
+            })
+            .any(),
+        DebugConsoleEntry::Expression(expression) => stack((
+            text("> ")
+                .style(move |s| s.color(config.get().color(LapceColor::EDITOR_DIM))),
+            text(expression),
+        ))
+        .any(),
+        DebugConsoleEntry::Error(message) => text(message)
+            .style(move |s| s.color(config.get().color(LapceColor::LAPCE_ERROR)))
+            .any(),
+        // Results are shown as the nodes of their tree
+        DebugConsoleEntry::Result { .. } => empty().any(),
+    }
+}
+
+/// The tree of an evaluation result, whose structured values are expanded on click
+fn variable_tree(
+    terminal: TerminalPanelData,
+    tree: VariableTree,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    dyn_stack(
+        move || {
+            let mut root = tree.root.get();
+            let len = root.total_len();
+            root.slice(0..len).collect::<Vec<_>>()
+        },
+        |node| {
+            (
+                node.item.name().to_string(),
+                node.item.value().map(|v| v.to_string()),
+                node.item.reference(),
+                node.expanded,
+                node.level,
+            )
+        },
+        move |node| variable_node(terminal.clone(), tree, node, config),
     )
-    .style(|s| s.size_pct(100.0, 100.0))
+    .style(|s| s.flex_col().min_width_full())
+}
+
+fn variable_node(
+    terminal: TerminalPanelData,
+    tree: VariableTree,
+    node: DapVariableViewdata,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    let level = node.level;
+    let reference = node.item.reference();
+    let name = node.item.name();
+    let value = node.item.value().unwrap_or("");
+    let content = if name.is_empty() {
+        value.to_string()
+    } else {
+        format!("{name}: {value}")
+    };
+    stack((
+        svg(move || {
+            let config = config.get();
+            let svg_str = match node.expanded {
+                true => LapceIcons::ITEM_OPENED,
+                false => LapceIcons::ITEM_CLOSED,
+            };
+            config.ui_svg(svg_str)
+        })
+        .style(move |s| {
+            let config = config.get();
+            let size = config.ui.icon_size() as f32;
+
+            let color = if reference > 0 {
+                config.color(LapceColor::LAPCE_ICON_ACTIVE)
+            } else {
+                Color::TRANSPARENT
+            };
+            s.size(size, size).color(color)
+        }),
+        text(content),
+    ))
+    .on_click_stop(move |_| {
+        if reference > 0 {
+            if let Some(dap) = terminal.get_active_dap(false) {
+                dap.toggle_var_expand(tree, node.parent.clone(), reference);
+            }
+        }
+    })
+    .style(move |s| {
+        s.items_center()
+            .padding_left((level * 10) as f32)
+            .min_width_pct(100.0)
+            .hover(|s| {
+                s.apply_if(reference > 0, |s| {
+                    s.cursor(CursorStyle::Pointer).background(
+                        config.get().color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                    )
+                })
+            })
+    })
 }
@@ -0,0 +1,400 @@
+use std::{path::PathBuf, rc::Rc, sync::Arc};
+
+use floem::{
+    context::PaintCx,
+    id::Id,
+    peniko::kurbo::{Circle, Line, Size},
+    reactive::{create_memo, Memo, ReadSignal},
+    style::{CursorStyle, Style},
+    view::{AnyWidget, View, ViewData, Widget},
+    views::{
+        container, label, scroll, stack, svg, virtual_stack, Decorators,
+        VirtualDirection, VirtualItemSize,
+    },
+};
+use lapce_rpc::source_control::FileDiff;
+
+use super::position::PanelPosition;
+use crate::{
+    app::clickable_icon,
+    config::{color::LapceColor, icon::LapceIcons, LapceConfig},
+    git_log::{GitLogData, GitLogViewItem, GraphRow},
+    source_control::relative_time,
+    window_tab::WindowTabData,
+    workspace::LapceWorkspace,
+};
+
+/// The width of a lane of the commit graph
+const LANE_WIDTH: f64 = 12.0;
+
+/// The colors of the lanes of the commit graph, which repeat when there are
+/// more lanes
+const LANE_COLORS: [&str; 6] = [
+    LapceColor::TERMINAL_BLUE,
+    LapceColor::TERMINAL_GREEN,
+    LapceColor::TERMINAL_MAGENTA,
+    LapceColor::TERMINAL_YELLOW,
+    LapceColor::TERMINAL_CYAN,
+    LapceColor::TERMINAL_RED,
+];
+
+pub fn git_log_panel(
+    window_tab_data: Rc<WindowTabData>,
+    _position: PanelPosition,
+) -> impl View {
+    let config = window_tab_data.common.config;
+    let workspace = window_tab_data.common.workspace.clone();
+    let git_log = window_tab_data.git_log.clone();
+    let path = git_log.path;
+    stack((
+        stack((
+            label(move || match path.get() {
+                Some(path) => {
+                    format!("History of {}", workspace_relative(&workspace, &path))
+                }
+                None => "Commits".to_string(),
+            })
+            .style(|s| s.flex_grow(1.0).text_ellipsis().min_width(0.0)),
+            {
+                let git_log = git_log.clone();
+                clickable_icon(
+                    || LapceIcons::GIT_LOG,
+                    move || {
+                        git_log.show(None);
+                    },
+                    || false,
+                    move || path.get().is_none(),
+                    || "Show All Commits",
+                    config,
+                )
+            },
+            clickable_icon(
+                || LapceIcons::GIT_LOG_REFRESH,
+                move || {
+                    git_log.refresh();
+                },
+                || false,
+                || false,
+                || "Refresh",
+                config,
+            ),
+        ))
+        .style(move |s| {
+            s.items_center()
+                .padding_left(10.0)
+                .padding_right(6.0)
+                .padding_vert(2.0)
+                .width_pct(100.0)
+                .background(config.get().color(LapceColor::EDITOR_BACKGROUND))
+        }),
+        git_log_list_view(window_tab_data),
+    ))
+    .style(|s| s.flex_col().size_pct(100.0, 100.0))
+}
+
+fn git_log_list_view(window_tab_data: Rc<WindowTabData>) -> impl View {
+    let git_log = window_tab_data.git_log.clone();
+    let log = git_log.log;
+    let log_id = git_log.log_id;
+    let loading = git_log.loading;
+    let has_more = git_log.has_more;
+    let ui_line_height = window_tab_data.common.ui_line_height;
+    let config = window_tab_data.common.config;
+    let workspace = window_tab_data.common.workspace.clone();
+
+    // All the rows have the graph as wide as the widest one, so the summaries
+    // line up
+    let graph_width = create_memo(move |_| {
+        log.with(|log| {
+            log.commits
+                .iter()
+                .map(|commit| commit.graph.width())
+                .max()
+                .unwrap_or(0)
+        }) as f64
+            * LANE_WIDTH
+    });
+
+    stack((
+        label(move || {
+            if loading.get() {
+                "Loading...".to_string()
+            } else {
+                "No commits".to_string()
+            }
+        })
+        .style(move |s| {
+            s.padding_horiz(10.0).apply_if(
+                !loading.get() && log.with(|log| !log.commits.is_empty()),
+                |s| s.hide(),
+            )
+        }),
+        container(
+            scroll(
+                stack((
+                    virtual_stack(
+                        VirtualDirection::Vertical,
+                        VirtualItemSize::Fixed(Box::new(move || {
+                            ui_line_height.get()
+                        })),
+                        move || log.get(),
+                        move |item| {
+                            (
+                                log_id.get_untracked(),
+                                item.commit.id.clone(),
+                                item.file.clone(),
+                                item.expanded,
+                            )
+                        },
+                        {
+                            let git_log = git_log.clone();
+                            move |item| {
+                                git_log_item_view(
+                                    git_log.clone(),
+                                    item,
+                                    workspace.clone(),
+                                    graph_width,
+                                    ui_line_height,
+                                    config,
+                                )
+                            }
+                        },
+                    )
+                    .style(|s| s.flex_col().min_width_full()),
+                    label(|| "Load More Commits".to_string())
+                        .on_click_stop(move |_| {
+                            git_log.load_more();
+                        })
+                        .style(move |s| {
+                            let config = config.get();
+                            s.padding_horiz(10.0)
+                                .min_width_full()
+                                .color(config.color(LapceColor::EDITOR_LINK))
+                                .cursor(CursorStyle::Pointer)
+                                .apply_if(!has_more.get() || loading.get(), |s| {
+                                    s.hide()
+                                })
+                                .hover(|s| {
+                                    s.background(
+                                        config.color(
+                                            LapceColor::PANEL_HOVERED_BACKGROUND,
+                                        ),
+                                    )
+                                })
+                        }),
+                ))
+                .style(|s| s.flex_col().min_width_full()),
+            )
+            .style(|s| s.absolute().size_full()),
+        )
+        .style(|s| s.width_full().flex_grow(1.0).flex_basis(0)),
+    ))
+    .style(|s| s.flex_col().width_full().line_height(1.6).flex_grow(1.0))
+}
+
+fn git_log_item_view(
+    git_log: GitLogData,
+    item: GitLogViewItem,
+    workspace: Arc<LapceWorkspace>,
+    graph_width: Memo<f64>,
+    ui_line_height: Memo<f64>,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    let graph = graph_view(item.graph.clone(), config).style(move |s| {
+        s.min_width(graph_width.get() as f32)
+            .width(graph_width.get() as f32)
+            .height_full()
+            .margin_left(6.0)
+    });
+
+    let content = if let Some(diff) = item.file.clone() {
+        let path = match &diff {
+            FileDiff::Renamed(new, _) => new.clone(),
+            FileDiff::Modified(path)
+            | FileDiff::Added(path)
+            | FileDiff::Deleted(path)
+            | FileDiff::Conflicted(path) => path.clone(),
+        };
+        let relative = PathBuf::from(workspace_relative(&workspace, &path));
+        let file_name = relative
+            .file_name()
+            .and_then(|s| s.to_str())
+            .unwrap_or("")
+            .to_string();
+        let folder = relative
+            .parent()
+            .and_then(|s| s.to_str())
+            .unwrap_or("")
+            .to_string();
+        let commit = item.commit.clone();
+        stack((
+            svg(move || config.get().file_svg(&path).0).style(move |s| {
+                let config = config.get();
+                let size = config.ui.icon_size() as f32;
+                let color = config.file_svg(&relative).1;
+                s.min_width(size)
+                    .size(size, size)
+                    .margin_left(20.0)
+                    .margin_right(6.0)
+                    .apply_opt(color, Style::color)
+            }),
+            label(move || file_name.clone()).style(|s| s.margin_right(6.0)),
+            label(move || folder.clone()).style(move |s| {
+                s.text_ellipsis()
+                    .min_width(0.0)
+                    .color(config.get().color(LapceColor::EDITOR_DIM))
+            }),
+        ))
+        .on_click_stop(move |_| {
+            git_log.open_file_changes(&commit, &diff);
+        })
+        .any()
+    } else {
+        let index = item.index;
+        let expanded = item.expanded;
+        let author = item.commit.author.clone();
+        let time = item.commit.time;
+        stack((
+            svg(move || {
+                let svg_str = if expanded {
+                    LapceIcons::ITEM_OPENED
+                } else {
+                    LapceIcons::ITEM_CLOSED
+                };
+                config.get().ui_svg(svg_str)
+            })
+            .style(move |s| {
+                let config = config.get();
+                let size = config.ui.icon_size() as f32;
+                s.min_width(size)
+                    .size(size, size)
+                    .margin_horiz(6.0)
+                    .color(config.color(LapceColor::LAPCE_ICON_ACTIVE))
+            }),
+            label(move || item.commit.summary.clone()).style(|s| {
+                s.text_ellipsis()
+                    .min_width(0.0)
+                    .flex_grow(1.0)
+                    .flex_basis(0.0)
+            }),
+            label(move || author.clone()).style(move |s| {
+                s.margin_left(10.0)
+                    .color(config.get().color(LapceColor::EDITOR_DIM))
+            }),
+            label(move || {
+                let now = chrono::Local::now().timestamp();
+                relative_time(now - time)
+            })
+            .style(move |s| {
+                s.margin_left(10.0)
+                    .color(config.get().color(LapceColor::EDITOR_DIM))
+            }),
+        ))
+        .on_click_stop(move |_| {
+            git_log.toggle_expand(index);
+        })
+        .any()
+    };
+
+    stack((graph, content.style(|s| s.items_center().flex_grow(1.0)))).style(
+        move |s| {
+            s.items_center()
+                .padding_right(10.0)
+                .width_full()
+                .height(ui_line_height.get() as f32)
+                .cursor(CursorStyle::Pointer)
+                .hover(|s| {
+                    s.background(
+                        config.get().color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                    )
+                })
+        },
+    )
+}
+
+/// The path relative to the workspace, which is how the log shows files
+fn workspace_relative(workspace: &LapceWorkspace, path: &PathBuf) -> String {
+    workspace
+        .path
+        .as_ref()
+        .and_then(|workspace_path| path.strip_prefix(workspace_path).ok())
+        .unwrap_or(path)
+        .to_string_lossy()
+        .to_string()
+}
+
+/// Paints the part of the commit graph in a row of the log
+struct GraphView {
+    id: Id,
+    data: ViewData,
+    graph: GraphRow,
+    config: ReadSignal<Arc<LapceConfig>>,
+    size: Size,
+}
+
+fn graph_view(graph: GraphRow, config: ReadSignal<Arc<LapceConfig>>) -> GraphView {
+    let id = Id::next();
+    GraphView {
+        id,
+        data: ViewData::new(id),
+        graph,
+        config,
+        size: Size::ZERO,
+    }
+}
+
+impl View for GraphView {
+    fn view_data(&self) -> &ViewData {
+        &self.data
+    }
+
+    fn view_data_mut(&mut self) -> &mut ViewData {
+        &mut self.data
+    }
+
+    fn build(self) -> AnyWidget {
+        Box::new(self)
+    }
+}
+
+impl Widget for GraphView {
+    fn view_data(&self) -> &ViewData {
+        &self.data
+    }
+
+    fn view_data_mut(&mut self) -> &mut ViewData {
+        &mut self.data
+    }
+
+    fn compute_layout(
+        &mut self,
+        cx: &mut floem::context::ComputeLayoutCx,
+    ) -> Option<floem::peniko::kurbo::Rect> {
+        if let Some(layout) = cx.get_layout(self.id) {
+            self.size =
+                Size::new(layout.size.width as f64, layout.size.height as f64);
+        }
+        None
+    }
+
+    fn paint(&mut self, cx: &mut PaintCx) {
+        let config = self.config.get_untracked();
+        let height = self.size.height;
+        let middle = height / 2.0;
+        let x = |lane: usize| lane as f64 * LANE_WIDTH + LANE_WIDTH / 2.0;
+        let color =
+            |lane: usize| config.color(LANE_COLORS[lane % LANE_COLORS.len()]);
+
+        for (from, to) in &self.graph.top {
+            let line = Line::new((x(*from), 0.0), (x(*to), middle));
+            cx.stroke(&line, color(*from), 1.5);
+        }
+        for (from, to) in &self.graph.bottom {
+            let line = Line::new((x(*from), middle), (x(*to), height));
+            cx.stroke(&line, color(*to), 1.5);
+        }
+        if let Some(lane) = self.graph.lane {
+            cx.fill(&Circle::new((x(lane), middle), 3.5), color(lane), 0.0);
+        }
+    }
+}
@@ -2,7 +2,7 @@
 
 use floem::{
     event::EventListener,
-    reactive::ReadSignal,
+    reactive::{create_memo, create_rw_signal, ReadSignal, RwSignal},
     style::{CursorStyle, Style},
     view::View,
     views::{
@@ -10,16 +10,20 @@
         VirtualDirection, VirtualItemSize,
     },
 };
+use lapce_rpc::proxy::SearchMatch;
 use lapce_xi_rope::find::CaseMatching;
 
 use super::{kind::PanelKind, position::PanelPosition};
 use crate::{
-    app::clickable_icon,
+    app::{clickable_icon, tooltip_label},
     command::InternalCommand,
     config::{color::LapceColor, icon::LapceIcons, LapceConfig},
-    editor::location::{EditorLocation, EditorPosition},
+    editor::{
+        location::{EditorLocation, EditorPosition},
+        EditorData,
+    },
     focus_text::focus_text,
-    global_search::{GlobalSearchData, SearchMatchData},
+    global_search::{GlobalSearchData, GlobalSearchInput, SearchMatchData},
     listener::Listener,
     text_input::text_input,
     window_tab::{Focus, WindowTabData},
@@ -39,73 +43,214 @@ pub fn global_search_panel(
     let whole_word = global_search.common.find.whole_words;
     let is_regex = global_search.common.find.is_regex;
 
+    let replace_editor = global_search.replace_editor.clone();
+    let replace_active = global_search.replace_active;
+    let focused_input = global_search.focused_input;
+    let search_result_data = global_search.search_result;
+    let last_replace = global_search.last_replace;
+
     let focus = global_search.common.focus;
-    let is_focused = move || focus.get() == Focus::Panel(PanelKind::Search);
+    let is_focused = move || {
+        focus.get() == Focus::Panel(PanelKind::Search)
+            && focused_input.get() == GlobalSearchInput::Search
+    };
+    let is_replace_focused = move || {
+        focus.get() == Focus::Panel(PanelKind::Search)
+            && focused_input.get() == GlobalSearchInput::Replace
+    };
 
     stack((
-        container(
+        stack((
+            clickable_icon(
+                move || {
+                    if replace_active.get() {
+                        LapceIcons::ITEM_OPENED
+                    } else {
+                        LapceIcons::ITEM_CLOSED
+                    }
+                },
+                move || {
+                    replace_active.update(|active| *active = !*active);
+                    if !replace_active.get_untracked()
+                        && focused_input.get_untracked()
+                            == GlobalSearchInput::Replace
+                    {
+                        focused_input.set(GlobalSearchInput::Search);
+                    }
+                },
+                || false,
+                || false,
+                || "Toggle Replace",
+                config,
+            )
+            .style(|s| s.padding_right(4.0)),
             stack((
-                text_input(editor, is_focused).style(|s| s.width_pct(100.0)),
-                clickable_icon(
-                    || LapceIcons::SEARCH_CASE_SENSITIVE,
-                    move || {
-                        let new = match case_matching.get_untracked() {
-                            CaseMatching::Exact => CaseMatching::CaseInsensitive,
-                            CaseMatching::CaseInsensitive => CaseMatching::Exact,
-                        };
-                        case_matching.set(new);
-                    },
-                    move || case_matching.get() == CaseMatching::Exact,
-                    || false,
-                    || "Case Sensitive",
-                    config,
-                )
-                .style(|s| s.padding_vert(4.0)),
-                clickable_icon(
-                    || LapceIcons::SEARCH_WHOLE_WORD,
-                    move || {
-                        whole_word.update(|whole_word| {
-                            *whole_word = !*whole_word;
-                        });
-                    },
-                    move || whole_word.get(),
-                    || false,
-                    || "Whole Word",
-                    config,
-                )
-                .style(|s| s.padding_left(6.0)),
-                clickable_icon(
-                    || LapceIcons::SEARCH_REGEX,
-                    move || {
-                        is_regex.update(|is_regex| {
-                            *is_regex = !*is_regex;
-                        });
-                    },
-                    move || is_regex.get(),
-                    || false,
-                    || "Use Regex",
-                    config,
-                )
-                .style(|s| s.padding_left(6.0)),
+                stack((
+                    text_input(editor, is_focused).style(|s| s.width_pct(100.0)),
+                    clickable_icon(
+                        || LapceIcons::SEARCH_CASE_SENSITIVE,
+                        move || {
+                            let new = match case_matching.get_untracked() {
+                                CaseMatching::Exact => CaseMatching::CaseInsensitive,
+                                CaseMatching::CaseInsensitive => CaseMatching::Exact,
+                            };
+                            case_matching.set(new);
+                        },
+                        move || case_matching.get() == CaseMatching::Exact,
+                        || false,
+                        || "Case Sensitive",
+                        config,
+                    )
+                    .style(|s| s.padding_vert(4.0)),
+                    clickable_icon(
+                        || LapceIcons::SEARCH_WHOLE_WORD,
+                        move || {
+                            whole_word.update(|whole_word| {
+                                *whole_word = !*whole_word;
+                            });
+                        },
+                        move || whole_word.get(),
+                        || false,
+                        || "Whole Word",
+                        config,
+                    )
+                    .style(|s| s.padding_left(6.0)),
+                    clickable_icon(
+                        || LapceIcons::SEARCH_REGEX,
+                        move || {
+                            is_regex.update(|is_regex| {
+                                *is_regex = !*is_regex;
+                            });
+                        },
+                        move || is_regex.get(),
+                        || false,
+                        || "Use Regex",
+                        config,
+                    )
+                    .style(|s| s.padding_left(6.0)),
+                ))
+                .on_event_cont(EventListener::PointerDown, move |_| {
+                    focus.set(Focus::Panel(PanelKind::Search));
+                    focused_input.set(GlobalSearchInput::Search);
+                })
+                .style(move |s| {
+                    s.width_pct(100.0)
+                        .padding_right(6.0)
+                        .items_center()
+                        .border(1.0)
+                        .border_radius(6.0)
+                        .border_color(config.get().color(LapceColor::LAPCE_BORDER))
+                }),
+                stack((
+                    text_input(replace_editor, is_replace_focused)
+                        .style(|s| s.width_pct(100.0)),
+                    clickable_icon(
+                        || LapceIcons::SEARCH_REPLACE_ALL,
+                        {
+                            let global_search = global_search.clone();
+                            move || {
+                                global_search.replace_all();
+                            }
+                        },
+                        || false,
+                        move || search_result_data.with(|result| result.is_empty()),
+                        || "Replace All",
+                        config,
+                    )
+                    .style(|s| s.padding_vert(4.0)),
+                    clickable_icon(
+                        || LapceIcons::LOCATION_BACKWARD,
+                        {
+                            let global_search = global_search.clone();
+                            move || {
+                                global_search.undo_replace();
+                            }
+                        },
+                        || false,
+                        move || last_replace.with(|undo| undo.is_none()),
+                        || "Undo Replace",
+                        config,
+                    )
+                    .style(|s| s.padding_left(6.0)),
+                ))
+                .on_event_cont(EventListener::PointerDown, move |_| {
+                    focus.set(Focus::Panel(PanelKind::Search));
+                    focused_input.set(GlobalSearchInput::Replace);
+                })
+                .style(move |s| {
+                    s.width_pct(100.0)
+                        .margin_top(4.0)
+                        .padding_right(6.0)
+                        .items_center()
+                        .border(1.0)
+                        .border_radius(6.0)
+                        .border_color(config.get().color(LapceColor::LAPCE_BORDER))
+                        .apply_if(!replace_active.get(), |s| s.hide())
+                }),
             ))
-            .on_event_cont(EventListener::PointerDown, move |_| {
-                focus.set(Focus::Panel(PanelKind::Search));
-            })
-            .style(move |s| {
-                s.width_pct(100.0)
-                    .padding_right(6.0)
-                    .items_center()
-                    .border(1.0)
-                    .border_radius(6.0)
-                    .border_color(config.get().color(LapceColor::LAPCE_BORDER))
-            }),
-        )
+            .style(|s| s.flex_col().flex_grow(1.0).min_width(0.0)),
+        ))
         .style(|s| s.width_pct(100.0).padding(10.0)),
+        search_filter_input(
+            global_search.include_editor.clone(),
+            "files to include",
+            GlobalSearchInput::Include,
+            focused_input,
+            focus,
+            config,
+        ),
+        search_filter_input(
+            global_search.exclude_editor.clone(),
+            "files to exclude",
+            GlobalSearchInput::Exclude,
+            focused_input,
+            focus,
+            config,
+        ),
         search_result(workspace, global_search, internal_command, config),
     ))
     .style(|s| s.absolute().size_pct(100.0, 100.0).flex_col())
 }
 
+fn search_filter_input(
+    editor: EditorData,
+    title: &'static str,
+    input: GlobalSearchInput,
+    focused_input: RwSignal<GlobalSearchInput>,
+    focus: RwSignal<Focus>,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    stack((
+        label(move || title.to_string()).style(move |s| {
+            s.margin_bottom(2.0)
+                .color(config.get().color(LapceColor::EDITOR_DIM))
+        }),
+        container(
+            text_input(editor, move || {
+                focus.get() == Focus::Panel(PanelKind::Search)
+                    && focused_input.get() == input
+            })
+            .style(|s| s.width_pct(100.0)),
+        )
+        .on_event_cont(EventListener::PointerDown, move |_| {
+            focus.set(Focus::Panel(PanelKind::Search));
+            focused_input.set(input);
+        })
+        .style(move |s| {
+            s.width_pct(100.0)
+                .border(1.0)
+                .border_radius(6.0)
+                .border_color(config.get().color(LapceColor::LAPCE_BORDER))
+        }),
+    ))
+    .style(|s| {
+        s.flex_col()
+            .width_pct(100.0)
+            .padding_horiz(10.0)
+            .padding_bottom(10.0)
+    })
+}
+
 fn search_result(
     workspace: Arc<LapceWorkspace>,
     global_search_data: GlobalSearchData,
@@ -122,9 +267,13 @@ fn search_result(
                         match_data.height()
                     },
                 )),
-                move || global_search_data.clone(),
+                {
+                    let global_search_data = global_search_data.clone();
+                    move || global_search_data.clone()
+                },
                 move |(path, _)| path.to_owned(),
                 move |(path, match_data)| {
+                    let global_search_data = global_search_data.clone();
                     let full_path = path.clone();
                     let path = if let Some(workspace_path) = workspace.path.as_ref()
                     {
@@ -149,6 +298,7 @@ fn search_result(
                         .to_string();
 
                     let expanded = match_data.expanded;
+                    let is_hovered = create_rw_signal(false);
 
                     stack((
                         stack((
@@ -195,11 +345,28 @@ fn search_result(
                                     .text_ellipsis()
                                 }),
                             ))
-                            .style(move |s| s.min_width(0.0).items_center()),
+                            .style(move |s| {
+                                s.min_width(0.0).flex_grow(1.0).items_center()
+                            }),
+                            search_result_actions(
+                                global_search_data.clone(),
+                                full_path.clone(),
+                                None,
+                                config,
+                            )
+                            .style(move |s| {
+                                s.apply_if(!is_hovered.get(), |s| s.hide())
+                            }),
                         ))
                         .on_click_stop(move |_| {
                             expanded.update(|expanded| *expanded = !*expanded);
                         })
+                        .on_event_cont(EventListener::PointerEnter, move |_| {
+                            is_hovered.set(true);
+                        })
+                        .on_event_cont(EventListener::PointerLeave, move |_| {
+                            is_hovered.set(false);
+                        })
                         .style(move |s| {
                             s.width_pct(100.0)
                                 .min_width_pct(100.0)
@@ -228,57 +395,94 @@ fn search_result(
                             move |m| {
                                 let path = full_path.clone();
                                 let line_number = m.line;
-                                let start = m.start;
-                                let end = m.end;
-                                let line_content = m.line_content.clone();
+                                let is_hovered = create_rw_signal(false);
+                                let actions = search_result_actions(
+                                    global_search_data.clone(),
+                                    path.clone(),
+                                    Some(m.clone()),
+                                    config,
+                                );
+                                let global_search = global_search_data.clone();
+                                let content = create_memo(move |_| {
+                                    global_search.replace_preview(&m).unwrap_or_else(
+                                        || (m.line_content.clone(), m.start, m.end),
+                                    )
+                                });
 
-                                focus_text(
-                                    move || {
-                                        let config = config.get();
-                                        let content = if config
-                                            .ui
-                                            .trim_search_results_whitespace
-                                        {
-                                            m.line_content.trim()
-                                        } else {
-                                            &m.line_content
-                                        };
-                                        format!("{}: {content}", m.line,)
-                                    },
-                                    move || {
-                                        let config = config.get();
-                                        let mut offset = if config
-                                            .ui
-                                            .trim_search_results_whitespace
-                                        {
-                                            line_content.trim_start().len() as i32
-                                                - line_content.len() as i32
-                                        } else {
-                                            0
-                                        };
-                                        offset +=
-                                            line_number.to_string().len() as i32 + 2;
+                                stack((
+                                    focus_text(
+                                        move || {
+                                            let config = config.get();
+                                            content.with(|(content, _, _)| {
+                                                let content = if config
+                                                    .ui
+                                                    .trim_search_results_whitespace
+                                                {
+                                                    content.trim()
+                                                } else {
+                                                    content
+                                                };
+                                                format!("{line_number}: {content}")
+                                            })
+                                        },
+                                        move || {
+                                            let config = config.get();
+                                            content.with(|(content, start, end)| {
+                                                let mut offset = if config
+                                                    .ui
+                                                    .trim_search_results_whitespace
+                                                {
+                                                    content.trim_start().len() as i32
+                                                        - content.len() as i32
+                                                } else {
+                                                    0
+                                                };
+                                                offset +=
+                                                    line_number.to_string().len()
+                                                        as i32
+                                                        + 2;
 
-                                        ((start as i32 + offset) as usize
-                                            ..(end as i32 + offset) as usize)
-                                            .collect()
-                                    },
-                                    move || {
-                                        config.get().color(LapceColor::EDITOR_FOCUS)
-                                    },
-                                )
+                                                ((*start as i32 + offset) as usize
+                                                    ..(*end as i32 + offset)
+                                                        as usize)
+                                                    .collect()
+                                            })
+                                        },
+                                        move || {
+                                            config
+                                                .get()
+                                                .color(LapceColor::EDITOR_FOCUS)
+                                        },
+                                    )
+                                    .style(|s| s.flex_grow(1.0).min_width(0.0)),
+                                    actions.style(move |s| {
+                                        s.apply_if(!is_hovered.get(), |s| s.hide())
+                                    }),
+                                ))
                                 .style(move |s| {
                                     let config = config.get();
                                     let icon_size = config.ui.icon_size() as f32;
-                                    s.margin_left(10.0 + icon_size + 6.0).hover(
-                                        |s| {
+                                    s.margin_left(10.0 + icon_size + 6.0)
+                                        .items_center()
+                                        .hover(|s| {
                                             s.cursor(CursorStyle::Pointer)
                                                 .background(config.color(
                                                 LapceColor::PANEL_HOVERED_BACKGROUND,
                                             ))
-                                        },
-                                    )
+                                        })
                                 })
+                                .on_event_cont(
+                                    EventListener::PointerEnter,
+                                    move |_| {
+                                        is_hovered.set(true);
+                                    },
+                                )
+                                .on_event_cont(
+                                    EventListener::PointerLeave,
+                                    move |_| {
+                                        is_hovered.set(false);
+                                    },
+                                )
                                 .on_click_stop(
                                     move |_| {
                                         internal_command.send(
@@ -312,3 +516,79 @@ fn search_result(
     })
     .style(|s| s.size_pct(100.0, 100.0))
 }
+
+/// The replace and dismiss buttons of a search result, for a single match if
+/// one is given, or else for all the matches of the file.
+fn search_result_actions(
+    global_search: GlobalSearchData,
+    path: PathBuf,
+    m: Option<SearchMatch>,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    let replace_active = global_search.replace_active;
+    let is_match = m.is_some();
+    let replace = {
+        let global_search = global_search.clone();
+        let path = path.clone();
+        let m = m.clone();
+        move || match m.as_ref() {
+            Some(m) => global_search.replace_match(&path, m),
+            None => global_search.replace_file(&path),
+        }
+    };
+    let dismiss = move || match m.as_ref() {
+        Some(m) => global_search.dismiss_match(&path, m),
+        None => global_search.dismiss_file(&path),
+    };
+
+    stack((
+        search_result_action(
+            LapceIcons::SEARCH_REPLACE,
+            replace,
+            move || {
+                if is_match {
+                    "Replace"
+                } else {
+                    "Replace All in File"
+                }
+            },
+            config,
+        )
+        .style(move |s| s.apply_if(!replace_active.get(), |s| s.hide())),
+        search_result_action(
+            LapceIcons::CLOSE,
+            dismiss,
+            move || if is_match { "Dismiss" } else { "Dismiss File" },
+            config,
+        ),
+    ))
+    .style(|s| s.padding_horiz(6.0).items_center())
+}
+
+fn search_result_action(
+    icon: &'static str,
+    on_click: impl Fn() + 'static,
+    tooltip: impl Fn() -> &'static str + 'static + Clone,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    tooltip_label(
+        config,
+        container(svg(move || config.get().ui_svg(icon)).style(move |s| {
+            let config = config.get();
+            let size = config.ui.icon_size() as f32;
+            s.size(size, size)
+                .color(config.color(LapceColor::LAPCE_ICON_ACTIVE))
+        }))
+        .on_click_stop(move |_| {
+            on_click();
+        })
+        .style(move |s| {
+            s.padding_horiz(2.0).border_radius(4.0).hover(|s| {
+                s.cursor(CursorStyle::Pointer).background(
+                    config.get().color(LapceColor::PANEL_CURRENT_BACKGROUND),
+                )
+            })
+        }),
+        tooltip,
+    )
+}
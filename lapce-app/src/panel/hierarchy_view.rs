@@ -0,0 +1,175 @@
+use std::rc::Rc;
+
+use floem::{
+    peniko::Color,
+    view::View,
+    views::{
+        container, label, scroll, stack, svg, text, virtual_stack, Decorators,
+        VirtualDirection, VirtualItemSize,
+    },
+};
+
+use super::position::PanelPosition;
+use crate::{
+    app::clickable_icon,
+    config::{color::LapceColor, icon::LapceIcons},
+    window_tab::WindowTabData,
+};
+
+pub fn hierarchy_panel(
+    window_tab_data: Rc<WindowTabData>,
+    _position: PanelPosition,
+) -> impl View {
+    let config = window_tab_data.common.config;
+    let hierarchy = window_tab_data.hierarchy.clone();
+    let kind = hierarchy.kind;
+    let plugin_id = hierarchy.plugin_id;
+    stack((
+        stack((
+            label(move || kind.get().title().to_string())
+                .style(|s| s.flex_grow(1.0)),
+            {
+                let hierarchy = hierarchy.clone();
+                clickable_icon(
+                    || LapceIcons::HIERARCHY_SWAP,
+                    move || {
+                        hierarchy.reverse();
+                    },
+                    || false,
+                    move || plugin_id.get().is_none(),
+                    move || format!("Show {}", kind.get().reversed().title()),
+                    config,
+                )
+            },
+        ))
+        .style(move |s| {
+            s.items_center()
+                .padding_left(10.0)
+                .padding_right(6.0)
+                .padding_vert(2.0)
+                .width_pct(100.0)
+                .background(config.get().color(LapceColor::EDITOR_BACKGROUND))
+        }),
+        hierarchy_tree_view(window_tab_data),
+    ))
+    .style(|s| s.flex_col().size_pct(100.0, 100.0))
+}
+
+fn hierarchy_tree_view(window_tab_data: Rc<WindowTabData>) -> impl View {
+    let hierarchy = window_tab_data.hierarchy.clone();
+    let tree = hierarchy.tree;
+    let tree_id = hierarchy.tree_id;
+    let loading = hierarchy.loading;
+    let ui_line_height = window_tab_data.common.ui_line_height;
+    let config = window_tab_data.common.config;
+    stack((
+        label(move || {
+            if loading.get() {
+                "Loading...".to_string()
+            } else {
+                "No results".to_string()
+            }
+        })
+        .style(move |s| {
+            s.padding_horiz(10.0).apply_if(
+                !loading.get() && tree.with(|tree| !tree.roots.is_empty()),
+                |s| s.hide(),
+            )
+        }),
+        container(
+            scroll(
+                virtual_stack(
+                    VirtualDirection::Vertical,
+                    VirtualItemSize::Fixed(Box::new(move || ui_line_height.get())),
+                    move || tree.get(),
+                    move |node| {
+                        (
+                            tree_id.get_untracked(),
+                            node.path.clone(),
+                            node.expanded,
+                            node.leaf,
+                        )
+                    },
+                    move |node| {
+                        let level = node.level;
+                        let expanded = node.expanded;
+                        let leaf = node.leaf;
+                        let kind = node.item.kind();
+                        let detail =
+                            node.item.detail().unwrap_or_default().to_string();
+                        let hierarchy_expand = hierarchy.clone();
+                        let hierarchy_jump = hierarchy.clone();
+                        let path = node.path.clone();
+                        stack((
+                            svg(move || {
+                                let config = config.get();
+                                let svg_str = match expanded {
+                                    true => LapceIcons::ITEM_OPENED,
+                                    false => LapceIcons::ITEM_CLOSED,
+                                };
+                                config.ui_svg(svg_str)
+                            })
+                            .on_click_stop(move |_| {
+                                if !leaf {
+                                    hierarchy_expand.toggle_expand(&path);
+                                }
+                            })
+                            .style(move |s| {
+                                let config = config.get();
+                                let size = config.ui.icon_size() as f32;
+                                let color = if leaf {
+                                    Color::TRANSPARENT
+                                } else {
+                                    config.color(LapceColor::LAPCE_ICON_ACTIVE)
+                                };
+                                s.size(size, size).margin_left(10.0).color(color)
+                            }),
+                            svg(move || {
+                                let config = config.get();
+                                config.symbol_svg(&kind).unwrap_or_else(|| {
+                                    config.ui_svg(LapceIcons::FILE)
+                                })
+                            })
+                            .style(move |s| {
+                                let config = config.get();
+                                let size = config.ui.icon_size() as f32;
+                                s.min_width(size)
+                                    .size(size, size)
+                                    .margin_horiz(5.0)
+                                    .color(
+                                        config.color(LapceColor::LAPCE_ICON_ACTIVE),
+                                    )
+                            }),
+                            text(node.item.name().to_string()),
+                            text(detail).style(move |s| {
+                                s.margin_left(6.0).color(
+                                    config.get().color(LapceColor::EDITOR_DIM),
+                                )
+                            }),
+                        ))
+                        .on_click_stop(move |_| {
+                            hierarchy_jump.jump_to(&node);
+                        })
+                        .style(move |s| {
+                            s.items_center()
+                                .padding_right(10.0)
+                                .padding_left((level * 10) as f32)
+                                .min_width_pct(100.0)
+                                .hover(|s| {
+                                    s.background(
+                                        config.get().color(
+                                            LapceColor::PANEL_HOVERED_BACKGROUND,
+                                        ),
+                                    )
+                                })
+                        })
+                    },
+                )
+                .style(|s| s.flex_col().min_width_full()),
+            )
+            .style(|s| s.absolute().size_full()),
+        )
+        .style(|s| s.width_full().flex_grow(1.0).flex_basis(0)),
+    ))
+    .style(|s| s.flex_col().width_full().line_height(1.6).flex_grow(1.0))
+}
@@ -15,6 +15,8 @@ pub enum PanelKind {
     Search,
     Problem,
     Debug,
+    Hierarchy,
+    GitLog,
 }
 
 impl PanelKind {
@@ -27,6 +29,8 @@ pub fn svg_name(&self) -> &'static str {
             PanelKind::Search => LapceIcons::SEARCH,
             PanelKind::Problem => LapceIcons::PROBLEM,
             PanelKind::Debug => LapceIcons::DEBUG,
+            PanelKind::Hierarchy => LapceIcons::HIERARCHY,
+            PanelKind::GitLog => LapceIcons::GIT_LOG,
         }
     }
 
@@ -1,6 +1,8 @@
 pub mod data;
 pub mod debug_view;
+pub mod git_log_view;
 pub mod global_search_view;
+pub mod hierarchy_view;
 pub mod kind;
 pub mod plugin_view;
 pub mod position;
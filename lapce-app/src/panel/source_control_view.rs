@@ -1,11 +1,11 @@
-use std::{path::PathBuf, rc::Rc};
+use std::{path::PathBuf, rc::Rc, sync::Arc};
 
 use floem::{
     action::show_context_menu,
     event::{Event, EventListener},
     menu::{Menu, MenuItem},
     peniko::kurbo::Rect,
-    reactive::{create_memo, create_rw_signal},
+    reactive::{create_memo, create_rw_signal, ReadSignal},
     style::{CursorStyle, Style},
     view::View,
     views::{
@@ -15,12 +15,12 @@
     },
 };
 use lapce_core::buffer::rope_text::RopeText;
-use lapce_rpc::source_control::FileDiff;
+use lapce_rpc::source_control::{DiffInfo, FileDiff};
 
 use super::{kind::PanelKind, position::PanelPosition, view::panel_header};
 use crate::{
     command::{CommandKind, InternalCommand, LapceCommand, LapceWorkbenchCommand},
-    config::{color::LapceColor, icon::LapceIcons},
+    config::{color::LapceColor, icon::LapceIcons, LapceConfig},
     editor::view::editor_view,
     settings::checkbox,
     source_control::SourceControlData,
@@ -55,6 +55,7 @@ pub fn source_control_panel(
     let debug_breakline = create_memo(move |_| None);
 
     stack((
+        repositories_view(source_control.clone()),
         stack((
             container({
                 scroll({
@@ -146,40 +147,85 @@ pub fn source_control_panel(
                     .background(config.color(LapceColor::EDITOR_BACKGROUND))
             }),
             {
+                let amend = source_control.amend;
+                let sign_off = source_control.sign_off;
                 let source_control = source_control.clone();
-                label(|| "Commit".to_string())
-                    .on_click_stop(move |_| {
-                        source_control.commit();
-                    })
-                    .style(move |s| {
-                        let config = config.get();
-                        s.margin_top(10.0)
-                            .line_height(1.6)
-                            .width_pct(100.0)
-                            .justify_center()
-                            .border(1.0)
-                            .border_radius(6.0)
-                            .border_color(config.color(LapceColor::LAPCE_BORDER))
-                            .hover(|s| {
-                                s.cursor(CursorStyle::Pointer).background(
-                                    config
-                                        .color(LapceColor::PANEL_HOVERED_BACKGROUND),
-                                )
-                            })
-                            .active(|s| {
-                                s.background(config.color(
+                stack((
+                    commit_option(
+                        "Amend Last Commit",
+                        move || amend.get(),
+                        move || source_control.toggle_amend(),
+                        config,
+                    ),
+                    commit_option(
+                        "Sign Off",
+                        move || sign_off.get(),
+                        move || sign_off.update(|sign_off| *sign_off = !*sign_off),
+                        config,
+                    )
+                    .style(|s| s.margin_left(10.0)),
+                ))
+                .style(|s| s.margin_top(10.0))
+            },
+            {
+                let amend = source_control.amend;
+                let source_control = source_control.clone();
+                label(move || {
+                    if amend.get() {
+                        "Amend".to_string()
+                    } else {
+                        "Commit".to_string()
+                    }
+                })
+                .on_click_stop(move |_| {
+                    source_control.commit();
+                })
+                .style(move |s| {
+                    let config = config.get();
+                    s.margin_top(10.0)
+                        .line_height(1.6)
+                        .width_pct(100.0)
+                        .justify_center()
+                        .border(1.0)
+                        .border_radius(6.0)
+                        .border_color(config.color(LapceColor::LAPCE_BORDER))
+                        .hover(|s| {
+                            s.cursor(CursorStyle::Pointer).background(
+                                config.color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                            )
+                        })
+                        .active(|s| {
+                            s.background(
+                                config.color(
                                     LapceColor::PANEL_HOVERED_ACTIVE_BACKGROUND,
-                                ))
-                            })
-                    })
+                                ),
+                            )
+                        })
+                })
             },
         ))
         .style(|s| s.flex_col().width_pct(100.0).padding(10.0)),
+        {
+            let staged_diffs = source_control.staged_diffs;
+            stack((
+                panel_header("Staged Changes".to_string(), config),
+                file_diffs_view(source_control.clone(), true),
+            ))
+            .style(move |s| {
+                s.flex_col()
+                    .width_pct(100.0)
+                    .flex_grow(1.0)
+                    .flex_basis(0.0)
+                    .apply_if(staged_diffs.with(|diffs| diffs.is_empty()), |s| {
+                        s.hide()
+                    })
+            })
+        },
         stack((
             panel_header("Changes".to_string(), config),
-            file_diffs_view(source_control),
+            file_diffs_view(source_control, false),
         ))
-        .style(|s| s.flex_col().size_pct(100.0, 100.0)),
+        .style(|s| s.flex_col().width_pct(100.0).flex_grow(1.0).flex_basis(0.0)),
     ))
     .on_event_stop(EventListener::PointerDown, move |_| {
         if focus.get_untracked() != Focus::Panel(PanelKind::SourceControl) {
@@ -189,8 +235,248 @@ pub fn source_control_panel(
     .style(|s| s.flex_col().size_pct(100.0, 100.0))
 }
 
-fn file_diffs_view(source_control: SourceControlData) -> impl View {
-    let file_diffs = source_control.file_diffs;
+/// The repositories in the workspace to choose from, each with its changes,
+/// which is only shown when there are more than one
+fn repositories_view(source_control: SourceControlData) -> impl View {
+    let config = source_control.common.config;
+    let workspace = source_control.common.workspace.clone();
+    let repositories = source_control.repositories;
+    let repository = source_control.repository;
+    let internal_command = source_control.common.internal_command;
+
+    let view_fn = move |diff: DiffInfo| {
+        let path = diff.path.clone();
+        let name = workspace
+            .path
+            .as_ref()
+            .and_then(|workspace_path| path.strip_prefix(workspace_path).ok())
+            .filter(|relative| !relative.as_os_str().is_empty())
+            .unwrap_or(&path)
+            .to_string_lossy()
+            .to_string();
+        let changes = diff.diffs.len() + diff.staged.len();
+        let head = diff.head.clone();
+        let files: Vec<(FileDiff, bool)> = diff
+            .staged
+            .iter()
+            .map(|file| (file.clone(), true))
+            .chain(diff.diffs.iter().map(|file| (file.clone(), false)))
+            .collect();
+        let is_selected = {
+            let path = path.clone();
+            move || repository.with(|repository| repository.as_ref() == Some(&path))
+        };
+
+        let file_view = {
+            let source_control = source_control.clone();
+            let repository_path = path.clone();
+            move |(file, staged): (FileDiff, bool)| {
+                let full_path = file.path().clone();
+                let relative = full_path
+                    .strip_prefix(&repository_path)
+                    .unwrap_or(&full_path)
+                    .to_path_buf();
+                let file_name = relative
+                    .file_name()
+                    .and_then(|s| s.to_str())
+                    .unwrap_or("")
+                    .to_string();
+                let folder = relative
+                    .parent()
+                    .and_then(|s| s.to_str())
+                    .unwrap_or("")
+                    .to_string();
+                let (icon, color) = file_diff_icon(&file);
+                let source_control = source_control.clone();
+                let repository_path = repository_path.clone();
+                stack((
+                    svg(move || config.get().file_svg(&relative).0).style(
+                        move |s| {
+                            let config = config.get();
+                            let size = config.ui.icon_size() as f32;
+                            s.min_width(size).size(size, size).margin(6.0)
+                        },
+                    ),
+                    label(move || file_name.clone())
+                        .style(|s| s.text_ellipsis().margin_right(6.0)),
+                    label(move || folder.clone()).style(move |s| {
+                        s.text_ellipsis()
+                            .flex_grow(1.0)
+                            .flex_basis(0.0)
+                            .min_width(0.0)
+                            .color(config.get().color(LapceColor::EDITOR_DIM))
+                    }),
+                    svg(move || config.get().ui_svg(icon)).style(move |s| {
+                        let config = config.get();
+                        let size = config.ui.icon_size() as f32;
+                        s.min_width(size)
+                            .size(size, size)
+                            .margin_left(6.0)
+                            .color(config.color(color))
+                    }),
+                ))
+                .on_click_stop(move |_| {
+                    source_control.select_repository(repository_path.clone());
+                    let path = full_path.clone();
+                    if matches!(file, FileDiff::Conflicted(_)) {
+                        internal_command
+                            .send(InternalCommand::OpenMergeEditor { path });
+                    } else {
+                        internal_command
+                            .send(InternalCommand::OpenFileChanges { path, staged });
+                    }
+                })
+                .style(move |s| {
+                    let config = config.get();
+                    s.items_center()
+                        .padding_left(20.0)
+                        .padding_right(10.0)
+                        .width_pct(100.0)
+                        .cursor(CursorStyle::Pointer)
+                        .hover(|s| {
+                            s.background(
+                                config.color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                            )
+                        })
+                })
+            }
+        };
+
+        let source_control = source_control.clone();
+        let header = stack((
+            label(move || name.clone()).style(|s| {
+                s.text_ellipsis()
+                    .min_width(0.0)
+                    .flex_grow(1.0)
+                    .flex_basis(0.0)
+            }),
+            label(move || head.clone()).style(move |s| {
+                s.margin_left(10.0)
+                    .color(config.get().color(LapceColor::EDITOR_DIM))
+            }),
+            label(move || changes.to_string()).style(move |s| {
+                s.margin_left(10.0)
+                    .color(config.get().color(LapceColor::EDITOR_DIM))
+                    .apply_if(changes == 0, |s| s.hide())
+            }),
+        ))
+        .on_click_stop(move |_| {
+            source_control.select_repository(path.clone());
+        })
+        .style({
+            let is_selected = is_selected.clone();
+            move |s| {
+                let config = config.get();
+                s.items_center()
+                    .padding_horiz(10.0)
+                    .width_pct(100.0)
+                    .cursor(CursorStyle::Pointer)
+                    .apply_if(is_selected(), |s| {
+                        s.background(
+                            config.color(LapceColor::PANEL_CURRENT_BACKGROUND),
+                        )
+                    })
+                    .hover(|s| {
+                        s.background(
+                            config.color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                        )
+                    })
+            }
+        });
+
+        stack((
+            header,
+            // The changes of the chosen repository are listed in full below,
+            // where they are staged and discarded
+            dyn_stack(
+                move || files.clone(),
+                |(file, staged)| (file.clone(), *staged),
+                file_view,
+            )
+            .style(move |s| {
+                s.flex_col()
+                    .width_pct(100.0)
+                    .apply_if(is_selected(), |s| s.hide())
+            }),
+        ))
+        .style(|s| s.flex_col().width_pct(100.0))
+    };
+
+    stack((
+        panel_header("Repositories".to_string(), config),
+        scroll(
+            dyn_stack(
+                move || repositories.get(),
+                // The rows show the head and the changes
+                |diff| {
+                    (
+                        diff.path.clone(),
+                        diff.head.clone(),
+                        diff.diffs.clone(),
+                        diff.staged.clone(),
+                    )
+                },
+                view_fn,
+            )
+            .style(|s| s.line_height(1.6).flex_col().width_pct(100.0)),
+        )
+        .style(|s| s.width_pct(100.0).max_height(300.0)),
+    ))
+    .style(move |s| {
+        s.flex_col()
+            .width_pct(100.0)
+            .apply_if(repositories.with(|repos| repos.len() < 2), |s| s.hide())
+    })
+}
+
+/// The icon and the color of the kind of change
+fn file_diff_icon(diff: &FileDiff) -> (&'static str, &'static str) {
+    match diff {
+        FileDiff::Modified(_) => (
+            LapceIcons::SCM_DIFF_MODIFIED,
+            LapceColor::SOURCE_CONTROL_MODIFIED,
+        ),
+        FileDiff::Added(_) => {
+            (LapceIcons::SCM_DIFF_ADDED, LapceColor::SOURCE_CONTROL_ADDED)
+        }
+        FileDiff::Deleted(_) => (
+            LapceIcons::SCM_DIFF_REMOVED,
+            LapceColor::SOURCE_CONTROL_REMOVED,
+        ),
+        FileDiff::Renamed(_, _) => (
+            LapceIcons::SCM_DIFF_RENAMED,
+            LapceColor::SOURCE_CONTROL_MODIFIED,
+        ),
+        FileDiff::Conflicted(_) => (
+            LapceIcons::SCM_DIFF_CONFLICTED,
+            LapceColor::SOURCE_CONTROL_CONFLICTED,
+        ),
+    }
+}
+
+/// An option of the next commit, which is switched on and off by clicking it
+fn commit_option(
+    text: &'static str,
+    checked: impl Fn() -> bool + 'static,
+    on_click: impl Fn() + 'static,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    stack((
+        checkbox(checked, config),
+        label(move || text.to_string()).style(|s| s.margin_left(6.0)),
+    ))
+    .on_click_stop(move |_| on_click())
+    .style(|s| s.items_center().cursor(CursorStyle::Pointer))
+}
+
+/// The list of the staged changes, or of the changes in the working tree which
+/// aren't staged.
+fn file_diffs_view(source_control: SourceControlData, staged: bool) -> impl View {
+    let file_diffs = if staged {
+        source_control.staged_diffs
+    } else {
+        source_control.file_diffs
+    };
     let config = source_control.common.config;
     let workspace = source_control.common.workspace.clone();
     let panel_rect = create_rw_signal(Rect::ZERO);
@@ -198,11 +484,15 @@ fn file_diffs_view(source_control: SourceControlData) -> impl View {
     let lapce_command = source_control.common.lapce_command;
     let internal_command = source_control.common.internal_command;
 
-    let view_fn = move |(path, (diff, checked)): (PathBuf, (FileDiff, bool))| {
+    let view_fn = move |(path, diff): (PathBuf, FileDiff)| {
         let diff_for_style = diff.clone();
         let full_path = path.clone();
         let diff_for_menu = diff.clone();
+        let diff_for_stage = diff.clone();
+        let diff_for_click = diff.clone();
         let path_for_click = full_path.clone();
+        let source_control = source_control.clone();
+        let source_control_for_menu = source_control.clone();
 
         let path = if let Some(workspace_path) = workspace.path.as_ref() {
             path.strip_prefix(workspace_path)
@@ -223,15 +513,6 @@ fn file_diffs_view(source_control: SourceControlData) -> impl View {
             .to_string();
         let style_path = path.clone();
         stack((
-            checkbox(move || checked, config)
-                .style(|s| s.hover(|s| s.cursor(CursorStyle::Pointer)))
-                .on_click_stop(move |_| {
-                    file_diffs.update(|diffs| {
-                        if let Some((_, checked)) = diffs.get_mut(&full_path) {
-                            *checked = !*checked;
-                        }
-                    });
-                }),
             svg(move || config.get().file_svg(&path).0).style(move |s| {
                 let config = config.get();
                 let size = config.ui.icon_size() as f32;
@@ -262,31 +543,40 @@ fn file_diffs_view(source_control: SourceControlData) -> impl View {
                     .color(config.get().color(LapceColor::EDITOR_DIM))
                     .min_width(0.0)
             }),
-            container({
+            stack((
                 svg(move || {
-                    let svg = match &diff {
-                        FileDiff::Modified(_) => LapceIcons::SCM_DIFF_MODIFIED,
-                        FileDiff::Added(_) => LapceIcons::SCM_DIFF_ADDED,
-                        FileDiff::Deleted(_) => LapceIcons::SCM_DIFF_REMOVED,
-                        FileDiff::Renamed(_, _) => LapceIcons::SCM_DIFF_RENAMED,
+                    let svg = if staged {
+                        LapceIcons::SCM_CHANGE_REMOVE
+                    } else {
+                        LapceIcons::SCM_CHANGE_ADD
                     };
                     config.get().ui_svg(svg)
                 })
+                .on_click_stop(move |_| {
+                    if staged {
+                        source_control.unstage(&[diff_for_stage.clone()]);
+                    } else {
+                        source_control.stage(&[diff_for_stage.clone()]);
+                    }
+                })
                 .style(move |s| {
                     let config = config.get();
                     let size = config.ui.icon_size() as f32;
-                    let color = match &diff_for_style {
-                        FileDiff::Modified(_) => LapceColor::SOURCE_CONTROL_MODIFIED,
-                        FileDiff::Added(_) => LapceColor::SOURCE_CONTROL_ADDED,
-                        FileDiff::Deleted(_) => LapceColor::SOURCE_CONTROL_REMOVED,
-                        FileDiff::Renamed(_, _) => {
-                            LapceColor::SOURCE_CONTROL_MODIFIED
-                        }
-                    };
-                    let color = config.color(color);
-                    s.min_width(size).size(size, size).color(color)
-                })
-            })
+                    s.min_width(size)
+                        .size(size, size)
+                        .margin_right(6.0)
+                        .color(config.color(LapceColor::LAPCE_ICON_ACTIVE))
+                        .hover(|s| s.cursor(CursorStyle::Pointer))
+                }),
+                svg(move || config.get().ui_svg(file_diff_icon(&diff).0)).style(
+                    move |s| {
+                        let config = config.get();
+                        let size = config.ui.icon_size() as f32;
+                        let color = config.color(file_diff_icon(&diff_for_style).1);
+                        s.min_width(size).size(size, size).color(color)
+                    },
+                ),
+            ))
             .style(|s| {
                 s.absolute()
                     .size_pct(100.0, 100.0)
@@ -296,13 +586,36 @@ fn file_diffs_view(source_control: SourceControlData) -> impl View {
             }),
         ))
         .on_click_stop(move |_| {
-            internal_command.send(InternalCommand::OpenFileChanges {
-                path: path_for_click.clone(),
-            });
+            let path = path_for_click.clone();
+            if matches!(diff_for_click, FileDiff::Conflicted(_)) {
+                internal_command.send(InternalCommand::OpenMergeEditor { path });
+            } else {
+                internal_command
+                    .send(InternalCommand::OpenFileChanges { path, staged });
+            }
         })
         .on_event_cont(EventListener::PointerDown, move |event| {
             let diff_for_menu = diff_for_menu.clone();
+            let source_control = source_control_for_menu.clone();
 
+            let stage = {
+                let source_control = source_control.clone();
+                let diff = diff_for_menu.clone();
+                move || {
+                    if staged {
+                        source_control.unstage(&[diff.clone()]);
+                    } else {
+                        source_control.stage(&[diff.clone()]);
+                    }
+                }
+            };
+            let stage_all = move || {
+                if staged {
+                    source_control.unstage_all();
+                } else {
+                    source_control.stage_all();
+                }
+            };
             let discard = move || {
                 lapce_command.send(LapceCommand {
                     kind: CommandKind::Workbench(
@@ -314,8 +627,22 @@ fn file_diffs_view(source_control: SourceControlData) -> impl View {
 
             if let Event::PointerDown(pointer_event) = event {
                 if pointer_event.button.is_secondary() {
-                    let menu = Menu::new("")
-                        .entry(MenuItem::new("Discard Changes").action(discard));
+                    let menu = if staged {
+                        Menu::new("")
+                            .entry(MenuItem::new("Unstage Changes").action(stage))
+                            .entry(
+                                MenuItem::new("Unstage All Changes")
+                                    .action(stage_all),
+                            )
+                    } else {
+                        Menu::new("")
+                            .entry(MenuItem::new("Stage Changes").action(stage))
+                            .entry(
+                                MenuItem::new("Stage All Changes").action(stage_all),
+                            )
+                            .separator()
+                            .entry(MenuItem::new("Discard Changes").action(discard))
+                    };
                     show_context_menu(menu, None);
                 }
             }
@@ -324,7 +651,7 @@ fn file_diffs_view(source_control: SourceControlData) -> impl View {
             let config = config.get();
             let size = config.ui.icon_size() as f32;
             s.padding_left(10.0)
-                .padding_right(10.0 + size + 6.0)
+                .padding_right(10.0 + size + 6.0 + size + 6.0)
                 .width_pct(100.0)
                 .items_center()
                 .hover(|s| {
@@ -337,9 +664,7 @@ fn file_diffs_view(source_control: SourceControlData) -> impl View {
         scroll({
             dyn_stack(
                 move || file_diffs.get(),
-                |(path, (diff, checked))| {
-                    (path.to_path_buf(), diff.clone(), *checked)
-                },
+                |(path, diff)| (path.to_path_buf(), diff.clone()),
                 view_fn,
             )
             .style(|s| s.line_height(1.6).flex_col().width_pct(100.0))
@@ -349,5 +674,5 @@ fn file_diffs_view(source_control: SourceControlData) -> impl View {
     .on_resize(move |rect| {
         panel_rect.set(rect);
     })
-    .style(|s| s.size_pct(100.0, 100.0))
+    .style(|s| s.width_pct(100.0).flex_grow(1.0).flex_basis(0.0))
 }
@@ -12,7 +12,9 @@
 
 use super::{
     debug_view::debug_panel,
+    git_log_view::git_log_panel,
     global_search_view::global_search_panel,
+    hierarchy_view::hierarchy_panel,
     kind::PanelKind,
     plugin_view::plugin_panel,
     position::{PanelContainerPosition, PanelPosition},
@@ -310,6 +312,12 @@ fn panel_view(
                 PanelKind::Debug => {
                     container(debug_panel(window_tab_data.clone(), position))
                 }
+                PanelKind::Hierarchy => {
+                    container(hierarchy_panel(window_tab_data.clone(), position))
+                }
+                PanelKind::GitLog => {
+                    container(git_log_panel(window_tab_data.clone(), position))
+                }
             };
             view.style(|s| s.size_pct(100.0, 100.0))
         },
@@ -364,6 +372,8 @@ fn panel_picker(
                 PanelKind::Search => (LapceIcons::SEARCH, "Search"),
                 PanelKind::Problem => (LapceIcons::PROBLEM, "Problems"),
                 PanelKind::Debug => (LapceIcons::DEBUG_ALT, "Debug"),
+                PanelKind::Hierarchy => (LapceIcons::HIERARCHY, "Hierarchy"),
+                PanelKind::GitLog => (LapceIcons::GIT_LOG, "Git Log"),
             };
             let is_active = {
                 let window_tab_data = window_tab_data.clone();
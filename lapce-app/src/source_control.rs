@@ -6,23 +6,55 @@
     views::editor::id::EditorId,
 };
 use indexmap::IndexMap;
-use lapce_core::mode::Mode;
-use lapce_rpc::source_control::FileDiff;
+use lapce_core::{buffer::diff::DiffLines, mode::Mode};
+use lapce_rpc::{
+    proxy::{ProxyCallback, ProxyResponse},
+    source_control::{BlameHunk, DiffInfo, FileDiff, GitHunk},
+};
+use lapce_xi_rope::Rope;
+use lsp_types::{Diagnostic, DiagnosticSeverity, Position};
 
 use crate::{
-    command::{CommandExecuted, CommandKind},
+    alert::AlertButton,
+    command::{CommandExecuted, CommandKind, InternalCommand},
+    doc::{Doc, DocContent, EditorDiagnostic},
     editor::EditorData,
+    ext_event::create_ext_action,
     keypress::{condition::Condition, KeyPressFocus},
     window_tab::CommonData,
 };
 
 #[derive(Clone)]
 pub struct SourceControlData {
-    // VCS modified files & whether they should be included in the next commit
-    pub file_diffs: RwSignal<IndexMap<PathBuf, (FileDiff, bool)>>,
+    /// The repositories in the workspace, starting with the one the workspace
+    /// is in, followed by the ones in its folders
+    pub repositories: RwSignal<im::Vector<DiffInfo>>,
+    /// The working directory of the chosen repository, which the changes,
+    /// the branches and the commits are of. Until one is chosen, it's the
+    /// first.
+    pub repository: RwSignal<Option<PathBuf>>,
+    // VCS modified files which aren't staged
+    pub file_diffs: RwSignal<IndexMap<PathBuf, FileDiff>>,
+    // VCS modified files in the index, which go in the next commit
+    pub staged_diffs: RwSignal<IndexMap<PathBuf, FileDiff>>,
     pub branch: RwSignal<String>,
     pub branches: RwSignal<im::Vector<String>>,
     pub tags: RwSignal<im::Vector<String>>,
+    /// The upstream branch which the head branch tracks
+    pub upstream: RwSignal<Option<String>>,
+    /// How many commits the head branch is ahead and behind its upstream
+    pub ahead: RwSignal<usize>,
+    pub behind: RwSignal<usize>,
+    /// Whether the next commit replaces the one `HEAD` points to
+    pub amend: RwSignal<bool>,
+    /// Whether a `Signed-off-by` trailer is added to the commit message
+    pub sign_off: RwSignal<bool>,
+    /// The content of the file `commit.template` points to, which the commit
+    /// message starts with
+    pub commit_template: RwSignal<Option<String>>,
+    /// The message of the commit being amended, when it was put in the
+    /// commit editor
+    amend_message: RwSignal<Option<String>>,
     pub editor: Rc<EditorData>,
     pub common: Rc<CommonData>,
 }
@@ -66,47 +98,675 @@ pub fn new(
         editors: RwSignal<im::HashMap<EditorId, Rc<EditorData>>>,
         common: Rc<CommonData>,
     ) -> Self {
-        Self {
+        let editor = Rc::new(EditorData::new_local(cx, editors, common.clone()));
+
+        // The commit message is linted as it's typed
+        let doc = editor.doc();
+        cx.create_effect(move |_| {
+            let diagnostics = doc
+                .buffer
+                .with(|buffer| lint_commit_message(&buffer.to_string()));
+            doc.diagnostics.diagnostics.set(
+                diagnostics
+                    .into_iter()
+                    .map(|diagnostic| EditorDiagnostic {
+                        range: (0, 0),
+                        diagnostic,
+                    })
+                    .collect(),
+            );
+            doc.init_diagnostics();
+        });
+
+        let source_control = Self {
+            repositories: cx.create_rw_signal(im::Vector::new()),
+            repository: cx.create_rw_signal(None),
             file_diffs: cx.create_rw_signal(IndexMap::new()),
+            staged_diffs: cx.create_rw_signal(IndexMap::new()),
             branch: cx.create_rw_signal("".to_string()),
             branches: cx.create_rw_signal(im::Vector::new()),
             tags: cx.create_rw_signal(im::Vector::new()),
-            editor: Rc::new(EditorData::new_local(cx, editors, common.clone())),
+            upstream: cx.create_rw_signal(None),
+            ahead: cx.create_rw_signal(0),
+            behind: cx.create_rw_signal(0),
+            amend: cx.create_rw_signal(false),
+            sign_off: cx.create_rw_signal(false),
+            commit_template: cx.create_rw_signal(None),
+            amend_message: cx.create_rw_signal(None),
+            editor,
             common,
+        };
+        source_control.load_commit_template();
+        source_control
+    }
+
+    pub fn set_repositories(&self, repositories: Vec<DiffInfo>) {
+        let repository = self.repository.get_untracked();
+        let diff = repository
+            .as_ref()
+            .and_then(|path| repositories.iter().find(|diff| &diff.path == path))
+            .or_else(|| repositories.first())
+            .cloned();
+        let path = diff.as_ref().map(|diff| diff.path.clone());
+        self.repositories.set(repositories.into_iter().collect());
+        if let Some(diff) = diff.as_ref() {
+            self.set_diff_info(diff);
+        }
+        // Nothing was chosen yet or the chosen repository was removed, so the
+        // proxy is told about the one shown instead, or it would run the git
+        // operations in the workspace
+        if path != repository {
+            self.set_repository(path);
         }
     }
 
-    pub fn commit(&self) {
-        let diffs: Vec<FileDiff> = self.file_diffs.with_untracked(|file_diffs| {
-            file_diffs
+    /// Make the repository the one which the panel shows the changes of, and
+    /// which commits, checkouts and the other git operations work on
+    pub fn select_repository(&self, path: PathBuf) {
+        let Some(diff) = self.repositories.with_untracked(|repos| {
+            repos.iter().find(|diff| diff.path == path).cloned()
+        }) else {
+            return;
+        };
+        if self.repository.get_untracked().as_ref() == Some(&path) {
+            return;
+        }
+        self.set_diff_info(&diff);
+        self.set_repository(Some(path));
+    }
+
+    fn set_repository(&self, path: Option<PathBuf>) {
+        self.repository.set(path.clone());
+        self.common.proxy.git_set_repository(path);
+        if self.amend.get_untracked() {
+            self.toggle_amend();
+        }
+        self.load_commit_template();
+    }
+
+    fn set_diff_info(&self, diff: &DiffInfo) {
+        self.branch.set(diff.head.clone());
+        self.branches.set(diff.branches.iter().cloned().collect());
+        self.tags.set(diff.tags.iter().cloned().collect());
+        self.upstream.set(diff.upstream.clone());
+        self.ahead.set(diff.ahead);
+        self.behind.set(diff.behind);
+        self.file_diffs.set(
+            diff.diffs
                 .iter()
-                .filter_map(
-                    |(_, (diff, checked))| {
-                        if *checked {
-                            Some(diff)
-                        } else {
-                            None
-                        }
-                    },
-                )
-                .cloned()
-                .collect()
-        });
-        if diffs.is_empty() {
+                .map(|diff| (diff.path().clone(), diff.clone()))
+                .collect(),
+        );
+        self.staged_diffs.set(
+            diff.staged
+                .iter()
+                .map(|diff| (diff.path().clone(), diff.clone()))
+                .collect(),
+        );
+    }
+
+    pub fn commit(&self) {
+        let amend = self.amend.get_untracked();
+        let nothing_staged =
+            self.staged_diffs.with_untracked(|diffs| diffs.is_empty());
+        // Without anything staged, all the changes are committed, while
+        // amending only changes the message
+        let diffs: Vec<FileDiff> = if nothing_staged && !amend {
+            self.file_diffs
+                .with_untracked(|diffs| diffs.values().cloned().collect())
+        } else {
+            Vec::new()
+        };
+        if diffs.is_empty() && nothing_staged && !amend {
+            return;
+        }
+
+        let message = self
+            .editor
+            .doc()
+            .buffer
+            .with_untracked(|buffer| buffer.to_string());
+        if commit_subject(&message).is_none() {
             return;
         }
 
+        self.common.proxy.git_commit(
+            message,
+            diffs,
+            amend,
+            self.sign_off.get_untracked(),
+        );
+        self.amend.set(false);
+        self.amend_message.set(None);
+        self.reset_message();
+    }
+
+    /// Put the message in the commit editor, with the cursor on the subject
+    fn set_message(&self, message: &str) {
+        self.editor.doc().reload(Rope::from(message), true);
+        self.editor
+            .cursor()
+            .update(|cursor| cursor.set_offset(0, false, false));
+    }
+
+    /// Start the commit message over from the template, if there's one
+    fn reset_message(&self) {
+        match self.commit_template.get_untracked() {
+            Some(template) => self.set_message(&template),
+            None => self.editor.reset(),
+        }
+    }
+
+    fn message_is_empty(&self) -> bool {
         let message = self
             .editor
             .doc()
             .buffer
             .with_untracked(|buffer| buffer.to_string());
-        let message = message.trim();
-        if message.is_empty() {
+        message.trim().is_empty()
+            || self.commit_template.with_untracked(|template| {
+                template.as_deref() == Some(message.as_str())
+            })
+    }
+
+    pub fn load_commit_template(&self) {
+        let data = self.clone();
+        let send = create_ext_action(self.common.scope, move |template| {
+            // The template of another repository is replaced too
+            let is_empty = data.message_is_empty();
+            data.commit_template.set(template);
+            if is_empty {
+                data.reset_message();
+            }
+        });
+        self.common.proxy.git_commit_template(move |result| {
+            if let Ok(ProxyResponse::GitCommitTemplateResponse { template }) = result
+            {
+                send(template);
+            }
+        });
+    }
+
+    /// Switch amending the last commit on or off. Its message is put in the
+    /// commit editor unless something was already written there, and taken
+    /// out again when it's switched off without changes.
+    pub fn toggle_amend(&self) {
+        let amend = !self.amend.get_untracked();
+        self.amend.set(amend);
+        if !amend {
+            let message = self
+                .editor
+                .doc()
+                .buffer
+                .with_untracked(|buffer| buffer.to_string());
+            if self.amend_message.get_untracked().as_deref() == Some(&message) {
+                self.reset_message();
+            }
+            self.amend_message.set(None);
             return;
         }
 
-        self.editor.reset();
-        self.common.proxy.git_commit(message.to_string(), diffs);
+        if !self.message_is_empty() {
+            return;
+        }
+        let data = self.clone();
+        let send = create_ext_action(self.common.scope, move |message: String| {
+            // It could have been switched off or written in since
+            if data.amend.get_untracked() && data.message_is_empty() {
+                data.set_message(&message);
+                data.amend_message.set(Some(message));
+            }
+        });
+        self.common.proxy.git_head_message(move |result| {
+            if let Ok(ProxyResponse::GitHeadMessageResponse { message }) = result {
+                send(message);
+            }
+        });
+    }
+
+    pub fn stage(&self, diffs: &[FileDiff]) {
+        let files = diffs.iter().flat_map(FileDiff::paths).collect::<Vec<_>>();
+        if !files.is_empty() {
+            self.common.proxy.git_stage_files(files);
+        }
+    }
+
+    pub fn unstage(&self, diffs: &[FileDiff]) {
+        let files = diffs.iter().flat_map(FileDiff::paths).collect::<Vec<_>>();
+        if !files.is_empty() {
+            self.common.proxy.git_unstage_files(files);
+        }
+    }
+
+    pub fn stage_all(&self) {
+        let diffs = self
+            .file_diffs
+            .with_untracked(|diffs| diffs.values().cloned().collect::<Vec<_>>());
+        self.stage(&diffs);
+    }
+
+    pub fn unstage_all(&self) {
+        let diffs = self
+            .staged_diffs
+            .with_untracked(|diffs| diffs.values().cloned().collect::<Vec<_>>());
+        self.unstage(&diffs);
+    }
+
+    pub fn fetch(&self) {
+        self.common
+            .proxy
+            .git_fetch(self.alert_on_error("Git Fetch failed"));
+    }
+
+    pub fn pull(&self, rebase: bool) {
+        self.common
+            .proxy
+            .git_pull(rebase, self.alert_on_error("Git Pull failed"));
+    }
+
+    pub fn push(&self) {
+        self.common
+            .proxy
+            .git_push(self.alert_on_error("Git Push failed"));
+    }
+
+    pub fn create_branch(&self, name: String, start: Option<String>) {
+        self.common.proxy.git_create_branch(
+            name,
+            start,
+            self.alert_on_error("Git Create Branch failed"),
+        );
+    }
+
+    pub fn rename_branch(&self, branch: String, name: String) {
+        self.common.proxy.git_rename_branch(
+            branch,
+            name,
+            self.alert_on_error("Git Rename Branch failed"),
+        );
+    }
+
+    /// Delete the branch. A branch which isn't merged can be deleted anyway from
+    /// the alert which says so.
+    pub fn delete_branch(&self, branch: String, force: bool) {
+        let data = self.clone();
+        let internal_command = self.common.internal_command;
+        let send = create_ext_action(self.common.scope, {
+            let branch = branch.clone();
+            move |msg: String| {
+                let buttons = if force {
+                    Vec::new()
+                } else {
+                    vec![AlertButton {
+                        text: "Delete Anyway".to_string(),
+                        action: Rc::new(move || {
+                            internal_command.send(InternalCommand::HideAlert);
+                            data.delete_branch(branch.clone(), true);
+                        }),
+                    }]
+                };
+                internal_command.send(InternalCommand::ShowAlert {
+                    title: "Git Delete Branch failed".to_string(),
+                    msg,
+                    buttons,
+                });
+            }
+        });
+        self.common
+            .proxy
+            .git_delete_branch(branch, force, move |result| {
+                if let Err(e) = result {
+                    send(e.message);
+                }
+            });
+    }
+
+    /// Save the file the conflicts of a merge were resolved in, and stage it to
+    /// mark it as resolved. With conflicts left in it, the alert which says
+    /// so can complete the merge anyway.
+    pub fn complete_merge(&self, doc: Rc<Doc>) {
+        let DocContent::File { path, .. } = doc.content.get_untracked() else {
+            return;
+        };
+        let conflicts = doc.conflicts.with_untracked(|c| c.len());
+        let internal_command = self.common.internal_command;
+        if conflicts > 0 {
+            let data = self.clone();
+            internal_command.send(InternalCommand::ShowAlert {
+                title: format!(
+                    "{} still has {conflicts} conflict{}",
+                    path.file_name().unwrap_or_default().to_string_lossy(),
+                    if conflicts == 1 { "" } else { "s" }
+                ),
+                msg: "The file is staged with the conflict markers left in it."
+                    .to_string(),
+                buttons: vec![AlertButton {
+                    text: "Complete Anyway".to_string(),
+                    action: Rc::new(move || {
+                        internal_command.send(InternalCommand::HideAlert);
+                        data.save_and_stage(&doc, path.clone());
+                    }),
+                }],
+            });
+        } else {
+            self.save_and_stage(&doc, path);
+        }
+    }
+
+    fn save_and_stage(&self, doc: &Doc, path: PathBuf) {
+        let data = self.clone();
+        doc.save(move || {
+            data.stage(&[FileDiff::Conflicted(path)]);
+        });
+    }
+
+    pub fn merge(&self, reference: String) {
+        self.common
+            .proxy
+            .git_merge(reference, self.alert_on_error("Git Merge failed"));
+    }
+
+    pub fn stash(&self, message: Option<String>) {
+        self.common
+            .proxy
+            .git_stash_push(message, self.alert_on_error("Git Stash failed"));
+    }
+
+    pub fn stash_pop(&self, index: usize) {
+        self.common
+            .proxy
+            .git_stash_pop(index, self.alert_on_error("Git Stash Pop failed"));
+    }
+
+    pub fn stash_apply(&self, index: usize) {
+        self.common
+            .proxy
+            .git_stash_apply(index, self.alert_on_error("Git Stash Apply failed"));
+    }
+
+    pub fn stash_drop(&self, index: usize) {
+        self.common
+            .proxy
+            .git_stash_drop(index, self.alert_on_error("Git Stash Drop failed"));
+    }
+
+    /// Failures of git operations, like refused credentials or conflicts, are
+    /// shown in an alert
+    fn alert_on_error(&self, title: &'static str) -> impl ProxyCallback + 'static {
+        let internal_command = self.common.internal_command;
+        let send = create_ext_action(self.common.scope, move |msg: String| {
+            internal_command.send(InternalCommand::ShowAlert {
+                title: title.to_string(),
+                msg,
+                buttons: Vec::new(),
+            });
+        });
+        move |result| {
+            if let Err(e) = result {
+                send(e.message);
+            }
+        }
+    }
+}
+
+/// The longest a commit subject can be before tools like `git log --oneline`
+/// start cutting it off
+pub const COMMIT_SUBJECT_MAX_LENGTH: usize = 72;
+
+/// The line number and the text of the subject of a commit message, which is
+/// its first line that isn't blank or a comment
+pub fn commit_subject(message: &str) -> Option<(usize, &str)> {
+    message
+        .lines()
+        .enumerate()
+        .find(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'))
+}
+
+/// The warnings shown in the commit editor, which flag the part of the subject
+/// line past [`COMMIT_SUBJECT_MAX_LENGTH`]
+pub fn lint_commit_message(message: &str) -> Vec<Diagnostic> {
+    let Some((line, subject)) = commit_subject(message) else {
+        return Vec::new();
+    };
+    let subject = subject.trim_end();
+    let length = subject.chars().count();
+    if length <= COMMIT_SUBJECT_MAX_LENGTH {
+        return Vec::new();
+    }
+
+    // The columns of positions are in UTF-16 code units
+    let start: usize = subject
+        .chars()
+        .take(COMMIT_SUBJECT_MAX_LENGTH)
+        .map(char::len_utf16)
+        .sum();
+    let end = subject.encode_utf16().count();
+    vec![Diagnostic {
+        range: lsp_types::Range::new(
+            Position::new(line as u32, start as u32),
+            Position::new(line as u32, end as u32),
+        ),
+        severity: Some(DiagnosticSeverity::WARNING),
+        source: Some("git".to_string()),
+        message: format!(
+            "The subject line is {length} characters long, \
+             more than {COMMIT_SUBJECT_MAX_LENGTH}"
+        ),
+        ..Default::default()
+    }]
+}
+
+/// Group the line changes of a diff into hunks, which are the runs of removed
+/// and added lines between unchanged lines.
+pub fn diff_hunks(changes: &[DiffLines]) -> Vec<GitHunk> {
+    let mut hunks = Vec::new();
+    let mut current: Option<GitHunk> = None;
+    let mut old_line = 0;
+    let mut new_line = 0;
+    for change in changes {
+        match change {
+            DiffLines::Left(range) => {
+                let hunk = current.get_or_insert(GitHunk {
+                    old_start: range.start,
+                    old_lines: 0,
+                    new_start: new_line,
+                    new_lines: 0,
+                });
+                hunk.old_lines += range.len();
+                old_line = range.end;
+            }
+            DiffLines::Right(range) => {
+                let hunk = current.get_or_insert(GitHunk {
+                    old_start: old_line,
+                    old_lines: 0,
+                    new_start: range.start,
+                    new_lines: 0,
+                });
+                hunk.new_lines += range.len();
+                new_line = range.end;
+            }
+            DiffLines::Both(info) => {
+                hunks.extend(current.take());
+                old_line = info.left.end;
+                new_line = info.right.end;
+            }
+        }
+    }
+    hunks.extend(current);
+    hunks
+}
+
+/// The hunk which has the line on its new side. The marker of removed lines is
+/// between two lines, so it belongs to both of them.
+pub fn hunk_at_line(hunks: &[GitHunk], line: usize) -> Option<GitHunk> {
+    hunks.iter().copied().find(|hunk| {
+        if hunk.new_lines == 0 {
+            line == hunk.new_start || line + 1 == hunk.new_start
+        } else {
+            line >= hunk.new_start && line < hunk.new_start + hunk.new_lines
+        }
+    })
+}
+
+/// The blame of the line, if it has any
+pub fn blame_at_line<'a>(
+    hunks: impl IntoIterator<Item = &'a BlameHunk>,
+    line: usize,
+) -> Option<&'a BlameHunk> {
+    hunks
+        .into_iter()
+        .find(|hunk| line >= hunk.start_line && line < hunk.start_line + hunk.lines)
+}
+
+/// The annotation displayed after the cursor line, such as
+/// `Jane Doe, 3 days ago • Fix the build`
+pub fn blame_annotation(hunk: &BlameHunk, now: i64) -> String {
+    match &hunk.commit {
+        Some(commit) => format!(
+            "{}, {} • {}",
+            commit.author,
+            relative_time(now - commit.time),
+            commit.summary
+        ),
+        None => "You, Uncommitted changes".to_string(),
+    }
+}
+
+/// The text in the blame gutter for the first line of the hunk, which is the
+/// date and the author of the commit
+pub fn blame_gutter_text(hunk: &BlameHunk) -> String {
+    match &hunk.commit {
+        Some(commit) => {
+            let date = chrono::DateTime::from_timestamp(commit.time, 0)
+                .map(|date| {
+                    date.with_timezone(&chrono::Local)
+                        .format("%Y-%m-%d")
+                        .to_string()
+                })
+                .unwrap_or_default();
+            format!("{date} {}", commit.author)
+        }
+        None => "Uncommitted".to_string(),
+    }
+}
+
+/// How long ago something happened, given the seconds since, such as
+/// `3 days ago`
+pub fn relative_time(seconds: i64) -> String {
+    const MINUTE: i64 = 60;
+    const HOUR: i64 = 60 * MINUTE;
+    const DAY: i64 = 24 * HOUR;
+    const MONTH: i64 = 30 * DAY;
+    const YEAR: i64 = 365 * DAY;
+
+    let (count, unit) = if seconds < MINUTE {
+        return "just now".to_string();
+    } else if seconds < HOUR {
+        (seconds / MINUTE, "minute")
+    } else if seconds < DAY {
+        (seconds / HOUR, "hour")
+    } else if seconds < MONTH {
+        (seconds / DAY, "day")
+    } else if seconds < YEAR {
+        (seconds / MONTH, "month")
+    } else {
+        (seconds / YEAR, "year")
+    };
+    if count == 1 {
+        format!("1 {unit} ago")
+    } else {
+        format!("{count} {unit}s ago")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Range;
+
+    use lapce_core::buffer::diff::{DiffBothInfo, DiffLines};
+    use lapce_rpc::source_control::GitHunk;
+
+    use lsp_types::Position;
+
+    use super::{
+        commit_subject, diff_hunks, hunk_at_line, lint_commit_message, relative_time,
+    };
+
+    fn both(left: Range<usize>, right: Range<usize>) -> DiffLines {
+        DiffLines::Both(DiffBothInfo {
+            left,
+            right,
+            skip: None,
+        })
+    }
+
+    #[test]
+    fn test_diff_hunks() {
+        let changes = [
+            both(0..2, 0..2),
+            DiffLines::Left(2..3),
+            DiffLines::Right(2..4),
+            both(3..5, 4..6),
+            DiffLines::Left(5..7),
+            both(7..8, 6..7),
+            DiffLines::Right(7..8),
+        ];
+        let hunks = diff_hunks(&changes);
+        assert_eq!(
+            hunks,
+            vec![
+                GitHunk {
+                    old_start: 2,
+                    old_lines: 1,
+                    new_start: 2,
+                    new_lines: 2,
+                },
+                GitHunk {
+                    old_start: 5,
+                    old_lines: 2,
+                    new_start: 6,
+                    new_lines: 0,
+                },
+                GitHunk {
+                    old_start: 8,
+                    old_lines: 0,
+                    new_start: 7,
+                    new_lines: 1,
+                },
+            ]
+        );
+
+        assert_eq!(hunk_at_line(&hunks, 3), Some(hunks[0]));
+        assert_eq!(hunk_at_line(&hunks, 4), None);
+        assert_eq!(hunk_at_line(&hunks, 5), Some(hunks[1]));
+        assert_eq!(hunk_at_line(&hunks, 6), Some(hunks[1]));
+        assert_eq!(hunk_at_line(&hunks, 7), Some(hunks[2]));
+    }
+
+    #[test]
+    fn test_relative_time() {
+        assert_eq!(relative_time(-5), "just now");
+        assert_eq!(relative_time(59), "just now");
+        assert_eq!(relative_time(60), "1 minute ago");
+        assert_eq!(relative_time(60 * 60 * 3 + 10), "3 hours ago");
+        assert_eq!(relative_time(60 * 60 * 24 * 45), "1 month ago");
+        assert_eq!(relative_time(60 * 60 * 24 * 800), "2 years ago");
+    }
+
+    #[test]
+    fn test_lint_commit_message() {
+        let subject = "a".repeat(72);
+        assert!(lint_commit_message(&subject).is_empty());
+        assert!(lint_commit_message("").is_empty());
+
+        // The comments of a template come before the subject
+        let message = format!("# Subject\n\n{subject}é!\n\nBody");
+        assert_eq!(commit_subject(&message).map(|(line, _)| line), Some(2));
+        let diagnostics = lint_commit_message(&message);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].range.start, Position::new(2, 72));
+        assert_eq!(diagnostics[0].range.end, Position::new(2, 74));
+
+        assert_eq!(commit_subject("# Only a comment\n\n"), None);
     }
 }
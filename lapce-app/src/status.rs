@@ -54,12 +54,18 @@ pub fn status(
         (errors, warnings)
     });
     let branch = source_control.branch;
+    let upstream = source_control.upstream;
+    let ahead = source_control.ahead;
+    let behind = source_control.behind;
     let file_diffs = source_control.file_diffs;
+    let staged_diffs = source_control.staged_diffs;
     let branch = move || {
         format!(
             "{}{}",
             branch.get(),
-            if file_diffs.with(|diffs| diffs.is_empty()) {
+            if file_diffs.with(|diffs| diffs.is_empty())
+                && staged_diffs.with(|diffs| diffs.is_empty())
+            {
                 ""
             } else {
                 "*"
@@ -130,6 +136,13 @@ pub fn status(
                     s.margin_left(10.0)
                         .color(config.get().color(LapceColor::STATUS_FOREGROUND))
                 }),
+                label(move || format!("{}↓ {}↑", behind.get(), ahead.get())).style(
+                    move |s| {
+                        s.margin_left(10.0)
+                            .color(config.get().color(LapceColor::STATUS_FOREGROUND))
+                            .apply_if(upstream.with(|u| u.is_none()), |s| s.hide())
+                    },
+                ),
             ))
             .style(move |s| {
                 s.display(if branch().is_empty() {
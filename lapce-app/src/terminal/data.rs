@@ -414,8 +414,12 @@ fn new_raw_terminal(
 
             profile.environment = run_debug.env;
 
-            profile.command = Some(run_debug.program);
-            profile.arguments = run_debug.args;
+            // There's nothing to run when attaching to a process, which leaves
+            // the terminal with the shell
+            if !run_debug.program.is_empty() {
+                profile.command = Some(run_debug.program);
+                profile.arguments = run_debug.args;
+            }
         }
 
         {
@@ -768,6 +772,8 @@ pub fn expand(
                 (command, args)
             } else if let Some(prelaunch) = prelaunch {
                 (prelaunch.program.clone(), prelaunch.args.clone())
+            } else if run_debug.is_attach() {
+                (String::new(), None)
             } else {
                 (run_debug.program.clone(), run_debug.args.clone())
             };
@@ -7,7 +7,8 @@
 use lapce_core::mode::Mode;
 use lapce_rpc::{
     dap_types::{
-        self, DapId, RunDebugConfig, StackFrame, Stopped, ThreadId, Variable,
+        self, DapId, DebuggerCapabilities, Output, RunDebugConfig, StackFrame,
+        Stopped, ThreadId, Variable,
     },
     proxy::ProxyResponse,
     terminal::{TermId, TerminalProfile},
@@ -62,6 +63,7 @@ pub fn new(
         let breakline = {
             let active_term = debug.active_term;
             let daps = debug.daps;
+            let attached = debug.attached;
             cx.create_memo(move |_| {
                 let active_term = active_term.get();
                 let active_term = match active_term {
@@ -83,14 +85,15 @@ pub fn new(
                     }
                     None
                 });
-                let term = match term {
-                    Some(term) => term,
-                    None => return None,
+                let stopped = match term {
+                    Some(term) => term
+                        .run_debug
+                        .with(|run_debug| run_debug.as_ref().map(|r| r.stopped)),
+                    None => attached.with(|attached| {
+                        attached.get(&active_term).map(|r| r.stopped)
+                    }),
                 };
-                let stopped = term
-                    .run_debug
-                    .with(|run_debug| run_debug.as_ref().map(|r| r.stopped))
-                    .unwrap_or(true);
+                let stopped = stopped.unwrap_or(true);
                 if stopped {
                     return None;
                 }
@@ -381,9 +384,27 @@ pub fn terminal_stopped(&self, term_id: &TermId) {
                     let run_debug = terminal.run_debug.get_untracked();
                     if let Some(run_debug) = run_debug {
                         if run_debug.mode == RunDebugMode::Debug {
+                            // The debugger won't ask for a terminal to run the
+                            // program in when attaching, so the session is the
+                            // one of the prelaunch terminal
+                            if run_debug.config.is_attach() {
+                                let dap_id = run_debug.config.dap_id;
+                                self.debug.daps.update(|daps| {
+                                    daps.insert(
+                                        dap_id,
+                                        DapData::new(
+                                            self.cx,
+                                            dap_id,
+                                            *term_id,
+                                            self.common.clone(),
+                                        ),
+                                    );
+                                });
+                            }
                             self.common.proxy.dap_start(
                                 run_debug.config,
                                 self.debug.source_breakpoints(),
+                                self.debug.global_breakpoints(),
                             )
                         } else {
                             terminal.new_process(Some(run_debug));
@@ -435,6 +456,15 @@ pub fn get_stopped_run_debug_terminal(
     }
 
     pub fn restart_run_debug(&self, term_id: TermId) -> Option<()> {
+        if self.set_attached_stopped(&term_id, false).is_some() {
+            let dap_id = self.get_run_debug(&term_id, false)?.config.dap_id;
+            self.common.proxy.dap_restart(
+                dap_id,
+                self.debug.source_breakpoints(),
+                self.debug.global_breakpoints(),
+            );
+            return Some(());
+        }
         let (_, terminal_tab, index, terminal) =
             self.get_terminal_in_tab(&term_id)?;
         let run_debug = terminal.run_debug.get_untracked()?;
@@ -465,9 +495,11 @@ pub fn restart_run_debug(&self, term_id: TermId) -> Option<()> {
                     terminal.run_debug.get_untracked().as_ref()?.config.dap_id;
                 let daps = self.debug.daps.get_untracked();
                 let dap = daps.get(&dap_id)?;
-                self.common
-                    .proxy
-                    .dap_restart(dap.dap_id, self.debug.source_breakpoints());
+                self.common.proxy.dap_restart(
+                    dap.dap_id,
+                    self.debug.source_breakpoints(),
+                    self.debug.global_breakpoints(),
+                );
                 term_id
             }
         };
@@ -477,6 +509,24 @@ pub fn restart_run_debug(&self, term_id: TermId) -> Option<()> {
         Some(())
     }
 
+    /// Mark the debug session which attached to a process as stopped or not,
+    /// returning `None` if the session isn't one which attached
+    fn set_attached_stopped(&self, term_id: &TermId, stopped: bool) -> Option<()> {
+        if !self
+            .debug
+            .attached
+            .with_untracked(|attached| attached.contains_key(term_id))
+        {
+            return None;
+        }
+        self.debug.attached.update(|attached| {
+            if let Some(process) = attached.get_mut(term_id) {
+                process.stopped = stopped;
+            }
+        });
+        Some(())
+    }
+
     pub fn focus_terminal(&self, term_id: TermId) {
         if let Some((tab_index, terminal_tab, index, _terminal)) =
             self.get_terminal_in_tab(&term_id)
@@ -504,7 +554,7 @@ pub fn update_debug_active_term(&self) {
                     self.debug.active_term.set(Some(term_id));
                 }
             } else if let Some(active) = current_active {
-                if self.get_terminal(&active).is_none() {
+                if self.get_run_debug(&active, false).is_none() {
                     self.debug.active_term.set(None);
                 }
             }
@@ -514,8 +564,7 @@ pub fn update_debug_active_term(&self) {
     }
 
     pub fn stop_run_debug(&self, term_id: TermId) -> Option<()> {
-        let terminal = self.get_terminal(&term_id)?;
-        let run_debug = terminal.run_debug.get_untracked()?;
+        let run_debug = self.get_run_debug(&term_id, false)?;
 
         match run_debug.mode {
             RunDebugMode::Run => {
@@ -528,6 +577,10 @@ pub fn stop_run_debug(&self, term_id: TermId) -> Option<()> {
                 self.common.proxy.dap_stop(dap.dap_id);
             }
         }
+        // There's no terminal to tell when a session which attached is over
+        if self.set_attached_stopped(&term_id, true).is_some() {
+            return Some(());
+        }
 
         self.focus_terminal(term_id);
         Some(())
@@ -563,6 +616,12 @@ pub fn run_debug_process(
                 }
             });
         }
+        let attached = if tracked {
+            self.debug.attached.get()
+        } else {
+            self.debug.attached.get_untracked()
+        };
+        processes.extend(attached);
         processes.sort_by_key(|(_, process)| process.created);
         processes
     }
@@ -593,6 +652,30 @@ pub fn dap_continued(&self, dap_id: &DapId) {
         }
     }
 
+    pub fn dap_output(&self, dap_id: &DapId, output: &Output) {
+        let dap = self
+            .debug
+            .daps
+            .with_untracked(|daps| daps.get(dap_id).cloned());
+        if let Some(dap) = dap {
+            dap.output(output);
+        }
+    }
+
+    pub fn dap_capabilities(
+        &self,
+        dap_id: &DapId,
+        capabilities: &DebuggerCapabilities,
+    ) {
+        let dap = self
+            .debug
+            .daps
+            .with_untracked(|daps| daps.get(dap_id).cloned());
+        if let Some(dap) = dap {
+            dap.capabilities.set(Some(capabilities.clone()));
+        }
+    }
+
     pub fn dap_stopped(
         &self,
         dap_id: &DapId,
@@ -606,15 +689,13 @@ pub fn dap_stopped(
             .with_untracked(|daps| daps.get(dap_id).cloned());
         if let Some(dap) = dap {
             dap.stopped(self.cx, stopped, stack_frames, variables);
+            dap.evaluate_watches(&self.debug.watches.get_untracked());
         }
         floem::action::focus_window();
     }
 
     pub fn dap_continue(&self, term_id: TermId) -> Option<()> {
-        let terminal = self.get_terminal(&term_id)?;
-        let dap_id = terminal
-            .run_debug
-            .with_untracked(|r| r.as_ref().map(|r| r.config.dap_id))?;
+        let dap_id = self.get_run_debug(&term_id, false)?.config.dap_id;
         let thread_id = self.debug.daps.with_untracked(|daps| {
             daps.get(&dap_id)
                 .and_then(|dap| dap.thread_id.get_untracked())
@@ -625,10 +706,7 @@ pub fn dap_continue(&self, term_id: TermId) -> Option<()> {
     }
 
     pub fn dap_pause(&self, term_id: TermId) -> Option<()> {
-        let terminal = self.get_terminal(&term_id)?;
-        let dap_id = terminal
-            .run_debug
-            .with_untracked(|r| r.as_ref().map(|r| r.config.dap_id))?;
+        let dap_id = self.get_run_debug(&term_id, false)?.config.dap_id;
         let thread_id = self.debug.daps.with_untracked(|daps| {
             daps.get(&dap_id)
                 .and_then(|dap| dap.thread_id.get_untracked())
@@ -639,10 +717,7 @@ pub fn dap_pause(&self, term_id: TermId) -> Option<()> {
     }
 
     pub fn dap_step_over(&self, term_id: TermId) -> Option<()> {
-        let terminal = self.get_terminal(&term_id)?;
-        let dap_id = terminal
-            .run_debug
-            .with_untracked(|r| r.as_ref().map(|r| r.config.dap_id))?;
+        let dap_id = self.get_run_debug(&term_id, false)?.config.dap_id;
         let thread_id = self.debug.daps.with_untracked(|daps| {
             daps.get(&dap_id)
                 .and_then(|dap| dap.thread_id.get_untracked())
@@ -653,10 +728,7 @@ pub fn dap_step_over(&self, term_id: TermId) -> Option<()> {
     }
 
     pub fn dap_step_into(&self, term_id: TermId) -> Option<()> {
-        let terminal = self.get_terminal(&term_id)?;
-        let dap_id = terminal
-            .run_debug
-            .with_untracked(|r| r.as_ref().map(|r| r.config.dap_id))?;
+        let dap_id = self.get_run_debug(&term_id, false)?.config.dap_id;
         let thread_id = self.debug.daps.with_untracked(|daps| {
             daps.get(&dap_id)
                 .and_then(|dap| dap.thread_id.get_untracked())
@@ -667,10 +739,7 @@ pub fn dap_step_into(&self, term_id: TermId) -> Option<()> {
     }
 
     pub fn dap_step_out(&self, term_id: TermId) -> Option<()> {
-        let terminal = self.get_terminal(&term_id)?;
-        let dap_id = terminal
-            .run_debug
-            .with_untracked(|r| r.as_ref().map(|r| r.config.dap_id))?;
+        let dap_id = self.get_run_debug(&term_id, false)?.config.dap_id;
         let thread_id = self.debug.daps.with_untracked(|daps| {
             daps.get(&dap_id)
                 .and_then(|dap| dap.thread_id.get_untracked())
@@ -680,6 +749,30 @@ pub fn dap_step_out(&self, term_id: TermId) -> Option<()> {
         Some(())
     }
 
+    /// The process which runs in the terminal, or the debug session which
+    /// attached to a process, which doesn't have a terminal
+    pub fn get_run_debug(
+        &self,
+        term_id: &TermId,
+        tracked: bool,
+    ) -> Option<RunDebugProcess> {
+        let Some(terminal) = self.get_terminal(term_id) else {
+            let get = |attached: &im::HashMap<TermId, RunDebugProcess>| {
+                attached.get(term_id).cloned()
+            };
+            return if tracked {
+                self.debug.attached.with(get)
+            } else {
+                self.debug.attached.with_untracked(get)
+            };
+        };
+        if tracked {
+            terminal.run_debug.get()
+        } else {
+            terminal.run_debug.get_untracked()
+        }
+    }
+
     pub fn get_active_dap(&self, tracked: bool) -> Option<DapData> {
         let active_term = if tracked {
             self.debug.active_term.get()?
@@ -690,16 +783,7 @@ pub fn get_active_dap(&self, tracked: bool) -> Option<DapData> {
     }
 
     pub fn get_dap(&self, term_id: TermId, tracked: bool) -> Option<DapData> {
-        let terminal = self.get_terminal(&term_id)?;
-        let dap_id = if tracked {
-            terminal
-                .run_debug
-                .with(|r| r.as_ref().map(|r| r.config.dap_id))?
-        } else {
-            terminal
-                .run_debug
-                .with_untracked(|r| r.as_ref().map(|r| r.config.dap_id))?
-        };
+        let dap_id = self.get_run_debug(&term_id, tracked)?.config.dap_id;
 
         if tracked {
             self.debug.daps.with(|daps| daps.get(&dap_id).cloned())
@@ -712,6 +796,8 @@ pub fn get_dap(&self, term_id: TermId, tracked: bool) -> Option<DapData> {
 
     pub fn dap_frame_scopes(&self, dap_id: DapId, frame_id: usize) {
         if let Some(dap) = self.debug.daps.get_untracked().get(&dap_id) {
+            dap.frame_id.set(Some(frame_id));
+            dap.evaluate_watches(&self.debug.watches.get_untracked());
             let variables = dap.variables;
             let send = create_ext_action(self.common.scope, move |result| {
                 if let Ok(ProxyResponse::DapGetScopesResponse { scopes }) = result {
@@ -1,5 +1,5 @@
 use std::{
-    collections::{BTreeMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     env,
     path::{Path, PathBuf},
     rc::Rc,
@@ -28,6 +28,7 @@
     core::CoreNotification,
     dap_types::RunDebugConfig,
     file::{Naming, PathObject},
+    plugin::PluginId,
     proxy::{ProxyResponse, ProxyRpcHandler, ProxyStatus},
     source_control::FileDiff,
     terminal::TermId,
@@ -48,20 +49,31 @@
     completion::{CompletionData, CompletionStatus},
     config::LapceConfig,
     db::LapceDb,
-    debug::{DapData, LapceBreakpoint, RunDebugMode, RunDebugProcess},
+    debug::{
+        set_breakpoint_id, BreakpointEditData, DapData, DebugInput, DebugPanelData,
+        LapceBreakpoint, RunDebugMode, RunDebugProcess,
+    },
     doc::{DocContent, EditorDiagnostic},
-    editor::location::{EditorLocation, EditorPosition},
+    editor::{
+        location::{EditorLocation, EditorPosition},
+        EditorData,
+    },
     editor_tab::EditorTabChild,
     file_explorer::data::FileExplorerData,
     find::Find,
+    git_log::GitLogData,
     global_search::GlobalSearchData,
+    hierarchy::{HierarchyData, HierarchyKind},
     hover::HoverData,
     id::WindowTabId,
     inline_completion::InlineCompletionData,
     keypress::{condition::Condition, EventRef, KeyPressData, KeyPressFocus},
     listener::Listener,
     main_split::{MainSplitData, SplitData, SplitDirection, SplitMoveDirection},
-    palette::{kind::PaletteKind, PaletteData, PaletteStatus},
+    palette::{
+        kind::PaletteKind, PaletteData, PaletteStatus, SCMInputAction,
+        SCMReferenceAction, SCMStashAction,
+    },
     panel::{
         data::{default_panel_order, PanelData},
         kind::PanelKind,
@@ -85,6 +97,7 @@ pub enum Focus {
     Palette,
     CodeAction,
     Rename,
+    BreakpointEdit,
     AboutPopup,
     Panel(PanelKind),
 }
@@ -135,6 +148,14 @@ pub struct CommonData {
     pub proxy_status: RwSignal<Option<ProxyStatus>>,
     pub mouse_hover_timer: RwSignal<TimerToken>,
     pub breakpoints: RwSignal<BTreeMap<PathBuf, BTreeMap<usize, LapceBreakpoint>>>,
+    /// The characters each plugin's language server formats after when they're
+    /// typed
+    pub on_type_format_triggers: RwSignal<HashMap<PluginId, Vec<String>>>,
+    /// The files git has conflicts in, which are the only ones the documents
+    /// look for conflict markers in
+    pub conflicted_files: RwSignal<HashSet<PathBuf>>,
+    /// Whether the editors show the git blame of each line in their gutter
+    pub show_blame: RwSignal<bool>,
     // the current focused view which will receive keyboard events
     pub keyboard_focus: RwSignal<Option<floem::id::Id>>,
     pub window_common: Rc<WindowCommonData>,
@@ -150,11 +171,15 @@ pub struct WindowTabData {
     pub file_explorer: FileExplorerData,
     pub panel: PanelData,
     pub terminal: TerminalPanelData,
+    pub debug_panel: DebugPanelData,
     pub plugin: PluginData,
     pub code_action: RwSignal<CodeActionData>,
     pub source_control: SourceControlData,
     pub rename: RenameData,
+    pub breakpoint_edit: BreakpointEditData,
     pub global_search: GlobalSearchData,
+    pub hierarchy: HierarchyData,
+    pub git_log: GitLogData,
     pub about_data: AboutData,
     pub alert_data: AlertBoxData,
     pub layout_rect: RwSignal<Rect>,
@@ -182,6 +207,10 @@ fn check_condition(&self, condition: Condition) -> bool {
                 self.common.focus.get_untracked()
                     == Focus::Panel(PanelKind::SourceControl)
             }
+            Condition::DebugConsoleFocus => {
+                self.common.focus.get_untracked() == Focus::Panel(PanelKind::Debug)
+                    && self.debug_panel.input.get_untracked() == DebugInput::Console
+            }
             _ => false,
         }
     }
@@ -350,6 +379,9 @@ pub fn new(
             mouse_hover_timer: cx.create_rw_signal(TimerToken::INVALID),
             window_origin: cx.create_rw_signal(Point::ZERO),
             breakpoints: cx.create_rw_signal(BTreeMap::new()),
+            on_type_format_triggers: cx.create_rw_signal(HashMap::new()),
+            conflicted_files: cx.create_rw_signal(HashSet::new()),
+            show_blame: cx.create_rw_signal(false),
             keyboard_focus: cx.create_rw_signal(None),
             window_common: window_common.clone(),
         });
@@ -453,10 +485,35 @@ pub fn new(
                     })
                     .collect(),
             );
+            terminal.debug.function_breakpoints.set(
+                workspace_info
+                    .function_breakpoints
+                    .iter()
+                    .cloned()
+                    .collect(),
+            );
+            terminal
+                .debug
+                .exception_filters
+                .set(workspace_info.exception_filters.clone());
         }
 
         let rename = RenameData::new(cx, main_split.editors, common.clone());
+        let breakpoint_edit = BreakpointEditData::new(
+            cx,
+            main_split.editors,
+            terminal.debug.clone(),
+            common.clone(),
+        );
+        let debug_panel = DebugPanelData::new(
+            cx,
+            main_split.editors,
+            terminal.clone(),
+            common.clone(),
+        );
         let global_search = GlobalSearchData::new(cx, main_split.clone());
+        let hierarchy = HierarchyData::new(cx, common.clone());
+        let git_log = GitLogData::new(cx, common.clone());
 
         let plugin = PluginData::new(
             cx,
@@ -495,13 +552,17 @@ pub fn new(
             palette,
             main_split,
             terminal,
+            debug_panel,
             panel,
             file_explorer,
             code_action,
             source_control,
             plugin,
             rename,
+            breakpoint_edit,
             global_search,
+            hierarchy,
+            git_log,
             about_data,
             alert_data,
             layout_rect: cx.create_rw_signal(Rect::ZERO),
@@ -519,6 +580,7 @@ pub fn new(
             let focus = window_tab_data.common.focus;
             let active_editor = window_tab_data.main_split.active_editor;
             let rename_active = window_tab_data.rename.active;
+            let breakpoint_edit_active = window_tab_data.breakpoint_edit.active;
             let internal_command = window_tab_data.common.internal_command;
             cx.create_effect(move |_| {
                 let focus = focus.get();
@@ -528,6 +590,11 @@ pub fn new(
                 if focus != Focus::Rename && rename_active.get_untracked() {
                     rename_active.set(false);
                 }
+                if focus != Focus::BreakpointEdit
+                    && breakpoint_edit_active.get_untracked()
+                {
+                    breakpoint_edit_active.set(false);
+                }
             });
         }
 
@@ -587,6 +654,11 @@ pub fn reload_config(&self) {
             keypress.update_keymaps(&config);
         });
         self.set_config.set(Arc::new(config));
+
+        // The inline blame may have just been turned on
+        for (_, doc) in self.main_split.docs.get_untracked() {
+            doc.get_blame();
+        }
     }
 
     pub fn run_lapce_command(&self, cmd: LapceCommand) {
@@ -1010,7 +1082,8 @@ pub fn run_workbench_command(
                 self.palette.run(PaletteKind::RunAndDebug);
             }
             PaletteSCMReferences => {
-                self.palette.run(PaletteKind::SCMReferences);
+                self.palette
+                    .run_scm_references(SCMReferenceAction::Checkout);
             }
             ChangeColorTheme => {
                 self.palette.run(PaletteKind::ColorTheme);
@@ -1025,6 +1098,33 @@ pub fn run_workbench_command(
                 self.palette.run(PaletteKind::LineEnding);
             }
             DiffFiles => self.palette.run(PaletteKind::DiffFiles),
+            CompareWithRevision => {
+                self.palette.run_scm_references(SCMReferenceAction::Compare);
+            }
+            CompareWithOpenEditor => self.palette.run(PaletteKind::OpenEditors),
+            CompareWithClipboard => {
+                self.main_split.compare_with_clipboard();
+            }
+            NextDiffChange => {
+                if let Some(diff_editor) = self.main_split.active_diff_editor() {
+                    diff_editor.go_to_change(true);
+                }
+            }
+            PreviousDiffChange => {
+                if let Some(diff_editor) = self.main_split.active_diff_editor() {
+                    diff_editor.go_to_change(false);
+                }
+            }
+            ToggleInlineDiff => {
+                if let Some(diff_editor) = self.main_split.active_diff_editor() {
+                    diff_editor.toggle_inline();
+                }
+            }
+            ToggleDiffIgnoreWhitespace => {
+                if let Some(diff_editor) = self.main_split.active_diff_editor() {
+                    diff_editor.toggle_ignore_whitespace();
+                }
+            }
 
             // ==== Running / Debugging ====
             RunAndDebugRestart => {
@@ -1042,6 +1142,10 @@ pub fn run_workbench_command(
                     self.terminal.stop_run_debug(term_id);
                 }
             }
+            DebugConsoleEvaluate => {
+                self.debug_panel.evaluate();
+            }
+            ConfirmBreakpointEdit => {}
 
             // ==== UI ====
             ZoomIn => {
@@ -1130,6 +1234,50 @@ pub fn run_workbench_command(
             ToggleSearchFocus => {
                 self.toggle_panel_focus(PanelKind::Search);
             }
+            GotoImplementation => {
+                if let Some(editor) = self.main_split.active_editor.get_untracked() {
+                    editor.go_to_implementation();
+                }
+            }
+            GotoDeclaration => {
+                if let Some(editor) = self.main_split.active_editor.get_untracked() {
+                    editor.go_to_declaration();
+                }
+            }
+            FormatSelection => {
+                if let Some(editor) = self.main_split.active_editor.get_untracked() {
+                    editor.format_selection();
+                }
+            }
+            ShowCallHierarchy | ShowTypeHierarchy => {
+                let Some(editor) = self.main_split.active_editor.get_untracked()
+                else {
+                    return;
+                };
+                let Some((path, _, position)) = editor.cursor_lsp_position() else {
+                    return;
+                };
+                let kind = if cmd == ShowCallHierarchy {
+                    HierarchyKind::IncomingCalls
+                } else {
+                    HierarchyKind::Supertypes
+                };
+                self.hierarchy.prepare(kind, path, position);
+                self.show_panel(PanelKind::Hierarchy);
+            }
+            Fold | Unfold | ToggleFold | FoldAll | UnfoldAll | FoldLevel1
+            | FoldLevel2 | FoldLevel3 | FoldLevel4 | FoldLevel5 | FoldLevel6
+            | FoldLevel7 => {
+                if let Some(editor) = self.main_split.active_editor.get_untracked() {
+                    editor.run_fold_command(&cmd);
+                }
+            }
+            GlobalSearchReplaceAll => {
+                self.global_search.replace_all();
+            }
+            GlobalSearchUndoReplace => {
+                self.global_search.undo_replace();
+            }
             ToggleTerminalVisual => {
                 self.toggle_panel_visual(PanelKind::Terminal);
             }
@@ -1151,6 +1299,12 @@ pub fn run_workbench_command(
             ToggleSearchVisual => {
                 self.toggle_panel_visual(PanelKind::Search);
             }
+            ToggleHierarchyVisual => {
+                self.toggle_panel_visual(PanelKind::Hierarchy);
+            }
+            ToggleGitLogVisual => {
+                self.toggle_panel_visual(PanelKind::GitLog);
+            }
             FocusEditor => {
                 self.common.focus.set(Focus::Workbench);
             }
@@ -1190,7 +1344,9 @@ pub fn run_workbench_command(
                         FileDiff::Added(path) => {
                             self.common.proxy.trash_path(path, Box::new(|_| {}));
                         }
-                        FileDiff::Modified(path) | FileDiff::Deleted(path) => {
+                        FileDiff::Modified(path)
+                        | FileDiff::Deleted(path)
+                        | FileDiff::Conflicted(path) => {
                             self.common.proxy.git_discard_files_changes(vec![path]);
                         }
                         FileDiff::Renamed(old_path, new_path) => {
@@ -1205,6 +1361,92 @@ pub fn run_workbench_command(
             SourceControlDiscardWorkspaceChanges => {
                 // TODO:
             }
+            SourceControlStageAll => {
+                self.source_control.stage_all();
+            }
+            SourceControlUnstageAll => {
+                self.source_control.unstage_all();
+            }
+            SourceControlStageHunk => {
+                if let Some(editor) = self.main_split.active_editor.get_untracked() {
+                    editor.stage_hunk(editor.cursor_line());
+                }
+            }
+            SourceControlUnstageHunk => {
+                if let Some(editor) = self.main_split.active_editor.get_untracked() {
+                    editor.unstage_hunk(editor.cursor_line());
+                }
+            }
+            SourceControlRevertHunk => {
+                if let Some(editor) = self.main_split.active_editor.get_untracked() {
+                    editor.revert_hunk(editor.cursor_line());
+                }
+            }
+            SourceControlShowLog => {
+                self.git_log.show(None);
+                self.show_panel(PanelKind::GitLog);
+            }
+            SourceControlShowFileHistory => {
+                let Some(editor) = self.main_split.active_editor.get_untracked()
+                else {
+                    return;
+                };
+                let Some(path) =
+                    editor.doc().content.get_untracked().path().cloned()
+                else {
+                    return;
+                };
+                self.git_log.show(Some(path));
+                self.show_panel(PanelKind::GitLog);
+            }
+            SourceControlFetch => {
+                self.source_control.fetch();
+            }
+            SourceControlPull => {
+                self.source_control.pull(false);
+            }
+            SourceControlPullRebase => {
+                self.source_control.pull(true);
+            }
+            SourceControlPush => {
+                self.source_control.push();
+            }
+            SourceControlCreateBranch => {
+                self.palette
+                    .run_scm_input(SCMInputAction::CreateBranch { start: None });
+            }
+            SourceControlCreateBranchFrom => {
+                self.palette
+                    .run_scm_references(SCMReferenceAction::BranchFrom);
+            }
+            SourceControlRenameBranch => {
+                self.palette.run_scm_references(SCMReferenceAction::Rename);
+            }
+            SourceControlDeleteBranch => {
+                self.palette.run_scm_references(SCMReferenceAction::Delete);
+            }
+            SourceControlMergeBranch => {
+                self.palette.run_scm_references(SCMReferenceAction::Merge);
+            }
+            SourceControlStash => {
+                self.palette.run_scm_input(SCMInputAction::StashMessage);
+            }
+            SourceControlStashPop => {
+                self.palette.run_scm_stashes(SCMStashAction::Pop);
+            }
+            SourceControlStashApply => {
+                self.palette.run_scm_stashes(SCMStashAction::Apply);
+            }
+            SourceControlStashDrop => {
+                self.palette.run_scm_stashes(SCMStashAction::Drop);
+            }
+            SourceControlToggleBlame => {
+                self.common.show_blame.update(|show| *show = !*show);
+                // The blame isn't kept up to date while nothing shows it
+                for (_, doc) in self.main_split.docs.get_untracked() {
+                    doc.get_blame();
+                }
+            }
 
             // ==== UI ====
             ShowAbout => {
@@ -1321,8 +1563,14 @@ pub fn run_internal_command(&self, cmd: InternalCommand) {
                     None,
                 );
             }
-            InternalCommand::OpenFileChanges { path } => {
-                self.main_split.open_file_changes(path);
+            InternalCommand::OpenFileChanges { path, staged } => {
+                self.main_split.open_file_changes(path, staged);
+            }
+            InternalCommand::OpenMergeEditor { path } => {
+                self.main_split.open_merge_editor(path);
+            }
+            InternalCommand::OpenCommitFileChanges { commit, diff } => {
+                self.main_split.open_commit_file_changes(commit, diff);
             }
             InternalCommand::ReloadFileExplorer => {
                 self.file_explorer.reload();
@@ -1528,7 +1776,9 @@ pub fn run_internal_command(&self, cmd: InternalCommand) {
                 self.main_split.run_code_action(plugin_id, action);
             }
             InternalCommand::ApplyWorkspaceEdit { edit } => {
-                self.main_split.apply_workspace_edit(&edit);
+                if let Err(err) = self.main_split.apply_workspace_edit(&edit) {
+                    error!("failed to apply workspace edit: {err}");
+                }
             }
             InternalCommand::SaveJumpLocation {
                 path,
@@ -1688,31 +1938,29 @@ fn handle_core_notification(&self, rpc: &CoreNotification) {
             CoreNotification::ProxyStatus { status } => {
                 self.common.proxy_status.set(Some(status.to_owned()));
             }
-            CoreNotification::DiffInfo { diff } => {
-                self.source_control.branch.set(diff.head.clone());
-                self.source_control
-                    .branches
-                    .set(diff.branches.iter().cloned().collect());
-                self.source_control
-                    .tags
-                    .set(diff.tags.iter().cloned().collect());
-                self.source_control.file_diffs.update(|file_diffs| {
-                    *file_diffs = diff
-                        .diffs
+            CoreNotification::DiffInfo { repositories } => {
+                self.source_control.set_repositories(repositories.clone());
+                self.common.conflicted_files.set(
+                    repositories
                         .iter()
-                        .cloned()
-                        .map(|diff| {
-                            let checked = file_diffs
-                                .get(diff.path())
-                                .map_or(true, |(_, c)| *c);
-                            (diff.path().clone(), (diff, checked))
+                        .flat_map(|repo| repo.diffs.iter().chain(&repo.staged))
+                        .filter_map(|diff| match diff {
+                            FileDiff::Conflicted(path) => Some(path.clone()),
+                            _ => None,
                         })
-                        .collect();
-                });
+                        .collect(),
+                );
 
                 let docs = self.main_split.docs.get_untracked();
                 for (_, doc) in docs {
                     doc.retrieve_head();
+                    doc.get_blame();
+                    doc.find_conflicts();
+                }
+                let diff_editors = self.main_split.diff_editors.get_untracked();
+                for (_, diff_editor) in diff_editors {
+                    diff_editor.left.doc().retrieve_history();
+                    diff_editor.right.doc().retrieve_history();
                 }
             }
             CoreNotification::CompletionResponse {
@@ -1764,6 +2012,36 @@ fn handle_core_notification(&self, rpc: &CoreNotification) {
                     doc.init_diagnostics();
                 }
             }
+            CoreNotification::CodeLensResponse {
+                path,
+                rev,
+                plugin_id,
+                lenses,
+            } => {
+                if let Some(doc) = self
+                    .main_split
+                    .docs
+                    .with_untracked(|docs| docs.get(path).cloned())
+                {
+                    doc.receive_code_lens(*rev, *plugin_id, lenses.clone());
+                }
+            }
+            CoreNotification::OnTypeFormattingTriggers {
+                plugin_id,
+                triggers,
+            } => {
+                self.common.on_type_format_triggers.update(|plugins| {
+                    plugins.insert(*plugin_id, triggers.clone());
+                });
+            }
+            CoreNotification::ApplyWorkspaceEdit { id, edit } => {
+                let result = self.main_split.apply_workspace_edit(edit);
+                self.common.proxy.workspace_edit_applied(
+                    *id,
+                    result.is_ok(),
+                    result.err().map(|err| err.to_string()),
+                );
+            }
             CoreNotification::TerminalProcessStopped { term_id } => {
                 let _ = self
                     .common
@@ -1808,9 +2086,24 @@ fn handle_core_notification(&self, rpc: &CoreNotification) {
             CoreNotification::DapContinued { dap_id } => {
                 self.terminal.dap_continued(dap_id);
             }
+            CoreNotification::DapOutput { dap_id, output } => {
+                self.terminal.dap_output(dap_id, output);
+            }
+            CoreNotification::DapCapabilities {
+                dap_id,
+                capabilities,
+            } => {
+                self.terminal.dap_capabilities(dap_id, capabilities);
+            }
             CoreNotification::DapBreakpointsResp {
-                path, breakpoints, ..
+                dap_id,
+                path,
+                breakpoints,
             } => {
+                let doc = self
+                    .main_split
+                    .docs
+                    .with_untracked(|docs| docs.get(path).cloned());
                 self.terminal.debug.breakpoints.update(|all_breakpoints| {
                     if let Some(current_breakpoints) = all_breakpoints.get_mut(path)
                     {
@@ -1822,15 +2115,25 @@ fn handle_core_notification(&self, rpc: &CoreNotification) {
                                 continue;
                             }
                             if let Some(breakpoint) = breakpoints.get(i) {
-                                current_breakpoint.id = breakpoint.id;
+                                set_breakpoint_id(
+                                    &mut current_breakpoint.ids,
+                                    *dap_id,
+                                    breakpoint.id,
+                                );
                                 current_breakpoint.verified = breakpoint.verified;
                                 current_breakpoint.message =
                                     breakpoint.message.clone();
                                 if let Some(new_line) = breakpoint.line {
                                     if current_breakpoint.line + 1 != new_line {
                                         line_changed.insert(current_breakpoint.line);
-                                        current_breakpoint.line =
-                                            new_line.saturating_sub(1);
+                                        let line = new_line.saturating_sub(1);
+                                        current_breakpoint.line = line;
+                                        if let Some(doc) = doc.as_ref() {
+                                            current_breakpoint.offset =
+                                                doc.buffer.with_untracked(|b| {
+                                                    b.offset_of_line(line)
+                                                });
+                                        }
                                     }
                                 }
                             }
@@ -1845,6 +2148,30 @@ fn handle_core_notification(&self, rpc: &CoreNotification) {
                     }
                 });
             }
+            CoreNotification::DapFunctionBreakpointsResp {
+                dap_id,
+                breakpoints,
+            } => {
+                self.terminal
+                    .debug
+                    .function_breakpoints_resp(*dap_id, breakpoints);
+            }
+            CoreNotification::DapBreakpointChanged {
+                dap_id,
+                reason,
+                breakpoint,
+            } => {
+                let docs = self.main_split.docs.get_untracked();
+                self.terminal.debug.breakpoint_changed(
+                    *dap_id,
+                    reason,
+                    breakpoint,
+                    |path, line| {
+                        let doc = docs.get(path)?;
+                        Some(doc.buffer.with_untracked(|b| b.offset_of_line(line)))
+                    },
+                );
+            }
             CoreNotification::OpenFileChanged { path, content } => {
                 self.main_split.open_file_changed(path, content);
             }
@@ -1905,6 +2232,7 @@ pub fn key_down<'a>(&self, event: impl Into<EventRef<'a>> + Copy) -> bool {
                 keypress.key_down(event, &code_action)
             }
             Focus::Rename => keypress.key_down(event, &self.rename),
+            Focus::BreakpointEdit => keypress.key_down(event, &self.breakpoint_edit),
             Focus::AboutPopup => keypress.key_down(event, &self.about_data),
             Focus::Panel(PanelKind::Terminal) => {
                 self.terminal.key_down(event, &keypress)
@@ -1918,6 +2246,9 @@ pub fn key_down<'a>(&self, event: impl Into<EventRef<'a>> + Copy) -> bool {
             Focus::Panel(PanelKind::SourceControl) => {
                 keypress.key_down(event, &self.source_control)
             }
+            Focus::Panel(PanelKind::Debug) => {
+                keypress.key_down(event, &self.debug_panel)
+            }
             _ => false,
         };
 
@@ -1949,6 +2280,14 @@ pub fn workspace_info(&self) -> WorkspaceInfo {
                     (path, breakpoints.into_values().collect::<Vec<_>>())
                 })
                 .collect(),
+            function_breakpoints: self
+                .terminal
+                .debug
+                .function_breakpoints
+                .get_untracked()
+                .into_iter()
+                .collect(),
+            exception_filters: self.terminal.debug.exception_filters.get_untracked(),
         }
     }
 
@@ -2108,14 +2447,10 @@ pub fn code_action_origin(&self) -> Point {
     }
 
     pub fn rename_origin(&self) -> Point {
-        let config = self.common.config.get();
         if !self.rename.active.get() {
             return Point::ZERO;
         }
 
-        let tab_size = self.layout_rect.get().size();
-        let rename_size = self.rename.layout_rect.get().size();
-
         let editor_data =
             if let Some(editor) = self.main_split.active_editor.get_untracked() {
                 editor
@@ -2123,6 +2458,54 @@ pub fn rename_origin(&self) -> Point {
                 return Point::ZERO;
             };
 
+        self.editor_popup_origin(
+            &editor_data,
+            self.rename.start.get_untracked(),
+            self.rename.layout_rect.get().size(),
+        )
+    }
+
+    pub fn breakpoint_edit_origin(&self) -> Point {
+        if !self.breakpoint_edit.active.get() {
+            return Point::ZERO;
+        }
+
+        let editor_data =
+            self.breakpoint_edit
+                .editor_id
+                .get_untracked()
+                .and_then(|editor_id| {
+                    self.main_split
+                        .editors
+                        .with_untracked(|editors| editors.get(&editor_id).cloned())
+                });
+        let Some(editor_data) = editor_data else {
+            return Point::ZERO;
+        };
+
+        let line = self.breakpoint_edit.breakpoint.with(|b| b.line);
+        let offset = editor_data
+            .doc()
+            .buffer
+            .with_untracked(|buffer| buffer.offset_of_line(line));
+        self.editor_popup_origin(
+            &editor_data,
+            offset,
+            self.breakpoint_edit.layout_rect.get().size(),
+        )
+    }
+
+    /// Where to put a popup of the size below the offset in the editor, or above
+    /// it when there isn't enough room
+    fn editor_popup_origin(
+        &self,
+        editor_data: &EditorData,
+        offset: usize,
+        popup_size: Size,
+    ) -> Point {
+        let config = self.common.config.get();
+        let tab_size = self.layout_rect.get().size();
+
         let (window_origin, viewport, editor) = (
             editor_data.window_origin(),
             editor_data.viewport(),
@@ -2130,10 +2513,8 @@ pub fn rename_origin(&self) -> Point {
         );
 
         // TODO(minor): What affinity should we use for this?
-        let (_point_above, point_below) = editor.points_of_offset(
-            self.rename.start.get_untracked(),
-            CursorAffinity::Forward,
-        );
+        let (_point_above, point_below) =
+            editor.points_of_offset(offset, CursorAffinity::Forward);
 
         let window_origin =
             window_origin.get() - self.common.window_origin.get().to_vec2();
@@ -2142,12 +2523,12 @@ pub fn rename_origin(&self) -> Point {
         let mut origin = window_origin
             + Vec2::new(point_below.x - viewport.x0, point_below.y - viewport.y0);
 
-        if origin.y + rename_size.height > tab_size.height {
+        if origin.y + popup_size.height > tab_size.height {
             origin.y =
-                origin.y - config.editor.line_height() as f64 - rename_size.height;
+                origin.y - config.editor.line_height() as f64 - popup_size.height;
         }
-        if origin.x + rename_size.width + 1.0 > tab_size.width {
-            origin.x = tab_size.width - rename_size.width - 1.0;
+        if origin.x + popup_size.width + 1.0 > tab_size.width {
+            origin.x = tab_size.width - popup_size.width - 1.0;
         }
         if origin.x <= 0.0 {
             origin.x = 0.0;
@@ -2188,7 +2569,9 @@ fn toggle_panel_focus(&self, kind: PanelKind) {
             PanelKind::FileExplorer
             | PanelKind::Plugin
             | PanelKind::Problem
-            | PanelKind::Debug => {
+            | PanelKind::Debug
+            | PanelKind::Hierarchy
+            | PanelKind::GitLog => {
                 // Some panels don't accept focus (yet). Fall back to visibility check
                 // in those cases.
                 self.panel.is_panel_visible(&kind)
@@ -2288,12 +2671,49 @@ fn run_and_debug(
                 self.run_in_terminal(cx, mode, config, false);
             }
             RunDebugMode::Debug => {
-                if config.prelaunch.is_some() {
+                if config.needs_process() {
+                    self.palette.run_attach_process(config.clone());
+                } else if config.prelaunch.is_some() {
                     self.run_in_terminal(cx, mode, config, false);
+                } else if config.is_attach() {
+                    // Nothing is run when attaching, so the session doesn't
+                    // have a terminal
+                    let term_id = TermId::next();
+                    self.terminal.debug.attached.update(|attached| {
+                        attached.insert(
+                            term_id,
+                            RunDebugProcess {
+                                mode: *mode,
+                                config: config.clone(),
+                                stopped: false,
+                                created: Instant::now(),
+                                is_prelaunch: false,
+                            },
+                        );
+                    });
+                    self.terminal.debug.daps.update(|daps| {
+                        daps.insert(
+                            config.dap_id,
+                            DapData::new(
+                                cx,
+                                config.dap_id,
+                                term_id,
+                                self.common.clone(),
+                            ),
+                        );
+                    });
+                    self.terminal.debug.active_term.set(Some(term_id));
+                    self.panel.show_panel(&PanelKind::Debug);
+                    self.common.proxy.dap_start(
+                        config.clone(),
+                        self.terminal.debug.source_breakpoints(),
+                        self.terminal.debug.global_breakpoints(),
+                    );
                 } else {
                     self.common.proxy.dap_start(
                         config.clone(),
                         self.terminal.debug.source_breakpoints(),
+                        self.terminal.debug.global_breakpoints(),
                     )
                 };
             }
@@ -2,7 +2,11 @@
 
 use serde::{Deserialize, Serialize};
 
-use crate::{debug::LapceBreakpoint, main_split::SplitInfo, panel::data::PanelInfo};
+use crate::{
+    debug::{LapceBreakpoint, LapceFunctionBreakpoint},
+    main_split::SplitInfo,
+    panel::data::PanelInfo,
+};
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Hash)]
 pub struct SshHost {
@@ -156,4 +160,8 @@ pub struct WorkspaceInfo {
     pub split: SplitInfo,
     pub panel: PanelInfo,
     pub breakpoints: HashMap<PathBuf, Vec<LapceBreakpoint>>,
+    #[serde(default)]
+    pub function_breakpoints: Vec<LapceFunctionBreakpoint>,
+    #[serde(default)]
+    pub exception_filters: HashMap<String, bool>,
 }
@@ -1,10 +1,16 @@
-use std::mem;
+use std::{
+    collections::{HashMap, HashSet},
+    mem,
+    ops::Range,
+};
 
+use lapce_rpc::plugin::PluginId;
 use lapce_xi_rope::{
     interval::IntervalBounds,
     tree::{DefaultMetric, Leaf, Node, NodeInfo, TreeBuilder},
-    Cursor, Delta, Interval, Metric,
+    Cursor, Delta, Interval, Metric, RopeDelta, Transformer,
 };
+use lsp_types::CodeLens;
 
 const MIN_LEAF: usize = 5;
 const MAX_LEAF: usize = 10;
@@ -319,10 +325,191 @@ fn next(&mut self) -> Option<Self::Item> {
     }
 }
 
+/// A code lens, identified by the plugin it's from and its index in the
+/// latest response of that plugin, which the responses of the other plugins
+/// don't shift.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CodeLensId {
+    pub plugin_id: PluginId,
+    pub index: usize,
+}
+
+/// The code lenses of a document, such as the `Run | Debug` of a test, merged
+/// from all the plugins which provide them.
+#[derive(Clone, Debug, Default)]
+pub struct CodeLenses {
+    /// (Plugin -> (Offset of the start of the range, Code lens))
+    plugins: HashMap<PluginId, Vec<(usize, CodeLens)>>,
+    /// The code lenses which have been sent to be resolved
+    resolving: HashSet<CodeLensId>,
+}
+
+impl CodeLenses {
+    pub fn is_empty(&self) -> bool {
+        self.plugins.is_empty()
+    }
+
+    /// Replace the code lenses of the plugin with the ones of its latest
+    /// response, keeping the ones of the other plugins.
+    pub fn set(&mut self, plugin_id: PluginId, lenses: Vec<(usize, CodeLens)>) {
+        self.resolving.retain(|id| id.plugin_id != plugin_id);
+        if lenses.is_empty() {
+            self.plugins.remove(&plugin_id);
+        } else {
+            self.plugins.insert(plugin_id, lenses);
+        }
+    }
+
+    pub fn get(&self, id: CodeLensId) -> Option<&CodeLens> {
+        self.plugins
+            .get(&id.plugin_id)?
+            .get(id.index)
+            .map(|(_, lens)| lens)
+    }
+
+    /// Mark the code lens as sent to be resolved, returning whether it wasn't
+    /// already.
+    pub fn start_resolve(&mut self, id: CodeLensId) -> bool {
+        self.resolving.insert(id)
+    }
+
+    /// Replace the code lens with its resolved version, unless the plugin has
+    /// responded with other code lenses since it was sent.
+    pub fn resolve(&mut self, id: CodeLensId, lens: CodeLens) {
+        if let Some((_, item)) = self
+            .plugins
+            .get_mut(&id.plugin_id)
+            .and_then(|lenses| lenses.get_mut(id.index))
+        {
+            if item.range == lens.range {
+                *item = lens;
+            }
+        }
+    }
+
+    /// Move the code lenses along with an edit of the document.
+    pub fn apply_delta(&mut self, delta: &RopeDelta) {
+        let mut transformer = Transformer::new(delta);
+        for lenses in self.plugins.values_mut() {
+            for (offset, _) in lenses.iter_mut() {
+                *offset = transformer.transform(*offset, false);
+            }
+        }
+    }
+
+    /// The code lenses which start in the range of offsets, in the order of
+    /// their offsets.
+    pub fn range(&self, range: Range<usize>) -> Vec<(CodeLensId, &CodeLens)> {
+        let mut lenses: Vec<(usize, CodeLensId, &CodeLens)> = self
+            .plugins
+            .iter()
+            .flat_map(|(plugin_id, lenses)| {
+                lenses.iter().enumerate().map(|(index, (offset, lens))| {
+                    let id = CodeLensId {
+                        plugin_id: *plugin_id,
+                        index,
+                    };
+                    (*offset, id, lens)
+                })
+            })
+            .filter(|(offset, _, _)| range.contains(offset))
+            .collect();
+        lenses.sort_by_key(|(offset, id, _)| (*offset, id.plugin_id.0, id.index));
+        lenses.into_iter().map(|(_, id, lens)| (id, lens)).collect()
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use lapce_xi_rope::{Rope, RopeInfo};
+    use lsp_types::{Command, Position};
+
     use super::*;
 
+    fn code_lens(line: u32, title: Option<&str>) -> CodeLens {
+        let position = Position::new(line, 0);
+        CodeLens {
+            range: lsp_types::Range::new(position, position),
+            command: title
+                .map(|title| Command::new(title.to_string(), "".to_string(), None)),
+            data: None,
+        }
+    }
+
+    fn titles(
+        lenses: &CodeLenses,
+        range: Range<usize>,
+    ) -> Vec<(u64, usize, String)> {
+        lenses
+            .range(range)
+            .into_iter()
+            .map(|(id, lens)| {
+                let title = lens.command.as_ref().map(|c| c.title.clone());
+                (id.plugin_id.0, id.index, title.unwrap_or_default())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_code_lenses_merge() {
+        let mut lenses = CodeLenses::default();
+        let (a, b) = (PluginId(1), PluginId(2));
+        lenses.set(
+            a,
+            vec![(10, code_lens(1, Some("Run"))), (0, code_lens(0, None))],
+        );
+        lenses.set(b, vec![(10, code_lens(1, Some("References")))]);
+        assert_eq!(
+            titles(&lenses, 0..20),
+            vec![
+                (1, 1, "".to_string()),
+                (1, 0, "Run".to_string()),
+                (2, 0, "References".to_string()),
+            ]
+        );
+
+        // A resolved lens keeps its place, even after another response of a
+        // different plugin
+        let id = CodeLensId {
+            plugin_id: a,
+            index: 1,
+        };
+        assert!(lenses.start_resolve(id));
+        assert!(!lenses.start_resolve(id));
+        lenses.set(b, vec![]);
+        lenses.resolve(id, code_lens(0, Some("Debug")));
+        assert_eq!(
+            titles(&lenses, 0..20),
+            vec![(1, 1, "Debug".to_string()), (1, 0, "Run".to_string())]
+        );
+
+        // A lens from an older response isn't applied to a different one
+        lenses.set(a, vec![(5, code_lens(3, None))]);
+        lenses.resolve(
+            CodeLensId {
+                plugin_id: a,
+                index: 0,
+            },
+            code_lens(0, Some("Debug")),
+        );
+        assert_eq!(titles(&lenses, 0..20), vec![(1, 0, "".to_string())]);
+        assert!(lenses.start_resolve(CodeLensId {
+            plugin_id: a,
+            index: 0
+        }));
+    }
+
+    #[test]
+    fn test_code_lenses_apply_delta() {
+        let mut lenses = CodeLenses::default();
+        lenses.set(PluginId(1), vec![(4, code_lens(1, Some("Run")))]);
+        let delta: Delta<RopeInfo> =
+            Delta::simple_edit(Interval::new(0, 0), Rope::from("ab\n"), 10);
+        lenses.apply_delta(&delta);
+        assert!(titles(&lenses, 0..7).is_empty());
+        assert_eq!(titles(&lenses, 7..8), vec![(1, 0, "Run".to_string())]);
+    }
+
     #[test]
     fn test_lens_metric() {
         let mut builder = LensBuilder::new();
@@ -35,7 +35,7 @@
     util::RopeProvider,
 };
 use crate::{
-    buffer::rope_text::RopeText,
+    buffer::rope_text::{RopeText, RopeTextRef},
     language::{self, LapceLanguage},
     lens::{Lens, LensBuilder},
     style::SCOPES,
@@ -1093,6 +1093,40 @@ pub fn sticky_headers(&self, offset: usize) -> Option<Vec<usize>> {
         Some(offsets)
     }
 
+    /// Line ranges `(start_line, end_line)` of the nodes spanning multiple lines,
+    /// for use as folding ranges when no language server provides them.
+    /// A last line starting with a closing bracket is left out of the range so
+    /// that it stays visible when folded. Only the largest range starting on
+    /// each line is kept.
+    pub fn folding_ranges(&self) -> Option<Vec<(usize, usize)>> {
+        let tree = self.layers.as_ref()?.try_tree()?;
+        let mut ranges: HashMap<usize, usize> = HashMap::new();
+        let text = RopeTextRef::new(&self.text);
+        let mut cursor = tree.walk();
+        // Skip the root node, as folding the whole file isn't useful
+        if !cursor.goto_first_child() {
+            return Some(Vec::new());
+        }
+        loop {
+            let node = cursor.node();
+            if let Some((start_line, end_line)) =
+                fold_lines(&text, node.start_byte(), node.end_byte())
+            {
+                let end = ranges.entry(start_line).or_insert(end_line);
+                *end = (*end).max(end_line);
+            }
+
+            if cursor.goto_first_child() {
+                continue;
+            }
+            while !cursor.goto_next_sibling() {
+                if !cursor.goto_parent() || cursor.node() == tree.root_node() {
+                    return Some(ranges.into_iter().sorted().collect());
+                }
+            }
+        }
+    }
+
     pub fn find_enclosing_parentheses(
         &self,
         offset: usize,
@@ -1166,10 +1200,55 @@ pub fn find_enclosing_pair(&self, offset: usize) -> Option<(usize, usize)> {
     }
 }
 
+/// The lines a node spanning `start..end` folds, leaving the line with its
+/// closing bracket shown, or `None` if it only spans one line.
+fn fold_lines(
+    text: &RopeTextRef,
+    start: usize,
+    end: usize,
+) -> Option<(usize, usize)> {
+    let start_line = text.line_of_offset(start);
+    // Nodes such as line comments end after the newline
+    let end = end.saturating_sub(1).max(start);
+    let mut end_line = text.line_of_offset(end);
+    let closing_line = text
+        .line_content(end_line)
+        .trim_start()
+        .starts_with(['}', ')', ']']);
+    if closing_line && end_line > start_line + 1 {
+        end_line -= 1;
+    }
+    (end_line > start_line).then_some((start_line, end_line))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_fold_lines() {
+        let rope = Rope::from("fn a() {\n    b();\n}\n// c\nfn d() {}\n");
+        let text = RopeTextRef::new(&rope);
+        // The body of `a` leaves its closing bracket shown
+        assert_eq!(Some((0, 1)), fold_lines(&text, 0, 20));
+        // A line comment ends after its newline
+        assert_eq!(None, fold_lines(&text, 20, 25));
+        assert_eq!(None, fold_lines(&text, 25, 34));
+
+        let rope = Rope::from("fn a() {\n}\n");
+        let text = RopeTextRef::new(&rope);
+        assert_eq!(Some((0, 1)), fold_lines(&text, 0, 10));
+    }
+
+    #[cfg(feature = "lang-rust")]
+    #[test]
+    fn test_folding_ranges() {
+        let code = "fn a() {\n    if b {\n        c();\n    }\n}\n\nfn d() {}\n";
+        let mut syntax = Syntax::from_language(LapceLanguage::Rust);
+        syntax.parse(1, Rope::from(code), None);
+        assert_eq!(Some(vec![(0, 3), (1, 2)]), syntax.folding_ranges());
+    }
+
     #[test]
     fn test_lens() {
         let lens = Syntax::lens_from_normal_lines(5, 25, 2, &[4]);
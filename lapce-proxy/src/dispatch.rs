@@ -11,7 +11,7 @@
 };
 
 use alacritty_terminal::{event::WindowSize, event_loop::Msg};
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, Result};
 use crossbeam_channel::Sender;
 use git2::ErrorCode::NotFound;
 use git2::{build::CheckoutBuilder, DiffOptions, Oid, Repository};
@@ -24,7 +24,7 @@
     file::FileNodeItem,
     proxy::{
         ProxyHandler, ProxyNotification, ProxyRequest, ProxyResponse,
-        ProxyRpcHandler, SearchMatch,
+        ProxyRpcHandler, ReplacedFile, SearchMatch,
     },
     source_control::{DiffInfo, FileDiff},
     style::{LineStyle, SemanticStyles},
@@ -33,13 +33,31 @@
 };
 use lapce_xi_rope::Rope;
 use lsp_types::{
-    MessageType, Position, Range, ShowMessageParams, TextDocumentItem, Url,
+    FileChangeType, FileEvent, MessageType, Position, Range, ShowMessageParams,
+    TextDocumentItem, TextEdit, Url,
 };
 use parking_lot::Mutex;
 
 use crate::{
-    buffer::{get_mod_time, load_file, Buffer},
-    plugin::{catalog::PluginCatalog, PluginCatalogRpcHandler},
+    buffer::{get_mod_time, load_file, read_path_to_string, Buffer},
+    git::{
+        git_blame, git_commit_template, git_conflicted_files, git_create_branch,
+        git_delete_branch, git_fetch, git_file_index, git_file_repository,
+        git_file_revision, git_head_message, git_log, git_merge, git_merge_heads,
+        git_pull, git_push, git_rename_branch, git_stage_files, git_stage_hunk,
+        git_stash_apply, git_stash_drop, git_stash_list, git_stash_pop,
+        git_stash_push, git_unstage_files, git_unstage_hunk, git_upstream_status,
+        repo_path, sign_off_message, RepositoryDirs,
+    },
+    plugin::{
+        catalog::PluginCatalog,
+        dap::{cargo_executable, list_processes},
+        PluginCatalogRpcHandler,
+    },
+    search::{
+        build_search_regex, line_replacements, matches_by_line, replace_in_content,
+        SearchFilter,
+    },
     terminal::{Terminal, TerminalSender},
     watcher::{FileWatcher, Notify, WatchToken},
 };
@@ -49,6 +67,12 @@
 
 pub struct Dispatcher {
     workspace: Option<PathBuf>,
+    /// The repository chosen in the source control panel, when there are more
+    /// than one in the workspace
+    git_repository: Option<PathBuf>,
+    /// The repositories in the workspace, which are looked for again when the
+    /// file watcher sees folders being added or removed
+    repository_dirs: RepositoryDirs,
     pub proxy_rpc: ProxyRpcHandler,
     core_rpc: CoreRpcHandler,
     catalog_rpc: PluginCatalogRpcHandler,
@@ -78,6 +102,8 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                     self.workspace.clone(),
                     self.core_rpc.clone(),
                     self.proxy_rpc.clone(),
+                    self.catalog_rpc.clone(),
+                    self.repository_dirs.clone(),
                 ));
                 if let Some(workspace) = self.workspace.as_ref() {
                     self.file_watcher
@@ -137,6 +163,17 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
             } => {
                 self.catalog_rpc.signature_help(request_id, &path, position);
             }
+            GetCodeLens { path, rev } => {
+                self.catalog_rpc.get_code_lens(&path, rev);
+            }
+            WorkspaceEditApplied {
+                id,
+                applied,
+                failure_reason,
+            } => {
+                self.catalog_rpc
+                    .workspace_edit_applied(id, applied, failure_reason);
+            }
             Shutdown {} => {
                 self.catalog_rpc.shutdown();
                 for (_, sender) in self.terminals.iter() {
@@ -156,6 +193,30 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                     buffer.rope.clone(),
                 );
             }
+            GlobalReplaceUndo { files } => {
+                for (path, file) in files {
+                    // Leave the files which were changed since the replace
+                    // alone, rather than overwriting those changes
+                    let result = read_path_to_string(&path).and_then(|content| {
+                        if content != file.replaced {
+                            return Err(anyhow!(
+                                "the file was changed since the replace"
+                            ));
+                        }
+                        fs::write(&path, file.original)?;
+                        Ok(())
+                    });
+                    if let Err(e) = result {
+                        self.core_rpc.show_message(
+                            "Undo Replace failure".to_owned(),
+                            ShowMessageParams {
+                                typ: MessageType::ERROR,
+                                message: format!("{}: {e}", path.display()),
+                            },
+                        );
+                    }
+                }
+            }
             UpdatePluginConfigs { configs } => {
                 let _ = self.catalog_rpc.update_plugin_configs(configs);
             }
@@ -219,8 +280,13 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
             DapStart {
                 config,
                 breakpoints,
+                global_breakpoints,
             } => {
-                let _ = self.catalog_rpc.dap_start(config, breakpoints);
+                let _ = self.catalog_rpc.dap_start(
+                    config,
+                    breakpoints,
+                    global_breakpoints,
+                );
             }
             DapProcessId {
                 dap_id,
@@ -253,8 +319,13 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
             DapRestart {
                 dap_id,
                 breakpoints,
+                global_breakpoints,
             } => {
-                let _ = self.catalog_rpc.dap_restart(dap_id, breakpoints);
+                let _ = self.catalog_rpc.dap_restart(
+                    dap_id,
+                    breakpoints,
+                    global_breakpoints,
+                );
             }
             DapSetBreakpoints {
                 dap_id,
@@ -265,6 +336,19 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                     self.catalog_rpc
                         .dap_set_breakpoints(dap_id, path, breakpoints);
             }
+            DapSetFunctionBreakpoints {
+                dap_id,
+                breakpoints,
+            } => {
+                let _ = self
+                    .catalog_rpc
+                    .dap_set_function_breakpoints(dap_id, breakpoints);
+            }
+            DapSetExceptionBreakpoints { dap_id, filters } => {
+                let _ = self
+                    .catalog_rpc
+                    .dap_set_exception_breakpoints(dap_id, filters);
+            }
             InstallVolt { volt } => {
                 let catalog_rpc = self.catalog_rpc.clone();
                 let _ = catalog_rpc.install_volt(volt);
@@ -281,9 +365,14 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
             EnableVolt { volt } => {
                 let _ = self.catalog_rpc.enable_volt(volt);
             }
-            GitCommit { message, diffs } => {
-                if let Some(workspace) = self.workspace.as_ref() {
-                    match git_commit(workspace, &message, diffs) {
+            GitCommit {
+                message,
+                diffs,
+                amend,
+                sign_off,
+            } => {
+                if let Some(repository) = self.git_repository() {
+                    match git_commit(&repository, &message, diffs, amend, sign_off) {
                         Ok(()) => (),
                         Err(e) => {
                             self.core_rpc.show_message(
@@ -298,27 +387,21 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                 }
             }
             GitCheckout { reference } => {
-                if let Some(workspace) = self.workspace.as_ref() {
-                    match git_checkout(workspace, &reference) {
+                if let Some(repository) = self.git_repository() {
+                    match git_checkout(&repository, &reference) {
                         Ok(()) => (),
                         Err(e) => eprintln!("{e:?}"),
                     }
                 }
             }
             GitDiscardFilesChanges { files } => {
-                if let Some(workspace) = self.workspace.as_ref() {
-                    match git_discard_files_changes(
-                        workspace,
-                        files.iter().map(AsRef::as_ref),
-                    ) {
-                        Ok(()) => (),
-                        Err(e) => eprintln!("{e:?}"),
-                    }
+                if let Err(e) = git_discard_files_changes(&files) {
+                    eprintln!("{e:?}");
                 }
             }
             GitDiscardWorkspaceChanges {} => {
-                if let Some(workspace) = self.workspace.as_ref() {
-                    match git_discard_workspace_changes(workspace) {
+                if let Some(repository) = self.git_repository() {
+                    match git_discard_workspace_changes(&repository) {
                         Ok(()) => (),
                         Err(e) => eprintln!("{e:?}"),
                     }
@@ -332,6 +415,33 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                     }
                 }
             }
+            GitSetRepository { path } => {
+                self.git_repository = path;
+            }
+            GitStageFiles { files } => {
+                if let Err(e) = git_stage_files(&files) {
+                    eprintln!("{e:?}");
+                }
+            }
+            GitUnstageFiles { files } => {
+                if let Err(e) = git_unstage_files(&files) {
+                    eprintln!("{e:?}");
+                }
+            }
+            GitStageHunk {
+                path,
+                hunk,
+                content,
+            } => {
+                if let Err(e) = git_stage_hunk(&path, &hunk, &content) {
+                    eprintln!("{e:?}");
+                }
+            }
+            GitUnstageHunk { path, hunk } => {
+                if let Err(e) = git_unstage_hunk(&path, &hunk) {
+                    eprintln!("{e:?}");
+                }
+            }
         }
     }
 
@@ -356,19 +466,162 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                 );
             }
             BufferHead { path } => {
-                let result = if let Some(workspace) = self.workspace.as_ref() {
-                    let result = file_get_head(workspace, &path);
-                    if let Ok((_blob_id, content)) = result {
-                        Ok(ProxyResponse::BufferHeadResponse {
-                            version: "head".to_string(),
-                            content,
+                let result = if let Ok(content) = git_file_index(&path) {
+                    Ok(ProxyResponse::BufferHeadResponse {
+                        version: "head".to_string(),
+                        content,
+                    })
+                } else {
+                    Err(RpcError {
+                        code: 0,
+                        message: "can't get file head".to_string(),
+                    })
+                };
+                self.respond_rpc(id, result);
+            }
+            GitFileRevision { path, revision } => {
+                let result = if let Ok(content) = git_file_revision(&path, &revision)
+                {
+                    Ok(ProxyResponse::BufferHeadResponse {
+                        version: revision,
+                        content,
+                    })
+                } else {
+                    Err(RpcError {
+                        code: 0,
+                        message: "can't get file revision".to_string(),
+                    })
+                };
+                self.respond_rpc(id, result);
+            }
+            GitBlame { path } => {
+                // Blame the content of the buffer rather than the file on disk,
+                // so the lines match what the editor shows
+                let content = self
+                    .buffers
+                    .get(&path)
+                    .map(|buffer| buffer.rope.to_string());
+                let proxy_rpc = self.proxy_rpc.clone();
+                thread::spawn(move || {
+                    let result = git_blame(&path, content.as_deref())
+                        .map(|hunks| ProxyResponse::GitBlameResponse { hunks })
+                        .map_err(|e| RpcError {
+                            code: 0,
+                            message: e.to_string(),
+                        });
+                    proxy_rpc.handle_response(id, result);
+                });
+            }
+            GitLog { path, skip, limit } => {
+                // The commits of a file are the ones of the repository it's in,
+                // like a submodule, which needn't be the chosen repository
+                let repository = match path.as_deref() {
+                    Some(path) => git_file_repository(path),
+                    None => match self.git_repository() {
+                        Some(repository) => {
+                            Repository::discover(repository).map_err(Into::into)
+                        }
+                        None => Err(anyhow!("no workspace set")),
+                    },
+                };
+                let repository = match repository {
+                    Ok(repository) => repository,
+                    Err(e) => {
+                        self.respond_rpc(
+                            id,
+                            Err(RpcError {
+                                code: 0,
+                                message: e.to_string(),
+                            }),
+                        );
+                        return;
+                    }
+                };
+                let proxy_rpc = self.proxy_rpc.clone();
+                // Walking a long history is slow, so it's done on another thread
+                thread::spawn(move || {
+                    let result = git_log(&repository, path.as_deref(), skip, limit)
+                        .map(|commits| ProxyResponse::GitLogResponse { commits })
+                        .map_err(|e| RpcError {
+                            code: 0,
+                            message: e.to_string(),
+                        });
+                    proxy_rpc.handle_response(id, result);
+                });
+            }
+            GitCommitDiffs { commit } => {
+                let result = if let Some(repository) = self.git_repository() {
+                    git_commit_diffs(&repository, &commit)
+                        .map(|diffs| ProxyResponse::GitCommitDiffsResponse { diffs })
+                        .map_err(|e| RpcError {
+                            code: 0,
+                            message: e.to_string(),
                         })
-                    } else {
-                        Err(RpcError {
+                } else {
+                    Err(RpcError {
+                        code: 0,
+                        message: "no workspace set".to_string(),
+                    })
+                };
+                self.respond_rpc(id, result);
+            }
+            GitFetch {} => {
+                self.git_request(id, git_fetch);
+            }
+            GitPull { rebase } => {
+                self.git_request(id, move |workspace| git_pull(workspace, rebase));
+            }
+            GitPush {} => {
+                self.git_request(id, git_push);
+            }
+            GitCreateBranch { name, start } => {
+                self.git_request(id, move |workspace| {
+                    git_create_branch(workspace, &name, start.as_deref())
+                });
+            }
+            GitRenameBranch { branch, name } => {
+                self.git_request(id, move |workspace| {
+                    git_rename_branch(workspace, &branch, &name)
+                });
+            }
+            GitDeleteBranch { branch, force } => {
+                self.git_request(id, move |workspace| {
+                    git_delete_branch(workspace, &branch, force)
+                });
+            }
+            GitMerge { reference } => {
+                self.git_request(id, move |workspace| {
+                    git_merge(workspace, &reference)
+                });
+            }
+            GitStashList {} => {
+                let result = if let Some(repository) = self.git_repository() {
+                    git_stash_list(&repository)
+                        .map(|stashes| ProxyResponse::GitStashListResponse {
+                            stashes,
+                        })
+                        .map_err(|e| RpcError {
                             code: 0,
-                            message: "can't get file head".to_string(),
+                            message: e.to_string(),
+                        })
+                } else {
+                    Err(RpcError {
+                        code: 0,
+                        message: "no workspace set".to_string(),
+                    })
+                };
+                self.respond_rpc(id, result);
+            }
+            GitCommitTemplate {} => {
+                let result = if let Some(repository) = self.git_repository() {
+                    git_commit_template(&repository)
+                        .map(|template| ProxyResponse::GitCommitTemplateResponse {
+                            template,
+                        })
+                        .map_err(|e| RpcError {
+                            code: 0,
+                            message: e.to_string(),
                         })
-                    }
                 } else {
                     Err(RpcError {
                         code: 0,
@@ -377,15 +630,68 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                 };
                 self.respond_rpc(id, result);
             }
+            GitHeadMessage {} => {
+                let result = if let Some(repository) = self.git_repository() {
+                    git_head_message(&repository)
+                        .map(|message| ProxyResponse::GitHeadMessageResponse {
+                            message,
+                        })
+                        .map_err(|e| RpcError {
+                            code: 0,
+                            message: e.to_string(),
+                        })
+                } else {
+                    Err(RpcError {
+                        code: 0,
+                        message: "no workspace set".to_string(),
+                    })
+                };
+                self.respond_rpc(id, result);
+            }
+            GitStashPush { message } => {
+                self.git_request(id, move |workspace| {
+                    git_stash_push(workspace, message.as_deref())
+                });
+            }
+            GitStashPop { index } => {
+                self.git_request(id, move |workspace| {
+                    git_stash_pop(workspace, index)
+                });
+            }
+            GitStashApply { index } => {
+                self.git_request(id, move |workspace| {
+                    git_stash_apply(workspace, index)
+                });
+            }
+            GitStashDrop { index } => {
+                self.git_request(id, move |workspace| {
+                    git_stash_drop(workspace, index)
+                });
+            }
             GlobalSearch {
                 pattern,
                 case_sensitive,
                 whole_word,
                 is_regex,
+                include,
+                exclude,
             } => {
                 static WORKER_ID: AtomicU64 = AtomicU64::new(0);
                 let our_id = WORKER_ID.fetch_add(1, Ordering::SeqCst) + 1;
 
+                let filter = match SearchFilter::new(&include, &exclude) {
+                    Ok(filter) => filter,
+                    Err(e) => {
+                        self.respond_rpc(
+                            id,
+                            Err(RpcError {
+                                code: 0,
+                                message: e.to_string(),
+                            }),
+                        );
+                        return;
+                    }
+                };
                 let workspace = self.workspace.clone();
                 let buffers = self
                     .buffers
@@ -397,6 +703,19 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
 
                 // Perform the search on another thread to avoid blocking the proxy thread
                 thread::spawn(move || {
+                    // Skip the excluded folders while walking, rather than
+                    // walking through everything inside of them
+                    let walk = |path: &PathBuf| {
+                        let filter = filter.clone();
+                        let workspace = workspace.clone();
+                        ignore::WalkBuilder::new(path)
+                            .filter_entry(move |entry| {
+                                !filter
+                                    .is_excluded(entry.path(), workspace.as_deref())
+                            })
+                            .build()
+                            .flatten()
+                    };
                     proxy_rpc.handle_response(
                         id,
                         search_in_path(
@@ -404,13 +723,12 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                             &WORKER_ID,
                             workspace
                                 .iter()
-                                .flat_map(|w| ignore::Walk::new(w).flatten())
-                                .chain(
-                                    buffers.iter().flat_map(|p| {
-                                        ignore::Walk::new(p).flatten()
-                                    }),
-                                )
-                                .map(|p| p.into_path()),
+                                .flat_map(walk)
+                                .chain(buffers.iter().flat_map(walk))
+                                .map(|p| p.into_path())
+                                .filter(|p| {
+                                    filter.is_match(p, workspace.as_deref())
+                                }),
                             &pattern,
                             case_sensitive,
                             whole_word,
@@ -419,6 +737,26 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                     );
                 });
             }
+            GlobalReplace {
+                pattern,
+                case_sensitive,
+                whole_word,
+                is_regex,
+                replacement,
+                matches,
+            } => {
+                let result = global_replace(
+                    &self.buffers,
+                    &self.core_rpc,
+                    &pattern,
+                    case_sensitive,
+                    whole_word,
+                    is_regex,
+                    &replacement,
+                    matches,
+                );
+                self.respond_rpc(id, result);
+            }
             CompletionResolve {
                 plugin_id,
                 completion_item,
@@ -465,17 +803,56 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                     },
                 );
             }
-            GitGetRemoteFileUrl { file } => {
-                if let Some(workspace) = self.workspace.as_ref() {
-                    match git_get_remote_file_url(workspace, &file) {
-                        Ok(s) => self.proxy_rpc.handle_response(
-                            id,
-                            Ok(ProxyResponse::GitGetRemoteFileUrl { file_url: s }),
-                        ),
-                        Err(e) => eprintln!("{e:?}"),
-                    }
-                }
+            GetImplementation { path, position } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.get_implementation(
+                    &path,
+                    position,
+                    move |_, result| {
+                        let result = result.map(|implementation| {
+                            ProxyResponse::GetImplementationResponse {
+                                implementation,
+                            }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
+            GetDeclaration { path, position } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.get_declaration(
+                    &path,
+                    position,
+                    move |_, result| {
+                        let result = result.map(|declaration| {
+                            ProxyResponse::GetDeclarationResponse { declaration }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
             }
+            GetDocumentHighlight { path, position } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.get_document_highlight(
+                    &path,
+                    position,
+                    move |_, result| {
+                        let result = result.map(|highlights| {
+                            ProxyResponse::GetDocumentHighlightResponse {
+                                highlights: highlights.unwrap_or_default(),
+                            }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
+            GitGetRemoteFileUrl { file } => match git_get_remote_file_url(&file) {
+                Ok(s) => self.proxy_rpc.handle_response(
+                    id,
+                    Ok(ProxyResponse::GitGetRemoteFileUrl { file_url: s }),
+                ),
+                Err(e) => eprintln!("{e:?}"),
+            },
             GetDefinition {
                 request_id,
                 path,
@@ -530,6 +907,17 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                         proxy_rpc.handle_response(id, result);
                     });
             }
+            GetFoldingRange { path } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.get_folding_range(&path, move |_, result| {
+                    let result = result.map(|ranges| {
+                        ProxyResponse::GetFoldingRangeResponse {
+                            ranges: ranges.unwrap_or_default(),
+                        }
+                    });
+                    proxy_rpc.handle_response(id, result);
+                });
+            }
             GetInlineCompletions {
                 path,
                 position,
@@ -642,6 +1030,37 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                         proxy_rpc.handle_response(id, result);
                     });
             }
+            GetDocumentRangeFormatting { path, range } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.get_document_range_formatting(
+                    &path,
+                    range,
+                    move |_, result| {
+                        let result = result.map(|edits| {
+                            ProxyResponse::GetDocumentFormatting {
+                                edits: edits.unwrap_or_default(),
+                            }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
+            GetDocumentOnTypeFormatting { path, position, ch } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.get_document_on_type_formatting(
+                    &path,
+                    position,
+                    ch,
+                    move |_, result| {
+                        let result = result.map(|edits| {
+                            ProxyResponse::GetDocumentFormatting {
+                                edits: edits.unwrap_or_default(),
+                            }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
             PrepareRename { path, position } => {
                 let proxy_rpc = self.proxy_rpc.clone();
                 self.catalog_rpc.prepare_rename(
@@ -1004,6 +1423,127 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                     },
                 );
             }
+            PrepareCallHierarchy { path, position } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.prepare_call_hierarchy(
+                    &path,
+                    position,
+                    move |plugin_id, result| {
+                        let result = result.map(|items| {
+                            ProxyResponse::PrepareCallHierarchyResponse {
+                                plugin_id,
+                                items: items.unwrap_or_default(),
+                            }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
+            CallHierarchyIncomingCalls { plugin_id, item } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.call_hierarchy_incoming_calls(
+                    plugin_id,
+                    item,
+                    move |result| {
+                        let result = result.map(|calls| {
+                            ProxyResponse::CallHierarchyIncomingCallsResponse {
+                                calls: calls.unwrap_or_default(),
+                            }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
+            CallHierarchyOutgoingCalls { plugin_id, item } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.call_hierarchy_outgoing_calls(
+                    plugin_id,
+                    item,
+                    move |result| {
+                        let result = result.map(|calls| {
+                            ProxyResponse::CallHierarchyOutgoingCallsResponse {
+                                calls: calls.unwrap_or_default(),
+                            }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
+            PrepareTypeHierarchy { path, position } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.prepare_type_hierarchy(
+                    &path,
+                    position,
+                    move |plugin_id, result| {
+                        let result = result.map(|items| {
+                            ProxyResponse::PrepareTypeHierarchyResponse {
+                                plugin_id,
+                                items: items.unwrap_or_default(),
+                            }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
+            TypeHierarchySupertypes { plugin_id, item } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.type_hierarchy_supertypes(
+                    plugin_id,
+                    item,
+                    move |result| {
+                        let result = result.map(|items| {
+                            ProxyResponse::TypeHierarchyItemsResponse {
+                                items: items.unwrap_or_default(),
+                            }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
+            TypeHierarchySubtypes { plugin_id, item } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.type_hierarchy_subtypes(
+                    plugin_id,
+                    item,
+                    move |result| {
+                        let result = result.map(|items| {
+                            ProxyResponse::TypeHierarchyItemsResponse {
+                                items: items.unwrap_or_default(),
+                            }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
+            CodeLensResolve {
+                plugin_id,
+                code_lens,
+            } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.code_lens_resolve(
+                    plugin_id,
+                    code_lens,
+                    move |result| {
+                        let result = result.map(|code_lens| {
+                            ProxyResponse::CodeLensResolveResponse { code_lens }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
+            ExecuteCommand { plugin_id, command } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.execute_command(
+                    plugin_id,
+                    command,
+                    move |result| {
+                        let result = result.map(|result| {
+                            ProxyResponse::ExecuteCommandResponse { result }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
             DapVariable { dap_id, reference } => {
                 let proxy_rpc = self.proxy_rpc.clone();
                 self.catalog_rpc
@@ -1028,6 +1568,113 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                         );
                     });
             }
+            DapEvaluate {
+                dap_id,
+                expression,
+                frame_id,
+                context,
+            } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.dap_evaluate(
+                    dap_id,
+                    expression,
+                    frame_id,
+                    context,
+                    move |result| {
+                        proxy_rpc.handle_response(
+                            id,
+                            result.map(|result| {
+                                ProxyResponse::DapEvaluateResponse { result }
+                            }),
+                        );
+                    },
+                );
+            }
+            DapSetVariable {
+                dap_id,
+                variables_reference,
+                name,
+                value,
+            } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.dap_set_variable(
+                    dap_id,
+                    variables_reference,
+                    name,
+                    value,
+                    move |result| {
+                        proxy_rpc.handle_response(
+                            id,
+                            result.map(|result| {
+                                ProxyResponse::DapSetVariableResponse { result }
+                            }),
+                        );
+                    },
+                );
+            }
+            DapSetExpression {
+                dap_id,
+                expression,
+                value,
+                frame_id,
+            } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.dap_set_expression(
+                    dap_id,
+                    expression,
+                    value,
+                    frame_id,
+                    move |result| {
+                        proxy_rpc.handle_response(
+                            id,
+                            result.map(|result| {
+                                ProxyResponse::DapSetExpressionResponse { result }
+                            }),
+                        );
+                    },
+                );
+            }
+            ListProcesses {} => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                // Reading every process can be slow, so it's done on another thread
+                thread::spawn(move || {
+                    let result = list_processes()
+                        .map(|processes| ProxyResponse::ListProcessesResponse {
+                            processes,
+                        })
+                        .map_err(|e| RpcError {
+                            code: 0,
+                            message: e.to_string(),
+                        });
+                    proxy_rpc.handle_response(id, result);
+                });
+            }
+            CargoExecutable { args, cwd, env } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                let core_rpc = self.core_rpc.clone();
+                // Building can take a while, so don't block the proxy thread
+                thread::spawn(move || {
+                    let result =
+                        cargo_executable(&args, cwd.as_deref(), env.as_ref())
+                            .map(|path| ProxyResponse::CargoExecutableResponse {
+                                path,
+                            })
+                            .map_err(|e| {
+                                core_rpc.show_message(
+                                    "Debug failure".to_owned(),
+                                    ShowMessageParams {
+                                        typ: MessageType::ERROR,
+                                        message: e.to_string(),
+                                    },
+                                );
+                                RpcError {
+                                    code: 0,
+                                    message: e.to_string(),
+                                }
+                            });
+                    proxy_rpc.handle_response(id, result);
+                });
+            }
         }
     }
 }
@@ -1041,6 +1688,8 @@ pub fn new(core_rpc: CoreRpcHandler, proxy_rpc: ProxyRpcHandler) -> Self {
 
         Self {
             workspace: None,
+            git_repository: None,
+            repository_dirs: RepositoryDirs::default(),
             proxy_rpc,
             core_rpc,
             catalog_rpc: plugin_rpc,
@@ -1055,14 +1704,65 @@ pub fn new(core_rpc: CoreRpcHandler, proxy_rpc: ProxyRpcHandler) -> Self {
     fn respond_rpc(&self, id: RequestId, result: Result<ProxyResponse, RpcError>) {
         self.proxy_rpc.handle_response(id, result);
     }
+
+    /// The folder of the repository which git operations that aren't about a
+    /// file work on, which is the one the workspace is in unless another one
+    /// was chosen
+    fn git_repository(&self) -> Option<PathBuf> {
+        self.git_repository
+            .clone()
+            .or_else(|| self.workspace.clone())
+    }
+
+    /// Run a git operation which changes the repository on another thread, as
+    /// talking to a remote or checking out a tree can take a while. The diff
+    /// info is sent again afterwards, because the branches, the working tree
+    /// and the ahead and behind counts can all change.
+    fn git_request(
+        &self,
+        id: RequestId,
+        f: impl FnOnce(&Path) -> Result<()> + Send + 'static,
+    ) {
+        let (Some(workspace), Some(repository)) =
+            (self.workspace.clone(), self.git_repository())
+        else {
+            self.respond_rpc(
+                id,
+                Err(RpcError {
+                    code: 0,
+                    message: "no workspace set".to_string(),
+                }),
+            );
+            return;
+        };
+        let proxy_rpc = self.proxy_rpc.clone();
+        let core_rpc = self.core_rpc.clone();
+        let repository_dirs = self.repository_dirs.clone();
+        thread::spawn(move || {
+            let result =
+                f(&repository)
+                    .map(|_| ProxyResponse::Success {})
+                    .map_err(|e| RpcError {
+                        code: 0,
+                        message: e.to_string(),
+                    });
+            proxy_rpc.handle_response(id, result);
+            let repositories = git_diff_new(&workspace, &repository_dirs);
+            if !repositories.is_empty() {
+                core_rpc.diff_info(repositories);
+            }
+        });
+    }
 }
 
 struct FileWatchNotifier {
     core_rpc: CoreRpcHandler,
     proxy_rpc: ProxyRpcHandler,
+    catalog_rpc: PluginCatalogRpcHandler,
     workspace: Option<PathBuf>,
+    repository_dirs: RepositoryDirs,
     workspace_fs_change_handler: Arc<Mutex<Option<Sender<bool>>>>,
-    last_diff: Arc<Mutex<DiffInfo>>,
+    last_diff: Arc<Mutex<Vec<DiffInfo>>>,
 }
 
 impl Notify for FileWatchNotifier {
@@ -1076,22 +1776,28 @@ fn new(
         workspace: Option<PathBuf>,
         core_rpc: CoreRpcHandler,
         proxy_rpc: ProxyRpcHandler,
+        catalog_rpc: PluginCatalogRpcHandler,
+        repository_dirs: RepositoryDirs,
     ) -> Self {
         let notifier = Self {
             workspace,
             core_rpc,
             proxy_rpc,
+            catalog_rpc,
+            repository_dirs,
             workspace_fs_change_handler: Arc::new(Mutex::new(None)),
-            last_diff: Arc::new(Mutex::new(DiffInfo::default())),
+            last_diff: Arc::new(Mutex::new(Vec::new())),
         };
 
         if let Some(workspace) = notifier.workspace.clone() {
             let core_rpc = notifier.core_rpc.clone();
             let last_diff = notifier.last_diff.clone();
+            let repository_dirs = notifier.repository_dirs.clone();
             thread::spawn(move || {
-                if let Some(diff) = git_diff_new(&workspace) {
-                    core_rpc.diff_info(diff.clone());
-                    *last_diff.lock() = diff;
+                let repositories = git_diff_new(&workspace, &repository_dirs);
+                if !repositories.is_empty() {
+                    core_rpc.diff_info(repositories.clone());
+                    *last_diff.lock() = repositories;
                 }
             });
         }
@@ -1119,6 +1825,11 @@ fn handle_open_file_fs_event(&self, event: notify::Event) {
     }
 
     fn handle_workspace_fs_event(&self, event: notify::Event) {
+        let changes = watched_file_events(&event);
+        if !changes.is_empty() {
+            self.catalog_rpc.did_change_watched_files(changes);
+        }
+
         let explorer_change = match &event.kind {
             notify::EventKind::Create(_)
             | notify::EventKind::Remove(_)
@@ -1126,6 +1837,9 @@ fn handle_workspace_fs_event(&self, event: notify::Event) {
             notify::EventKind::Modify(_) => false,
             _ => return,
         };
+        if changes_repositories(&event, &self.repository_dirs) {
+            self.repository_dirs.clear();
+        }
 
         let mut handler = self.workspace_fs_change_handler.lock();
         if let Some(sender) = handler.as_mut() {
@@ -1145,6 +1859,7 @@ fn handle_workspace_fs_event(&self, event: notify::Event) {
         let core_rpc = self.core_rpc.clone();
         let workspace = self.workspace.clone().unwrap();
         let last_diff = self.last_diff.clone();
+        let repository_dirs = self.repository_dirs.clone();
         thread::spawn(move || {
             thread::sleep(Duration::from_millis(500));
 
@@ -1162,18 +1877,102 @@ fn handle_workspace_fs_event(&self, event: notify::Event) {
             if explorer_change {
                 core_rpc.workspace_file_change();
             }
-            if let Some(diff) = git_diff_new(&workspace) {
-                let mut last_diff = last_diff.lock();
-                if diff != *last_diff {
-                    core_rpc.diff_info(diff.clone());
-                    *last_diff = diff;
-                }
+            let repositories = git_diff_new(&workspace, &repository_dirs);
+            let mut last_diff = last_diff.lock();
+            if repositories != *last_diff {
+                core_rpc.diff_info(repositories.clone());
+                *last_diff = repositories;
             }
         });
         *handler = Some(sender);
     }
 }
 
+/// Whether the event can add or remove a repository, which is when a `.git`,
+/// a folder with one, or a folder a known repository is in is created,
+/// removed or renamed. Other files, like the backups written on every save,
+/// don't count.
+fn changes_repositories(
+    event: &notify::Event,
+    repository_dirs: &RepositoryDirs,
+) -> bool {
+    use notify::event::{EventKind, ModifyKind};
+
+    if !matches!(
+        event.kind,
+        EventKind::Create(_)
+            | EventKind::Remove(_)
+            | EventKind::Modify(ModifyKind::Name(_))
+    ) {
+        return false;
+    }
+    event.paths.iter().any(|path| {
+        if path.file_name().is_some_and(|name| name == ".git") {
+            return true;
+        }
+        if path.components().any(|c| c.as_os_str() == ".git") {
+            return false;
+        }
+        path.join(".git").exists() || repository_dirs.has_repository_in(path)
+    })
+}
+
+/// Convert a workspace fs event into the file events which are sent to the
+/// language servers that watch them
+fn watched_file_events(event: &notify::Event) -> Vec<FileEvent> {
+    use notify::event::{EventKind, ModifyKind, RenameMode};
+
+    let all_paths = |typ: FileChangeType| {
+        event
+            .paths
+            .iter()
+            .map(|path| (path, typ))
+            .collect::<Vec<_>>()
+    };
+    let changes = match &event.kind {
+        EventKind::Create(_) => all_paths(FileChangeType::CREATED),
+        EventKind::Remove(_) => all_paths(FileChangeType::DELETED),
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both))
+            if event.paths.len() == 2 =>
+        {
+            vec![
+                (&event.paths[0], FileChangeType::DELETED),
+                (&event.paths[1], FileChangeType::CREATED),
+            ]
+        }
+        EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+            all_paths(FileChangeType::DELETED)
+        }
+        EventKind::Modify(ModifyKind::Name(RenameMode::To)) => {
+            all_paths(FileChangeType::CREATED)
+        }
+        // The watcher couldn't tell which side of the rename the path is on
+        EventKind::Modify(ModifyKind::Name(_)) => event
+            .paths
+            .iter()
+            .map(|path| {
+                if path.exists() {
+                    (path, FileChangeType::CREATED)
+                } else {
+                    (path, FileChangeType::DELETED)
+                }
+            })
+            .collect(),
+        EventKind::Modify(ModifyKind::Metadata(_)) => Vec::new(),
+        EventKind::Modify(_) => all_paths(FileChangeType::CHANGED),
+        _ => Vec::new(),
+    };
+    changes
+        .into_iter()
+        .filter_map(|(path, typ)| {
+            Some(FileEvent {
+                uri: Url::from_file_path(path).ok()?,
+                typ,
+            })
+        })
+        .collect()
+}
+
 #[derive(Clone, Debug)]
 pub struct DiffHunk {
     pub old_start: u32,
@@ -1191,23 +1990,26 @@ fn git_init(workspace_path: &Path) -> Result<()> {
 }
 
 fn git_commit(
-    workspace_path: &Path,
+    repository_path: &Path,
     message: &str,
     diffs: Vec<FileDiff>,
+    amend: bool,
+    sign_off: bool,
 ) -> Result<()> {
-    let repo = Repository::discover(workspace_path)?;
+    let mut repo = Repository::discover(repository_path)?;
+    let merge_heads = git_merge_heads(&mut repo)?;
     let mut index = repo.index()?;
     for diff in diffs {
         match diff {
-            FileDiff::Modified(p) | FileDiff::Added(p) => {
-                index.add_path(p.strip_prefix(workspace_path)?)?;
+            FileDiff::Modified(p) | FileDiff::Added(p) | FileDiff::Conflicted(p) => {
+                index.add_path(repo_path(&repo, &p)?)?;
             }
             FileDiff::Renamed(a, d) => {
-                index.add_path(a.strip_prefix(workspace_path)?)?;
-                index.remove_path(d.strip_prefix(workspace_path)?)?;
+                index.add_path(repo_path(&repo, &a)?)?;
+                index.remove_path(repo_path(&repo, &d)?)?;
             }
             FileDiff::Deleted(p) => {
-                index.remove_path(p.strip_prefix(workspace_path)?)?;
+                index.remove_path(repo_path(&repo, &p)?)?;
             }
         }
     }
@@ -1215,34 +2017,62 @@ fn git_commit(
     let tree = index.write_tree()?;
     let tree = repo.find_tree(tree)?;
 
-    match repo.signature() {
-        Ok(signature) => {
-            let parents = repo
-                .head()
-                .and_then(|head| Ok(vec![head.peel_to_commit()?]))
-                .unwrap_or(vec![]);
-            let parents_refs = parents.iter().collect::<Vec<_>>();
-
-            repo.commit(
-                Some("HEAD"),
-                &signature,
-                &signature,
-                message,
-                &tree,
-                &parents_refs,
-            )?;
-            Ok(())
-        }
-        Err(e) => match e.code() {
-            NotFound => Err(anyhow!(
-                "No user.name and/or user.email configured for this git repository."
-            )),
-            _ => Err(anyhow!(
-                "Error while creating commit's signature: {}",
-                e.message()
-            )),
-        },
+    let signature = repo.signature().map_err(|e| match e.code() {
+        NotFound => anyhow!(
+            "No user.name and/or user.email configured for this git repository."
+        ),
+        _ => anyhow!("Error while creating commit's signature: {}", e.message()),
+    })?;
+
+    // Like git, the comment lines of a template are left out of the message
+    let mut message = git2::message_prettify(message, Some(b'#'))?;
+    if message.is_empty() {
+        return Err(anyhow!("The commit message is empty."));
+    }
+    if sign_off {
+        message = sign_off_message(
+            &message,
+            signature.name().unwrap_or_default(),
+            signature.email().unwrap_or_default(),
+        );
+    }
+
+    if amend {
+        // The author of the commit stays the same
+        let head = repo.head()?.peel_to_commit()?;
+        head.amend(
+            Some("HEAD"),
+            None,
+            Some(&signature),
+            None,
+            Some(&message),
+            Some(&tree),
+        )?;
+        return Ok(());
+    }
+
+    let mut parents = repo
+        .head()
+        .and_then(|head| Ok(vec![head.peel_to_commit()?]))
+        .unwrap_or(vec![]);
+    // Concluding a merge which stopped on conflicts
+    for id in &merge_heads {
+        parents.push(repo.find_commit(*id)?);
     }
+    let parents_refs = parents.iter().collect::<Vec<_>>();
+
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        &message,
+        &tree,
+        &parents_refs,
+    )?;
+    if !merge_heads.is_empty() {
+        repo.cleanup_state()?;
+    }
+    Ok(())
 }
 
 fn git_checkout(workspace_path: &Path, reference: &str) -> Result<()> {
@@ -1253,33 +2083,17 @@ fn git_checkout(workspace_path: &Path, reference: &str) -> Result<()> {
     Ok(())
 }
 
-fn git_discard_files_changes<'a>(
-    workspace_path: &Path,
-    files: impl Iterator<Item = &'a Path>,
-) -> Result<()> {
-    let repo = Repository::discover(workspace_path)?;
-
-    let mut checkout_b = CheckoutBuilder::new();
-    checkout_b.update_only(false).force();
-
-    let mut had_path = false;
+fn git_discard_files_changes(files: &[PathBuf]) -> Result<()> {
+    // The files can be in different repositories
     for path in files {
-        // Remove the workspace path so it is relative to the folder
-        if let Ok(path) = path.strip_prefix(workspace_path) {
-            had_path = true;
-            checkout_b.path(path);
-        }
+        let repo = git_file_repository(path)?;
+        let mut checkout_b = CheckoutBuilder::new();
+        checkout_b
+            .update_only(false)
+            .force()
+            .path(repo_path(&repo, path)?);
+        repo.checkout_index(None, Some(&mut checkout_b))?;
     }
-
-    if !had_path {
-        // If there we no paths then we do nothing
-        // because the default behavior of checkout builder is to select all files
-        // if it is not given a path
-        return Ok(());
-    }
-
-    repo.checkout_index(None, Some(&mut checkout_b))?;
-
     Ok(())
 }
 
@@ -1294,31 +2108,48 @@ fn git_discard_workspace_changes(workspace_path: &Path) -> Result<()> {
 }
 
 fn git_delta_format(
-    workspace_path: &Path,
+    workdir: &Path,
     delta: &git2::DiffDelta,
 ) -> Option<(git2::Delta, git2::Oid, PathBuf)> {
     match delta.status() {
         git2::Delta::Added | git2::Delta::Untracked => Some((
             git2::Delta::Added,
             delta.new_file().id(),
-            delta.new_file().path().map(|p| workspace_path.join(p))?,
+            delta.new_file().path().map(|p| workdir.join(p))?,
         )),
         git2::Delta::Deleted => Some((
             git2::Delta::Deleted,
             delta.old_file().id(),
-            delta.old_file().path().map(|p| workspace_path.join(p))?,
+            delta.old_file().path().map(|p| workdir.join(p))?,
         )),
         git2::Delta::Modified => Some((
             git2::Delta::Modified,
             delta.new_file().id(),
-            delta.new_file().path().map(|p| workspace_path.join(p))?,
+            delta.new_file().path().map(|p| workdir.join(p))?,
         )),
         _ => None,
     }
 }
 
-fn git_diff_new(workspace_path: &Path) -> Option<DiffInfo> {
-    let repo = Repository::discover(workspace_path).ok()?;
+fn git_diff_new(
+    workspace_path: &Path,
+    repository_dirs: &RepositoryDirs,
+) -> Vec<DiffInfo> {
+    let repos = repository_dirs.repositories(workspace_path);
+    let workdirs = repos
+        .iter()
+        .filter_map(|repo| repo.workdir())
+        .collect::<Vec<_>>();
+    repos
+        .iter()
+        .filter_map(|repo| git_repository_diff(repo, &workdirs))
+        .collect()
+}
+
+/// The branches and the changes of the repository. The other repositories in
+/// it, which git lists as untracked folders, aren't changes of it.
+fn git_repository_diff(repo: &Repository, workdirs: &[&Path]) -> Option<DiffInfo> {
+    let workdir = repo.workdir()?;
     let name = match repo.head() {
         Ok(head) => head.shorthand()?.to_string(),
         _ => "(No branch)".to_owned(),
@@ -1349,8 +2180,10 @@ fn git_diff_new(workspace_path: &Path) -> Option<DiffInfo> {
         )
         .ok()?;
     for delta in diff.deltas() {
-        if let Some(delta) = git_delta_format(workspace_path, &delta) {
-            deltas.push(delta);
+        if let Some(delta) = git_delta_format(workdir, &delta) {
+            if !workdirs.contains(&delta.2.as_path()) {
+                deltas.push(delta);
+            }
         }
     }
 
@@ -1359,17 +2192,49 @@ fn git_diff_new(workspace_path: &Path) -> Option<DiffInfo> {
         _ => Oid::zero(),
     };
 
+    let mut staged_deltas = Vec::new();
     let cached_diff = repo
         .diff_tree_to_index(repo.find_tree(oid).ok().as_ref(), None, None)
         .ok();
 
     if let Some(cached_diff) = cached_diff {
         for delta in cached_diff.deltas() {
-            if let Some(delta) = git_delta_format(workspace_path, &delta) {
-                deltas.push(delta);
+            if let Some(delta) = git_delta_format(workdir, &delta) {
+                staged_deltas.push(delta);
             }
         }
     }
+
+    let (upstream, ahead, behind) = match git_upstream_status(repo) {
+        Some((upstream, ahead, behind)) => (Some(upstream), ahead, behind),
+        None => (None, 0, 0),
+    };
+
+    // Conflicted files are only shown as conflicted, until they are resolved
+    // by staging them
+    let conflicts = git_conflicted_files(repo).unwrap_or_default();
+    deltas.retain(|delta| !conflicts.contains(&delta.2));
+    staged_deltas.retain(|delta| !conflicts.contains(&delta.2));
+    for path in conflicts {
+        deltas.push((git2::Delta::Conflicted, Oid::zero(), path));
+    }
+
+    Some(DiffInfo {
+        path: workdir.to_path_buf(),
+        head: name,
+        branches,
+        tags,
+        diffs: git_file_diffs(&deltas),
+        staged: git_file_diffs(&staged_deltas),
+        upstream,
+        ahead,
+        behind,
+    })
+}
+
+/// Turn the deltas into file diffs, where a deleted and an added file with the
+/// same content are a rename
+fn git_file_diffs(deltas: &[(git2::Delta, git2::Oid, PathBuf)]) -> Vec<FileDiff> {
     let mut renames = Vec::new();
     let mut renamed_deltas = HashSet::new();
 
@@ -1401,6 +2266,7 @@ fn git_diff_new(workspace_path: &Path) -> Option<DiffInfo> {
             git2::Delta::Added => FileDiff::Added(delta.2.clone()),
             git2::Delta::Deleted => FileDiff::Deleted(delta.2.clone()),
             git2::Delta::Modified => FileDiff::Modified(delta.2.clone()),
+            git2::Delta::Conflicted => FileDiff::Conflicted(delta.2.clone()),
             _ => continue,
         };
         file_diffs.push(diff);
@@ -1409,31 +2275,34 @@ fn git_diff_new(workspace_path: &Path) -> Option<DiffInfo> {
         FileDiff::Modified(p)
         | FileDiff::Added(p)
         | FileDiff::Renamed(p, _)
-        | FileDiff::Deleted(p) => p.clone(),
+        | FileDiff::Deleted(p)
+        | FileDiff::Conflicted(p) => p.clone(),
     });
-    Some(DiffInfo {
-        head: name,
-        branches,
-        tags,
-        diffs: file_diffs,
-    })
+    file_diffs
 }
 
-fn file_get_head(workspace_path: &Path, path: &Path) -> Result<(String, String)> {
-    let repo = Repository::discover(workspace_path)?;
-    let head = repo.head()?;
-    let tree = head.peel_to_tree()?;
-    let tree_entry = tree.get_path(path.strip_prefix(workspace_path)?)?;
-    let blob = repo.find_blob(tree_entry.id())?;
-    let id = blob.id().to_string();
-    let content = std::str::from_utf8(blob.content())
-        .with_context(|| "content bytes to string")?
-        .to_string();
-    Ok((id, content))
+/// The files changed by the commit, compared to its first parent
+fn git_commit_diffs(repository_path: &Path, commit: &str) -> Result<Vec<FileDiff>> {
+    let repo = Repository::discover(repository_path)?;
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| anyhow!("repository has no working directory"))?;
+    let commit = repo.revparse_single(commit)?.peel_to_commit()?;
+    let parent_tree = match commit.parent(0) {
+        Ok(parent) => Some(parent.tree()?),
+        Err(_) => None,
+    };
+    let diff =
+        repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+    let deltas = diff
+        .deltas()
+        .filter_map(|delta| git_delta_format(workdir, &delta))
+        .collect::<Vec<_>>();
+    Ok(git_file_diffs(&deltas))
 }
 
-fn git_get_remote_file_url(workspace_path: &Path, file: &Path) -> Result<String> {
-    let repo = Repository::discover(workspace_path)?;
+fn git_get_remote_file_url(file: &Path) -> Result<String> {
+    let repo = git_file_repository(file)?;
     let head = repo.head()?;
     let target_remote = repo.find_remote(
         repo.branch_upstream_remote(head.name().unwrap())?
@@ -1467,8 +2336,7 @@ fn git_get_remote_file_url(workspace_path: &Path, file: &Path) -> Result<String>
 
     let commit = head.peel_to_commit()?.id();
 
-    let file_path = file
-        .strip_prefix(workspace_path)?
+    let file_path = repo_path(&repo, file)?
         .to_str()
         .ok_or(anyhow!("Couldn't convert file path to str"))?;
 
@@ -1518,35 +2386,40 @@ fn search_in_path(
                         return Ok(false);
                     }
 
-                    let mymatch = matcher.find(line.as_bytes())?.unwrap();
-                    let line = if line.len() > 200 {
-                        // Shorten the line to avoid sending over absurdly long-lines
-                        // (such as in minified javascript)
-                        // Note that the start/end are column based, not absolute from the
-                        // start of the file.
-                        let left_keep = line[..mymatch.start()]
-                            .chars()
-                            .rev()
-                            .take(100)
-                            .map(|c| c.len_utf8())
-                            .sum::<usize>();
-                        let right_keep = line[mymatch.end()..]
-                            .chars()
-                            .take(100)
-                            .map(|c| c.len_utf8())
-                            .sum::<usize>();
-                        let display_range =
-                            mymatch.start() - left_keep..mymatch.end() + right_keep;
-                        line[display_range].to_string()
-                    } else {
-                        line.to_string()
-                    };
-                    line_matches.push(SearchMatch {
-                        line: lnum as usize,
-                        start: mymatch.start(),
-                        end: mymatch.end(),
-                        line_content: line,
-                    });
+                    matcher.find_iter(line.as_bytes(), |mymatch| {
+                        if mymatch.is_empty() {
+                            return true;
+                        }
+                        let line = if line.len() > 200 {
+                            // Shorten the line to avoid sending over absurdly long-lines
+                            // (such as in minified javascript)
+                            // Note that the start/end are column based, not absolute from the
+                            // start of the file.
+                            let left_keep = line[..mymatch.start()]
+                                .chars()
+                                .rev()
+                                .take(100)
+                                .map(|c| c.len_utf8())
+                                .sum::<usize>();
+                            let right_keep = line[mymatch.end()..]
+                                .chars()
+                                .take(100)
+                                .map(|c| c.len_utf8())
+                                .sum::<usize>();
+                            let display_range = mymatch.start() - left_keep
+                                ..mymatch.end() + right_keep;
+                            line[display_range].to_string()
+                        } else {
+                            line.to_string()
+                        };
+                        line_matches.push(SearchMatch {
+                            line: lnum as usize,
+                            start: mymatch.start(),
+                            end: mymatch.end(),
+                            line_content: line,
+                        });
+                        true
+                    })?;
                     Ok(true)
                 }),
             );
@@ -1558,3 +2431,93 @@ fn search_in_path(
 
     Ok(ProxyResponse::GlobalSearchResponse { matches })
 }
+
+/// Replace the given search matches. Files which are open as buffers are left
+/// to the editor, which gets the edits to apply to their documents, while the
+/// other files are rewritten on disk.
+#[allow(clippy::too_many_arguments)]
+fn global_replace(
+    buffers: &HashMap<PathBuf, Buffer>,
+    core_rpc: &CoreRpcHandler,
+    pattern: &str,
+    case_sensitive: bool,
+    whole_word: bool,
+    is_regex: bool,
+    replacement: &str,
+    matches: IndexMap<PathBuf, Vec<SearchMatch>>,
+) -> Result<ProxyResponse, RpcError> {
+    let regex = build_search_regex(pattern, case_sensitive, whole_word, is_regex)
+        .map_err(|e| RpcError {
+            code: 0,
+            message: e.to_string(),
+        })?;
+
+    let mut edits = IndexMap::new();
+    let mut files = IndexMap::new();
+    for (path, matches) in matches {
+        let line_matches = matches_by_line(&matches);
+
+        if let Some(buffer) = buffers.get(&path) {
+            let mut buffer_edits = Vec::new();
+            for (line, matches) in line_matches {
+                let Some(line) = line.checked_sub(1) else {
+                    continue;
+                };
+                let start = buffer.offset_of_line(line);
+                let end = buffer.offset_of_line(line + 1);
+                let content = buffer.slice_to_cow(start..end);
+                let content = content.trim_end_matches(['\n', '\r']);
+                for (s, e, new_text) in line_replacements(
+                    &regex,
+                    content,
+                    &matches,
+                    replacement,
+                    is_regex,
+                ) {
+                    buffer_edits.push(TextEdit {
+                        range: Range {
+                            start: buffer.offset_to_position(start + s),
+                            end: buffer.offset_to_position(start + e),
+                        },
+                        new_text,
+                    });
+                }
+            }
+            if !buffer_edits.is_empty() {
+                edits.insert(path, buffer_edits);
+            }
+            continue;
+        }
+
+        let result = read_path_to_string(&path).and_then(|content| {
+            if let Some(new_content) = replace_in_content(
+                &content,
+                &regex,
+                &line_matches,
+                replacement,
+                is_regex,
+            ) {
+                fs::write(&path, &new_content)?;
+                files.insert(
+                    path.clone(),
+                    ReplacedFile {
+                        original: content,
+                        replaced: new_content,
+                    },
+                );
+            }
+            Ok(())
+        });
+        if let Err(e) = result {
+            core_rpc.show_message(
+                "Replace failure".to_owned(),
+                ShowMessageParams {
+                    typ: MessageType::ERROR,
+                    message: format!("{}: {e}", path.display()),
+                },
+            );
+        }
+    }
+
+    Ok(ProxyResponse::GlobalReplaceResponse { edits, files })
+}
@@ -0,0 +1,1238 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{anyhow, Context, Result};
+use git2::{
+    build::CheckoutBuilder, Branch, BranchType, Commit, Cred, CredentialType,
+    ErrorCode, FetchOptions, Index, IndexEntry, IndexTime, ObjectType, Oid,
+    PushOptions, RemoteCallbacks, Repository, RepositoryState, Sort,
+};
+use lapce_rpc::source_control::{
+    BlameCommit, BlameHunk, GitCommit, GitHunk, GitStash,
+};
+use parking_lot::Mutex;
+
+/// The path relative to the working directory of the repository, which is how
+/// git refers to files
+pub fn repo_path<'a>(repo: &Repository, path: &'a Path) -> Result<&'a Path> {
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| anyhow!("repository has no working directory"))?;
+    Ok(path.strip_prefix(workdir)?)
+}
+
+/// The repository which has the file, which is the innermost one when they're
+/// nested, like a submodule rather than the repository it's in
+pub fn git_file_repository(path: &Path) -> Result<Repository> {
+    // The file, or even its folder, may have been deleted. A submodule is a
+    // file of the repository it's in, so the search doesn't start at itself.
+    let dir = path
+        .ancestors()
+        .skip(1)
+        .find(|dir| dir.is_dir())
+        .ok_or_else(|| anyhow!("{} is not in a folder", path.display()))?;
+    Ok(Repository::discover(dir)?)
+}
+
+/// The files grouped by the repository they're in, with their paths relative
+/// to it
+fn files_by_repository(
+    files: &[PathBuf],
+) -> Result<Vec<(Repository, Vec<PathBuf>)>> {
+    let mut groups: Vec<(Repository, Vec<PathBuf>)> = Vec::new();
+    for path in files {
+        let repo = git_file_repository(path)?;
+        let relative = repo_path(&repo, path)?.to_path_buf();
+        match groups
+            .iter_mut()
+            .find(|(group, _)| group.workdir() == repo.workdir())
+        {
+            Some((_, paths)) => paths.push(relative),
+            None => groups.push((repo, vec![relative])),
+        }
+    }
+    Ok(groups)
+}
+
+/// How deep in the folders of the workspace repositories are looked for
+const REPOSITORY_SEARCH_DEPTH: usize = 3;
+
+/// The repositories in the workspace, starting with the one the workspace is
+/// in, followed by the ones in its folders, like submodules or other checkouts
+pub fn git_repositories(workspace_path: &Path) -> Vec<Repository> {
+    let mut repos = Vec::new();
+    if let Ok(repo) = Repository::discover(workspace_path) {
+        repos.push(repo);
+    }
+    let mut dirs = Vec::new();
+    find_repository_dirs(workspace_path, 1, &mut dirs);
+    for dir in dirs {
+        if let Ok(repo) = Repository::open(dir) {
+            push_repository(&mut repos, repo, workspace_path);
+        }
+    }
+
+    // Submodules can be deeper than the folders which are searched
+    let mut i = 0;
+    while i < repos.len() {
+        let submodules = repos[i]
+            .submodules()
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|submodule| submodule.open().ok())
+            .collect::<Vec<_>>();
+        for repo in submodules {
+            push_repository(&mut repos, repo, workspace_path);
+        }
+        i += 1;
+    }
+    repos
+}
+
+/// The working directories of the repositories in the workspace. Looking for
+/// them reads the folders of the workspace, so it's only done again after
+/// folders were added or removed.
+#[derive(Clone, Default)]
+pub struct RepositoryDirs(Arc<Mutex<Option<Vec<PathBuf>>>>);
+
+impl RepositoryDirs {
+    /// The repositories in the workspace, like `git_repositories`
+    pub fn repositories(&self, workspace_path: &Path) -> Vec<Repository> {
+        let mut dirs = self.0.lock();
+        if let Some(dirs) = dirs.as_ref() {
+            return dirs
+                .iter()
+                .filter_map(|dir| Repository::open(dir).ok())
+                .collect();
+        }
+        let repos = git_repositories(workspace_path);
+        *dirs = Some(
+            repos
+                .iter()
+                .filter_map(|repo| repo.workdir())
+                .map(Path::to_path_buf)
+                .collect(),
+        );
+        repos
+    }
+
+    /// Whether the path is the working directory of one of the repositories
+    /// found, or a folder which one is in
+    pub fn has_repository_in(&self, path: &Path) -> bool {
+        self.0
+            .lock()
+            .as_ref()
+            .is_some_and(|dirs| dirs.iter().any(|dir| dir.starts_with(path)))
+    }
+
+    /// Look for the repositories again the next time
+    pub fn clear(&self) {
+        self.0.lock().take();
+    }
+}
+
+/// Add the repository unless it's there already, or it's outside of the
+/// workspace, like a submodule of the repository the workspace is a folder of
+fn push_repository(
+    repos: &mut Vec<Repository>,
+    repo: Repository,
+    workspace_path: &Path,
+) {
+    let Some(workdir) = repo.workdir() else {
+        return;
+    };
+    if workdir.starts_with(workspace_path)
+        && !repos.iter().any(|r| r.workdir() == Some(workdir))
+    {
+        repos.push(repo);
+    }
+}
+
+/// The folders which are the working directory of a repository, which are
+/// the ones with a `.git` folder, or a `.git` file for a submodule
+fn find_repository_dirs(dir: &Path, depth: usize, dirs: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let hidden = entry.file_name().to_string_lossy().starts_with('.');
+        if hidden || !entry.file_type().is_ok_and(|t| t.is_dir()) {
+            continue;
+        }
+        if path.join(".git").exists() {
+            dirs.push(path.clone());
+        }
+        if depth < REPOSITORY_SEARCH_DEPTH {
+            find_repository_dirs(&path, depth + 1, dirs);
+        }
+    }
+}
+
+/// Put the files as they are in the working tree in the index, which removes
+/// the ones that were deleted.
+pub fn git_stage_files(files: &[PathBuf]) -> Result<()> {
+    for (repo, paths) in files_by_repository(files)? {
+        let workdir = repo
+            .workdir()
+            .ok_or_else(|| anyhow!("repository has no working directory"))?;
+        let mut index = repo.index()?;
+        for relative in paths {
+            if workdir.join(&relative).exists() {
+                index.add_path(&relative)?;
+            } else {
+                index.remove_path(&relative)?;
+            }
+        }
+        index.write()?;
+    }
+    Ok(())
+}
+
+/// Put the files as they are in `HEAD` back in the index, or take them out of
+/// it when there's no commit yet.
+pub fn git_unstage_files(files: &[PathBuf]) -> Result<()> {
+    for (repo, paths) in files_by_repository(files)? {
+        let head = repo
+            .head()
+            .ok()
+            .and_then(|head| head.peel(ObjectType::Commit).ok());
+        repo.reset_default(head.as_ref(), paths)?;
+    }
+    Ok(())
+}
+
+/// The content of the file in the index
+pub fn git_file_index(path: &Path) -> Result<String> {
+    let repo = git_file_repository(path)?;
+    let index = repo.index()?;
+    let (_, content) = index_content(&repo, &index, repo_path(&repo, path)?)?;
+    Ok(content)
+}
+
+/// The content of the file at a revision, such as `HEAD` or a commit id, or at
+/// a stage of the index like `:2`, where a conflicted file has the common
+/// ancestor at stage 1, our side at stage 2, and their side at stage 3.
+pub fn git_file_revision(path: &Path, revision: &str) -> Result<String> {
+    let repo = git_file_repository(path)?;
+    let relative = repo_path(&repo, path)?;
+    let id = if let Some(stage) = revision.strip_prefix(':') {
+        let stage = stage.parse()?;
+        repo.index()?
+            .get_path(relative, stage)
+            .ok_or_else(|| {
+                anyhow!("{} is not at stage {stage}", relative.display())
+            })?
+            .id
+    } else {
+        let tree = repo.revparse_single(revision)?.peel_to_tree()?;
+        tree.get_path(relative)?.id()
+    };
+    let blob = repo.find_blob(id)?;
+    let content = std::str::from_utf8(blob.content())
+        .with_context(|| "content bytes to string")?
+        .to_string();
+    Ok(content)
+}
+
+/// The files which have conflicts in the index, left by a merge, rebase or
+/// stash which couldn't apply all the changes cleanly
+pub fn git_conflicted_files(repo: &Repository) -> Result<Vec<PathBuf>> {
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| anyhow!("repository has no working directory"))?;
+    let mut paths = Vec::new();
+    for conflict in repo.index()?.conflicts()? {
+        let conflict = conflict?;
+        let entry = conflict
+            .our
+            .or(conflict.their)
+            .or(conflict.ancestor)
+            .ok_or_else(|| anyhow!("conflict without entries"))?;
+        let path = std::str::from_utf8(&entry.path)
+            .with_context(|| "path bytes to string")?;
+        paths.push(workdir.join(path));
+    }
+    Ok(paths)
+}
+
+/// The commits being merged into `HEAD` by a merge which stopped on
+/// conflicts, which become the other parents of the commit that concludes it
+pub fn git_merge_heads(repo: &mut Repository) -> Result<Vec<Oid>> {
+    let mut ids = Vec::new();
+    if repo.state() == RepositoryState::Merge {
+        repo.mergehead_foreach(|id| {
+            ids.push(*id);
+            true
+        })?;
+    }
+    Ok(ids)
+}
+
+/// A page of the commits of the repository reachable from `HEAD`, newest
+/// first, which only has the commits that changed the file when there is a
+/// path. The file has to be in the repository.
+pub fn git_log(
+    repo: &Repository,
+    path: Option<&Path>,
+    skip: usize,
+    limit: usize,
+) -> Result<Vec<GitCommit>> {
+    let relative = path.map(|path| repo_path(repo, path)).transpose()?;
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
+    revwalk.push_head()?;
+
+    let commits = revwalk
+        .filter_map(|id| repo.find_commit(id.ok()?).ok())
+        .filter(|commit| {
+            relative.map_or(true, |relative| commit_changed_path(commit, relative))
+        })
+        .skip(skip)
+        .take(limit)
+        .map(|commit| {
+            let author = commit.author();
+            GitCommit {
+                id: commit.id().to_string(),
+                parents: commit.parent_ids().map(|id| id.to_string()).collect(),
+                author: author.name().unwrap_or_default().to_string(),
+                email: author.email().unwrap_or_default().to_string(),
+                time: commit.time().seconds(),
+                summary: commit.summary().unwrap_or_default().to_string(),
+            }
+        })
+        .collect();
+    Ok(commits)
+}
+
+/// Whether the file is different in the commit than in all of its parents,
+/// so a merge which took the file from one side didn't change it.
+fn commit_changed_path(commit: &Commit, relative: &Path) -> bool {
+    let entry_id = |commit: &Commit| -> Option<Oid> {
+        Some(commit.tree().ok()?.get_path(relative).ok()?.id())
+    };
+    let id = entry_id(commit);
+    if commit.parent_count() == 0 {
+        return id.is_some();
+    }
+    commit.parents().all(|parent| entry_id(&parent) != id)
+}
+
+pub fn git_stage_hunk(path: &Path, hunk: &GitHunk, content: &str) -> Result<()> {
+    let repo = git_file_repository(path)?;
+    let mut index = repo.index()?;
+    let (entry, old) = index_content(&repo, &index, repo_path(&repo, path)?)?;
+    let new = replace_lines(&old, hunk.old_start, hunk.old_lines, content);
+    write_index_content(&mut index, entry, &new, path)
+}
+
+pub fn git_unstage_hunk(path: &Path, hunk: &GitHunk) -> Result<()> {
+    let repo = git_file_repository(path)?;
+    let mut index = repo.index()?;
+    let relative = repo_path(&repo, path)?;
+    let (entry, old) = index_content(&repo, &index, relative)?;
+
+    // A file which was added in the index has nothing in `HEAD`
+    let head = match repo.head().and_then(|head| head.peel_to_tree()) {
+        Ok(tree) => match tree.get_path(relative) {
+            Ok(tree_entry) => {
+                let blob = repo.find_blob(tree_entry.id())?;
+                std::str::from_utf8(blob.content())
+                    .with_context(|| "content bytes to string")?
+                    .to_string()
+            }
+            Err(_) => String::new(),
+        },
+        Err(_) => String::new(),
+    };
+
+    let head_lines = &head[line_range(&head, hunk.old_start, hunk.old_lines)];
+    let new = replace_lines(&old, hunk.new_start, hunk.new_lines, head_lines);
+    write_index_content(&mut index, entry, &new, path)
+}
+
+/// Blame the file as it is committed, then the content on top of that when
+/// there is one, so lines changed since are reported as uncommitted.
+pub fn git_blame(path: &Path, content: Option<&str>) -> Result<Vec<BlameHunk>> {
+    let repo = git_file_repository(path)?;
+    let blame = repo.blame_file(repo_path(&repo, path)?, None)?;
+    let blame = match content {
+        Some(content) => blame.blame_buffer(content.as_bytes())?,
+        None => blame,
+    };
+
+    let mut commits: HashMap<Oid, BlameCommit> = HashMap::new();
+    let mut hunks = Vec::new();
+    for hunk in blame.iter() {
+        let id = hunk.final_commit_id();
+        let commit = if id.is_zero() {
+            None
+        } else if let Some(commit) = commits.get(&id) {
+            Some(commit.clone())
+        } else {
+            let commit = repo.find_commit(id)?;
+            let author = commit.author();
+            let commit = BlameCommit {
+                id: id.to_string(),
+                author: author.name().unwrap_or_default().to_string(),
+                email: author.email().unwrap_or_default().to_string(),
+                time: commit.time().seconds(),
+                summary: commit.summary().unwrap_or_default().to_string(),
+            };
+            commits.insert(id, commit.clone());
+            Some(commit)
+        };
+        hunks.push(BlameHunk {
+            // git's line numbers are one based
+            start_line: hunk.final_start_line().saturating_sub(1),
+            lines: hunk.lines_in_hunk(),
+            commit,
+        });
+    }
+    Ok(hunks)
+}
+
+/// The upstream branch which the head branch tracks, like `origin/main`, and
+/// how many commits the head is ahead and behind it
+pub fn git_upstream_status(repo: &Repository) -> Option<(String, usize, usize)> {
+    let head = repo.head().ok()?;
+    if !head.is_branch() {
+        return None;
+    }
+    let branch = repo
+        .find_branch(head.shorthand()?, BranchType::Local)
+        .ok()?;
+    let upstream = branch.upstream().ok()?;
+    let (ahead, behind) = repo
+        .graph_ahead_behind(head.target()?, upstream.get().target()?)
+        .ok()?;
+    Some((upstream.name().ok()??.to_string(), ahead, behind))
+}
+
+/// Fetch the remote which the head branch tracks
+pub fn git_fetch(workspace_path: &Path) -> Result<()> {
+    let repo = Repository::discover(workspace_path)?;
+    fetch_head_remote(&repo)
+}
+
+/// Fetch, then bring the head branch up to date with its upstream, either by
+/// fast forwarding it or by rebasing its commits onto the upstream
+pub fn git_pull(workspace_path: &Path, rebase: bool) -> Result<()> {
+    let repo = Repository::discover(workspace_path)?;
+    fetch_head_remote(&repo)?;
+
+    let (name, branch) = head_branch(&repo)?;
+    let upstream = branch
+        .upstream()
+        .with_context(|| format!("The branch {name} has no upstream branch"))?;
+    let upstream_name = upstream.name()?.unwrap_or_default().to_string();
+    let upstream = repo.reference_to_annotated_commit(upstream.get())?;
+
+    let (analysis, _) = repo.merge_analysis(&[&upstream])?;
+    if analysis.is_up_to_date() {
+        return Ok(());
+    }
+
+    if analysis.is_fast_forward() {
+        return fast_forward(&repo, branch, upstream.id(), "pull: fast-forward");
+    }
+
+    if !rebase {
+        return Err(anyhow!(
+            "{name} and {upstream_name} have diverged, pull with rebase instead"
+        ));
+    }
+
+    let signature = repo.signature()?;
+    let mut rebase = repo.rebase(None, Some(&upstream), None, None)?;
+    while let Some(operation) = rebase.next() {
+        if let Err(e) = operation {
+            rebase.abort()?;
+            return Err(e.into());
+        }
+        if repo.index()?.has_conflicts() {
+            rebase.abort()?;
+            return Err(anyhow!(
+                "Rebasing onto {upstream_name} has conflicts, so the pull was aborted"
+            ));
+        }
+        match rebase.commit(None, &signature, None) {
+            Ok(_) => {}
+            // The commit is already in the upstream
+            Err(e) if e.code() == ErrorCode::Applied => {}
+            Err(e) => {
+                rebase.abort()?;
+                return Err(e.into());
+            }
+        }
+    }
+    rebase.finish(Some(&signature))?;
+    Ok(())
+}
+
+/// Push the head branch to its upstream, which is set to the branch of the
+/// same name on the remote when there isn't one yet
+pub fn git_push(workspace_path: &Path) -> Result<()> {
+    let repo = Repository::discover(workspace_path)?;
+    let (name, mut branch) = head_branch(&repo)?;
+
+    let config = repo.config()?;
+    let tracked = config
+        .get_string(&format!("branch.{name}.remote"))
+        .and_then(|remote| {
+            let merge = config.get_string(&format!("branch.{name}.merge"))?;
+            Ok((remote, merge))
+        })
+        .ok();
+    let (remote_name, merge) = match tracked.clone() {
+        Some(tracked) => tracked,
+        None => (default_remote(&repo)?, format!("refs/heads/{name}")),
+    };
+
+    let mut remote = repo.find_remote(&remote_name)?;
+    let rejected = RefCell::new(None);
+    let mut callbacks = remote_callbacks(&repo)?;
+    callbacks.push_update_reference(|reference, status| {
+        if let Some(status) = status {
+            *rejected.borrow_mut() = Some(format!("{reference}: {status}"));
+        }
+        Ok(())
+    });
+    let mut options = PushOptions::new();
+    options.remote_callbacks(callbacks);
+    remote.push(&[format!("refs/heads/{name}:{merge}")], Some(&mut options))?;
+    drop(options);
+    if let Some(rejected) = rejected.into_inner() {
+        return Err(anyhow!("The remote rejected the push of {rejected}"));
+    }
+
+    if tracked.is_none() {
+        branch.set_upstream(Some(&format!("{remote_name}/{name}")))?;
+    }
+    Ok(())
+}
+
+/// Create the branch and check it out. The branch starts at the reference, or
+/// at `HEAD` without one.
+pub fn git_create_branch(
+    workspace_path: &Path,
+    name: &str,
+    start: Option<&str>,
+) -> Result<()> {
+    let repo = Repository::discover(workspace_path)?;
+    // The branch is checked before the checkout, so the working tree isn't
+    // changed for a branch which can't be created
+    if !Branch::name_is_valid(name)? {
+        return Err(anyhow!("{name} is not a valid branch name"));
+    }
+    if repo.find_branch(name, BranchType::Local).is_ok() {
+        return Err(anyhow!("The branch {name} already exists"));
+    }
+    let commit = repo
+        .revparse_single(start.unwrap_or("HEAD"))?
+        .peel_to_commit()?;
+    // The checkout comes first, so the branch isn't created when the changes
+    // in the working tree are in the way
+    repo.checkout_tree(commit.as_object(), Some(CheckoutBuilder::new().safe()))?;
+    let branch = repo.branch(name, &commit, false)?;
+    let reference = branch
+        .get()
+        .name()
+        .ok_or_else(|| anyhow!("The name of the branch isn't valid utf-8"))?;
+    repo.set_head(reference)?;
+    Ok(())
+}
+
+pub fn git_rename_branch(
+    workspace_path: &Path,
+    branch: &str,
+    name: &str,
+) -> Result<()> {
+    let repo = Repository::discover(workspace_path)?;
+    repo.find_branch(branch, BranchType::Local)?
+        .rename(name, false)?;
+    Ok(())
+}
+
+/// Delete the branch. Its commits would be lost when it isn't merged into
+/// `HEAD`, so then it's only deleted when that's forced.
+pub fn git_delete_branch(
+    workspace_path: &Path,
+    branch: &str,
+    force: bool,
+) -> Result<()> {
+    let repo = Repository::discover(workspace_path)?;
+    let mut local = repo.find_branch(branch, BranchType::Local)?;
+    if !force {
+        let target = local
+            .get()
+            .target()
+            .ok_or_else(|| anyhow!("The branch {branch} has no commit"))?;
+        let head = repo.head()?.peel_to_commit()?.id();
+        if head != target && !repo.graph_descendant_of(head, target)? {
+            return Err(anyhow!("The branch {branch} isn't merged"));
+        }
+    }
+    local.delete()?;
+    Ok(())
+}
+
+/// Merge the reference into the head branch. When the merge has conflicts it's
+/// left in progress, so the conflicts can be resolved and committed.
+pub fn git_merge(workspace_path: &Path, reference: &str) -> Result<()> {
+    let repo = Repository::discover(workspace_path)?;
+    let (_, branch) = head_branch(&repo)?;
+    let commit = repo.revparse_single(reference)?.peel_to_commit()?;
+    let theirs = repo.find_annotated_commit(commit.id())?;
+
+    let (analysis, _) = repo.merge_analysis(&[&theirs])?;
+    if analysis.is_up_to_date() {
+        return Ok(());
+    }
+    if analysis.is_fast_forward() {
+        return fast_forward(&repo, branch, commit.id(), "merge: fast-forward");
+    }
+
+    repo.merge(&[&theirs], None, Some(CheckoutBuilder::new().safe()))?;
+    let mut index = repo.index()?;
+    if index.has_conflicts() {
+        return Err(anyhow!(
+            "Merging {reference} has conflicts, resolve them and commit the merge"
+        ));
+    }
+
+    let tree = repo.find_tree(index.write_tree()?)?;
+    let signature = repo.signature()?;
+    let head = repo.head()?.peel_to_commit()?;
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        &format!("Merge {reference}"),
+        &tree,
+        &[&head, &commit],
+    )?;
+    repo.cleanup_state()?;
+    Ok(())
+}
+
+pub fn git_stash_list(workspace_path: &Path) -> Result<Vec<GitStash>> {
+    let mut repo = Repository::discover(workspace_path)?;
+    let mut stashes = Vec::new();
+    repo.stash_foreach(|index, message, _| {
+        stashes.push(GitStash {
+            index,
+            message: message.to_string(),
+        });
+        true
+    })?;
+    Ok(stashes)
+}
+
+pub fn git_stash_push(workspace_path: &Path, message: Option<&str>) -> Result<()> {
+    let mut repo = Repository::discover(workspace_path)?;
+    let signature = repo.signature()?;
+    repo.stash_save2(&signature, message, None)?;
+    Ok(())
+}
+
+pub fn git_stash_pop(workspace_path: &Path, index: usize) -> Result<()> {
+    let mut repo = Repository::discover(workspace_path)?;
+    repo.stash_pop(index, None)?;
+    Ok(())
+}
+
+pub fn git_stash_apply(workspace_path: &Path, index: usize) -> Result<()> {
+    let mut repo = Repository::discover(workspace_path)?;
+    repo.stash_apply(index, None)?;
+    Ok(())
+}
+
+pub fn git_stash_drop(workspace_path: &Path, index: usize) -> Result<()> {
+    let mut repo = Repository::discover(workspace_path)?;
+    repo.stash_drop(index)?;
+    Ok(())
+}
+
+/// The content of the file which `commit.template` points to, if it's set
+pub fn git_commit_template(workspace_path: &Path) -> Result<Option<String>> {
+    let repo = Repository::discover(workspace_path)?;
+    let path = match repo.config()?.get_path("commit.template") {
+        Ok(path) => path,
+        Err(e) if e.code() == ErrorCode::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    // A relative path is relative to the top of the working tree, which is
+    // where git is usually run from
+    let path = match repo.workdir() {
+        Some(workdir) => workdir.join(path),
+        None => path,
+    };
+    let template = std::fs::read_to_string(&path).with_context(|| {
+        format!("can't read the commit template {}", path.display())
+    })?;
+    Ok(Some(template))
+}
+
+pub fn git_head_message(workspace_path: &Path) -> Result<String> {
+    let repo = Repository::discover(workspace_path)?;
+    let commit = repo.head()?.peel_to_commit()?;
+    Ok(commit.message().unwrap_or("").to_string())
+}
+
+/// Add a `Signed-off-by` trailer to the message, in the paragraph of trailers
+/// it ends with when there's one. Nothing is added when the last trailer is
+/// already the same.
+pub fn sign_off_message(message: &str, name: &str, email: &str) -> String {
+    let trailer = format!("Signed-off-by: {name} <{email}>");
+    let message = message.trim_end();
+    if message.lines().last() == Some(trailer.as_str()) {
+        return format!("{message}\n");
+    }
+    let ends_with_trailers = message
+        .rsplit_once("\n\n")
+        .is_some_and(|(_, last)| last.lines().all(is_trailer));
+    let separator = if ends_with_trailers { "\n" } else { "\n\n" };
+    format!("{message}{separator}{trailer}\n")
+}
+
+/// Whether the line is a trailer like `Reviewed-by: Jane Doe <jane@doe.com>`
+fn is_trailer(line: &str) -> bool {
+    line.split_once(": ").is_some_and(|(key, _)| {
+        !key.is_empty() && !key.contains(char::is_whitespace)
+    })
+}
+
+/// Move the branch forward to the commit, which has to be a descendant of it
+fn fast_forward(
+    repo: &Repository,
+    branch: Branch,
+    target: Oid,
+    message: &str,
+) -> Result<()> {
+    let object = repo.find_object(target, None)?;
+    repo.checkout_tree(&object, Some(CheckoutBuilder::new().safe()))?;
+    branch.into_reference().set_target(target, message)?;
+    Ok(())
+}
+
+/// The name of the branch which is checked out, and the branch
+fn head_branch(repo: &Repository) -> Result<(String, Branch<'_>)> {
+    let head = repo.head()?;
+    if !head.is_branch() {
+        return Err(anyhow!("The head isn't on a branch"));
+    }
+    let name = head
+        .shorthand()
+        .ok_or_else(|| anyhow!("The name of the branch isn't valid utf-8"))?
+        .to_string();
+    Ok((name, Branch::wrap(head)))
+}
+
+/// The remote which the head branch tracks, or the default one when it
+/// doesn't track any
+fn head_remote(repo: &Repository) -> Result<String> {
+    let remote = repo
+        .head()
+        .ok()
+        .and_then(|head| head.name().map(str::to_string))
+        .and_then(|name| repo.branch_upstream_remote(&name).ok())
+        .and_then(|remote| remote.as_str().map(str::to_string));
+    match remote {
+        Some(remote) => Ok(remote),
+        None => default_remote(repo),
+    }
+}
+
+/// `origin`, or the first remote when there's no `origin`
+fn default_remote(repo: &Repository) -> Result<String> {
+    let remotes = repo.remotes()?;
+    let remotes = remotes.iter().flatten().collect::<Vec<_>>();
+    if remotes.contains(&"origin") {
+        return Ok("origin".to_string());
+    }
+    remotes
+        .first()
+        .map(|remote| remote.to_string())
+        .ok_or_else(|| anyhow!("The repository has no remotes"))
+}
+
+fn fetch_head_remote(repo: &Repository) -> Result<()> {
+    let mut remote = repo.find_remote(&head_remote(repo)?)?;
+    let mut options = FetchOptions::new();
+    options.remote_callbacks(remote_callbacks(repo)?);
+    remote.fetch::<&str>(&[], Some(&mut options), None)?;
+    Ok(())
+}
+
+/// Credentials come from the ssh agent and from the credential helpers in the
+/// git config. Each kind is tried once, because libgit2 keeps asking for as
+/// long as the remote refuses them.
+fn remote_callbacks<'a>(repo: &Repository) -> Result<RemoteCallbacks<'a>> {
+    let config = repo.config()?;
+    let mut tried = CredentialType::empty();
+    let mut callbacks = RemoteCallbacks::new();
+    callbacks.credentials(move |url, username, allowed| {
+        if allowed.contains(CredentialType::SSH_KEY)
+            && !tried.contains(CredentialType::SSH_KEY)
+        {
+            tried |= CredentialType::SSH_KEY;
+            return Cred::ssh_key_from_agent(username.unwrap_or("git"));
+        }
+        if allowed.contains(CredentialType::USER_PASS_PLAINTEXT)
+            && !tried.contains(CredentialType::USER_PASS_PLAINTEXT)
+        {
+            tried |= CredentialType::USER_PASS_PLAINTEXT;
+            return Cred::credential_helper(&config, url, username);
+        }
+        if allowed.contains(CredentialType::DEFAULT)
+            && !tried.contains(CredentialType::DEFAULT)
+        {
+            tried |= CredentialType::DEFAULT;
+            return Cred::default();
+        }
+        Err(git2::Error::from_str(&format!(
+            "No credentials were accepted for {url}"
+        )))
+    });
+    Ok(callbacks)
+}
+
+fn index_content(
+    repo: &Repository,
+    index: &Index,
+    relative: &Path,
+) -> Result<(IndexEntry, String)> {
+    let entry = index
+        .get_path(relative, 0)
+        .ok_or_else(|| anyhow!("{} is not in the index", relative.display()))?;
+    let blob = repo.find_blob(entry.id)?;
+    let content = std::str::from_utf8(blob.content())
+        .with_context(|| "content bytes to string")?
+        .to_string();
+    Ok((entry, content))
+}
+
+/// Replace the content of the entry in the index with `content`, where `path`
+/// is the file of the entry in the working tree.
+fn write_index_content(
+    index: &mut Index,
+    mut entry: IndexEntry,
+    content: &str,
+    path: &Path,
+) -> Result<()> {
+    // The stat of the entry is what git compares with the file to tell whether
+    // it changed, so it mustn't match the file when the content doesn't
+    if std::fs::read(path).ok().as_deref() != Some(content.as_bytes()) {
+        entry.ctime = IndexTime::new(0, 0);
+        entry.mtime = IndexTime::new(0, 0);
+        entry.dev = 0;
+        entry.ino = 0;
+    }
+    entry.file_size = content.len() as u32;
+    index.add_frombuffer(&entry, content.as_bytes())?;
+    index.write()?;
+    Ok(())
+}
+
+/// The byte range of `count` lines from line `start`, which stops at the end of
+/// the text.
+fn line_range(text: &str, start: usize, count: usize) -> Range<usize> {
+    let offset_of_line = |line: usize| -> usize {
+        text.split_inclusive('\n').take(line).map(str::len).sum()
+    };
+    offset_of_line(start)..offset_of_line(start + count)
+}
+
+/// Replace `count` lines from line `start` of the text with `content`.
+fn replace_lines(text: &str, start: usize, count: usize, content: &str) -> String {
+    let range = line_range(text, start, count);
+    format!("{}{content}{}", &text[..range.start], &text[range.end..])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use git2::{
+        build::CheckoutBuilder, BranchType, Repository, RepositoryState, Status,
+    };
+    use lapce_rpc::source_control::GitHunk;
+
+    use super::{
+        git_commit_template, git_conflicted_files, git_create_branch,
+        git_delete_branch, git_fetch, git_file_repository, git_file_revision,
+        git_head_message, git_merge, git_merge_heads, git_pull, git_push,
+        git_rename_branch, git_repositories, git_stage_files, git_stage_hunk,
+        git_stash_apply, git_stash_drop, git_stash_list, git_stash_pop,
+        git_stash_push, git_unstage_hunk, git_upstream_status, replace_lines,
+        sign_off_message, RepositoryDirs,
+    };
+
+    #[test]
+    fn test_replace_lines() {
+        let text = "a\nb\nc\n";
+        assert_eq!(replace_lines(text, 1, 1, "x\ny\n"), "a\nx\ny\nc\n");
+        // Lines which were only added
+        assert_eq!(replace_lines(text, 1, 0, "x\n"), "a\nx\nb\nc\n");
+        // Lines which were only removed
+        assert_eq!(replace_lines(text, 0, 2, ""), "c\n");
+        assert_eq!(replace_lines(text, 3, 0, "d"), "a\nb\nc\nd");
+        assert_eq!(replace_lines("a\nb", 1, 1, "c"), "a\nc");
+    }
+
+    #[test]
+    fn test_sign_off_message() {
+        let sign_off = |message| sign_off_message(message, "Jane", "jane@doe.com");
+        assert_eq!(
+            sign_off("Fix"),
+            "Fix\n\nSigned-off-by: Jane <jane@doe.com>\n"
+        );
+        // The subject isn't a trailer even when it looks like one
+        assert_eq!(
+            sign_off("fix: the build\n"),
+            "fix: the build\n\nSigned-off-by: Jane <jane@doe.com>\n"
+        );
+        // The trailer goes with the other ones
+        assert_eq!(
+            sign_off("Fix\n\nCloses: #1\n"),
+            "Fix\n\nCloses: #1\nSigned-off-by: Jane <jane@doe.com>\n"
+        );
+        assert_eq!(
+            sign_off("Fix\n\nThe build is broken\n"),
+            "Fix\n\nThe build is broken\n\nSigned-off-by: Jane <jane@doe.com>\n"
+        );
+        // Signing off twice doesn't repeat it
+        assert_eq!(sign_off(&sign_off("Fix")), sign_off("Fix"));
+    }
+
+    /// A new directory for the repositories of a test
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("lapce-git-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn set_user(repo: &Repository) {
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Test").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+    }
+
+    fn commit_file(repo: &Repository, name: &str, content: &str) {
+        std::fs::write(repo.workdir().unwrap().join(name), content).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(name)).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = repo.signature().unwrap();
+        let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+        let parents = parent.iter().collect::<Vec<_>>();
+        repo.commit(Some("HEAD"), &signature, &signature, name, &tree, &parents)
+            .unwrap();
+    }
+
+    fn ahead_behind(repo: &Repository) -> Option<(usize, usize)> {
+        git_upstream_status(repo).map(|(_, ahead, behind)| (ahead, behind))
+    }
+
+    #[test]
+    fn test_fetch_pull_push() {
+        let dir = test_dir("remote");
+        let remote = Repository::init_bare(dir.join("remote.git")).unwrap();
+        let url = remote.path().to_str().unwrap();
+
+        let a = Repository::init(dir.join("a")).unwrap();
+        a.remote("origin", url).unwrap();
+        set_user(&a);
+        let a_path = a.workdir().unwrap();
+        commit_file(&a, "a.txt", "a\n");
+        assert_eq!(ahead_behind(&a), None);
+        // The first push sets the upstream
+        git_push(a_path).unwrap();
+        assert_eq!(ahead_behind(&a), Some((0, 0)));
+
+        let b = Repository::clone(url, dir.join("b")).unwrap();
+        set_user(&b);
+        let b_path = b.workdir().unwrap();
+        commit_file(&b, "b.txt", "b\n");
+        assert_eq!(ahead_behind(&b), Some((1, 0)));
+        git_push(b_path).unwrap();
+        assert_eq!(ahead_behind(&b), Some((0, 0)));
+
+        git_fetch(a_path).unwrap();
+        assert_eq!(ahead_behind(&a), Some((0, 1)));
+        git_pull(a_path, false).unwrap();
+        assert_eq!(ahead_behind(&a), Some((0, 0)));
+        assert!(a_path.join("b.txt").exists());
+
+        // Diverged branches only come together with a rebase
+        commit_file(&a, "c.txt", "c\n");
+        git_push(a_path).unwrap();
+        commit_file(&b, "d.txt", "d\n");
+        assert!(git_pull(b_path, false).is_err());
+        // The push is rejected, because it isn't a fast forward
+        assert!(git_push(b_path).is_err());
+        git_pull(b_path, true).unwrap();
+        assert_eq!(ahead_behind(&b), Some((1, 0)));
+        assert!(b_path.join("c.txt").exists());
+        git_push(b_path).unwrap();
+
+        // A conflict aborts the rebase, which leaves the branch as it was
+        git_pull(a_path, false).unwrap();
+        commit_file(&a, "a.txt", "x\n");
+        git_push(a_path).unwrap();
+        commit_file(&b, "a.txt", "y\n");
+        let head = b.head().unwrap().target();
+        assert!(git_pull(b_path, true).is_err());
+        assert_eq!(b.head().unwrap().target(), head);
+        assert_eq!(ahead_behind(&b), Some((1, 1)));
+        assert_eq!(
+            std::fs::read_to_string(b_path.join("a.txt")).unwrap(),
+            "y\n"
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    fn checkout(repo: &Repository, branch: &str) {
+        repo.set_head(&format!("refs/heads/{branch}")).unwrap();
+        repo.checkout_head(Some(CheckoutBuilder::new().force()))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_branches() {
+        let dir = test_dir("branches");
+        let repo = Repository::init(&dir).unwrap();
+        set_user(&repo);
+        let path = repo.workdir().unwrap();
+        commit_file(&repo, "a.txt", "a\n");
+        let main = repo.head().unwrap().shorthand().unwrap().to_string();
+
+        git_create_branch(path, "topic", None).unwrap();
+        assert_eq!(repo.head().unwrap().shorthand(), Some("topic"));
+        commit_file(&repo, "b.txt", "b\n");
+        git_create_branch(path, "other", Some(&main)).unwrap();
+        assert!(!path.join("b.txt").exists());
+        // Nothing is checked out for a branch which can't be created
+        assert!(git_create_branch(path, "topic", Some("topic")).is_err());
+        assert!(git_create_branch(path, "bad..name", Some("topic")).is_err());
+        assert!(!path.join("b.txt").exists());
+        assert_eq!(repo.head().unwrap().shorthand(), Some("other"));
+        assert!(git_delete_branch(path, "topic", false).is_err());
+
+        // Fast forwards
+        git_merge(path, "topic").unwrap();
+        assert!(path.join("b.txt").exists());
+        git_rename_branch(path, "topic", "merged").unwrap();
+        assert!(repo.find_branch("topic", BranchType::Local).is_err());
+        git_delete_branch(path, "merged", false).unwrap();
+
+        // Diverged branches are merged with a merge commit
+        git_create_branch(path, "side", None).unwrap();
+        commit_file(&repo, "c.txt", "c\n");
+        checkout(&repo, "other");
+        commit_file(&repo, "d.txt", "d\n");
+        git_merge(path, "side").unwrap();
+        assert_eq!(
+            repo.head()
+                .unwrap()
+                .peel_to_commit()
+                .unwrap()
+                .parent_count(),
+            2
+        );
+        assert!(path.join("c.txt").exists());
+        assert_eq!(repo.state(), RepositoryState::Clean);
+
+        // Conflicts leave the merge in progress
+        git_create_branch(path, "conflict", None).unwrap();
+        commit_file(&repo, "a.txt", "x\n");
+        checkout(&repo, "other");
+        commit_file(&repo, "a.txt", "y\n");
+        assert!(git_merge(path, "conflict").is_err());
+        // The index of `repo` was read before the merge
+        let mut repo = Repository::open(path).unwrap();
+        assert!(repo.index().unwrap().has_conflicts());
+        assert_eq!(repo.state(), RepositoryState::Merge);
+        assert_eq!(
+            git_conflicted_files(&repo).unwrap(),
+            vec![path.join("a.txt")]
+        );
+        assert_eq!(git_merge_heads(&mut repo).unwrap().len(), 1);
+        let stage =
+            |revision| git_file_revision(&path.join("a.txt"), revision).unwrap();
+        assert_eq!(stage(":1"), "a\n");
+        assert_eq!(stage(":2"), "y\n");
+        assert_eq!(stage(":3"), "x\n");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_stash() {
+        let dir = test_dir("stash");
+        let repo = Repository::init(&dir).unwrap();
+        set_user(&repo);
+        let path = repo.workdir().unwrap();
+        commit_file(&repo, "a.txt", "a\n");
+        let read = || std::fs::read_to_string(path.join("a.txt")).unwrap();
+
+        std::fs::write(path.join("a.txt"), "changed\n").unwrap();
+        git_stash_push(path, Some("work in progress")).unwrap();
+        assert_eq!(read(), "a\n");
+        let stashes = git_stash_list(path).unwrap();
+        assert_eq!(stashes.len(), 1);
+        assert_eq!(stashes[0].index, 0);
+        assert!(stashes[0].message.contains("work in progress"));
+
+        // Applying keeps the stash, popping drops it
+        git_stash_apply(path, 0).unwrap();
+        assert_eq!(read(), "changed\n");
+        assert_eq!(git_stash_list(path).unwrap().len(), 1);
+        repo.checkout_head(Some(CheckoutBuilder::new().force()))
+            .unwrap();
+        git_stash_pop(path, 0).unwrap();
+        assert_eq!(read(), "changed\n");
+        assert!(git_stash_list(path).unwrap().is_empty());
+
+        git_stash_push(path, None).unwrap();
+        git_stash_drop(path, 0).unwrap();
+        assert!(git_stash_list(path).unwrap().is_empty());
+        assert_eq!(read(), "a\n");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_stage_hunk() {
+        let dir = test_dir("hunk");
+        let repo = Repository::init(&dir).unwrap();
+        set_user(&repo);
+        let path = repo.workdir().unwrap().join("a.txt");
+        commit_file(&repo, "a.txt", "a\nb\nc\n");
+        std::fs::write(&path, "a\nB\nc\nd\n").unwrap();
+        let index_entry = || {
+            let mut index = repo.index().unwrap();
+            index.read(true).unwrap();
+            let entry = index.get_path(Path::new("a.txt"), 0).unwrap();
+            let blob = repo.find_blob(entry.id).unwrap();
+            (entry, String::from_utf8(blob.content().to_vec()).unwrap())
+        };
+        let status = || repo.status_file(Path::new("a.txt")).unwrap();
+
+        let hunk = GitHunk {
+            old_start: 1,
+            old_lines: 1,
+            new_start: 1,
+            new_lines: 1,
+        };
+        git_stage_hunk(&path, &hunk, "B\n").unwrap();
+        let (entry, content) = index_entry();
+        assert_eq!(content, "a\nB\nc\n");
+        // The stat doesn't match the file, which has a hunk that isn't staged
+        assert_eq!(entry.mtime.seconds(), 0);
+        assert_eq!(entry.ino, 0);
+        assert!(status().contains(Status::INDEX_MODIFIED | Status::WT_MODIFIED));
+
+        git_unstage_hunk(&path, &hunk).unwrap();
+        let (entry, content) = index_entry();
+        assert_eq!(content, "a\nb\nc\n");
+        assert_eq!(entry.mtime.seconds(), 0);
+        assert_eq!(status(), Status::WT_MODIFIED);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_commit_template() {
+        let dir = test_dir("template");
+        let repo = Repository::init(&dir).unwrap();
+        set_user(&repo);
+        let path = repo.workdir().unwrap();
+        assert_eq!(git_commit_template(path).unwrap(), None);
+
+        std::fs::write(path.join("template.txt"), "Subject\n\n# Why\n").unwrap();
+        repo.config()
+            .unwrap()
+            .set_str("commit.template", "template.txt")
+            .unwrap();
+        assert_eq!(
+            git_commit_template(path).unwrap().as_deref(),
+            Some("Subject\n\n# Why\n")
+        );
+
+        commit_file(&repo, "a.txt", "a\n");
+        assert_eq!(git_head_message(path).unwrap(), "a.txt");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_repositories() {
+        let dir = test_dir("repositories");
+        let root = Repository::init(&dir).unwrap();
+        set_user(&root);
+        commit_file(&root, "a.txt", "a\n");
+        let nested = Repository::init(dir.join("services").join("api")).unwrap();
+        set_user(&nested);
+        commit_file(&nested, "b.txt", "b\n");
+        // Hidden folders aren't searched
+        Repository::init(dir.join(".cache").join("repo")).unwrap();
+
+        let workdirs = git_repositories(&dir)
+            .iter()
+            .map(|repo| repo.workdir().unwrap().to_path_buf())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            workdirs,
+            vec![root.workdir().unwrap(), nested.workdir().unwrap()]
+        );
+
+        // The repositories are only looked for again after clearing
+        let repository_dirs = RepositoryDirs::default();
+        let count = || repository_dirs.repositories(&dir).len();
+        assert_eq!(count(), 2);
+        Repository::init(dir.join("web")).unwrap();
+        assert_eq!(count(), 2);
+        repository_dirs.clear();
+        assert_eq!(count(), 3);
+        assert!(repository_dirs.has_repository_in(&dir.join("web")));
+        assert!(repository_dirs.has_repository_in(&dir));
+        assert!(!repository_dirs.has_repository_in(&dir.join("web").join("a.bak")));
+        std::fs::remove_dir_all(dir.join("web")).unwrap();
+
+        // Files go to the innermost repository
+        let file = nested.workdir().unwrap().join("b.txt");
+        assert_eq!(
+            git_file_repository(&file).unwrap().workdir(),
+            nested.workdir()
+        );
+        let deleted = nested.workdir().unwrap().join("gone").join("c.txt");
+        assert_eq!(
+            git_file_repository(&deleted).unwrap().workdir(),
+            nested.workdir()
+        );
+
+        std::fs::write(&file, "changed\n").unwrap();
+        std::fs::write(root.workdir().unwrap().join("a.txt"), "changed\n").unwrap();
+        git_stage_files(&[file, root.workdir().unwrap().join("a.txt")]).unwrap();
+        for repo in [&root, &nested] {
+            let statuses = repo.statuses(None).unwrap();
+            assert!(statuses
+                .iter()
+                .any(|status| status.status().is_index_modified()));
+        }
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -3,7 +3,9 @@
 pub mod buffer;
 pub mod cli;
 pub mod dispatch;
+pub mod git;
 pub mod plugin;
+pub mod search;
 pub mod terminal;
 pub mod watcher;
 
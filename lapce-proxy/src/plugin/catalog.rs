@@ -11,7 +11,10 @@
 
 use lapce_rpc::plugin::VoltInfo;
 use lapce_rpc::{
-    dap_types::{self, DapId, DapServer, SetBreakpointsResponse},
+    dap_types::{
+        self, DapId, DapServer, SetBreakpointsResponse,
+        SetFunctionBreakpointsResponse,
+    },
     plugin::{PluginId, VoltID, VoltMetadata},
     proxy::ProxyResponse,
     style::LineStyle,
@@ -20,8 +23,9 @@
 use lapce_xi_rope::{Rope, RopeDelta};
 use lsp_types::request::Request;
 use lsp_types::{
-    notification::DidOpenTextDocument, DidOpenTextDocumentParams, SemanticTokens,
-    TextDocumentIdentifier, TextDocumentItem, VersionedTextDocumentIdentifier,
+    notification::DidOpenTextDocument, DidOpenTextDocumentParams, FileEvent,
+    SemanticTokens, TextDocumentIdentifier, TextDocumentItem,
+    VersionedTextDocumentIdentifier,
 };
 use parking_lot::Mutex;
 use psp_types::Notification;
@@ -322,6 +326,14 @@ pub fn handle_did_save_text_document(
         }
     }
 
+    pub fn handle_did_change_watched_files(&mut self, changes: Vec<FileEvent>) {
+        for (_, plugin) in self.plugins.iter() {
+            plugin.handle_rpc(PluginServerRpc::DidChangeWatchedFiles {
+                changes: changes.clone(),
+            });
+        }
+    }
+
     pub fn handle_did_change_text_document(
         &mut self,
         language_id: String,
@@ -458,6 +470,81 @@ pub fn dap_get_scopes(
         }
     }
 
+    pub fn dap_evaluate(
+        &self,
+        dap_id: DapId,
+        expression: String,
+        frame_id: Option<usize>,
+        context: Option<String>,
+        f: Box<dyn RpcCallback<dap_types::EvaluateResponse, RpcError>>,
+    ) {
+        if let Some(dap) = self.daps.get(&dap_id) {
+            dap.evaluate_async(
+                expression,
+                frame_id,
+                context,
+                |result: Result<dap_types::EvaluateResponse, RpcError>| {
+                    f.call(result)
+                },
+            );
+        } else {
+            f.call(Err(RpcError {
+                code: 0,
+                message: "plugin doesn't exist".to_string(),
+            }));
+        }
+    }
+
+    pub fn dap_set_variable(
+        &self,
+        dap_id: DapId,
+        variables_reference: usize,
+        name: String,
+        value: String,
+        f: Box<dyn RpcCallback<dap_types::SetVariableResponse, RpcError>>,
+    ) {
+        if let Some(dap) = self.daps.get(&dap_id) {
+            dap.set_variable_async(
+                variables_reference,
+                name,
+                value,
+                |result: Result<dap_types::SetVariableResponse, RpcError>| {
+                    f.call(result)
+                },
+            );
+        } else {
+            f.call(Err(RpcError {
+                code: 0,
+                message: "plugin doesn't exist".to_string(),
+            }));
+        }
+    }
+
+    pub fn dap_set_expression(
+        &self,
+        dap_id: DapId,
+        expression: String,
+        value: String,
+        frame_id: Option<usize>,
+        f: Box<dyn RpcCallback<dap_types::SetExpressionResponse, RpcError>>,
+    ) {
+        if let Some(dap) = self.daps.get(&dap_id) {
+            dap.set_expression_async(
+                expression,
+                value,
+                frame_id,
+                |result: Result<dap_types::SetExpressionResponse, RpcError>| {
+                    f.call(result)
+                },
+            );
+        } else {
+            f.call(Err(RpcError {
+                code: 0,
+                message: "plugin doesn't exist".to_string(),
+            }));
+        }
+    }
+
     pub fn handle_notification(&mut self, notification: PluginCatalogNotification) {
         use PluginCatalogNotification::*;
         match notification {
@@ -559,6 +646,7 @@ pub fn handle_notification(&mut self, notification: PluginCatalogNotification) {
             DapStart {
                 config,
                 breakpoints,
+                global_breakpoints,
             } => {
                 let workspace = self.workspace.clone();
                 let plugin_rpc = self.plugin_rpc.clone();
@@ -576,11 +664,12 @@ pub fn handle_notification(&mut self, notification: PluginCatalogNotification) {
                             },
                             config.clone(),
                             breakpoints,
+                            global_breakpoints,
                             plugin_rpc.clone(),
                         ) {
                             let _ = plugin_rpc.dap_loaded(dap_rpc.clone());
 
-                            let _ = dap_rpc.launch(&config);
+                            let _ = dap_rpc.start_debuggee(&config);
                         }
                     });
                 }
@@ -641,9 +730,10 @@ pub fn handle_notification(&mut self, notification: PluginCatalogNotification) {
             DapRestart {
                 dap_id,
                 breakpoints,
+                global_breakpoints,
             } => {
                 if let Some(dap) = self.daps.get(&dap_id) {
-                    dap.restart(breakpoints);
+                    dap.restart(breakpoints, global_breakpoints);
                 }
             }
             DapSetBreakpoints {
@@ -668,6 +758,35 @@ pub fn handle_notification(&mut self, notification: PluginCatalogNotification) {
                     );
                 }
             }
+            DapSetFunctionBreakpoints {
+                dap_id,
+                breakpoints,
+            } => {
+                if let Some(dap) = self.daps.get(&dap_id) {
+                    let core_rpc = self.plugin_rpc.core_rpc.clone();
+                    dap.set_function_breakpoints_async(
+                        breakpoints,
+                        move |result: Result<
+                            SetFunctionBreakpointsResponse,
+                            RpcError,
+                        >| {
+                            if let Ok(resp) = result {
+                                core_rpc.dap_function_breakpoints_resp(
+                                    dap_id,
+                                    resp.breakpoints,
+                                );
+                            }
+                        },
+                    );
+                }
+            }
+            DapSetExceptionBreakpoints { dap_id, filters } => {
+                if let Some(dap) = self.daps.get(&dap_id).cloned() {
+                    thread::spawn(move || {
+                        let _ = dap.set_exception_breakpoints(filters);
+                    });
+                }
+            }
             RegisterDebuggerType {
                 debugger_type,
                 program,
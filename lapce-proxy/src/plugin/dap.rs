@@ -14,16 +14,23 @@
 use crossbeam_channel::{Receiver, Sender};
 use lapce_rpc::{
     dap_types::{
-        self, ConfigurationDone, Continue, ContinueArguments, ContinueResponse,
-        DapEvent, DapId, DapPayload, DapRequest, DapResponse, DapServer,
-        DebuggerCapabilities, Disconnect, Initialize, Launch, Next, NextArguments,
-        Pause, PauseArguments, Request, RunDebugConfig, RunInTerminal,
-        RunInTerminalArguments, RunInTerminalResponse, Scope, Scopes,
-        ScopesArguments, ScopesResponse, SetBreakpoints, SetBreakpointsArguments,
-        SetBreakpointsResponse, Source, SourceBreakpoint, StackTrace,
-        StackTraceArguments, StackTraceResponse, StepIn, StepInArguments, StepOut,
-        StepOutArguments, Terminate, ThreadId, Threads, ThreadsResponse, Variable,
-        Variables, VariablesArguments, VariablesResponse,
+        self, Attach, ConfigurationDone, Continue, ContinueArguments,
+        ContinueResponse, DapEvent, DapId, DapPayload, DapRequest, DapResponse,
+        DapServer, DebuggerCapabilities, Disconnect, Evaluate, EvaluateArguments,
+        EvaluateResponse, FunctionBreakpoint, GlobalBreakpoints, Initialize, Launch,
+        Next, NextArguments, Pause, PauseArguments, ProcessInfo, Request,
+        RunDebugConfig, RunDebugRequest, RunInTerminal, RunInTerminalArguments,
+        RunInTerminalResponse, Scope, Scopes, ScopesArguments, ScopesResponse,
+        SetBreakpoints, SetBreakpointsArguments, SetBreakpointsResponse,
+        SetExceptionBreakpoints, SetExceptionBreakpointsArguments,
+        SetExceptionBreakpointsResponse, SetExpression, SetExpressionArguments,
+        SetExpressionResponse, SetFunctionBreakpoints,
+        SetFunctionBreakpointsArguments, SetFunctionBreakpointsResponse,
+        SetVariable, SetVariableArguments, SetVariableResponse, Source,
+        SourceBreakpoint, StackTrace, StackTraceArguments, StackTraceResponse,
+        StepIn, StepInArguments, StepOut, StepOutArguments, Terminate, ThreadId,
+        Threads, ThreadsResponse, Variable, Variables, VariablesArguments,
+        VariablesResponse,
     },
     terminal::TermId,
     RpcError,
@@ -42,6 +49,7 @@ pub struct DapClient {
     dap_server: DapServer,
     config: RunDebugConfig,
     breakpoints: HashMap<PathBuf, Vec<SourceBreakpoint>>,
+    global_breakpoints: GlobalBreakpoints,
     term_id: Option<TermId>,
     capabilities: Option<DebuggerCapabilities>,
     terminated: bool,
@@ -54,6 +62,7 @@ pub fn new(
         dap_server: DapServer,
         config: RunDebugConfig,
         breakpoints: HashMap<PathBuf, Vec<SourceBreakpoint>>,
+        global_breakpoints: GlobalBreakpoints,
         plugin_rpc: PluginCatalogRpcHandler,
     ) -> Result<Self> {
         let dap_rpc = DapRpcHandler::new(config.dap_id);
@@ -64,6 +73,7 @@ pub fn new(
             config,
             dap_rpc,
             breakpoints,
+            global_breakpoints,
             term_id: None,
             capabilities: None,
             terminated: false,
@@ -76,9 +86,16 @@ pub fn start(
         dap_server: DapServer,
         config: RunDebugConfig,
         breakpoints: HashMap<PathBuf, Vec<SourceBreakpoint>>,
+        global_breakpoints: GlobalBreakpoints,
         plugin_rpc: PluginCatalogRpcHandler,
     ) -> Result<DapRpcHandler> {
-        let mut dap = Self::new(dap_server, config, breakpoints, plugin_rpc)?;
+        let mut dap = Self::new(
+            dap_server,
+            config,
+            breakpoints,
+            global_breakpoints,
+            plugin_rpc,
+        )?;
         dap.start_process()?;
 
         let dap_rpc = dap.dap_rpc.clone();
@@ -193,6 +210,13 @@ fn handle_host_request(&mut self, req: &DapRequest) -> Result<Value> {
                 let (term_id, process_id) =
                     self.dap_rpc.termain_process_rx.recv()?;
                 self.term_id = Some(term_id);
+                // The debug session is only created in the UI with its terminal,
+                // which is after the capabilities were first sent
+                if let Some(capabilities) = self.capabilities.clone() {
+                    self.plugin_rpc
+                        .core_rpc
+                        .dap_capabilities(self.config.dap_id, capabilities);
+                }
                 let resp = RunInTerminalResponse {
                     process_id,
                     shell_process_id: None,
@@ -218,6 +242,7 @@ fn handle_host_event(&mut self, event: &DapEvent) -> Result<()> {
                         );
                     }
                 }
+                self.set_global_breakpoints();
                 // send dap configurations here
                 let _ = self.dap_rpc.request::<ConfigurationDone>(());
             }
@@ -290,12 +315,24 @@ fn handle_host_event(&mut self, event: &DapEvent) -> Result<()> {
                 let _ = self.check_restart();
             }
             DapEvent::Thread { .. } => {}
-            DapEvent::Output(_) => {}
-            DapEvent::Breakpoint { .. } => {}
+            DapEvent::Output(output) => {
+                self.plugin_rpc
+                    .core_rpc
+                    .dap_output(self.dap_rpc.dap_id, output.clone());
+            }
+            DapEvent::Breakpoint { reason, breakpoint } => {
+                self.plugin_rpc.core_rpc.dap_breakpoint_changed(
+                    self.config.dap_id,
+                    reason.clone(),
+                    breakpoint.clone(),
+                );
+            }
             DapEvent::Module { .. } => {}
             DapEvent::LoadedSource { .. } => {}
             DapEvent::Process(_) => {}
-            DapEvent::Capabilities(_) => {}
+            DapEvent::Capabilities(capabilities) => {
+                self.update_capabilities(&capabilities.capabilities);
+            }
             DapEvent::Memory(_) => {}
         }
         Ok(())
@@ -326,18 +363,77 @@ pub(crate) fn initialize(&mut self) -> Result<()> {
             .dap_rpc
             .request::<Initialize>(params)
             .map_err(|e| anyhow!(e.message))?;
+        self.plugin_rpc
+            .core_rpc
+            .dap_capabilities(self.config.dap_id, resp.clone());
         self.capabilities = Some(resp);
 
         Ok(())
     }
 
-    fn stop(&self) {
-        let dap_rpc = self.dap_rpc.clone();
-        if self
+    /// Set the function breakpoints and the exception filters the session
+    /// was started with, as far as the adapter supports them
+    fn set_global_breakpoints(&self) {
+        let Some(capabilities) = self.capabilities.as_ref() else {
+            return;
+        };
+
+        if capabilities.supports_function_breakpoints.unwrap_or(false)
+            && !self.global_breakpoints.functions.is_empty()
+        {
+            if let Ok(resp) = self
+                .dap_rpc
+                .set_function_breakpoints(self.global_breakpoints.functions.clone())
+            {
+                self.plugin_rpc.core_rpc.dap_function_breakpoints_resp(
+                    self.config.dap_id,
+                    resp.breakpoints,
+                );
+            }
+        }
+
+        if let Some(filters) = capabilities.exception_breakpoint_filters.as_ref() {
+            let filters = filters
+                .iter()
+                .filter(|f| f.enabled(&self.global_breakpoints.exception_filters))
+                .map(|f| f.filter.clone())
+                .collect();
+            let _ = self.dap_rpc.set_exception_breakpoints(filters);
+        }
+    }
+
+    /// The capabilities event only carries the capabilities that changed,
+    /// so merge them into the ones we got from the initialize response.
+    fn update_capabilities(&mut self, changed: &DebuggerCapabilities) {
+        let mut capabilities = self
             .capabilities
             .as_ref()
-            .and_then(|c| c.supports_terminate_request)
-            .unwrap_or(false)
+            .and_then(|c| serde_json::to_value(c).ok())
+            .unwrap_or_else(|| Value::Object(Default::default()));
+        if let (Some(current), Ok(Value::Object(changed))) =
+            (capabilities.as_object_mut(), serde_json::to_value(changed))
+        {
+            current.extend(changed);
+        }
+        if let Ok(capabilities) =
+            serde_json::from_value::<DebuggerCapabilities>(capabilities)
+        {
+            self.plugin_rpc
+                .core_rpc
+                .dap_capabilities(self.config.dap_id, capabilities.clone());
+            self.capabilities = Some(capabilities);
+        }
+    }
+
+    fn stop(&self) {
+        let dap_rpc = self.dap_rpc.clone();
+        // An attached process keeps running when the debugger disconnects from it
+        if !self.config.is_attach()
+            && self
+                .capabilities
+                .as_ref()
+                .and_then(|c| c.supports_terminate_request)
+                .unwrap_or(false)
         {
             thread::spawn(move || {
                 let _ = dap_rpc.terminate();
@@ -378,15 +474,20 @@ fn check_restart(&mut self) -> Result<()> {
         let dap_rpc = self.dap_rpc.clone();
         let config = self.config.clone();
         thread::spawn(move || {
-            let _ = dap_rpc.launch(&config);
+            let _ = dap_rpc.start_debuggee(&config);
         });
 
         Ok(())
     }
 
-    fn restart(&mut self, breakpoints: HashMap<PathBuf, Vec<SourceBreakpoint>>) {
+    fn restart(
+        &mut self,
+        breakpoints: HashMap<PathBuf, Vec<SourceBreakpoint>>,
+        global_breakpoints: GlobalBreakpoints,
+    ) {
         self.restarted = true;
         self.breakpoints = breakpoints;
+        self.global_breakpoints = global_breakpoints;
         if !self.terminated {
             self.stop();
         } else {
@@ -399,7 +500,7 @@ pub enum DapRpc {
     HostRequest(DapRequest),
     HostEvent(DapEvent),
     Stop,
-    Restart(HashMap<PathBuf, Vec<SourceBreakpoint>>),
+    Restart(HashMap<PathBuf, Vec<SourceBreakpoint>>, GlobalBreakpoints),
     Shutdown,
     Disconnected,
 }
@@ -463,8 +564,8 @@ pub fn mainloop(&self, dap_client: &mut DapClient) {
                 DapRpc::Stop => {
                     dap_client.stop();
                 }
-                DapRpc::Restart(breakpoints) => {
-                    dap_client.restart(breakpoints);
+                DapRpc::Restart(breakpoints, global_breakpoints) => {
+                    dap_client.restart(breakpoints, global_breakpoints);
                 }
                 DapRpc::Shutdown => {
                     if let Some(term_id) = dap_client.term_id {
@@ -586,7 +687,16 @@ pub fn handle_server_message(&self, message_str: &str) {
         }
     }
 
-    pub fn launch(&self, config: &RunDebugConfig) -> Result<()> {
+    /// Start debugging with the configuration, launching its program or
+    /// attaching to a running process
+    pub fn start_debuggee(&self, config: &RunDebugConfig) -> Result<()> {
+        match config.request {
+            RunDebugRequest::Launch => self.launch(config),
+            RunDebugRequest::Attach => self.attach(config),
+        }
+    }
+
+    fn launch(&self, config: &RunDebugConfig) -> Result<()> {
         let params = serde_json::json!({
             "program": config.program,
             "args": config.args,
@@ -599,12 +709,45 @@ pub fn launch(&self, config: &RunDebugConfig) -> Result<()> {
         Ok(())
     }
 
+    fn attach(&self, config: &RunDebugConfig) -> Result<()> {
+        // The attach arguments aren't specified by the protocol, so send the
+        // names the common debug adapters use: `pid` for CodeLLDB, `processId`
+        // for debugpy and cppdbg, and `connect` for debug servers listening on
+        // a port
+        let mut params = serde_json::json!({
+            "cwd": config.cwd,
+        });
+        if !config.program.is_empty() {
+            params["program"] = config.program.clone().into();
+        }
+        if let Some(pid) = config.pid {
+            params["pid"] = pid.into();
+            params["processId"] = pid.into();
+        }
+        if config.host.is_some() || config.port.is_some() {
+            params["connect"] = serde_json::json!({
+                "host": config.host.as_deref().unwrap_or("localhost"),
+                "port": config.port,
+            });
+        }
+        let _resp = self
+            .request::<Attach>(params)
+            .map_err(|e| anyhow!(e.message))?;
+        Ok(())
+    }
+
     pub fn stop(&self) {
         let _ = self.rpc_tx.send(DapRpc::Stop);
     }
 
-    pub fn restart(&self, breakpoints: HashMap<PathBuf, Vec<SourceBreakpoint>>) {
-        let _ = self.rpc_tx.send(DapRpc::Restart(breakpoints));
+    pub fn restart(
+        &self,
+        breakpoints: HashMap<PathBuf, Vec<SourceBreakpoint>>,
+        global_breakpoints: GlobalBreakpoints,
+    ) {
+        let _ = self
+            .rpc_tx
+            .send(DapRpc::Restart(breakpoints, global_breakpoints));
     }
 
     fn disconnected(&self) {
@@ -671,6 +814,37 @@ pub fn set_breakpoints(
         Ok(resp)
     }
 
+    pub fn set_function_breakpoints_async(
+        &self,
+        breakpoints: Vec<FunctionBreakpoint>,
+        f: impl RpcCallback<SetFunctionBreakpointsResponse, RpcError> + 'static,
+    ) {
+        let params = SetFunctionBreakpointsArguments { breakpoints };
+        self.request_async::<SetFunctionBreakpoints>(params, f);
+    }
+
+    pub fn set_function_breakpoints(
+        &self,
+        breakpoints: Vec<FunctionBreakpoint>,
+    ) -> Result<SetFunctionBreakpointsResponse> {
+        let params = SetFunctionBreakpointsArguments { breakpoints };
+        let resp = self
+            .request::<SetFunctionBreakpoints>(params)
+            .map_err(|e| anyhow!(e.message))?;
+        Ok(resp)
+    }
+
+    pub fn set_exception_breakpoints(
+        &self,
+        filters: Vec<String>,
+    ) -> Result<Option<SetExceptionBreakpointsResponse>> {
+        let params = SetExceptionBreakpointsArguments { filters };
+        let resp = self
+            .request::<SetExceptionBreakpoints>(params)
+            .map_err(|e| anyhow!(e.message))?;
+        Ok(resp)
+    }
+
     pub fn continue_thread(&self, thread_id: ThreadId) -> Result<ContinueResponse> {
         let params = ContinueArguments { thread_id };
         let resp = self
@@ -781,4 +955,235 @@ pub fn step_out(&self, thread_id: ThreadId) {
 
         self.request_async::<StepOut>(args, move |_| {});
     }
+
+    pub fn evaluate_async(
+        &self,
+        expression: String,
+        frame_id: Option<usize>,
+        context: Option<String>,
+        f: impl RpcCallback<EvaluateResponse, RpcError> + 'static,
+    ) {
+        let args = EvaluateArguments {
+            expression,
+            frame_id,
+            context,
+            format: None,
+        };
+
+        self.request_async::<Evaluate>(args, f);
+    }
+
+    pub fn set_variable_async(
+        &self,
+        variables_reference: usize,
+        name: String,
+        value: String,
+        f: impl RpcCallback<SetVariableResponse, RpcError> + 'static,
+    ) {
+        let args = SetVariableArguments {
+            variables_reference,
+            name,
+            value,
+            format: None,
+        };
+
+        self.request_async::<SetVariable>(args, f);
+    }
+
+    pub fn set_expression_async(
+        &self,
+        expression: String,
+        value: String,
+        frame_id: Option<usize>,
+        f: impl RpcCallback<SetExpressionResponse, RpcError> + 'static,
+    ) {
+        let args = SetExpressionArguments {
+            expression,
+            value,
+            frame_id,
+            format: None,
+        };
+
+        self.request_async::<SetExpression>(args, f);
+    }
+}
+
+/// The processes running on this machine, which a debugger can attach to
+pub fn list_processes() -> Result<Vec<ProcessInfo>> {
+    #[cfg(not(target_os = "windows"))]
+    {
+        let output = Command::new("ps")
+            .args(["-A", "-o", "pid=", "-o", "args="])
+            .output()?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        Ok(stdout.lines().filter_map(parse_ps_line).collect())
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        let mut process = Command::new("tasklist");
+        process.args(["/fo", "csv", "/nh"]);
+        // CREATE_NO_WINDOW
+        std::os::windows::process::CommandExt::creation_flags(
+            &mut process,
+            0x08000000,
+        );
+        let output = process.output()?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        Ok(stdout.lines().filter_map(parse_tasklist_line).collect())
+    }
+}
+
+/// Build the target of a cargo command and find the executable it produces,
+/// for a debugger to launch. `args` are the ones of e.g. `cargo test`, without
+/// the arguments of the executable itself.
+pub fn cargo_executable(
+    args: &[String],
+    cwd: Option<&str>,
+    env: Option<&HashMap<String, String>>,
+) -> Result<PathBuf> {
+    let mut args = args.to_vec();
+    match args.first().map(|arg| arg.as_str()) {
+        Some("run") => args[0] = "build".to_string(),
+        Some("test") | Some("bench") => args.insert(1, "--no-run".to_string()),
+        _ => {}
+    }
+    args.push("--message-format=json".to_string());
+
+    let mut process = Command::new("cargo");
+    process.args(&args);
+    if let Some(cwd) = cwd {
+        process.current_dir(cwd);
+    }
+    if let Some(env) = env {
+        process.envs(env);
+    }
+    #[cfg(target_os = "windows")]
+    // CREATE_NO_WINDOW
+    std::os::windows::process::CommandExt::creation_flags(&mut process, 0x08000000);
+    let output = process.output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!(
+            "cargo {} failed: {}",
+            args.join(" "),
+            stderr.lines().last().unwrap_or_default()
+        ));
+    }
+    executable_from_cargo_messages(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// The executable among the artifacts in the JSON messages of cargo, of which
+/// there has to be exactly one for the debugger to know what to launch.
+fn executable_from_cargo_messages(messages: &str) -> Result<PathBuf> {
+    let executables: Vec<PathBuf> = messages
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .filter(|message| message["reason"] == "compiler-artifact")
+        .filter_map(|message| message["executable"].as_str().map(PathBuf::from))
+        .collect();
+    match executables.as_slice() {
+        [executable] => Ok(executable.clone()),
+        [] => Err(anyhow!("cargo didn't build an executable")),
+        _ => Err(anyhow!("cargo built more than one executable")),
+    }
+}
+
+/// Parse a line of `ps -o pid= -o args=`, e.g. `  42 /usr/bin/python3 app.py`
+#[cfg(not(target_os = "windows"))]
+fn parse_ps_line(line: &str) -> Option<ProcessInfo> {
+    let (pid, command) = line.trim_start().split_once(char::is_whitespace)?;
+    let pid = pid.parse().ok()?;
+    let command = command.trim().to_string();
+    let name = command
+        .split_whitespace()
+        .next()
+        .map(|program| {
+            std::path::Path::new(program)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| program.to_string())
+        })
+        .unwrap_or_default();
+    Some(ProcessInfo { pid, name, command })
+}
+
+/// Parse a line of `tasklist /fo csv /nh`, e.g.
+/// `"python.exe","42","Console","1","10,000 K"`
+#[cfg(target_os = "windows")]
+fn parse_tasklist_line(line: &str) -> Option<ProcessInfo> {
+    let mut fields = line.trim().trim_matches('"').split("\",\"");
+    let name = fields.next()?.to_string();
+    let pid = fields.next()?.parse().ok()?;
+    Some(ProcessInfo {
+        pid,
+        command: name.clone(),
+        name,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_executable_from_cargo_messages() {
+        use super::executable_from_cargo_messages;
+
+        let lib = serde_json::json!({
+            "reason": "compiler-artifact",
+            "target": { "name": "dep" },
+            "executable": null,
+        })
+        .to_string();
+        let test = serde_json::json!({
+            "reason": "compiler-artifact",
+            "target": { "name": "foo" },
+            "executable": "/ws/target/debug/deps/foo-1234",
+        })
+        .to_string();
+        let finished =
+            serde_json::json!({ "reason": "build-finished", "success": true })
+                .to_string();
+
+        let messages = [lib.as_str(), test.as_str(), finished.as_str()].join("\n");
+        assert_eq!(
+            executable_from_cargo_messages(&messages).unwrap(),
+            std::path::PathBuf::from("/ws/target/debug/deps/foo-1234")
+        );
+        let messages = [lib.as_str(), finished.as_str()].join("\n");
+        assert!(executable_from_cargo_messages(&messages).is_err());
+        let messages = [test.as_str(), test.as_str()].join("\n");
+        assert!(executable_from_cargo_messages(&messages).is_err());
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn test_parse_ps_line() {
+        use super::parse_ps_line;
+
+        let process =
+            parse_ps_line("  42 /usr/bin/python3 -m app --port 80").unwrap();
+        assert_eq!(process.pid, 42);
+        assert_eq!(process.name, "python3");
+        assert_eq!(process.command, "/usr/bin/python3 -m app --port 80");
+
+        let process = parse_ps_line("2 [kthreadd]").unwrap();
+        assert_eq!(process.pid, 2);
+        assert_eq!(process.name, "[kthreadd]");
+
+        assert!(parse_ps_line("").is_none());
+        assert!(parse_ps_line("PID COMMAND").is_none());
+    }
+
+    #[cfg(target_os = "windows")]
+    #[test]
+    fn test_parse_tasklist_line() {
+        use super::parse_tasklist_line;
+
+        let process = parse_tasklist_line(
+            "\"python.exe\",\"42\",\"Console\",\"1\",\"10,000 K\"",
+        )
+        .unwrap();
+        assert_eq!(process.pid, 42);
+        assert_eq!(process.name, "python.exe");
+    }
 }
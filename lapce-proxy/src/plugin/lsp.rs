@@ -71,6 +71,10 @@ fn method_registered(&mut self, method: &str) -> bool {
         self.host.method_registered(method)
     }
 
+    fn request_supported(&mut self, method: &str, params: &Params) -> bool {
+        self.host.request_supported(method, params)
+    }
+
     fn document_supported(
         &mut self,
         lanaguage_id: Option<&str>,
@@ -89,7 +93,7 @@ fn handle_handler_notification(
                 self.initialize();
             }
             InitializeResult(result) => {
-                self.host.server_capabilities = result.capabilities;
+                self.host.set_server_capabilities(result.capabilities);
             }
             Shutdown => {
                 self.shutdown();
@@ -151,6 +155,10 @@ fn handle_did_change_text_document(
         );
     }
 
+    fn handle_did_change_watched_files(&self, changes: Vec<FileEvent>) {
+        self.host.handle_did_change_watched_files(changes);
+    }
+
     fn format_semantic_tokens(
         &self,
         tokens: SemanticTokens,
@@ -175,6 +183,7 @@ fn new(
         server_uri: Url,
         args: Vec<String>,
         options: Option<Value>,
+        configurations: Option<Value>,
     ) -> Result<Self> {
         let server = match server_uri.scheme() {
             "file" => {
@@ -277,6 +286,7 @@ fn new(
             plugin_rpc.core_rpc.clone(),
             server_rpc.clone(),
             plugin_rpc.clone(),
+            configurations,
         );
 
         Ok(Self {
@@ -302,6 +312,7 @@ pub fn start(
         server_uri: Url,
         args: Vec<String>,
         options: Option<Value>,
+        configurations: Option<Value>,
     ) -> Result<PluginId> {
         let mut lsp = Self::new(
             plugin_rpc,
@@ -315,6 +326,7 @@ pub fn start(
             server_uri,
             args,
             options,
+            configurations,
         )?;
         let plugin_id = lsp.server_rpc.plugin_id;
 
@@ -359,7 +371,7 @@ fn initialize(&mut self) {
             false,
         ) {
             let result: InitializeResult = serde_json::from_value(value).unwrap();
-            self.host.server_capabilities = result.capabilities;
+            self.host.set_server_capabilities(result.capabilities);
             self.server_rpc.server_notification(
                 Initialized::METHOD,
                 InitializedParams {},
@@ -23,7 +23,10 @@
 use lapce_core::directory::Directory;
 use lapce_rpc::{
     core::CoreRpcHandler,
-    dap_types::{self, DapId, RunDebugConfig, SourceBreakpoint, ThreadId},
+    dap_types::{
+        self, DapId, FunctionBreakpoint, GlobalBreakpoints, RunDebugConfig,
+        SourceBreakpoint, ThreadId,
+    },
     plugin::{PluginId, VoltInfo, VoltMetadata},
     proxy::ProxyRpcHandler,
     style::LineStyle,
@@ -33,22 +36,39 @@
 use lapce_xi_rope::{Rope, RopeDelta};
 use lsp_types::{
     request::{
-        CodeActionRequest, CodeActionResolveRequest, Completion,
-        DocumentSymbolRequest, Formatting, GotoDefinition, GotoTypeDefinition,
-        GotoTypeDefinitionParams, GotoTypeDefinitionResponse, HoverRequest,
-        InlayHintRequest, InlineCompletionRequest, PrepareRenameRequest, References,
-        Rename, Request, ResolveCompletionItem, SelectionRangeRequest,
-        SemanticTokensFullRequest, SignatureHelpRequest, WorkspaceSymbolRequest,
+        CallHierarchyIncomingCalls, CallHierarchyOutgoingCalls,
+        CallHierarchyPrepare, CodeActionRequest, CodeActionResolveRequest,
+        CodeLensRequest, CodeLensResolve, Completion, DocumentHighlightRequest,
+        DocumentSymbolRequest, ExecuteCommand, FoldingRangeRequest, Formatting,
+        GotoDeclaration, GotoDeclarationParams, GotoDeclarationResponse,
+        GotoDefinition, GotoImplementation, GotoImplementationParams,
+        GotoImplementationResponse, GotoTypeDefinition, GotoTypeDefinitionParams,
+        GotoTypeDefinitionResponse, HoverRequest, InlayHintRequest,
+        InlineCompletionRequest, OnTypeFormatting, PrepareRenameRequest,
+        RangeFormatting, References, Rename, Request, ResolveCompletionItem,
+        SelectionRangeRequest, SemanticTokensFullRequest, SignatureHelpRequest,
+        TypeHierarchyPrepare, TypeHierarchySubtypes, TypeHierarchySupertypes,
+        WorkspaceSymbolRequest,
     },
-    ClientCapabilities, CodeAction, CodeActionCapabilityResolveSupport,
-    CodeActionClientCapabilities, CodeActionContext, CodeActionKind,
-    CodeActionKindLiteralSupport, CodeActionLiteralSupport, CodeActionParams,
-    CodeActionResponse, CompletionClientCapabilities, CompletionItem,
-    CompletionItemCapability, CompletionItemCapabilityResolveSupport,
-    CompletionParams, CompletionResponse, Diagnostic, DocumentFormattingParams,
-    DocumentSymbolParams, DocumentSymbolResponse, FormattingOptions, GotoCapability,
-    GotoDefinitionParams, GotoDefinitionResponse, Hover, HoverClientCapabilities,
-    HoverParams, InlayHint, InlayHintClientCapabilities, InlayHintParams,
+    ApplyWorkspaceEditResponse, CallHierarchyClientCapabilities,
+    CallHierarchyIncomingCall, CallHierarchyIncomingCallsParams, CallHierarchyItem,
+    CallHierarchyOutgoingCall, CallHierarchyOutgoingCallsParams,
+    CallHierarchyPrepareParams, ClientCapabilities, CodeAction,
+    CodeActionCapabilityResolveSupport, CodeActionClientCapabilities,
+    CodeActionContext, CodeActionKind, CodeActionKindLiteralSupport,
+    CodeActionLiteralSupport, CodeActionParams, CodeActionResponse, CodeLens,
+    CodeLensClientCapabilities, CodeLensParams, Command,
+    CompletionClientCapabilities, CompletionItem, CompletionItemCapability,
+    CompletionItemCapabilityResolveSupport, CompletionParams, CompletionResponse,
+    Diagnostic, DidChangeWatchedFilesClientCapabilities, DocumentFormattingParams,
+    DocumentHighlight, DocumentHighlightClientCapabilities, DocumentHighlightParams,
+    DocumentOnTypeFormattingClientCapabilities, DocumentOnTypeFormattingParams,
+    DocumentRangeFormattingClientCapabilities, DocumentRangeFormattingParams,
+    DocumentSymbolParams, DocumentSymbolResponse, ExecuteCommandParams, FileEvent,
+    FoldingRange, FoldingRangeClientCapabilities, FoldingRangeParams,
+    FormattingOptions, GotoCapability, GotoDefinitionParams, GotoDefinitionResponse,
+    Hover, HoverClientCapabilities, HoverParams, InlayHint,
+    InlayHintClientCapabilities, InlayHintParams,
     InlineCompletionClientCapabilities, InlineCompletionParams,
     InlineCompletionResponse, InlineCompletionTriggerKind, Location, MarkupKind,
     MessageActionItemCapabilities, ParameterInformationSettings,
@@ -60,10 +80,11 @@
     SignatureHelpClientCapabilities, SignatureHelpParams,
     SignatureInformationSettings, SymbolInformation, TextDocumentClientCapabilities,
     TextDocumentIdentifier, TextDocumentItem, TextDocumentPositionParams,
-    TextDocumentSyncClientCapabilities, TextEdit, Url,
-    VersionedTextDocumentIdentifier, WindowClientCapabilities,
-    WorkDoneProgressParams, WorkspaceClientCapabilities, WorkspaceEdit,
-    WorkspaceSymbolClientCapabilities, WorkspaceSymbolParams,
+    TextDocumentSyncClientCapabilities, TextEdit, TypeHierarchyClientCapabilities,
+    TypeHierarchyItem, TypeHierarchyPrepareParams, TypeHierarchySubtypesParams,
+    TypeHierarchySupertypesParams, Url, VersionedTextDocumentIdentifier,
+    WindowClientCapabilities, WorkDoneProgressParams, WorkspaceClientCapabilities,
+    WorkspaceEdit, WorkspaceSymbolClientCapabilities, WorkspaceSymbolParams,
 };
 use parking_lot::Mutex;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
@@ -74,7 +95,7 @@
 use self::{
     catalog::PluginCatalog,
     dap::DapRpcHandler,
-    psp::{ClonableCallback, PluginServerRpcHandler, RpcCallback},
+    psp::{ClonableCallback, PluginServerRpcHandler, ResponseSender, RpcCallback},
     wasi::{load_volt, start_volt},
 };
 use crate::buffer::language_id_from_path;
@@ -122,6 +143,27 @@ pub enum PluginCatalogRpc {
             >,
         >,
     },
+    DapEvaluate {
+        dap_id: DapId,
+        expression: String,
+        frame_id: Option<usize>,
+        context: Option<String>,
+        f: Box<dyn RpcCallback<dap_types::EvaluateResponse, RpcError>>,
+    },
+    DapSetVariable {
+        dap_id: DapId,
+        variables_reference: usize,
+        name: String,
+        value: String,
+        f: Box<dyn RpcCallback<dap_types::SetVariableResponse, RpcError>>,
+    },
+    DapSetExpression {
+        dap_id: DapId,
+        expression: String,
+        value: String,
+        frame_id: Option<usize>,
+        f: Box<dyn RpcCallback<dap_types::SetExpressionResponse, RpcError>>,
+    },
     DidOpenTextDocument {
         document: TextDocumentItem,
     },
@@ -138,6 +180,9 @@ pub enum PluginCatalogRpc {
         text_document: TextDocumentIdentifier,
         text: Rope,
     },
+    DidChangeWatchedFiles {
+        changes: Vec<FileEvent>,
+    },
     Handler(PluginCatalogNotification),
     RemoveVolt {
         volt: VoltInfo,
@@ -160,6 +205,7 @@ pub enum PluginCatalogNotification {
     DapStart {
         config: RunDebugConfig,
         breakpoints: HashMap<PathBuf, Vec<SourceBreakpoint>>,
+        global_breakpoints: GlobalBreakpoints,
     },
     DapProcessId {
         dap_id: DapId,
@@ -195,12 +241,21 @@ pub enum PluginCatalogNotification {
     DapRestart {
         dap_id: DapId,
         breakpoints: HashMap<PathBuf, Vec<SourceBreakpoint>>,
+        global_breakpoints: GlobalBreakpoints,
     },
     DapSetBreakpoints {
         dap_id: DapId,
         path: PathBuf,
         breakpoints: Vec<SourceBreakpoint>,
     },
+    DapSetFunctionBreakpoints {
+        dap_id: DapId,
+        breakpoints: Vec<FunctionBreakpoint>,
+    },
+    DapSetExceptionBreakpoints {
+        dap_id: DapId,
+        filters: Vec<String>,
+    },
     RegisterDebuggerType {
         debugger_type: String,
         program: String,
@@ -215,10 +270,12 @@ pub struct PluginCatalogRpcHandler {
     proxy_rpc: ProxyRpcHandler,
     plugin_tx: Sender<PluginCatalogRpc>,
     plugin_rx: Arc<Mutex<Option<Receiver<PluginCatalogRpc>>>>,
-    #[allow(dead_code)]
     id: Arc<AtomicU64>,
     #[allow(dead_code, clippy::type_complexity)]
     pending: Arc<Mutex<HashMap<u64, Sender<Result<Value, RpcError>>>>>,
+    /// The language servers' `workspace/applyEdit` requests which are waiting
+    /// for the edit to be applied in the UI
+    workspace_edits: Arc<Mutex<HashMap<u64, ResponseSender>>>,
 }
 
 impl PluginCatalogRpcHandler {
@@ -231,6 +288,7 @@ pub fn new(core_rpc: CoreRpcHandler, proxy_rpc: ProxyRpcHandler) -> Self {
             plugin_rx: Arc::new(Mutex::new(Some(plugin_rx))),
             id: Arc::new(AtomicU64::new(0)),
             pending: Arc::new(Mutex::new(HashMap::new())),
+            workspace_edits: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
@@ -325,6 +383,9 @@ pub fn mainloop(&self, plugin: &mut PluginCatalog) {
                         new_text,
                     );
                 }
+                PluginCatalogRpc::DidChangeWatchedFiles { changes } => {
+                    plugin.handle_did_change_watched_files(changes);
+                }
                 PluginCatalogRpc::DapVariable {
                     dap_id,
                     reference,
@@ -339,6 +400,40 @@ pub fn mainloop(&self, plugin: &mut PluginCatalog) {
                 } => {
                     plugin.dap_get_scopes(dap_id, frame_id, f);
                 }
+                PluginCatalogRpc::DapEvaluate {
+                    dap_id,
+                    expression,
+                    frame_id,
+                    context,
+                    f,
+                } => {
+                    plugin.dap_evaluate(dap_id, expression, frame_id, context, f);
+                }
+                PluginCatalogRpc::DapSetVariable {
+                    dap_id,
+                    variables_reference,
+                    name,
+                    value,
+                    f,
+                } => {
+                    plugin.dap_set_variable(
+                        dap_id,
+                        variables_reference,
+                        name,
+                        value,
+                        f,
+                    );
+                }
+                PluginCatalogRpc::DapSetExpression {
+                    dap_id,
+                    expression,
+                    value,
+                    frame_id,
+                    f,
+                } => {
+                    plugin
+                        .dap_set_expression(dap_id, expression, value, frame_id, f);
+                }
                 PluginCatalogRpc::Shutdown => {
                     return;
                 }
@@ -491,6 +586,13 @@ pub fn did_save_text_document(&self, path: &Path, text: Rope) {
         });
     }
 
+    /// Tell the plugins about files in the workspace which changed on disk
+    pub fn did_change_watched_files(&self, changes: Vec<FileEvent>) {
+        let _ = self
+            .plugin_tx
+            .send(PluginCatalogRpc::DidChangeWatchedFiles { changes });
+    }
+
     pub fn did_change_text_document(
         &self,
         path: &Path,
@@ -577,6 +679,99 @@ pub fn get_type_definition(
         );
     }
 
+    pub fn get_implementation(
+        &self,
+        path: &Path,
+        position: Position,
+        cb: impl FnOnce(PluginId, Result<GotoImplementationResponse, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let method = GotoImplementation::METHOD;
+        let params = GotoImplementationParams {
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position,
+            },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+
+        let language_id =
+            Some(language_id_from_path(path).unwrap_or("").to_string());
+        self.send_request_to_all_plugins(
+            method,
+            params,
+            language_id,
+            Some(path.to_path_buf()),
+            cb,
+        );
+    }
+
+    pub fn get_document_highlight(
+        &self,
+        path: &Path,
+        position: Position,
+        cb: impl FnOnce(PluginId, Result<Option<Vec<DocumentHighlight>>, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let method = DocumentHighlightRequest::METHOD;
+        let params = DocumentHighlightParams {
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position,
+            },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+
+        let language_id =
+            Some(language_id_from_path(path).unwrap_or("").to_string());
+        self.send_request_to_all_plugins(
+            method,
+            params,
+            language_id,
+            Some(path.to_path_buf()),
+            cb,
+        );
+    }
+
+    pub fn get_declaration(
+        &self,
+        path: &Path,
+        position: Position,
+        cb: impl FnOnce(PluginId, Result<GotoDeclarationResponse, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let method = GotoDeclaration::METHOD;
+        let params = GotoDeclarationParams {
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position,
+            },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+
+        let language_id =
+            Some(language_id_from_path(path).unwrap_or("").to_string());
+        self.send_request_to_all_plugins(
+            method,
+            params,
+            language_id,
+            Some(path.to_path_buf()),
+            cb,
+        );
+    }
+
     pub fn get_references(
         &self,
         path: &Path,
@@ -675,6 +870,95 @@ pub fn get_inlay_hints(
         );
     }
 
+    pub fn get_folding_range(
+        &self,
+        path: &Path,
+        cb: impl FnOnce(PluginId, Result<Option<Vec<FoldingRange>>, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let method = FoldingRangeRequest::METHOD;
+        let params = FoldingRangeParams {
+            text_document: TextDocumentIdentifier { uri },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+        let language_id =
+            Some(language_id_from_path(path).unwrap_or("").to_string());
+        self.send_request_to_all_plugins(
+            method,
+            params,
+            language_id,
+            Some(path.to_path_buf()),
+            cb,
+        );
+    }
+
+    /// Ask the UI to apply a workspace edit, and answer the language server
+    /// once it reports back whether the edit was applied.
+    pub fn apply_workspace_edit(&self, edit: WorkspaceEdit, resp: ResponseSender) {
+        let id = self.id.fetch_add(1, Ordering::Relaxed);
+        self.workspace_edits.lock().insert(id, resp);
+        self.core_rpc.apply_workspace_edit(id, edit);
+    }
+
+    pub fn workspace_edit_applied(
+        &self,
+        id: u64,
+        applied: bool,
+        failure_reason: Option<String>,
+    ) {
+        if let Some(resp) = self.workspace_edits.lock().remove(&id) {
+            resp.send(ApplyWorkspaceEditResponse {
+                applied,
+                failure_reason,
+                failed_change: None,
+            });
+        }
+    }
+
+    /// Ask every plugin for the code lenses of the file. Unlike most requests,
+    /// all of the responses are sent on, as the editor shows the code lenses
+    /// of all plugins together.
+    pub fn get_code_lens(&self, path: &Path, rev: u64) {
+        let uri = Url::from_file_path(path).unwrap();
+        let method = CodeLensRequest::METHOD;
+        let params = CodeLensParams {
+            text_document: TextDocumentIdentifier { uri },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+        let core_rpc = self.core_rpc.clone();
+        let language_id =
+            Some(language_id_from_path(path).unwrap_or("").to_string());
+        let path = path.to_path_buf();
+        self.send_request(
+            None,
+            None,
+            method,
+            params,
+            language_id,
+            Some(path.clone()),
+            true,
+            move |plugin_id, result| {
+                if let Ok(value) = result {
+                    if let Ok(lenses) =
+                        serde_json::from_value::<Option<Vec<CodeLens>>>(value)
+                    {
+                        core_rpc.code_lens_response(
+                            path,
+                            rev,
+                            plugin_id,
+                            lenses.unwrap_or_default(),
+                        );
+                    }
+                }
+            },
+        );
+    }
+
     pub fn get_inline_completions(
         &self,
         path: &Path,
@@ -782,6 +1066,73 @@ pub fn get_document_formatting(
         );
     }
 
+    pub fn get_document_range_formatting(
+        &self,
+        path: &Path,
+        range: Range,
+        cb: impl FnOnce(PluginId, Result<Option<Vec<TextEdit>>, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let method = RangeFormatting::METHOD;
+        let params = DocumentRangeFormattingParams {
+            text_document: TextDocumentIdentifier { uri },
+            range,
+            options: FormattingOptions {
+                tab_size: 4,
+                insert_spaces: true,
+                ..Default::default()
+            },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+        };
+        let language_id =
+            Some(language_id_from_path(path).unwrap_or("").to_string());
+        self.send_request_to_all_plugins(
+            method,
+            params,
+            language_id,
+            Some(path.to_path_buf()),
+            cb,
+        );
+    }
+
+    pub fn get_document_on_type_formatting(
+        &self,
+        path: &Path,
+        position: Position,
+        ch: String,
+        cb: impl FnOnce(PluginId, Result<Option<Vec<TextEdit>>, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let method = OnTypeFormatting::METHOD;
+        let params = DocumentOnTypeFormattingParams {
+            text_document_position: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position,
+            },
+            ch,
+            options: FormattingOptions {
+                tab_size: 4,
+                insert_spaces: true,
+                ..Default::default()
+            },
+        };
+        let language_id =
+            Some(language_id_from_path(path).unwrap_or("").to_string());
+        self.send_request_to_all_plugins(
+            method,
+            params,
+            language_id,
+            Some(path.to_path_buf()),
+            cb,
+        );
+    }
+
     pub fn prepare_rename(
         &self,
         path: &Path,
@@ -1075,6 +1426,228 @@ pub fn action_resolve(
         );
     }
 
+    pub fn code_lens_resolve(
+        &self,
+        plugin_id: PluginId,
+        code_lens: CodeLens,
+        cb: impl FnOnce(Result<CodeLens, RpcError>) + Send + Clone + 'static,
+    ) {
+        let method = CodeLensResolve::METHOD;
+        self.send_request(
+            Some(plugin_id),
+            None,
+            method,
+            code_lens,
+            None,
+            None,
+            true,
+            move |_, result| {
+                let result = match result {
+                    Ok(value) => {
+                        if let Ok(item) = serde_json::from_value::<CodeLens>(value) {
+                            Ok(item)
+                        } else {
+                            Err(RpcError {
+                                code: 0,
+                                message: "code_lens item deserialize error"
+                                    .to_string(),
+                            })
+                        }
+                    }
+                    Err(e) => Err(e),
+                };
+                cb(result)
+            },
+        );
+    }
+
+    pub fn execute_command(
+        &self,
+        plugin_id: PluginId,
+        command: Command,
+        cb: impl FnOnce(Result<Value, RpcError>) + Send + Clone + 'static,
+    ) {
+        let method = ExecuteCommand::METHOD;
+        let params = ExecuteCommandParams {
+            command: command.command,
+            arguments: command.arguments.unwrap_or_default(),
+            work_done_progress_params: WorkDoneProgressParams::default(),
+        };
+        self.send_request(
+            Some(plugin_id),
+            None,
+            method,
+            params,
+            None,
+            None,
+            true,
+            move |_, result| cb(result),
+        );
+    }
+
+    /// Send a request to a single plugin, deserializing its response.
+    fn send_request_to_plugin<P, Resp>(
+        &self,
+        plugin_id: PluginId,
+        method: &'static str,
+        params: P,
+        cb: impl FnOnce(Result<Resp, RpcError>) + Send + Clone + 'static,
+    ) where
+        P: Serialize,
+        Resp: DeserializeOwned,
+    {
+        self.send_request(
+            Some(plugin_id),
+            None,
+            method,
+            params,
+            None,
+            None,
+            true,
+            move |_, result| {
+                let result = result.and_then(|value| {
+                    serde_json::from_value::<Resp>(value).map_err(|_| RpcError {
+                        code: 0,
+                        message: "deserialize error".to_string(),
+                    })
+                });
+                cb(result)
+            },
+        );
+    }
+
+    pub fn prepare_call_hierarchy(
+        &self,
+        path: &Path,
+        position: Position,
+        cb: impl FnOnce(PluginId, Result<Option<Vec<CallHierarchyItem>>, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let method = CallHierarchyPrepare::METHOD;
+        let params = CallHierarchyPrepareParams {
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position,
+            },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+        };
+
+        let language_id =
+            Some(language_id_from_path(path).unwrap_or("").to_string());
+        self.send_request_to_all_plugins(
+            method,
+            params,
+            language_id,
+            Some(path.to_path_buf()),
+            cb,
+        );
+    }
+
+    pub fn call_hierarchy_incoming_calls(
+        &self,
+        plugin_id: PluginId,
+        item: CallHierarchyItem,
+        cb: impl FnOnce(Result<Option<Vec<CallHierarchyIncomingCall>>, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        let method = CallHierarchyIncomingCalls::METHOD;
+        let params = CallHierarchyIncomingCallsParams {
+            item,
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+        self.send_request_to_plugin(plugin_id, method, params, cb);
+    }
+
+    pub fn call_hierarchy_outgoing_calls(
+        &self,
+        plugin_id: PluginId,
+        item: CallHierarchyItem,
+        cb: impl FnOnce(Result<Option<Vec<CallHierarchyOutgoingCall>>, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        let method = CallHierarchyOutgoingCalls::METHOD;
+        let params = CallHierarchyOutgoingCallsParams {
+            item,
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+        self.send_request_to_plugin(plugin_id, method, params, cb);
+    }
+
+    pub fn prepare_type_hierarchy(
+        &self,
+        path: &Path,
+        position: Position,
+        cb: impl FnOnce(PluginId, Result<Option<Vec<TypeHierarchyItem>>, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let method = TypeHierarchyPrepare::METHOD;
+        let params = TypeHierarchyPrepareParams {
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position,
+            },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+        };
+
+        let language_id =
+            Some(language_id_from_path(path).unwrap_or("").to_string());
+        self.send_request_to_all_plugins(
+            method,
+            params,
+            language_id,
+            Some(path.to_path_buf()),
+            cb,
+        );
+    }
+
+    pub fn type_hierarchy_supertypes(
+        &self,
+        plugin_id: PluginId,
+        item: TypeHierarchyItem,
+        cb: impl FnOnce(Result<Option<Vec<TypeHierarchyItem>>, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        let method = TypeHierarchySupertypes::METHOD;
+        let params = TypeHierarchySupertypesParams {
+            item,
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+        self.send_request_to_plugin(plugin_id, method, params, cb);
+    }
+
+    pub fn type_hierarchy_subtypes(
+        &self,
+        plugin_id: PluginId,
+        item: TypeHierarchyItem,
+        cb: impl FnOnce(Result<Option<Vec<TypeHierarchyItem>>, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        let method = TypeHierarchySubtypes::METHOD;
+        let params = TypeHierarchySubtypesParams {
+            item,
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+        self.send_request_to_plugin(plugin_id, method, params, cb);
+    }
+
     pub fn did_open_document(
         &self,
         path: &Path,
@@ -1168,10 +1741,12 @@ pub fn dap_start(
         &self,
         config: RunDebugConfig,
         breakpoints: HashMap<PathBuf, Vec<SourceBreakpoint>>,
+        global_breakpoints: GlobalBreakpoints,
     ) -> Result<()> {
         self.catalog_notification(PluginCatalogNotification::DapStart {
             config,
             breakpoints,
+            global_breakpoints,
         })
     }
 
@@ -1237,10 +1812,12 @@ pub fn dap_restart(
         &self,
         dap_id: DapId,
         breakpoints: HashMap<PathBuf, Vec<SourceBreakpoint>>,
+        global_breakpoints: GlobalBreakpoints,
     ) -> Result<()> {
         self.catalog_notification(PluginCatalogNotification::DapRestart {
             dap_id,
             breakpoints,
+            global_breakpoints,
         })
     }
 
@@ -1257,6 +1834,32 @@ pub fn dap_set_breakpoints(
         })
     }
 
+    pub fn dap_set_function_breakpoints(
+        &self,
+        dap_id: DapId,
+        breakpoints: Vec<FunctionBreakpoint>,
+    ) -> Result<()> {
+        self.catalog_notification(
+            PluginCatalogNotification::DapSetFunctionBreakpoints {
+                dap_id,
+                breakpoints,
+            },
+        )
+    }
+
+    pub fn dap_set_exception_breakpoints(
+        &self,
+        dap_id: DapId,
+        filters: Vec<String>,
+    ) -> Result<()> {
+        self.catalog_notification(
+            PluginCatalogNotification::DapSetExceptionBreakpoints {
+                dap_id,
+                filters,
+            },
+        )
+    }
+
     pub fn dap_variable(
         &self,
         dap_id: DapId,
@@ -1286,6 +1889,59 @@ pub fn dap_get_scopes(
         });
     }
 
+    pub fn dap_evaluate(
+        &self,
+        dap_id: DapId,
+        expression: String,
+        frame_id: Option<usize>,
+        context: Option<String>,
+        f: impl FnOnce(Result<dap_types::EvaluateResponse, RpcError>) + Send + 'static,
+    ) {
+        let _ = self.plugin_tx.send(PluginCatalogRpc::DapEvaluate {
+            dap_id,
+            expression,
+            frame_id,
+            context,
+            f: Box::new(f),
+        });
+    }
+
+    pub fn dap_set_variable(
+        &self,
+        dap_id: DapId,
+        variables_reference: usize,
+        name: String,
+        value: String,
+        f: impl FnOnce(Result<dap_types::SetVariableResponse, RpcError>) + Send + 'static,
+    ) {
+        let _ = self.plugin_tx.send(PluginCatalogRpc::DapSetVariable {
+            dap_id,
+            variables_reference,
+            name,
+            value,
+            f: Box::new(f),
+        });
+    }
+
+    pub fn dap_set_expression(
+        &self,
+        dap_id: DapId,
+        expression: String,
+        value: String,
+        frame_id: Option<usize>,
+        f: impl FnOnce(Result<dap_types::SetExpressionResponse, RpcError>)
+            + Send
+            + 'static,
+    ) {
+        let _ = self.plugin_tx.send(PluginCatalogRpc::DapSetExpression {
+            dap_id,
+            expression,
+            value,
+            frame_id,
+            f: Box::new(f),
+        });
+    }
+
     pub fn register_debugger_type(
         &self,
         debugger_type: String,
@@ -1513,12 +2169,43 @@ fn client_capabilities() -> ClientCapabilities {
             definition: Some(GotoCapability {
                 ..Default::default()
             }),
+            implementation: Some(GotoCapability {
+                // Note: This is explicitly specified for the same reason as `type_definition`
+                link_support: Some(false),
+                ..Default::default()
+            }),
+            declaration: Some(GotoCapability {
+                link_support: Some(false),
+                ..Default::default()
+            }),
             publish_diagnostics: Some(PublishDiagnosticsClientCapabilities {
                 ..Default::default()
             }),
             inline_completion: Some(InlineCompletionClientCapabilities {
                 ..Default::default()
             }),
+            code_lens: Some(CodeLensClientCapabilities {
+                ..Default::default()
+            }),
+            document_highlight: Some(DocumentHighlightClientCapabilities {
+                ..Default::default()
+            }),
+            folding_range: Some(FoldingRangeClientCapabilities {
+                line_folding_only: Some(true),
+                ..Default::default()
+            }),
+            call_hierarchy: Some(CallHierarchyClientCapabilities {
+                ..Default::default()
+            }),
+            type_hierarchy: Some(TypeHierarchyClientCapabilities {
+                ..Default::default()
+            }),
+            range_formatting: Some(DocumentRangeFormattingClientCapabilities {
+                ..Default::default()
+            }),
+            on_type_formatting: Some(DocumentOnTypeFormattingClientCapabilities {
+                ..Default::default()
+            }),
 
             ..Default::default()
         }),
@@ -1535,10 +2222,28 @@ fn client_capabilities() -> ClientCapabilities {
             symbol: Some(WorkspaceSymbolClientCapabilities {
                 ..Default::default()
             }),
-            configuration: Some(false),
+            apply_edit: Some(true),
+            configuration: Some(true),
+            did_change_watched_files: Some(
+                DidChangeWatchedFilesClientCapabilities {
+                    dynamic_registration: Some(true),
+                    relative_pattern_support: Some(true),
+                },
+            ),
             workspace_folders: Some(true),
             ..Default::default()
         }),
+        // The commands of code lenses that are handled by the editor itself,
+        // rust-analyzer only sends its run/debug lenses when these are listed.
+        experimental: Some(serde_json::json!({
+            "commands": {
+                "commands": [
+                    "rust-analyzer.runSingle",
+                    "rust-analyzer.debugSingle",
+                    "rust-analyzer.showReferences",
+                ],
+            },
+        })),
         ..Default::default()
     }
 }
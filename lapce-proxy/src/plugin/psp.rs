@@ -13,6 +13,7 @@
 use crossbeam_channel::{Receiver, Sender};
 use dyn_clone::DynClone;
 use floem_editor_core::buffer::rope_text::{RopeText, RopeTextRef};
+use globset::{GlobBuilder, GlobMatcher};
 use jsonrpc_lite::{Id, JsonRpc, Params};
 use lapce_core::{encoding::offset_utf16_to_utf8, rope_text_pos::RopeTextPosition};
 use lapce_rpc::{
@@ -24,27 +25,37 @@
 use lapce_xi_rope::{Rope, RopeDelta};
 use lsp_types::{
     notification::{
-        DidChangeTextDocument, DidOpenTextDocument, DidSaveTextDocument,
-        Initialized, LogMessage, Notification, Progress, PublishDiagnostics,
-        ShowMessage,
+        DidChangeTextDocument, DidChangeWatchedFiles, DidOpenTextDocument,
+        DidSaveTextDocument, Initialized, LogMessage, Notification, Progress,
+        PublishDiagnostics, ShowMessage,
     },
     request::{
-        CodeActionRequest, CodeActionResolveRequest, Completion,
-        DocumentSymbolRequest, Formatting, GotoDefinition, GotoTypeDefinition,
+        ApplyWorkspaceEdit, CallHierarchyIncomingCalls, CallHierarchyOutgoingCalls,
+        CallHierarchyPrepare, CodeActionRequest, CodeActionResolveRequest,
+        CodeLensRequest, CodeLensResolve, Completion, DocumentHighlightRequest,
+        DocumentSymbolRequest, ExecuteCommand, FoldingRangeRequest, Formatting,
+        GotoDeclaration, GotoDefinition, GotoImplementation, GotoTypeDefinition,
         HoverRequest, Initialize, InlayHintRequest, InlineCompletionRequest,
-        PrepareRenameRequest, References, RegisterCapability, Rename,
-        ResolveCompletionItem, SelectionRangeRequest, SemanticTokensFullRequest,
-        SignatureHelpRequest, WorkDoneProgressCreate, WorkspaceSymbolRequest,
+        OnTypeFormatting, PrepareRenameRequest, RangeFormatting, References,
+        RegisterCapability, Rename, ResolveCompletionItem, SelectionRangeRequest,
+        SemanticTokensFullRequest, SignatureHelpRequest, TypeHierarchyPrepare,
+        TypeHierarchySubtypes, TypeHierarchySupertypes, UnregisterCapability,
+        WorkDoneProgressCreate, WorkspaceConfiguration, WorkspaceSymbolRequest,
     },
-    CodeActionProviderCapability, DidChangeTextDocumentParams,
-    DidSaveTextDocumentParams, DocumentSelector, HoverProviderCapability,
-    InitializeResult, LogMessageParams, OneOf, ProgressParams,
-    PublishDiagnosticsParams, Range, Registration, RegistrationParams,
-    SemanticTokens, SemanticTokensLegend, SemanticTokensServerCapabilities,
-    ServerCapabilities, ShowMessageParams, TextDocumentContentChangeEvent,
-    TextDocumentIdentifier, TextDocumentSaveRegistrationOptions,
-    TextDocumentSyncCapability, TextDocumentSyncKind, TextDocumentSyncSaveOptions,
-    VersionedTextDocumentIdentifier,
+    ApplyWorkspaceEditParams, CallHierarchyServerCapability,
+    CodeActionProviderCapability, ConfigurationParams, DeclarationCapability,
+    DidChangeTextDocumentParams, DidChangeWatchedFilesParams,
+    DidChangeWatchedFilesRegistrationOptions, DidSaveTextDocumentParams,
+    DocumentOnTypeFormattingParams, DocumentSelector, FileChangeType, FileEvent,
+    FileSystemWatcher, FoldingRangeProviderCapability, GlobPattern,
+    HoverProviderCapability, ImplementationProviderCapability, InitializeResult,
+    LogMessageParams, OneOf, ProgressParams, PublishDiagnosticsParams, Range,
+    Registration, RegistrationParams, SemanticTokens, SemanticTokensLegend,
+    SemanticTokensServerCapabilities, ServerCapabilities, ShowMessageParams,
+    TextDocumentContentChangeEvent, TextDocumentIdentifier,
+    TextDocumentSaveRegistrationOptions, TextDocumentSyncCapability,
+    TextDocumentSyncKind, TextDocumentSyncSaveOptions, UnregistrationParams,
+    VersionedTextDocumentIdentifier, WatchKind,
 };
 use parking_lot::Mutex;
 use psp_types::{
@@ -154,6 +165,9 @@ pub enum PluginServerRpc {
             )>,
         >,
     },
+    DidChangeWatchedFiles {
+        changes: Vec<FileEvent>,
+    },
     FormatSemanticTokens {
         tokens: SemanticTokens,
         text: Rope,
@@ -209,6 +223,7 @@ fn document_supported(
         path: Option<&Path>,
     ) -> bool;
     fn method_registered(&mut self, method: &str) -> bool;
+    fn request_supported(&mut self, method: &str, params: &Params) -> bool;
     fn handle_host_notification(&mut self, method: String, params: Params);
     fn handle_host_request(
         &mut self,
@@ -242,6 +257,7 @@ fn handle_did_change_text_document(
             )>,
         >,
     );
+    fn handle_did_change_watched_files(&self, changes: Vec<FileEvent>);
     fn format_semantic_tokens(
         &self,
         tokens: SemanticTokens,
@@ -440,6 +456,7 @@ pub fn mainloop<H>(&self, handler: &mut H)
                     if handler
                         .document_supported(language_id.as_deref(), path.as_deref())
                         && handler.method_registered(&method)
+                        && handler.request_supported(&method, &params)
                     {
                         self.send_server_request(id, &method, params, rh);
                     } else {
@@ -503,6 +520,9 @@ pub fn mainloop<H>(&self, handler: &mut H)
                         change,
                     );
                 }
+                PluginServerRpc::DidChangeWatchedFiles { changes } => {
+                    handler.handle_did_change_watched_files(changes);
+                }
                 PluginServerRpc::FormatSemanticTokens { tokens, text, f } => {
                     handler.format_semantic_tokens(tokens, text, f);
                 }
@@ -582,9 +602,75 @@ struct SaveRegistration {
     filters: Vec<DocumentFilter>,
 }
 
+/// A glob pattern of a `workspace/didChangeWatchedFiles` registration
+struct FileSystemWatch {
+    /// The folder the paths are matched relative to, which is the base of a
+    /// relative pattern, or otherwise the workspace unless the pattern is
+    /// absolute
+    base: Option<PathBuf>,
+    glob: GlobMatcher,
+    kind: WatchKind,
+}
+
+impl FileSystemWatch {
+    fn new(watcher: FileSystemWatcher, workspace: Option<&Path>) -> Option<Self> {
+        let (base, pattern) = match watcher.glob_pattern {
+            GlobPattern::String(pattern) => {
+                let base = if Path::new(&pattern).is_absolute() {
+                    None
+                } else {
+                    workspace.map(|workspace| workspace.to_path_buf())
+                };
+                (base, pattern)
+            }
+            GlobPattern::Relative(relative) => {
+                let base = match relative.base_uri {
+                    OneOf::Left(folder) => folder.uri,
+                    OneOf::Right(uri) => uri,
+                };
+                (Some(base.to_file_path().ok()?), relative.pattern)
+            }
+        };
+        let glob = GlobBuilder::new(&pattern)
+            .literal_separator(true)
+            .build()
+            .ok()?
+            .compile_matcher();
+        Some(Self {
+            base,
+            glob,
+            kind: watcher.kind.unwrap_or(WatchKind::all()),
+        })
+    }
+
+    fn matches(&self, event: &FileEvent) -> bool {
+        let kind = match event.typ {
+            FileChangeType::CREATED => WatchKind::Create,
+            FileChangeType::CHANGED => WatchKind::Change,
+            FileChangeType::DELETED => WatchKind::Delete,
+            _ => return false,
+        };
+        if !self.kind.contains(kind) {
+            return false;
+        }
+        let Ok(path) = event.uri.to_file_path() else {
+            return false;
+        };
+        match &self.base {
+            Some(base) => path
+                .strip_prefix(base)
+                .is_ok_and(|path| self.glob.is_match(path)),
+            None => self.glob.is_match(&path),
+        }
+    }
+}
+
 #[derive(Default)]
 struct ServerRegistrations {
     save: Option<SaveRegistration>,
+    /// The file watches of the `workspace/didChangeWatchedFiles` registrations,
+    /// by their registration id
+    watched_files: HashMap<String, Vec<FileSystemWatch>>,
 }
 
 pub struct PluginHostHandler {
@@ -598,6 +684,9 @@ pub struct PluginHostHandler {
     pub server_rpc: PluginServerRpcHandler,
     pub server_capabilities: ServerCapabilities,
     server_registrations: ServerRegistrations,
+    /// The configuration of the volt, which answers the `workspace/configuration`
+    /// requests of its language servers
+    configurations: Option<Value>,
 
     /// Language servers that this plugin has spawned.  
     /// Note that these plugin ids could be 'dead' if the LSP died/exited.  
@@ -615,6 +704,7 @@ pub fn new(
         core_rpc: CoreRpcHandler,
         server_rpc: PluginServerRpcHandler,
         catalog_rpc: PluginCatalogRpcHandler,
+        configurations: Option<Value>,
     ) -> Self {
         let document_selector = document_selector
             .iter()
@@ -631,6 +721,7 @@ pub fn new(
             server_rpc,
             server_capabilities: ServerCapabilities::default(),
             server_registrations: ServerRegistrations::default(),
+            configurations,
             spawned_lsp: HashMap::new(),
         }
     }
@@ -662,6 +753,56 @@ pub fn document_supported(
         }
     }
 
+    /// Set the capabilities the server initialized with, and let the UI know
+    /// which characters it formats after.
+    pub fn set_server_capabilities(&mut self, capabilities: ServerCapabilities) {
+        let triggers = capabilities
+            .document_on_type_formatting_provider
+            .as_ref()
+            .map(|options| {
+                std::iter::once(options.first_trigger_character.clone())
+                    .chain(options.more_trigger_character.iter().flatten().cloned())
+                    .collect()
+            })
+            .unwrap_or_default();
+        self.server_capabilities = capabilities;
+        self.core_rpc
+            .on_type_formatting_triggers(self.server_rpc.plugin_id, triggers);
+    }
+
+    /// Whether the server wants this particular request, for requests which
+    /// it only handles in some cases, such as on type formatting which is only
+    /// for its trigger characters.
+    pub fn request_supported(&self, method: &str, params: &Params) -> bool {
+        match method {
+            OnTypeFormatting::METHOD => {
+                let Some(options) = self
+                    .server_capabilities
+                    .document_on_type_formatting_provider
+                    .as_ref()
+                else {
+                    return false;
+                };
+                let Some(params) =
+                    serde_json::to_value(params).ok().and_then(|params| {
+                        serde_json::from_value::<DocumentOnTypeFormattingParams>(
+                            params,
+                        )
+                        .ok()
+                    })
+                else {
+                    return false;
+                };
+                options.first_trigger_character == params.ch
+                    || options
+                        .more_trigger_character
+                        .as_ref()
+                        .is_some_and(|chars| chars.contains(&params.ch))
+            }
+            _ => true,
+        }
+    }
+
     pub fn method_registered(&mut self, method: &str) -> bool {
         match method {
             Initialize::METHOD => true,
@@ -727,6 +868,64 @@ pub fn method_registered(&mut self, method: &str) -> bool {
             GotoTypeDefinition::METHOD => {
                 self.server_capabilities.type_definition_provider.is_some()
             }
+            GotoImplementation::METHOD => self
+                .server_capabilities
+                .implementation_provider
+                .as_ref()
+                .map(|i| match i {
+                    ImplementationProviderCapability::Simple(is_capable) => {
+                        *is_capable
+                    }
+                    ImplementationProviderCapability::Options(_) => true,
+                })
+                .unwrap_or(false),
+            GotoDeclaration::METHOD => self
+                .server_capabilities
+                .declaration_provider
+                .as_ref()
+                .map(|d| match d {
+                    DeclarationCapability::Simple(is_capable) => *is_capable,
+                    DeclarationCapability::RegistrationOptions(_)
+                    | DeclarationCapability::Options(_) => true,
+                })
+                .unwrap_or(false),
+            CallHierarchyPrepare::METHOD
+            | CallHierarchyIncomingCalls::METHOD
+            | CallHierarchyOutgoingCalls::METHOD => self
+                .server_capabilities
+                .call_hierarchy_provider
+                .as_ref()
+                .map(|c| match c {
+                    CallHierarchyServerCapability::Simple(is_capable) => *is_capable,
+                    CallHierarchyServerCapability::Options(_) => true,
+                })
+                .unwrap_or(false),
+            // The server capabilities don't include type hierarchies in this
+            // version of lsp-types, so leave it to the server to reject them
+            TypeHierarchyPrepare::METHOD
+            | TypeHierarchySupertypes::METHOD
+            | TypeHierarchySubtypes::METHOD => true,
+            FoldingRangeRequest::METHOD => self
+                .server_capabilities
+                .folding_range_provider
+                .as_ref()
+                .map(|f| match f {
+                    FoldingRangeProviderCapability::Simple(is_capable) => {
+                        *is_capable
+                    }
+                    FoldingRangeProviderCapability::FoldingProvider(_)
+                    | FoldingRangeProviderCapability::Options(_) => true,
+                })
+                .unwrap_or(false),
+            DocumentHighlightRequest::METHOD => self
+                .server_capabilities
+                .document_highlight_provider
+                .as_ref()
+                .map(|d| match d {
+                    OneOf::Left(is_capable) => *is_capable,
+                    OneOf::Right(_) => true,
+                })
+                .unwrap_or(false),
             References::METHOD => self
                 .server_capabilities
                 .references_provider
@@ -754,6 +953,19 @@ pub fn method_registered(&mut self, method: &str) -> bool {
                     OneOf::Right(_) => true,
                 })
                 .unwrap_or(false),
+            RangeFormatting::METHOD => self
+                .server_capabilities
+                .document_range_formatting_provider
+                .as_ref()
+                .map(|f| match f {
+                    OneOf::Left(is_capable) => *is_capable,
+                    OneOf::Right(_) => true,
+                })
+                .unwrap_or(false),
+            OnTypeFormatting::METHOD => self
+                .server_capabilities
+                .document_on_type_formatting_provider
+                .is_some(),
             SemanticTokensFullRequest::METHOD => {
                 self.server_capabilities.semantic_tokens_provider.is_some()
             }
@@ -780,6 +992,18 @@ pub fn method_registered(&mut self, method: &str) -> bool {
             CodeActionResolveRequest::METHOD => {
                 self.server_capabilities.code_action_provider.is_some()
             }
+            CodeLensRequest::METHOD => {
+                self.server_capabilities.code_lens_provider.is_some()
+            }
+            CodeLensResolve::METHOD => self
+                .server_capabilities
+                .code_lens_provider
+                .as_ref()
+                .and_then(|c| c.resolve_provider)
+                .unwrap_or(false),
+            ExecuteCommand::METHOD => {
+                self.server_capabilities.execute_command_provider.is_some()
+            }
             _ => false,
         }
     }
@@ -849,6 +1073,23 @@ fn register_capability(&mut self, registration: Registration) -> Result<()> {
                         .unwrap_or_default(),
                 });
             }
+            DidChangeWatchedFiles::METHOD => {
+                let options = registration
+                    .register_options
+                    .ok_or_else(|| anyhow!("don't have options"))?;
+                let options: DidChangeWatchedFilesRegistrationOptions =
+                    serde_json::from_value(options)?;
+                self.server_registrations.watched_files.insert(
+                    registration.id,
+                    options
+                        .watchers
+                        .into_iter()
+                        .filter_map(|watcher| {
+                            FileSystemWatch::new(watcher, self.workspace.as_deref())
+                        })
+                        .collect(),
+                );
+            }
             _ => {
                 eprintln!(
                     "don't handle register capability for {}",
@@ -859,6 +1100,16 @@ fn register_capability(&mut self, registration: Registration) -> Result<()> {
         Ok(())
     }
 
+    fn unregister_capabilities(&mut self, params: UnregistrationParams) {
+        for unregistration in params.unregisterations {
+            if unregistration.method == DidChangeWatchedFiles::METHOD {
+                self.server_registrations
+                    .watched_files
+                    .remove(&unregistration.id);
+            }
+        }
+    }
+
     pub fn handle_request(
         &mut self,
         _id: Id,
@@ -887,6 +1138,32 @@ pub fn process_request(
                 self.register_capabilities(params.registrations);
                 resp.send_null();
             }
+            UnregisterCapability::METHOD => {
+                let params: UnregistrationParams =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                self.unregister_capabilities(params);
+                resp.send_null();
+            }
+            ApplyWorkspaceEdit::METHOD => {
+                let params: ApplyWorkspaceEditParams =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                self.catalog_rpc.apply_workspace_edit(params.edit, resp);
+            }
+            WorkspaceConfiguration::METHOD => {
+                let params: ConfigurationParams =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                let result: Vec<Value> = params
+                    .items
+                    .iter()
+                    .map(|item| {
+                        configuration_section(
+                            self.configurations.as_ref(),
+                            item.section.as_deref(),
+                        )
+                    })
+                    .collect();
+                resp.send(result);
+            }
             ExecuteProcess::METHOD => {
                 let params: ExecuteProcessParams =
                     serde_json::from_value(serde_json::to_value(params)?)?;
@@ -918,6 +1195,7 @@ pub fn process_request(
                 let catalog_rpc = self.catalog_rpc.clone();
                 let volt_id = self.volt_id.clone();
                 let volt_display_name = self.volt_display_name.clone();
+                let configurations = self.configurations.clone();
 
                 let spawned_by = self.server_rpc.plugin_id;
                 let plugin_id = PluginId::next();
@@ -936,6 +1214,7 @@ pub fn process_request(
                         params.server_uri,
                         params.server_args,
                         params.options,
+                        configurations,
                     );
                 });
             }
@@ -1028,6 +1307,7 @@ pub fn handle_notification(
                 let catalog_rpc = self.catalog_rpc.clone();
                 let volt_id = self.volt_id.clone();
                 let volt_display_name = self.volt_display_name.clone();
+                let configurations = self.configurations.clone();
                 thread::spawn(move || {
                     let _ = LspClient::start(
                         catalog_rpc,
@@ -1041,6 +1321,7 @@ pub fn handle_notification(
                         params.server_uri,
                         params.server_args,
                         params.options,
+                        configurations,
                     );
                 });
             }
@@ -1101,6 +1382,30 @@ pub fn handle_did_save_text_document(
         );
     }
 
+    /// Send the file changes which match the file watches the server registered
+    pub fn handle_did_change_watched_files(&self, changes: Vec<FileEvent>) {
+        let changes: Vec<FileEvent> = changes
+            .into_iter()
+            .filter(|change| {
+                self.server_registrations
+                    .watched_files
+                    .values()
+                    .flatten()
+                    .any(|watch| watch.matches(change))
+            })
+            .collect();
+        if changes.is_empty() {
+            return;
+        }
+        self.server_rpc.server_notification(
+            DidChangeWatchedFiles::METHOD,
+            DidChangeWatchedFilesParams { changes },
+            None,
+            None,
+            false,
+        );
+    }
+
     pub fn handle_did_change_text_document(
         &mut self,
         lanaguage_id: String,
@@ -1314,3 +1619,161 @@ fn semantic_tokens_legend(
         ) => &options.semantic_tokens_options.legend,
     }
 }
+
+/// Get the value of a dotted configuration section, such as `rust-analyzer.cargo`,
+/// or the whole configuration when no section is asked for.
+/// Sections which aren't configured are `null`, so that the server uses its defaults.
+fn configuration_section(
+    configurations: Option<&Value>,
+    section: Option<&str>,
+) -> Value {
+    let Some(configurations) = configurations else {
+        return Value::Null;
+    };
+    let Some(section) = section.filter(|s| !s.is_empty()) else {
+        return configurations.clone();
+    };
+    section
+        .split('.')
+        .try_fold(configurations, |value, key| value.get(key))
+        .cloned()
+        .unwrap_or(Value::Null)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use lsp_types::{
+        FileChangeType, FileEvent, FileSystemWatcher, GlobPattern, OneOf,
+        RelativePattern, Url, WatchKind,
+    };
+    use serde_json::{json, Value};
+
+    use super::{configuration_section, FileSystemWatch};
+
+    fn file_event(path: &str, typ: FileChangeType) -> FileEvent {
+        FileEvent {
+            uri: Url::from_file_path(path).unwrap(),
+            typ,
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_file_system_watch() {
+        let workspace = Some(Path::new("/work/lapce"));
+        let watch = FileSystemWatch::new(
+            FileSystemWatcher {
+                glob_pattern: GlobPattern::String("**/Cargo.toml".to_string()),
+                kind: None,
+            },
+            workspace,
+        )
+        .unwrap();
+        assert!(watch.matches(&file_event(
+            "/work/lapce/Cargo.toml",
+            FileChangeType::CHANGED
+        )));
+        assert!(watch.matches(&file_event(
+            "/work/lapce/lapce-app/Cargo.toml",
+            FileChangeType::CHANGED
+        )));
+        assert!(!watch.matches(&file_event(
+            "/work/lapce/Cargo.lock",
+            FileChangeType::CHANGED
+        )));
+
+        // A pattern which isn't relative to any folder is relative to the
+        // workspace
+        let watch = FileSystemWatch::new(
+            FileSystemWatcher {
+                glob_pattern: GlobPattern::String("src/*.rs".to_string()),
+                kind: None,
+            },
+            workspace,
+        )
+        .unwrap();
+        assert!(watch.matches(&file_event(
+            "/work/lapce/src/main.rs",
+            FileChangeType::CHANGED
+        )));
+        assert!(!watch.matches(&file_event(
+            "/work/other/src/main.rs",
+            FileChangeType::CHANGED
+        )));
+
+        let watch = FileSystemWatch::new(
+            FileSystemWatcher {
+                glob_pattern: GlobPattern::String("/work/*/Cargo.toml".to_string()),
+                kind: None,
+            },
+            workspace,
+        )
+        .unwrap();
+        assert!(watch.matches(&file_event(
+            "/work/other/Cargo.toml",
+            FileChangeType::CHANGED
+        )));
+
+        let watch = FileSystemWatch::new(
+            FileSystemWatcher {
+                glob_pattern: GlobPattern::Relative(RelativePattern {
+                    base_uri: OneOf::Right(
+                        Url::from_file_path("/work/lapce").unwrap(),
+                    ),
+                    pattern: "src/*.rs".to_string(),
+                }),
+                kind: Some(WatchKind::Create | WatchKind::Delete),
+            },
+            None,
+        )
+        .unwrap();
+        assert!(watch.matches(&file_event(
+            "/work/lapce/src/main.rs",
+            FileChangeType::CREATED
+        )));
+        // Only created and deleted files are watched
+        assert!(!watch.matches(&file_event(
+            "/work/lapce/src/main.rs",
+            FileChangeType::CHANGED
+        )));
+        // `*` doesn't match across directories
+        assert!(!watch.matches(&file_event(
+            "/work/lapce/src/bin/main.rs",
+            FileChangeType::DELETED
+        )));
+        assert!(!watch.matches(&file_event(
+            "/work/other/src/main.rs",
+            FileChangeType::DELETED
+        )));
+    }
+
+    #[test]
+    fn test_configuration_section() {
+        let configurations = json!({
+            "rust-analyzer": {
+                "cargo": {
+                    "features": "all",
+                },
+            },
+        });
+        let configurations = Some(&configurations);
+        assert_eq!(
+            configuration_section(configurations, Some("rust-analyzer.cargo")),
+            json!({ "features": "all" })
+        );
+        assert_eq!(
+            configuration_section(configurations, Some("rust-analyzer.check")),
+            Value::Null
+        );
+        assert_eq!(
+            configuration_section(configurations, None),
+            configurations.cloned().unwrap()
+        );
+        assert_eq!(
+            configuration_section(None, Some("rust-analyzer")),
+            Value::Null
+        );
+    }
+}
@@ -21,7 +21,7 @@
 };
 use lapce_xi_rope::{Rope, RopeDelta};
 use lsp_types::{
-    notification::Initialized, request::Initialize, DocumentFilter,
+    notification::Initialized, request::Initialize, DocumentFilter, FileEvent,
     InitializeParams, InitializedParams, TextDocumentContentChangeEvent,
     TextDocumentIdentifier, Url, VersionedTextDocumentIdentifier,
     WorkDoneProgressParams, WorkspaceFolder,
@@ -94,6 +94,10 @@ fn method_registered(&mut self, method: &str) -> bool {
         self.host.method_registered(method)
     }
 
+    fn request_supported(&mut self, method: &str, params: &Params) -> bool {
+        self.host.request_supported(method, params)
+    }
+
     fn document_supported(
         &mut self,
         language_id: Option<&str>,
@@ -112,7 +116,7 @@ fn handle_handler_notification(
                 self.initialize();
             }
             InitializeResult(result) => {
-                self.host.server_capabilities = result.capabilities;
+                self.host.set_server_capabilities(result.capabilities);
             }
             Shutdown => {
                 self.shutdown();
@@ -176,6 +180,10 @@ fn handle_did_change_text_document(
         );
     }
 
+    fn handle_did_change_watched_files(&self, changes: Vec<FileEvent>) {
+        self.host.handle_did_change_watched_files(changes);
+    }
+
     fn format_semantic_tokens(
         &self,
         tokens: lsp_types::SemanticTokens,
@@ -568,6 +576,7 @@ pub fn start_volt(
             plugin_rpc.core_rpc.clone(),
             rpc.clone(),
             plugin_rpc.clone(),
+            configurations.as_ref().map(unflatten_map),
         ),
         configurations,
     };
@@ -0,0 +1,374 @@
+use std::path::Path;
+
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use lapce_rpc::proxy::SearchMatch;
+use regex::{Captures, Regex, RegexBuilder};
+
+/// The "files to include / exclude" glob patterns of a global search.
+#[derive(Clone)]
+pub struct SearchFilter {
+    include: Option<GlobSet>,
+    exclude: Option<GlobSet>,
+}
+
+impl SearchFilter {
+    pub fn new(
+        include: &[String],
+        exclude: &[String],
+    ) -> Result<SearchFilter, globset::Error> {
+        Ok(SearchFilter {
+            include: build_glob_set(include)?,
+            exclude: build_glob_set(exclude)?,
+        })
+    }
+
+    /// Whether `path` should be searched. It's matched relative to
+    /// `workspace` when it's inside of it.
+    pub fn is_match(&self, path: &Path, workspace: Option<&Path>) -> bool {
+        if self.is_excluded(path, workspace) {
+            return false;
+        }
+        let path = relative_path(path, workspace);
+        self.include
+            .as_ref()
+            .map(|include| include.is_match(path))
+            .unwrap_or(true)
+    }
+
+    /// Whether `path` matches one of the patterns to leave out, so that a
+    /// folder can be skipped as a whole when walking the workspace.
+    pub fn is_excluded(&self, path: &Path, workspace: Option<&Path>) -> bool {
+        let path = relative_path(path, workspace);
+        self.exclude
+            .as_ref()
+            .map(|exclude| exclude.is_match(path))
+            .unwrap_or(false)
+    }
+}
+
+fn relative_path<'a>(path: &'a Path, workspace: Option<&Path>) -> &'a Path {
+    workspace
+        .and_then(|workspace| path.strip_prefix(workspace).ok())
+        .unwrap_or(path)
+}
+
+/// Patterns without a `/` match at any depth, like `.gitignore` entries, and a
+/// pattern matching a folder also matches everything inside of it.
+fn build_glob_set(patterns: &[String]) -> Result<Option<GlobSet>, globset::Error> {
+    let mut builder = GlobSetBuilder::new();
+    let mut is_empty = true;
+    for pattern in patterns {
+        let pattern = pattern
+            .trim()
+            .trim_start_matches("./")
+            .trim_end_matches('/');
+        if pattern.is_empty() {
+            continue;
+        }
+        let pattern = if pattern.contains('/') {
+            pattern.trim_start_matches('/').to_string()
+        } else {
+            format!("**/{pattern}")
+        };
+        for pattern in [pattern.clone(), format!("{pattern}/**")] {
+            builder.add(GlobBuilder::new(&pattern).literal_separator(true).build()?);
+        }
+        is_empty = false;
+    }
+    if is_empty {
+        return Ok(None);
+    }
+    builder.build().map(Some)
+}
+
+/// The regexes used to locate the matches of a global search when replacing
+/// them, built by [`build_search_regex`].
+pub struct SearchRegex {
+    /// The pattern itself, or with whole words the pattern matching the whole
+    /// of a word that was found
+    regex: Regex,
+    /// With whole words, the pattern between non-word characters or the ends of
+    /// the line, whose first group is the word. This is how grep matches words
+    /// when searching, so that the same matches are found again.
+    word: Option<Regex>,
+}
+
+impl SearchRegex {
+    /// The `(start, end)` of each match in `line`, with the captures of the
+    /// pattern
+    fn captures<'a>(&self, line: &'a str) -> Vec<(usize, usize, Captures<'a>)> {
+        let Some(word) = self.word.as_ref() else {
+            return self
+                .regex
+                .captures_iter(line)
+                .filter_map(|caps| {
+                    let m = caps.get(0)?;
+                    Some((m.start(), m.end(), caps))
+                })
+                .collect();
+        };
+
+        let mut captures = Vec::new();
+        let mut at = 0;
+        while at <= line.len() {
+            let Some(m) = word.captures_at(line, at).and_then(|caps| caps.get(1))
+            else {
+                break;
+            };
+            // The next word may start right after the non-word character which
+            // ends this one
+            at = if m.start() == m.end() {
+                m.end() + line[m.end()..].chars().next().map_or(1, char::len_utf8)
+            } else {
+                m.end()
+            };
+            if let Some(caps) = self.regex.captures(m.as_str()) {
+                captures.push((m.start(), m.end(), caps));
+            }
+        }
+        captures
+    }
+}
+
+/// Build the regexes used to locate the matches of a global search when
+/// replacing them, mirroring the options of the search itself.
+pub fn build_search_regex(
+    pattern: &str,
+    case_sensitive: bool,
+    whole_word: bool,
+    is_regex: bool,
+) -> Result<SearchRegex, regex::Error> {
+    let pattern = if is_regex {
+        pattern.to_string()
+    } else {
+        regex::escape(pattern)
+    };
+    let build = |pattern: &str| {
+        RegexBuilder::new(pattern)
+            .case_insensitive(!case_sensitive)
+            .build()
+    };
+    if !whole_word {
+        return Ok(SearchRegex {
+            regex: build(&pattern)?,
+            word: None,
+        });
+    }
+    Ok(SearchRegex {
+        regex: build(&format!("^(?:{pattern})$"))?,
+        word: Some(build(&format!(
+            r"(?:(?m:^)|\W)((?:{pattern}))(?:\W|(?m:$))"
+        ))?),
+    })
+}
+
+/// Group the `(start, end)` columns of the matches by their 1-based line.
+pub fn matches_by_line(
+    matches: &[SearchMatch],
+) -> Vec<(usize, Vec<(usize, usize)>)> {
+    let mut lines: Vec<(usize, Vec<(usize, usize)>)> = Vec::new();
+    for m in matches {
+        match lines.iter_mut().find(|(line, _)| *line == m.line) {
+            Some((_, ranges)) => ranges.push((m.start, m.end)),
+            None => lines.push((m.line, vec![(m.start, m.end)])),
+        }
+    }
+    lines
+}
+
+/// Compute the replacement text of each of the given `(start, end)` matches
+/// within `line`.
+/// When `is_regex` is set, capture groups such as `$1` or `${name}` are
+/// expanded in `replacement`. Matches that no longer line up with a match of
+/// `regex`, for example because the line changed since the search, are skipped.
+pub fn line_replacements(
+    regex: &SearchRegex,
+    line: &str,
+    matches: &[(usize, usize)],
+    replacement: &str,
+    is_regex: bool,
+) -> Vec<(usize, usize, String)> {
+    let mut replacements = Vec::new();
+    for (start, end, caps) in regex.captures(line) {
+        if start == end || !matches.contains(&(start, end)) {
+            continue;
+        }
+        let text = if is_regex {
+            let mut text = String::new();
+            caps.expand(replacement, &mut text);
+            text
+        } else {
+            replacement.to_string()
+        };
+        replacements.push((start, end, text));
+    }
+    replacements
+}
+
+/// Apply the replacements computed by [`line_replacements`] to `line`.
+pub fn apply_line_replacements(
+    line: &str,
+    replacements: &[(usize, usize, String)],
+) -> String {
+    let mut result = String::with_capacity(line.len());
+    let mut last = 0;
+    for (start, end, text) in replacements {
+        result.push_str(&line[last..*start]);
+        result.push_str(text);
+        last = *end;
+    }
+    result.push_str(&line[last..]);
+    result
+}
+
+/// Apply the replacements to the given 1-based lines of `content`, returning
+/// the new content, or `None` if nothing was replaced.
+pub fn replace_in_content(
+    content: &str,
+    regex: &SearchRegex,
+    line_matches: &[(usize, Vec<(usize, usize)>)],
+    replacement: &str,
+    is_regex: bool,
+) -> Option<String> {
+    let mut replaced = false;
+    let mut result = String::with_capacity(content.len());
+    for (i, line) in content.split_inclusive('\n').enumerate() {
+        let Some((_, matches)) = line_matches.iter().find(|(l, _)| *l == i + 1)
+        else {
+            result.push_str(line);
+            continue;
+        };
+        let text = line.trim_end_matches(['\n', '\r']);
+        let replacements =
+            line_replacements(regex, text, matches, replacement, is_regex);
+        if replacements.is_empty() {
+            result.push_str(line);
+            continue;
+        }
+        replaced = true;
+        result.push_str(&apply_line_replacements(text, &replacements));
+        result.push_str(&line[text.len()..]);
+    }
+    replaced.then_some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(include: &[&str], exclude: &[&str]) -> SearchFilter {
+        let to_vec = |patterns: &[&str]| {
+            patterns.iter().map(|p| p.to_string()).collect::<Vec<_>>()
+        };
+        SearchFilter::new(&to_vec(include), &to_vec(exclude)).unwrap()
+    }
+
+    #[test]
+    fn test_search_filter() {
+        let workspace = Path::new("/ws");
+
+        let f = filter(&["src/**/*.rs"], &["vendor/"]);
+        assert!(f.is_match(Path::new("/ws/src/a/b.rs"), Some(workspace)));
+        assert!(f.is_match(Path::new("/ws/src/b.rs"), Some(workspace)));
+        assert!(!f.is_match(Path::new("/ws/src/b.toml"), Some(workspace)));
+        assert!(!f.is_match(Path::new("/ws/lib/src/b.rs"), Some(workspace)));
+
+        let f = filter(&[], &["vendor", "*.lock"]);
+        assert!(f.is_match(Path::new("/ws/src/main.rs"), Some(workspace)));
+        assert!(!f.is_match(Path::new("/ws/vendor/a.rs"), Some(workspace)));
+        assert!(!f.is_match(Path::new("/ws/a/vendor/b/c.rs"), Some(workspace)));
+        assert!(!f.is_match(Path::new("/ws/Cargo.lock"), Some(workspace)));
+        assert!(f.is_excluded(Path::new("/ws/a/vendor"), Some(workspace)));
+        assert!(!f.is_excluded(Path::new("/ws/a/vendors"), Some(workspace)));
+
+        let f = filter(&["src/*.rs"], &[]);
+        assert!(f.is_match(Path::new("/ws/src/main.rs"), Some(workspace)));
+        assert!(!f.is_match(Path::new("/ws/src/a/b.rs"), Some(workspace)));
+    }
+
+    #[test]
+    fn test_literal_replace() {
+        let regex = build_search_regex("a.b", true, false, false).unwrap();
+        let line = "a.b axb a.b";
+        let replacements = line_replacements(&regex, line, &[(8, 11)], "c", false);
+        assert_eq!(replacements, vec![(8, 11, "c".to_string())]);
+        assert_eq!(apply_line_replacements(line, &replacements), "a.b axb c");
+    }
+
+    #[test]
+    fn test_regex_capture_groups() {
+        let regex =
+            build_search_regex(r"(?P<key>\w+)_(\d+)", true, false, true).unwrap();
+        let line = "foo_1 bar_2";
+        let replacements =
+            line_replacements(&regex, line, &[(0, 5), (6, 11)], "${2}_${key}", true);
+        assert_eq!(apply_line_replacements(line, &replacements), "1_foo 2_bar");
+    }
+
+    #[test]
+    fn test_case_and_whole_word() {
+        let regex = build_search_regex("foo", false, true, false).unwrap();
+        let line = "FOO foobar foo";
+        let replacements =
+            line_replacements(&regex, line, &[(0, 3), (11, 14)], "baz", false);
+        assert_eq!(
+            apply_line_replacements(line, &replacements),
+            "baz foobar baz"
+        );
+    }
+
+    #[test]
+    fn test_whole_word_with_non_word_characters() {
+        // The words are found the way grep finds them, so that a pattern
+        // which starts or ends with a non-word character matches too
+        let regex = build_search_regex("-foo", true, true, false).unwrap();
+        let line = "-foo a-foo -foo-bar";
+        let replacements = line_replacements(
+            &regex,
+            line,
+            &[(0, 4), (6, 10), (11, 15)],
+            "x",
+            false,
+        );
+        assert_eq!(
+            replacements.iter().map(|r| (r.0, r.1)).collect::<Vec<_>>(),
+            [(0, 4), (11, 15)]
+        );
+
+        // Words right next to each other, with capture groups
+        let regex = build_search_regex(r"(\w)\.", true, true, true).unwrap();
+        let line = "a. b.c.";
+        let replacements =
+            line_replacements(&regex, line, &[(0, 2), (3, 5), (5, 7)], "$1", true);
+        assert_eq!(apply_line_replacements(line, &replacements), "a b.c");
+    }
+
+    #[test]
+    fn test_stale_match_is_skipped() {
+        let regex = build_search_regex("foo", true, false, false).unwrap();
+        assert!(
+            line_replacements(&regex, "xfoo", &[(0, 3)], "bar", false).is_empty()
+        );
+    }
+
+    #[test]
+    fn test_replace_in_content() {
+        let regex = build_search_regex("foo", true, false, false).unwrap();
+        let content = "foo\r\nbar foo\nfoo";
+        assert_eq!(
+            replace_in_content(
+                content,
+                &regex,
+                &[(2, vec![(4, 7)]), (3, vec![(0, 3)])],
+                "baz",
+                false
+            )
+            .as_deref(),
+            Some("foo\r\nbar baz\nbaz")
+        );
+        assert_eq!(
+            replace_in_content(content, &regex, &[(1, vec![(1, 4)])], "baz", false),
+            None
+        );
+    }
+}
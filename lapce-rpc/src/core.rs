@@ -9,8 +9,8 @@
 
 use crossbeam_channel::{Receiver, Sender};
 use lsp_types::{
-    CompletionResponse, LogMessageParams, ProgressParams, PublishDiagnosticsParams,
-    ShowMessageParams, SignatureHelp,
+    CodeLens, CompletionResponse, LogMessageParams, ProgressParams,
+    PublishDiagnosticsParams, ShowMessageParams, SignatureHelp, WorkspaceEdit,
 };
 use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
@@ -55,6 +55,19 @@ pub enum CoreNotification {
         resp: SignatureHelp,
         plugin_id: PluginId,
     },
+    /// The code lenses of a plugin for the revision of the file
+    CodeLensResponse {
+        path: PathBuf,
+        rev: u64,
+        plugin_id: PluginId,
+        lenses: Vec<CodeLens>,
+    },
+    /// The characters which the plugin's language server formats the document
+    /// after when they're typed
+    OnTypeFormattingTriggers {
+        plugin_id: PluginId,
+        triggers: Vec<String>,
+    },
     OpenPaths {
         paths: Vec<PathObject>,
     },
@@ -62,6 +75,12 @@ pub enum CoreNotification {
     PublishDiagnostics {
         diagnostics: PublishDiagnosticsParams,
     },
+    /// A workspace edit requested by a language server, which is waiting for
+    /// a `WorkspaceEditApplied` with the same id
+    ApplyWorkspaceEdit {
+        id: u64,
+        edit: WorkspaceEdit,
+    },
     WorkDoneProgress {
         progress: ProgressParams,
     },
@@ -91,8 +110,10 @@ pub enum CoreNotification {
         volt: VoltInfo,
         only_installing: bool,
     },
+    /// The state of the repositories in the workspace, starting with the one
+    /// the workspace is in, followed by the ones in its folders
     DiffInfo {
-        diff: DiffInfo,
+        repositories: Vec<DiffInfo>,
     },
     UpdateTerminal {
         term_id: TermId,
@@ -125,11 +146,30 @@ pub enum CoreNotification {
     DapContinued {
         dap_id: DapId,
     },
+    DapOutput {
+        dap_id: DapId,
+        output: dap_types::Output,
+    },
+    DapCapabilities {
+        dap_id: DapId,
+        capabilities: dap_types::DebuggerCapabilities,
+    },
     DapBreakpointsResp {
         dap_id: DapId,
         path: PathBuf,
         breakpoints: Vec<dap_types::Breakpoint>,
     },
+    DapFunctionBreakpointsResp {
+        dap_id: DapId,
+        breakpoints: Vec<dap_types::Breakpoint>,
+    },
+    /// A breakpoint was changed by the debug adapter, e.g. verified once the
+    /// code it is in got loaded
+    DapBreakpointChanged {
+        dap_id: DapId,
+        reason: String,
+        breakpoint: dap_types::Breakpoint,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -229,8 +269,8 @@ pub fn workspace_file_change(&self) {
         self.notification(CoreNotification::WorkspaceFileChange);
     }
 
-    pub fn diff_info(&self, diff: DiffInfo) {
-        self.notification(CoreNotification::DiffInfo { diff });
+    pub fn diff_info(&self, repositories: Vec<DiffInfo>) {
+        self.notification(CoreNotification::DiffInfo { repositories });
     }
 
     pub fn open_file_changed(&self, path: PathBuf, content: String) {
@@ -265,6 +305,32 @@ pub fn signature_help_response(
         });
     }
 
+    pub fn code_lens_response(
+        &self,
+        path: PathBuf,
+        rev: u64,
+        plugin_id: PluginId,
+        lenses: Vec<CodeLens>,
+    ) {
+        self.notification(CoreNotification::CodeLensResponse {
+            path,
+            rev,
+            plugin_id,
+            lenses,
+        });
+    }
+
+    pub fn on_type_formatting_triggers(
+        &self,
+        plugin_id: PluginId,
+        triggers: Vec<String>,
+    ) {
+        self.notification(CoreNotification::OnTypeFormattingTriggers {
+            plugin_id,
+            triggers,
+        });
+    }
+
     pub fn volt_installed(&self, volt: VoltMetadata, icon: Option<Vec<u8>>) {
         self.notification(CoreNotification::VoltInstalled { volt, icon });
     }
@@ -299,6 +365,10 @@ pub fn publish_diagnostics(&self, diagnostics: PublishDiagnosticsParams) {
         self.notification(CoreNotification::PublishDiagnostics { diagnostics });
     }
 
+    pub fn apply_workspace_edit(&self, id: u64, edit: WorkspaceEdit) {
+        self.notification(CoreNotification::ApplyWorkspaceEdit { id, edit });
+    }
+
     pub fn work_done_progress(&self, progress: ProgressParams) {
         self.notification(CoreNotification::WorkDoneProgress { progress });
     }
@@ -349,6 +419,21 @@ pub fn dap_continued(&self, dap_id: DapId) {
         self.notification(CoreNotification::DapContinued { dap_id });
     }
 
+    pub fn dap_output(&self, dap_id: DapId, output: dap_types::Output) {
+        self.notification(CoreNotification::DapOutput { dap_id, output });
+    }
+
+    pub fn dap_capabilities(
+        &self,
+        dap_id: DapId,
+        capabilities: dap_types::DebuggerCapabilities,
+    ) {
+        self.notification(CoreNotification::DapCapabilities {
+            dap_id,
+            capabilities,
+        });
+    }
+
     pub fn dap_breakpoints_resp(
         &self,
         dap_id: DapId,
@@ -362,6 +447,30 @@ pub fn dap_breakpoints_resp(
         });
     }
 
+    pub fn dap_function_breakpoints_resp(
+        &self,
+        dap_id: DapId,
+        breakpoints: Vec<dap_types::Breakpoint>,
+    ) {
+        self.notification(CoreNotification::DapFunctionBreakpointsResp {
+            dap_id,
+            breakpoints,
+        });
+    }
+
+    pub fn dap_breakpoint_changed(
+        &self,
+        dap_id: DapId,
+        reason: String,
+        breakpoint: dap_types::Breakpoint,
+    ) {
+        self.notification(CoreNotification::DapBreakpointChanged {
+            dap_id,
+            reason,
+            breakpoint,
+        });
+    }
+
     pub fn home_dir(&self, path: PathBuf) {
         self.notification(CoreNotification::HomeDir { path });
     }
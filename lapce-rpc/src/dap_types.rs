@@ -42,23 +42,86 @@ pub struct RunDebugProgram {
     pub args: Option<Vec<String>>,
 }
 
+/// Whether a debug session starts the program or attaches to one which is
+/// already running
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum RunDebugRequest {
+    #[default]
+    Launch,
+    Attach,
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "kebab-case")]
 pub struct RunDebugConfig {
     #[serde(rename = "type")]
     pub ty: Option<String>,
     pub name: String,
+    #[serde(default)]
+    pub request: RunDebugRequest,
+    #[serde(default)]
     pub program: String,
     pub args: Option<Vec<String>>,
     pub cwd: Option<String>,
     pub env: Option<HashMap<String, String>>,
     pub prelaunch: Option<RunDebugProgram>,
+    /// The process to attach to, which is picked from the running processes
+    /// when there's neither a pid nor a host and port
+    pub pid: Option<u32>,
+    /// The host of a debug server to attach to, e.g. gdbserver or debugpy
+    pub host: Option<String>,
+    pub port: Option<u16>,
     #[serde(skip)]
     pub debug_command: Option<Vec<String>>,
     #[serde(skip)]
     pub dap_id: DapId,
 }
 
+impl RunDebugConfig {
+    pub fn is_attach(&self) -> bool {
+        self.request == RunDebugRequest::Attach
+    }
+
+    /// An attach configuration needs a process to be picked before it can start
+    pub fn needs_process(&self) -> bool {
+        self.is_attach()
+            && self.pid.is_none()
+            && self.host.is_none()
+            && self.port.is_none()
+    }
+
+    /// What the configuration runs or attaches to, to describe it
+    pub fn target(&self) -> String {
+        if !self.is_attach() {
+            return format!(
+                "{} {}",
+                self.program,
+                self.args.clone().unwrap_or_default().join(" ")
+            );
+        }
+        if let Some(pid) = self.pid {
+            format!("pid {pid}")
+        } else if self.host.is_some() || self.port.is_some() {
+            format!(
+                "{}:{}",
+                self.host.as_deref().unwrap_or("localhost"),
+                self.port.map(|p| p.to_string()).unwrap_or_default()
+            )
+        } else {
+            "pick a process".to_string()
+        }
+    }
+}
+
+/// A process running on the machine of the proxy, which can be attached to
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub name: String,
+    pub command: String,
+}
+
 pub trait Request {
     type Arguments: DeserializeOwned + Serialize;
     type Result: DeserializeOwned + Serialize;
@@ -189,6 +252,17 @@ pub struct ExceptionBreakpointsFilter {
     pub condition_description: Option<String>,
 }
 
+impl ExceptionBreakpointsFilter {
+    /// Whether the filter is on, given the filters the user has turned on or
+    /// off, and otherwise what the adapter defaults to
+    pub fn enabled(&self, choices: &HashMap<String, bool>) -> bool {
+        choices
+            .get(&self.filter)
+            .copied()
+            .unwrap_or_else(|| self.default.unwrap_or(false))
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct DebuggerCapabilities {
@@ -482,6 +556,14 @@ impl Request for Launch {
     const COMMAND: &'static str = "launch";
 }
 
+pub enum Attach {}
+
+impl Request for Attach {
+    type Arguments = Value;
+    type Result = Value;
+    const COMMAND: &'static str = "attach";
+}
+
 #[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RunInTerminalResponse {
@@ -541,6 +623,70 @@ impl Request for SetBreakpoints {
     const COMMAND: &'static str = "setBreakpoints";
 }
 
+#[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FunctionBreakpoint {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub condition: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hit_condition: Option<String>,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetFunctionBreakpointsArguments {
+    pub breakpoints: Vec<FunctionBreakpoint>,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetFunctionBreakpointsResponse {
+    pub breakpoints: Vec<Breakpoint>,
+}
+
+#[derive(Debug)]
+pub enum SetFunctionBreakpoints {}
+
+impl Request for SetFunctionBreakpoints {
+    type Arguments = SetFunctionBreakpointsArguments;
+    type Result = SetFunctionBreakpointsResponse;
+    const COMMAND: &'static str = "setFunctionBreakpoints";
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetExceptionBreakpointsArguments {
+    pub filters: Vec<String>,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetExceptionBreakpointsResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub breakpoints: Option<Vec<Breakpoint>>,
+}
+
+#[derive(Debug)]
+pub enum SetExceptionBreakpoints {}
+
+impl Request for SetExceptionBreakpoints {
+    type Arguments = SetExceptionBreakpointsArguments;
+    // Adapters may reply without a body
+    type Result = Option<SetExceptionBreakpointsResponse>;
+    const COMMAND: &'static str = "setExceptionBreakpoints";
+}
+
+/// The breakpoints that aren't on a line of a source file, which a debug
+/// session is started with
+#[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize, Serialize)]
+pub struct GlobalBreakpoints {
+    /// The exception filters the user turned on or off, the others are left
+    /// at the default of the adapter
+    pub exception_filters: HashMap<String, bool>,
+    pub functions: Vec<FunctionBreakpoint>,
+}
+
 #[derive(Debug)]
 pub enum ConfigurationDone {}
 
@@ -855,3 +1001,110 @@ impl Request for StepOut {
     type Result = ();
     const COMMAND: &'static str = "stepOut";
 }
+
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvaluateArguments {
+    pub expression: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frame_id: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<ValueFormat>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvaluateResponse {
+    pub result: String,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub ty: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presentation_hint: Option<VariablePresentationHint>,
+    pub variables_reference: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub named_variables: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub indexed_variables: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory_reference: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum Evaluate {}
+
+impl Request for Evaluate {
+    type Arguments = EvaluateArguments;
+    type Result = EvaluateResponse;
+    const COMMAND: &'static str = "evaluate";
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetVariableArguments {
+    pub variables_reference: usize,
+    pub name: String,
+    pub value: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<ValueFormat>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetVariableResponse {
+    pub value: String,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub ty: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variables_reference: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub named_variables: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub indexed_variables: Option<usize>,
+}
+
+#[derive(Debug)]
+pub enum SetVariable {}
+
+impl Request for SetVariable {
+    type Arguments = SetVariableArguments;
+    type Result = SetVariableResponse;
+    const COMMAND: &'static str = "setVariable";
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetExpressionArguments {
+    pub expression: String,
+    pub value: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frame_id: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<ValueFormat>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetExpressionResponse {
+    pub value: String,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub ty: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presentation_hint: Option<VariablePresentationHint>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variables_reference: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub named_variables: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub indexed_variables: Option<usize>,
+}
+
+#[derive(Debug)]
+pub enum SetExpression {}
+
+impl Request for SetExpression {
+    type Arguments = SetExpressionArguments;
+    type Result = SetExpressionResponse;
+    const COMMAND: &'static str = "setExpression";
+}
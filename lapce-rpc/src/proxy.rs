@@ -11,11 +11,16 @@
 use indexmap::IndexMap;
 use lapce_xi_rope::RopeDelta;
 use lsp_types::{
-    request::GotoTypeDefinitionResponse, CodeAction, CodeActionResponse,
-    CompletionItem, Diagnostic, DocumentSymbolResponse, GotoDefinitionResponse,
+    request::{
+        GotoDeclarationResponse, GotoImplementationResponse,
+        GotoTypeDefinitionResponse,
+    },
+    CallHierarchyIncomingCall, CallHierarchyItem, CallHierarchyOutgoingCall,
+    CodeAction, CodeActionResponse, CodeLens, Command, CompletionItem, Diagnostic,
+    DocumentHighlight, DocumentSymbolResponse, FoldingRange, GotoDefinitionResponse,
     Hover, InlayHint, InlineCompletionResponse, InlineCompletionTriggerKind,
-    Location, Position, PrepareRenameResponse, SelectionRange, SymbolInformation,
-    TextDocumentItem, TextEdit, WorkspaceEdit,
+    Location, Position, PrepareRenameResponse, Range, SelectionRange,
+    SymbolInformation, TextDocumentItem, TextEdit, TypeHierarchyItem, WorkspaceEdit,
 };
 use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
@@ -23,10 +28,13 @@
 use super::plugin::VoltID;
 use crate::{
     buffer::BufferId,
-    dap_types::{self, DapId, RunDebugConfig, SourceBreakpoint, ThreadId},
+    dap_types::{
+        self, DapId, FunctionBreakpoint, GlobalBreakpoints, RunDebugConfig,
+        SourceBreakpoint, ThreadId,
+    },
     file::{FileNodeItem, PathObject},
     plugin::{PluginId, VoltInfo, VoltMetadata},
-    source_control::FileDiff,
+    source_control::{BlameHunk, FileDiff, GitCommit, GitHunk, GitStash},
     style::SemanticStyles,
     terminal::{TermId, TerminalProfile},
     RequestId, RpcError, RpcMessage,
@@ -54,6 +62,15 @@ pub struct SearchMatch {
     pub line_content: String,
 }
 
+/// A file which a global replace wrote to disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplacedFile {
+    /// The content of the file before the replace
+    pub original: String,
+    /// The content the replace wrote, which undoing expects to still be there
+    pub replaced: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 #[serde(tag = "method", content = "params")]
@@ -62,14 +79,96 @@ pub enum ProxyRequest {
         buffer_id: BufferId,
         path: PathBuf,
     },
+    /// The content of the file in the git index, which the working changes are
+    /// compared against
     BufferHead {
         path: PathBuf,
     },
+    /// The content of the file at a revision, such as `HEAD` or a commit id
+    GitFileRevision {
+        path: PathBuf,
+        revision: String,
+    },
+    /// Which commit last changed each line of the file, for the content the
+    /// buffer currently has
+    GitBlame {
+        path: PathBuf,
+    },
+    /// The commits reachable from `HEAD`, newest first, and only the ones which
+    /// changed the file when there is a path
+    GitLog {
+        path: Option<PathBuf>,
+        skip: usize,
+        limit: usize,
+    },
+    /// The files changed by the commit, compared to its first parent
+    GitCommitDiffs {
+        commit: String,
+    },
+    /// Fetch the remote which the head branch tracks
+    GitFetch {},
+    /// Bring the head branch up to date with its upstream, which fast forwards
+    /// it, or rebases it when `rebase` is set and the branches have diverged
+    GitPull {
+        rebase: bool,
+    },
+    /// Push the head branch to its upstream, setting one up when there isn't
+    GitPush {},
+    /// Create a branch starting at the reference, or at `HEAD` without one, and
+    /// check it out
+    GitCreateBranch {
+        name: String,
+        start: Option<String>,
+    },
+    GitRenameBranch {
+        branch: String,
+        name: String,
+    },
+    /// Delete the branch, which has to be merged into `HEAD` unless it's forced
+    GitDeleteBranch {
+        branch: String,
+        force: bool,
+    },
+    /// Merge the reference into the branch which is checked out
+    GitMerge {
+        reference: String,
+    },
+    GitStashList {},
+    /// The content of the file which `commit.template` points to
+    GitCommitTemplate {},
+    /// The message of the commit `HEAD` points to, which an amended commit
+    /// starts with
+    GitHeadMessage {},
+    /// Stash the changes of the working tree and the index
+    GitStashPush {
+        message: Option<String>,
+    },
+    GitStashPop {
+        index: usize,
+    },
+    GitStashApply {
+        index: usize,
+    },
+    GitStashDrop {
+        index: usize,
+    },
     GlobalSearch {
         pattern: String,
         case_sensitive: bool,
         whole_word: bool,
         is_regex: bool,
+        /// Glob patterns of the files to search, all files when empty
+        include: Vec<String>,
+        /// Glob patterns of the files to leave out of the search
+        exclude: Vec<String>,
+    },
+    GlobalReplace {
+        pattern: String,
+        case_sensitive: bool,
+        whole_word: bool,
+        is_regex: bool,
+        replacement: String,
+        matches: IndexMap<PathBuf, Vec<SearchMatch>>,
     },
     CompletionResolve {
         plugin_id: PluginId,
@@ -109,9 +208,56 @@ pub enum ProxyRequest {
         path: PathBuf,
         position: Position,
     },
+    GetImplementation {
+        path: PathBuf,
+        position: Position,
+    },
+    GetDeclaration {
+        path: PathBuf,
+        position: Position,
+    },
+    GetDocumentHighlight {
+        path: PathBuf,
+        position: Position,
+    },
     GetInlayHints {
         path: PathBuf,
     },
+    GetFoldingRange {
+        path: PathBuf,
+    },
+    CodeLensResolve {
+        plugin_id: PluginId,
+        code_lens: CodeLens,
+    },
+    ExecuteCommand {
+        plugin_id: PluginId,
+        command: Command,
+    },
+    PrepareCallHierarchy {
+        path: PathBuf,
+        position: Position,
+    },
+    CallHierarchyIncomingCalls {
+        plugin_id: PluginId,
+        item: CallHierarchyItem,
+    },
+    CallHierarchyOutgoingCalls {
+        plugin_id: PluginId,
+        item: CallHierarchyItem,
+    },
+    PrepareTypeHierarchy {
+        path: PathBuf,
+        position: Position,
+    },
+    TypeHierarchySupertypes {
+        plugin_id: PluginId,
+        item: TypeHierarchyItem,
+    },
+    TypeHierarchySubtypes {
+        plugin_id: PluginId,
+        item: TypeHierarchyItem,
+    },
     GetInlineCompletions {
         path: PathBuf,
         position: Position,
@@ -144,6 +290,16 @@ pub enum ProxyRequest {
     GetDocumentFormatting {
         path: PathBuf,
     },
+    GetDocumentRangeFormatting {
+        path: PathBuf,
+        range: Range,
+    },
+    GetDocumentOnTypeFormatting {
+        path: PathBuf,
+        position: Position,
+        /// The character that was typed
+        ch: String,
+    },
     GetOpenFilesContent {},
     GetFiles {
         path: String,
@@ -193,6 +349,33 @@ pub enum ProxyRequest {
         dap_id: DapId,
         frame_id: usize,
     },
+    DapEvaluate {
+        dap_id: DapId,
+        expression: String,
+        frame_id: Option<usize>,
+        context: Option<String>,
+    },
+    DapSetVariable {
+        dap_id: DapId,
+        variables_reference: usize,
+        name: String,
+        value: String,
+    },
+    DapSetExpression {
+        dap_id: DapId,
+        expression: String,
+        value: String,
+        frame_id: Option<usize>,
+    },
+    /// The running processes, to pick the one a debugger attaches to
+    ListProcesses {},
+    /// Build the target of a cargo command, e.g. `cargo test`, and find the
+    /// executable it produces for a debugger to launch
+    CargoExecutable {
+        args: Vec<String>,
+        cwd: Option<String>,
+        env: Option<HashMap<String, String>>,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -226,11 +409,29 @@ pub enum ProxyNotification {
         path: PathBuf,
         position: Position,
     },
+    /// Ask every plugin for the code lenses of the file, which they each
+    /// respond to with a `CodeLensResponse`
+    GetCodeLens {
+        path: PathBuf,
+        rev: u64,
+    },
     Update {
         path: PathBuf,
         delta: RopeDelta,
         rev: u64,
     },
+    /// Whether the workspace edit of an `ApplyWorkspaceEdit` was applied, which
+    /// is the answer to the language server that requested it
+    WorkspaceEditApplied {
+        id: u64,
+        applied: bool,
+        failure_reason: Option<String>,
+    },
+    /// Restore the content of the files which were written to disk by a
+    /// global replace
+    GlobalReplaceUndo {
+        files: IndexMap<PathBuf, ReplacedFile>,
+    },
     UpdatePluginConfigs {
         configs: HashMap<String, HashMap<String, serde_json::Value>>,
     },
@@ -253,9 +454,13 @@ pub enum ProxyNotification {
     EnableVolt {
         volt: VoltInfo,
     },
+    /// Commit the diffs, or replace the commit `HEAD` points to with `amend`.
+    /// A `Signed-off-by` trailer is added to the message with `sign_off`.
     GitCommit {
         message: String,
         diffs: Vec<FileDiff>,
+        amend: bool,
+        sign_off: bool,
     },
     GitCheckout {
         reference: String,
@@ -265,6 +470,31 @@ pub enum ProxyNotification {
     },
     GitDiscardWorkspaceChanges {},
     GitInit {},
+    /// Make the repository the one which git operations that aren't about a
+    /// file, like checkout, fetch or stash, work on. Without a path, it's the
+    /// one the workspace is in.
+    GitSetRepository {
+        path: Option<PathBuf>,
+    },
+    GitStageFiles {
+        files: Vec<PathBuf>,
+    },
+    GitUnstageFiles {
+        files: Vec<PathBuf>,
+    },
+    /// Replace the old lines of the hunk in the index with `content`, which are
+    /// the new lines of the hunk in the working copy
+    GitStageHunk {
+        path: PathBuf,
+        hunk: GitHunk,
+        content: String,
+    },
+    /// Put the old lines of the hunk, which are in `HEAD`, back in place of the
+    /// new lines in the index
+    GitUnstageHunk {
+        path: PathBuf,
+        hunk: GitHunk,
+    },
     TerminalWrite {
         term_id: TermId,
         content: String,
@@ -280,6 +510,7 @@ pub enum ProxyNotification {
     DapStart {
         config: RunDebugConfig,
         breakpoints: HashMap<PathBuf, Vec<SourceBreakpoint>>,
+        global_breakpoints: GlobalBreakpoints,
     },
     DapProcessId {
         dap_id: DapId,
@@ -315,12 +546,22 @@ pub enum ProxyNotification {
     DapRestart {
         dap_id: DapId,
         breakpoints: HashMap<PathBuf, Vec<SourceBreakpoint>>,
+        global_breakpoints: GlobalBreakpoints,
     },
     DapSetBreakpoints {
         dap_id: DapId,
         path: PathBuf,
         breakpoints: Vec<SourceBreakpoint>,
     },
+    DapSetFunctionBreakpoints {
+        dap_id: DapId,
+        breakpoints: Vec<FunctionBreakpoint>,
+    },
+    /// Set the exception filters that are turned on, replacing the previous ones
+    DapSetExceptionBreakpoints {
+        dap_id: DapId,
+        filters: Vec<String>,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -338,6 +579,24 @@ pub enum ProxyResponse {
         version: String,
         content: String,
     },
+    GitBlameResponse {
+        hunks: Vec<BlameHunk>,
+    },
+    GitLogResponse {
+        commits: Vec<GitCommit>,
+    },
+    GitCommitDiffsResponse {
+        diffs: Vec<FileDiff>,
+    },
+    GitStashListResponse {
+        stashes: Vec<GitStash>,
+    },
+    GitCommitTemplateResponse {
+        template: Option<String>,
+    },
+    GitHeadMessageResponse {
+        message: String,
+    },
     ReadDirResponse {
         items: Vec<FileNodeItem>,
     },
@@ -359,6 +618,18 @@ pub enum ProxyResponse {
         request_id: usize,
         definition: GotoTypeDefinitionResponse,
     },
+    GetImplementationResponse {
+        implementation: GotoImplementationResponse,
+    },
+    GetDeclarationResponse {
+        declaration: GotoDeclarationResponse,
+    },
+    GetDocumentHighlightResponse {
+        highlights: Vec<DocumentHighlight>,
+    },
+    GetFoldingRangeResponse {
+        ranges: Vec<FoldingRange>,
+    },
     GetReferencesResponse {
         references: Vec<Location>,
     },
@@ -384,6 +655,29 @@ pub enum ProxyResponse {
     GetInlayHints {
         hints: Vec<InlayHint>,
     },
+    CodeLensResolveResponse {
+        code_lens: CodeLens,
+    },
+    ExecuteCommandResponse {
+        result: serde_json::Value,
+    },
+    PrepareCallHierarchyResponse {
+        plugin_id: PluginId,
+        items: Vec<CallHierarchyItem>,
+    },
+    CallHierarchyIncomingCallsResponse {
+        calls: Vec<CallHierarchyIncomingCall>,
+    },
+    CallHierarchyOutgoingCallsResponse {
+        calls: Vec<CallHierarchyOutgoingCall>,
+    },
+    PrepareTypeHierarchyResponse {
+        plugin_id: PluginId,
+        items: Vec<TypeHierarchyItem>,
+    },
+    TypeHierarchyItemsResponse {
+        items: Vec<TypeHierarchyItem>,
+    },
     GetInlineCompletions {
         completions: InlineCompletionResponse,
     },
@@ -402,12 +696,33 @@ pub enum ProxyResponse {
     GlobalSearchResponse {
         matches: IndexMap<PathBuf, Vec<SearchMatch>>,
     },
+    GlobalReplaceResponse {
+        /// The edits to apply to the files which are open as buffers
+        edits: IndexMap<PathBuf, Vec<TextEdit>>,
+        /// The files which were replaced on disk
+        files: IndexMap<PathBuf, ReplacedFile>,
+    },
     DapVariableResponse {
         varialbes: Vec<dap_types::Variable>,
     },
     DapGetScopesResponse {
         scopes: Vec<(dap_types::Scope, Vec<dap_types::Variable>)>,
     },
+    DapEvaluateResponse {
+        result: dap_types::EvaluateResponse,
+    },
+    DapSetVariableResponse {
+        result: dap_types::SetVariableResponse,
+    },
+    DapSetExpressionResponse {
+        result: dap_types::SetExpressionResponse,
+    },
+    ListProcessesResponse {
+        processes: Vec<dap_types::ProcessInfo>,
+    },
+    CargoExecutableResponse {
+        path: PathBuf,
+    },
     CreatePathResponse {
         path: PathBuf,
     },
@@ -536,8 +851,23 @@ pub fn git_init(&self) {
         self.notification(ProxyNotification::GitInit {});
     }
 
-    pub fn git_commit(&self, message: String, diffs: Vec<FileDiff>) {
-        self.notification(ProxyNotification::GitCommit { message, diffs });
+    pub fn git_set_repository(&self, path: Option<PathBuf>) {
+        self.notification(ProxyNotification::GitSetRepository { path });
+    }
+
+    pub fn git_commit(
+        &self,
+        message: String,
+        diffs: Vec<FileDiff>,
+        amend: bool,
+        sign_off: bool,
+    ) {
+        self.notification(ProxyNotification::GitCommit {
+            message,
+            diffs,
+            amend,
+            sign_off,
+        });
     }
 
     pub fn git_checkout(&self, reference: String) {
@@ -649,6 +979,108 @@ pub fn get_buffer_head(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
         self.request_async(ProxyRequest::BufferHead { path }, f);
     }
 
+    pub fn git_get_file_revision(
+        &self,
+        path: PathBuf,
+        revision: String,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GitFileRevision { path, revision }, f);
+    }
+
+    pub fn git_blame(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::GitBlame { path }, f);
+    }
+
+    pub fn git_log(
+        &self,
+        path: Option<PathBuf>,
+        skip: usize,
+        limit: usize,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GitLog { path, skip, limit }, f);
+    }
+
+    pub fn git_commit_diffs(&self, commit: String, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::GitCommitDiffs { commit }, f);
+    }
+
+    pub fn git_fetch(&self, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::GitFetch {}, f);
+    }
+
+    pub fn git_pull(&self, rebase: bool, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::GitPull { rebase }, f);
+    }
+
+    pub fn git_push(&self, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::GitPush {}, f);
+    }
+
+    pub fn git_create_branch(
+        &self,
+        name: String,
+        start: Option<String>,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GitCreateBranch { name, start }, f);
+    }
+
+    pub fn git_rename_branch(
+        &self,
+        branch: String,
+        name: String,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GitRenameBranch { branch, name }, f);
+    }
+
+    pub fn git_delete_branch(
+        &self,
+        branch: String,
+        force: bool,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GitDeleteBranch { branch, force }, f);
+    }
+
+    pub fn git_merge(&self, reference: String, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::GitMerge { reference }, f);
+    }
+
+    pub fn git_stash_list(&self, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::GitStashList {}, f);
+    }
+
+    pub fn git_commit_template(&self, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::GitCommitTemplate {}, f);
+    }
+
+    pub fn git_head_message(&self, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::GitHeadMessage {}, f);
+    }
+
+    pub fn git_stash_push(
+        &self,
+        message: Option<String>,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GitStashPush { message }, f);
+    }
+
+    pub fn git_stash_pop(&self, index: usize, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::GitStashPop { index }, f);
+    }
+
+    pub fn git_stash_apply(&self, index: usize, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::GitStashApply { index }, f);
+    }
+
+    pub fn git_stash_drop(&self, index: usize, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::GitStashDrop { index }, f);
+    }
+
     pub fn create_file(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
         self.request_async(ProxyRequest::CreateFile { path }, f);
     }
@@ -714,12 +1146,15 @@ pub fn save_buffer_as(
         );
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn global_search(
         &self,
         pattern: String,
         case_sensitive: bool,
         whole_word: bool,
         is_regex: bool,
+        include: Vec<String>,
+        exclude: Vec<String>,
         f: impl ProxyCallback + 'static,
     ) {
         self.request_async(
@@ -728,11 +1163,41 @@ pub fn global_search(
                 case_sensitive,
                 whole_word,
                 is_regex,
+                include,
+                exclude,
+            },
+            f,
+        );
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn global_replace(
+        &self,
+        pattern: String,
+        case_sensitive: bool,
+        whole_word: bool,
+        is_regex: bool,
+        replacement: String,
+        matches: IndexMap<PathBuf, Vec<SearchMatch>>,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::GlobalReplace {
+                pattern,
+                case_sensitive,
+                whole_word,
+                is_regex,
+                replacement,
+                matches,
             },
             f,
         );
     }
 
+    pub fn global_replace_undo(&self, files: IndexMap<PathBuf, ReplacedFile>) {
+        self.notification(ProxyNotification::GlobalReplaceUndo { files });
+    }
+
     pub fn save(
         &self,
         rev: u64,
@@ -848,6 +1313,33 @@ pub fn get_type_definition(
         );
     }
 
+    pub fn get_implementation(
+        &self,
+        path: PathBuf,
+        position: Position,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GetImplementation { path, position }, f);
+    }
+
+    pub fn get_declaration(
+        &self,
+        path: PathBuf,
+        position: Position,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GetDeclaration { path, position }, f);
+    }
+
+    pub fn get_document_highlight(
+        &self,
+        path: PathBuf,
+        position: Position,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GetDocumentHighlight { path, position }, f);
+    }
+
     pub fn get_references(
         &self,
         path: PathBuf,
@@ -882,6 +1374,31 @@ pub fn get_document_formatting(
         self.request_async(ProxyRequest::GetDocumentFormatting { path }, f);
     }
 
+    pub fn get_document_range_formatting(
+        &self,
+        path: PathBuf,
+        range: Range,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::GetDocumentRangeFormatting { path, range },
+            f,
+        );
+    }
+
+    pub fn get_document_on_type_formatting(
+        &self,
+        path: PathBuf,
+        position: Position,
+        ch: String,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::GetDocumentOnTypeFormatting { path, position, ch },
+            f,
+        );
+    }
+
     pub fn get_semantic_tokens(
         &self,
         path: PathBuf,
@@ -944,6 +1461,117 @@ pub fn get_inlay_hints(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
         self.request_async(ProxyRequest::GetInlayHints { path }, f);
     }
 
+    pub fn get_folding_range(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::GetFoldingRange { path }, f);
+    }
+
+    pub fn get_code_lens(&self, path: PathBuf, rev: u64) {
+        self.notification(ProxyNotification::GetCodeLens { path, rev });
+    }
+
+    pub fn workspace_edit_applied(
+        &self,
+        id: u64,
+        applied: bool,
+        failure_reason: Option<String>,
+    ) {
+        self.notification(ProxyNotification::WorkspaceEditApplied {
+            id,
+            applied,
+            failure_reason,
+        });
+    }
+
+    pub fn code_lens_resolve(
+        &self,
+        plugin_id: PluginId,
+        code_lens: CodeLens,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::CodeLensResolve {
+                plugin_id,
+                code_lens,
+            },
+            f,
+        );
+    }
+
+    pub fn execute_command(
+        &self,
+        plugin_id: PluginId,
+        command: Command,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::ExecuteCommand { plugin_id, command }, f);
+    }
+
+    pub fn prepare_call_hierarchy(
+        &self,
+        path: PathBuf,
+        position: Position,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::PrepareCallHierarchy { path, position }, f);
+    }
+
+    pub fn call_hierarchy_incoming_calls(
+        &self,
+        plugin_id: PluginId,
+        item: CallHierarchyItem,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::CallHierarchyIncomingCalls { plugin_id, item },
+            f,
+        );
+    }
+
+    pub fn call_hierarchy_outgoing_calls(
+        &self,
+        plugin_id: PluginId,
+        item: CallHierarchyItem,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::CallHierarchyOutgoingCalls { plugin_id, item },
+            f,
+        );
+    }
+
+    pub fn prepare_type_hierarchy(
+        &self,
+        path: PathBuf,
+        position: Position,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::PrepareTypeHierarchy { path, position }, f);
+    }
+
+    pub fn type_hierarchy_supertypes(
+        &self,
+        plugin_id: PluginId,
+        item: TypeHierarchyItem,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::TypeHierarchySupertypes { plugin_id, item },
+            f,
+        );
+    }
+
+    pub fn type_hierarchy_subtypes(
+        &self,
+        plugin_id: PluginId,
+        item: TypeHierarchyItem,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::TypeHierarchySubtypes { plugin_id, item },
+            f,
+        );
+    }
+
     pub fn get_inline_completions(
         &self,
         path: PathBuf,
@@ -980,6 +1608,26 @@ pub fn git_discard_workspace_changes(&self) {
         self.notification(ProxyNotification::GitDiscardWorkspaceChanges {});
     }
 
+    pub fn git_stage_files(&self, files: Vec<PathBuf>) {
+        self.notification(ProxyNotification::GitStageFiles { files });
+    }
+
+    pub fn git_unstage_files(&self, files: Vec<PathBuf>) {
+        self.notification(ProxyNotification::GitUnstageFiles { files });
+    }
+
+    pub fn git_stage_hunk(&self, path: PathBuf, hunk: GitHunk, content: String) {
+        self.notification(ProxyNotification::GitStageHunk {
+            path,
+            hunk,
+            content,
+        });
+    }
+
+    pub fn git_unstage_hunk(&self, path: PathBuf, hunk: GitHunk) {
+        self.notification(ProxyNotification::GitUnstageHunk { path, hunk });
+    }
+
     pub fn get_selection_range(
         &self,
         path: PathBuf,
@@ -993,10 +1641,12 @@ pub fn dap_start(
         &self,
         config: RunDebugConfig,
         breakpoints: HashMap<PathBuf, Vec<SourceBreakpoint>>,
+        global_breakpoints: GlobalBreakpoints,
     ) {
         self.notification(ProxyNotification::DapStart {
             config,
             breakpoints,
+            global_breakpoints,
         })
     }
 
@@ -1017,10 +1667,12 @@ pub fn dap_restart(
         &self,
         dap_id: DapId,
         breakpoints: HashMap<PathBuf, Vec<SourceBreakpoint>>,
+        global_breakpoints: GlobalBreakpoints,
     ) {
         self.notification(ProxyNotification::DapRestart {
             dap_id,
             breakpoints,
+            global_breakpoints,
         })
     }
 
@@ -1065,6 +1717,28 @@ pub fn dap_set_breakpoints(
         })
     }
 
+    pub fn dap_set_function_breakpoints(
+        &self,
+        dap_id: DapId,
+        breakpoints: Vec<FunctionBreakpoint>,
+    ) {
+        self.notification(ProxyNotification::DapSetFunctionBreakpoints {
+            dap_id,
+            breakpoints,
+        })
+    }
+
+    pub fn dap_set_exception_breakpoints(
+        &self,
+        dap_id: DapId,
+        filters: Vec<String>,
+    ) {
+        self.notification(ProxyNotification::DapSetExceptionBreakpoints {
+            dap_id,
+            filters,
+        })
+    }
+
     pub fn dap_variable(
         &self,
         dap_id: DapId,
@@ -1082,6 +1756,77 @@ pub fn dap_get_scopes(
     ) {
         self.request_async(ProxyRequest::DapGetScopes { dap_id, frame_id }, f);
     }
+
+    pub fn dap_evaluate(
+        &self,
+        dap_id: DapId,
+        expression: String,
+        frame_id: Option<usize>,
+        context: Option<String>,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::DapEvaluate {
+                dap_id,
+                expression,
+                frame_id,
+                context,
+            },
+            f,
+        );
+    }
+
+    pub fn dap_set_variable(
+        &self,
+        dap_id: DapId,
+        variables_reference: usize,
+        name: String,
+        value: String,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::DapSetVariable {
+                dap_id,
+                variables_reference,
+                name,
+                value,
+            },
+            f,
+        );
+    }
+
+    pub fn dap_set_expression(
+        &self,
+        dap_id: DapId,
+        expression: String,
+        value: String,
+        frame_id: Option<usize>,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::DapSetExpression {
+                dap_id,
+                expression,
+                value,
+                frame_id,
+            },
+            f,
+        );
+    }
+
+    pub fn list_processes(&self, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::ListProcesses {}, f);
+    }
+
+    pub fn cargo_executable(
+        &self,
+        args: Vec<String>,
+        cwd: Option<String>,
+        env: Option<HashMap<String, String>>,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::CargoExecutable { args, cwd, env }, f);
+    }
 }
 
 impl Default for ProxyRpcHandler {
@@ -4,10 +4,21 @@
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
 pub struct DiffInfo {
+    /// The working directory of the repository
+    pub path: PathBuf,
     pub head: String,
     pub branches: Vec<String>,
     pub tags: Vec<String>,
+    /// Changes in the working tree which aren't in the index yet
     pub diffs: Vec<FileDiff>,
+    /// Changes in the index which aren't committed yet
+    pub staged: Vec<FileDiff>,
+    /// The upstream branch which the head branch tracks, like `origin/main`
+    pub upstream: Option<String>,
+    /// How many commits the head branch has which its upstream doesn't
+    pub ahead: usize,
+    /// How many commits the upstream has which the head branch doesn't
+    pub behind: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
@@ -16,6 +27,9 @@ pub enum FileDiff {
     Added(PathBuf),
     Deleted(PathBuf),
     Renamed(PathBuf, PathBuf),
+    /// A file with conflicts left by a merge or rebase, which need to be
+    /// resolved before it can be staged
+    Conflicted(PathBuf),
 }
 
 impl FileDiff {
@@ -24,7 +38,73 @@ pub fn path(&self) -> &PathBuf {
             FileDiff::Modified(p)
             | FileDiff::Added(p)
             | FileDiff::Deleted(p)
+            | FileDiff::Conflicted(p)
             | FileDiff::Renamed(_, p) => p,
         }
     }
+
+    /// All the paths touched by the change, which for a rename are both the new
+    /// and the old path.
+    pub fn paths(&self) -> Vec<PathBuf> {
+        match &self {
+            FileDiff::Modified(p)
+            | FileDiff::Added(p)
+            | FileDiff::Deleted(p)
+            | FileDiff::Conflicted(p) => vec![p.clone()],
+            FileDiff::Renamed(a, d) => vec![a.clone(), d.clone()],
+        }
+    }
+}
+
+/// A changed region between an old and a new version of a file, with zero based
+/// line numbers. A region where lines were only added or only removed has no
+/// lines on the other side, and starts at the line where they would be.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub struct GitHunk {
+    pub old_start: usize,
+    pub old_lines: usize,
+    pub new_start: usize,
+    pub new_lines: usize,
+}
+
+/// A run of lines of a file which were last changed by the same commit, with a
+/// zero based start line.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BlameHunk {
+    pub start_line: usize,
+    pub lines: usize,
+    /// The commit which last changed the lines, or `None` when the changes
+    /// aren't committed yet
+    pub commit: Option<BlameCommit>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BlameCommit {
+    pub id: String,
+    pub author: String,
+    pub email: String,
+    /// The commit time in seconds since the unix epoch
+    pub time: i64,
+    pub summary: String,
+}
+
+/// An entry in the stash, where the newest one has index 0
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct GitStash {
+    pub index: usize,
+    pub message: String,
+}
+
+/// A commit in the history of the repository
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct GitCommit {
+    pub id: String,
+    /// The commits it was made on top of, where the first one is the branch it
+    /// was made on, and the others are the ones merged into it
+    pub parents: Vec<String>,
+    pub author: String,
+    pub email: String,
+    /// The commit time in seconds since the unix epoch
+    pub time: i64,
+    pub summary: String,
 }